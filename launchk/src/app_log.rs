@@ -0,0 +1,105 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+/// Records kept for the logs pane, older ones are dropped
+const MAX_RECORDS: usize = 1000;
+
+lazy_static! {
+    static ref RECORDS: Mutex<VecDeque<AppLogRecord>> = Mutex::new(VecDeque::new());
+}
+
+/// A log:: record from launchk, repeats of the last message are counted
+/// rather than kept (e.g. a domain that fails on every poll)
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AppLogRecord {
+    pub time: SystemTime,
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+    pub count: usize,
+}
+
+/// Keeps launchk's own log output for the logs pane, as nothing on
+/// stderr can be seen under the TUI. Still logs to stderr as RUST_LOG
+/// says.
+struct AppLogger {
+    env: env_logger::Logger,
+}
+
+impl AppLogger {
+    /// Debug from launchk, only warnings from xpc-sys which logs every
+    /// object it creates
+    fn captures(&self, metadata: &Metadata) -> bool {
+        if metadata.target().starts_with("launchk") {
+            metadata.level() <= Level::Debug
+        } else {
+            metadata.level() <= Level::Warn
+        }
+    }
+}
+
+impl Log for AppLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.captures(metadata) || self.env.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if self.env.matches(record) {
+            self.env.log(record);
+        }
+
+        if !self.captures(record.metadata()) {
+            return;
+        }
+
+        let message = record.args().to_string();
+        let mut records = match RECORDS.lock() {
+            Ok(r) => r,
+            Err(_) => return,
+        };
+
+        if let Some(last) = records.back_mut() {
+            if last.level == record.level() && last.message == message {
+                last.count += 1;
+                last.time = SystemTime::now();
+                return;
+            }
+        }
+
+        if records.len() == MAX_RECORDS {
+            records.pop_front();
+        }
+
+        records.push_back(AppLogRecord {
+            time: SystemTime::now(),
+            level: record.level(),
+            target: record.target().to_string(),
+            message,
+            count: 1,
+        });
+    }
+
+    fn flush(&self) {
+        self.env.flush();
+    }
+}
+
+/// Replaces env_logger::init
+pub fn init() {
+    let env = env_logger::Builder::from_default_env().build();
+    let max_level = env.filter().max(LevelFilter::Debug);
+
+    log::set_boxed_logger(Box::new(AppLogger { env })).expect("Must set logger");
+    log::set_max_level(max_level);
+}
+
+/// Kept records at least as severe as level, oldest first
+pub fn records(level: Level) -> Vec<AppLogRecord> {
+    RECORDS
+        .lock()
+        .map(|r| r.iter().filter(|r| r.level <= level).cloned().collect())
+        .unwrap_or_default()
+}
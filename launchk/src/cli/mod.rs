@@ -0,0 +1,788 @@
+use std::fs;
+use std::io::{self, Write};
+use std::ptr::slice_from_raw_parts;
+use std::thread;
+
+use serde::Serialize;
+use xpc_sys::enums::{DomainType, SessionType};
+use xpc_sys::objects::xpc_error::launchd_strerror;
+
+use crate::config::{self, poll_interval};
+use crate::launchd::domain_target::{parse_service_target, resolve_domain_target};
+use crate::launchd::entry_status::{get_entry_status, LaunchdEntryStatus};
+use crate::launchd::events::{job_events, EventLine};
+use crate::launchd::overrides::Overrides;
+use crate::launchd::plist::{load_plist_map, LABEL_TO_ENTRY_CONFIG};
+use crate::launchd::plist_lint::lint_file;
+use crate::launchd::preflight::Preflight;
+use crate::launchd::query::{
+    disable, dumpjpcategory, dumpstate, enable, list_all, list_all_detailed, list_errors, load,
+    print_domain, procinfo, procinfo_parsed, unload,
+};
+use crate::launchd::snapshot::Snapshot;
+
+static USAGE: &str = "usage: launchk [command] [--json] [--domain <type>] [--session <type>]
+                [--target <domain-target>]
+       launchk --script <file> [--json]
+       launchk --events
+
+Run without a command to start the TUI, or to list jobs when stdout is not
+a terminal (e.g. launchk | grep foo, or launchk --json | jq).
+
+commands:
+    list [filter]       List jobs, optionally filtered by label substring
+    load <label>        Load job from its plist, unless lint finds errors
+    unload <label>      Unload job
+    enable <label>      Enable job (enables load), label may be a service
+                        target, e.g. gui/502/com.example.agent
+    disable <label>     Disable job (prevents load)
+    dumpstate           launchctl dumpstate
+    dumpjpcategory      launchctl dumpjpcategory
+    print <target>      launchctl print for a domain target, e.g. system or gui/501
+    procinfo <pid>      launchctl procinfo, parsed with --json
+    error <code>        Describe a launchd error code
+    lint <path>         Check a plist for deprecated, conflicting and invalid keys
+    snapshot <path>     Save all jobs and their state
+    diff <old> [new]    Compare two snapshots, or a snapshot with now
+    overrides save <path>
+                        Save which jobs are disabled in each domain
+    overrides apply <path>
+                        Enable and disable jobs to match a saved file
+    help                Show this message
+
+--domain takes a name (system, user, login, session, pid, gui, requestor) or
+number, --session one of Aqua, Background, LoginWindow, StandardIO, System.
+
+--target takes a launchctl domain target for load, unload, enable and
+disable, e.g. gui/501, user/501, pid/<pid> or session/<asid>. gui, user and
+login also take a user name, e.g. gui/alice. Other users' domains require
+root.
+
+--events prints a JSON line whenever a job loads, unloads, starts, exits or
+changes PID, polling every LAUNCHK_POLL_INTERVAL_MS (or refresh_interval_ms
+from config.toml, default 1000). Each line has time (seconds since epoch),
+event (loaded, unloaded, started, exited, pid_changed), label and
+domain, plus pid, old_pid or status where they apply.
+
+--script runs load, unload, enable and disable from a file, one per line
+in order, e.g. `load com.example.agent gui/501`. Blank lines and lines
+starting with # are skipped. Failures are reported at the end.";
+
+/// Headless subcommands, mostly mirroring the omnibox commands
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum CliCommand {
+    List(Option<String>),
+    Load(String),
+    Unload(String),
+    Enable(String),
+    Disable(String),
+    DumpState,
+    DumpJetsamPropertiesCategory,
+    ProcInfo(i64),
+    // Domain target
+    Print(String),
+    Error(i64),
+    // Plist path
+    Lint(String),
+    Snapshot(String),
+    // (old, new)
+    Diff(String, Option<String>),
+    // Paths of print-disabled exports
+    OverridesSave(String),
+    OverridesApply(String),
+    // Path of a file with one command per line
+    Script(String),
+    // JSON lines of job changes until stdout closes
+    Events,
+    Help,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CliArgs {
+    pub command: CliCommand,
+    pub json: bool,
+    pub domain: Option<DomainType>,
+    pub session: Option<SessionType>,
+    /// uid, pid or asid from --target
+    pub handle: Option<u64>,
+}
+
+/// A row of `launchk list`
+#[derive(Debug, Clone, Serialize)]
+struct ListEntry {
+    label: String,
+    pid: i64,
+    loaded: bool,
+    domain: String,
+    session: String,
+    job_type: Option<String>,
+    plist_path: Option<String>,
+}
+
+/// Parse CLI arguments (without argv[0])
+pub fn parse<S: AsRef<str>>(args: &[S]) -> Result<CliArgs, String> {
+    let mut json = false;
+    let mut domain = None;
+    let mut session = None;
+    let mut handle = None;
+    let mut script = None;
+    let mut events = false;
+    let mut positional: Vec<String> = vec![];
+
+    let mut iter = args.iter().map(|a| a.as_ref());
+
+    while let Some(arg) = iter.next() {
+        match arg {
+            "--json" => json = true,
+            "--domain" => {
+                let value = iter.next().ok_or("--domain requires a value")?;
+                let domain_type: DomainType = value.parse().map_err(|e| format!("{}", e))?;
+                domain = Some(domain_type);
+            }
+            "--session" => {
+                let value = iter.next().ok_or("--session requires a value")?;
+                let session_type: SessionType = value.parse().map_err(|e| format!("{}", e))?;
+                session = Some(session_type);
+            }
+            "--target" => {
+                let value = iter.next().ok_or("--target requires a value")?;
+                let (domain_type, target_handle) = resolve_domain_target(value)?;
+                domain = Some(domain_type);
+                handle = target_handle;
+            }
+            "--script" => {
+                let value = iter.next().ok_or("--script requires a file")?;
+                script = Some(value.to_string());
+            }
+            "--events" => events = true,
+            "-h" | "--help" => positional.insert(0, "help".to_string()),
+            a if a.starts_with("--") => return Err(format!("Unknown flag {}", a)),
+            a => positional.push(a.to_string()),
+        }
+    }
+
+    if events {
+        if !positional.is_empty() || script.is_some() {
+            return Err("--events cannot be combined with a command".to_string());
+        }
+
+        return Ok(CliArgs {
+            command: CliCommand::Events,
+            json: true,
+            domain,
+            session,
+            handle,
+        });
+    }
+
+    if let Some(path) = script {
+        if !positional.is_empty() {
+            return Err("--script cannot be combined with a command".to_string());
+        }
+
+        return Ok(CliArgs {
+            command: CliCommand::Script(path),
+            json,
+            domain,
+            session,
+            handle,
+        });
+    }
+
+    let mut positional = positional.into_iter();
+    let name = positional.next().unwrap_or("help".to_string());
+    let mut required = |what: &str| {
+        positional
+            .next()
+            .ok_or_else(|| format!("{} requires a {}", name, what))
+    };
+
+    let command = match name.as_str() {
+        "list" => CliCommand::List(required("filter").ok()),
+        "load" => CliCommand::Load(required("label")?),
+        "unload" => CliCommand::Unload(required("label")?),
+        "enable" | "disable" => {
+            let mut label = required("label")?;
+
+            // launchctl style, e.g. gui/502/com.example.agent
+            if label.contains('/') {
+                let (target, service) = parse_service_target(&label)?;
+                domain = Some(target.domain_type);
+                handle = Some(target.handle);
+                label = service;
+            }
+
+            if name == "enable" {
+                CliCommand::Enable(label)
+            } else {
+                CliCommand::Disable(label)
+            }
+        }
+        "dumpstate" => CliCommand::DumpState,
+        "dumpjpcategory" => CliCommand::DumpJetsamPropertiesCategory,
+        "procinfo" => {
+            let pid = required("pid")?;
+            CliCommand::ProcInfo(pid.parse().map_err(|_| format!("Invalid pid {}", pid))?)
+        }
+        "print" => CliCommand::Print(required("domain target")?),
+        "error" => {
+            let code = required("code")?;
+            CliCommand::Error(code.parse().map_err(|_| format!("Invalid code {}", code))?)
+        }
+        "lint" => CliCommand::Lint(required("path")?),
+        "snapshot" => CliCommand::Snapshot(required("path")?),
+        "diff" => {
+            let old = required("path")?;
+            CliCommand::Diff(old, positional.next())
+        }
+        "overrides" => match required("save or apply")?.as_str() {
+            "save" => CliCommand::OverridesSave(required("path")?),
+            "apply" => CliCommand::OverridesApply(required("path")?),
+            other => return Err(format!("Unknown overrides command {}", other)),
+        },
+        "help" => CliCommand::Help,
+        other => return Err(format!("Unknown command {}", other)),
+    };
+
+    Ok(CliArgs {
+        command,
+        json,
+        domain,
+        session,
+        handle,
+    })
+}
+
+/// JSON lines for job changes, until stdout closes (e.g. | head)
+fn stream_events() -> Result<(), String> {
+    if let Err(e) = config::reload() {
+        eprintln!("launchk: {}", e);
+    }
+
+    let stdout = io::stdout();
+    let mut older = list_all_detailed();
+
+    loop {
+        thread::sleep(poll_interval());
+        let newer = list_all_detailed();
+        let mut out = stdout.lock();
+
+        for event in job_events(&older, &newer) {
+            let line = serde_json::to_string(&EventLine::from(event)).map_err(|e| e.to_string())?;
+
+            if writeln!(out, "{}", line).and_then(|_| out.flush()).is_err() {
+                return Ok(());
+            }
+        }
+
+        older = newer;
+    }
+}
+
+/// Run a CLI command, returning the process exit code
+pub fn run(args: CliArgs) -> i32 {
+    match execute(&args) {
+        Ok(()) => 0,
+        Err(e) if args.json => {
+            println!("{}", serde_json::json!({ "error": e }));
+            1
+        }
+        Err(e) => {
+            eprintln!("launchk: {}", e);
+            1
+        }
+    }
+}
+
+fn execute(args: &CliArgs) -> Result<(), String> {
+    load_plist_map();
+    execute_command(args)
+}
+
+fn execute_command(args: &CliArgs) -> Result<(), String> {
+    let CliArgs {
+        command,
+        json,
+        domain,
+        session,
+        handle,
+    } = args;
+
+    match command {
+        CliCommand::Script(path) => run_script(path, *json),
+        CliCommand::Events => stream_events(),
+        CliCommand::Help => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        CliCommand::Error(code) => {
+            let message = launchd_strerror(*code);
+
+            if *json {
+                println!(
+                    "{}",
+                    serde_json::json!({ "code": code, "message": message })
+                );
+            } else {
+                println!("{}", message);
+            }
+
+            Ok(())
+        }
+        CliCommand::Snapshot(path) => {
+            Snapshot::capture().save(path)?;
+            print_ok(*json)
+        }
+        CliCommand::Diff(old, new) => {
+            let old = Snapshot::read(old)?;
+            let new = match new {
+                Some(path) => Snapshot::read(path)?,
+                None => Snapshot::capture(),
+            };
+
+            let changes: Vec<String> = old.diff(&new).iter().map(|c| c.to_string()).collect();
+
+            if *json {
+                println!("{}", serde_json::json!({ "changes": changes }));
+            } else {
+                changes.iter().for_each(|c| println!("{}", c));
+            }
+
+            Ok(())
+        }
+        CliCommand::OverridesSave(path) => {
+            Overrides::capture().save(path)?;
+            print_ok(*json)
+        }
+        CliCommand::OverridesApply(path) => apply_overrides(path, *json),
+        CliCommand::List(filter) => print_list(filter.as_deref(), *json),
+        CliCommand::Lint(path) => print_lint(path, *json),
+        CliCommand::Load(label) => {
+            let (plist_path, domain, session) = resolve_load_args(label, domain, session)?;
+            let errors = lint_file(&plist_path)?.errors;
+            if !errors.is_empty() {
+                return Err(format!("Refusing to load {}: {}", label, errors.join(", ")));
+            }
+            load(label.clone(), plist_path, domain, session, *handle).map_err(|e| e.to_string())?;
+            print_ok(*json)
+        }
+        CliCommand::Unload(label) => {
+            let (plist_path, domain, session) = resolve_load_args(label, domain, session)?;
+            unload(label.clone(), plist_path, domain, session, *handle)
+                .map_err(|e| e.to_string())?;
+            print_ok(*json)
+        }
+        CliCommand::Enable(label) => {
+            enable(label.clone(), domain_or_status(label, domain), *handle)
+                .map_err(|e| e.to_string())?;
+            print_ok(*json)
+        }
+        CliCommand::Disable(label) => {
+            disable(label.clone(), domain_or_status(label, domain), *handle)
+                .map_err(|e| e.to_string())?;
+            print_ok(*json)
+        }
+        CliCommand::DumpState => {
+            let (size, shmem) = dumpstate().map_err(|e| e.to_string())?;
+            print_bytes(
+                "dumpstate",
+                unsafe { &*slice_from_raw_parts(shmem.region as *mut u8, size) },
+                *json,
+            )
+        }
+        CliCommand::DumpJetsamPropertiesCategory => {
+            let bytes = dumpjpcategory().map_err(|e| e.to_string())?;
+            print_bytes("dumpjpcategory", &bytes, *json)
+        }
+        CliCommand::Print(target) => {
+            let (domain_type, handle) = resolve_domain_target(target)?;
+            let (size, shmem) = print_domain(domain_type, handle).map_err(|e| e.to_string())?;
+            print_bytes(
+                "print",
+                unsafe { &*slice_from_raw_parts(shmem.region as *mut u8, size) },
+                *json,
+            )
+        }
+        CliCommand::ProcInfo(pid) if *json => {
+            let info = procinfo_parsed(*pid).map_err(|e| e.to_string())?;
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&info).map_err(|e| e.to_string())?
+            );
+            Ok(())
+        }
+        CliCommand::ProcInfo(pid) => {
+            let (size, shmem) = procinfo(*pid).map_err(|e| e.to_string())?;
+            print_bytes(
+                "procinfo",
+                unsafe { &*slice_from_raw_parts(shmem.region as *mut u8, size) },
+                *json,
+            )
+        }
+    }
+}
+
+/// A script line in omnibox syntax with the label first, e.g.
+/// `load com.example.agent gui/501`. None for blank lines and comments.
+fn parse_script_line(line: &str) -> Result<Option<CliArgs>, String> {
+    let line = line.trim();
+
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+
+    let mut words: Vec<&str> = line.split_whitespace().collect();
+
+    // A trailing domain target, as the omnibox takes
+    if let [name @ ("load" | "unload" | "enable" | "disable"), _, target] = words.as_slice() {
+        words = vec![name, words[1], "--target", target];
+    }
+
+    let args = parse(&words)?;
+
+    match args.command {
+        CliCommand::Load(_)
+        | CliCommand::Unload(_)
+        | CliCommand::Enable(_)
+        | CliCommand::Disable(_) => Ok(Some(args)),
+        _ => Err(format!("{} is not allowed in a script", words[0])),
+    }
+}
+
+/// A failed script line
+#[derive(Debug, Clone, Serialize)]
+struct ScriptError {
+    line: usize,
+    command: String,
+    error: String,
+}
+
+/// Run every line even if some fail, then report what failed
+fn run_script(path: &str, json: bool) -> Result<(), String> {
+    let script = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    let mut succeeded = 0;
+    let mut errors: Vec<ScriptError> = vec![];
+
+    for (number, line) in script.lines().enumerate() {
+        let result = parse_script_line(line).and_then(|args| match args {
+            Some(args) => execute_command(&args).map(|_| true),
+            None => Ok(false),
+        });
+
+        match result {
+            Ok(true) => succeeded += 1,
+            Ok(false) => (),
+            Err(error) => errors.push(ScriptError {
+                line: number + 1,
+                command: line.trim().to_string(),
+                error,
+            }),
+        }
+    }
+
+    if json {
+        println!(
+            "{}",
+            serde_json::json!({ "ok": succeeded, "failed": errors })
+        );
+    } else {
+        for e in errors.iter() {
+            eprintln!("{}:{}: {}: {}", path, e.line, e.command, e.error);
+        }
+
+        println!("{} ok, {} failed", succeeded, errors.len());
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} of {} commands failed",
+            errors.len(),
+            succeeded + errors.len()
+        ))
+    }
+}
+
+/// Enable and disable what differs from the file, reporting each change
+fn apply_overrides(path: &str, json: bool) -> Result<(), String> {
+    let (changes, blocked) =
+        Overrides::read(path)?.checked_changes_from(&Overrides::capture(), &Preflight::current());
+
+    // No typed confirmation here, so protected jobs stay enabled
+    let results: Vec<(String, Result<(), String>)> = changes
+        .iter()
+        .map(|c| match c.disables_protected() {
+            true => (
+                c.to_string(),
+                Err(format!(
+                    "{}: protected system job, disable it from launchk",
+                    c
+                )),
+            ),
+            false => (c.to_string(), c.apply()),
+        })
+        .chain(blocked.into_iter().map(|b| (b.clone(), Err(b))))
+        .collect();
+    let failed = results.iter().filter(|(_, r)| r.is_err()).count();
+
+    if json {
+        let applied: Vec<serde_json::Value> = results
+            .iter()
+            .map(|(change, result)| {
+                serde_json::json!({ "change": change, "error": result.as_ref().err() })
+            })
+            .collect();
+        println!("{}", serde_json::json!({ "changes": applied }));
+    } else {
+        for (change, result) in results.iter() {
+            match result {
+                Ok(()) => println!("{}", change),
+                Err(e) => eprintln!("{}", e),
+            }
+        }
+
+        println!("{} changed, {} failed", results.len() - failed, failed);
+    }
+
+    if failed == 0 {
+        Ok(())
+    } else {
+        Err(format!("{} of {} changes failed", failed, results.len()))
+    }
+}
+
+/// Errors and warnings, failing if there are errors (with --json the
+/// errors are in the output instead)
+fn print_lint(path: &str, json: bool) -> Result<(), String> {
+    let result = lint_file(path)?;
+
+    if json {
+        println!(
+            "{}",
+            serde_json::json!({
+                "path": path,
+                "errors": result.errors,
+                "warnings": result.warnings,
+            })
+        );
+
+        return Ok(());
+    }
+
+    for line in result.lines() {
+        println!("{}", line);
+    }
+
+    match result.errors.len() {
+        0 => Ok(()),
+        n => Err(format!("{} has {} error(s)", path, n)),
+    }
+}
+
+/// Find the plist path, and unless provided by flags, the domain and session
+/// type from entry status
+fn resolve_load_args(
+    label: &str,
+    domain: &Option<DomainType>,
+    session: &Option<SessionType>,
+) -> Result<(String, Option<DomainType>, Option<SessionType>), String> {
+    let LaunchdEntryStatus {
+        plist,
+        limit_load_to_session_type,
+        domain: status_domain,
+        ..
+    } = get_entry_status(label);
+    let plist = plist.ok_or_else(|| format!("Cannot find plist for {}", label))?;
+
+    Ok((
+        plist.plist_path,
+        domain.clone().or(known_domain(status_domain)),
+        session
+            .clone()
+            .or(known_session(limit_load_to_session_type)),
+    ))
+}
+
+fn known_domain(domain: DomainType) -> Option<DomainType> {
+    Some(domain).filter(|d| *d != DomainType::Unknown)
+}
+
+fn known_session(session: SessionType) -> Option<SessionType> {
+    Some(session).filter(|s| *s != SessionType::Unknown)
+}
+
+/// Use the provided domain, or try to find it in entry status, falling back
+/// on the requestor's domain
+fn domain_or_status(label: &str, domain: &Option<DomainType>) -> DomainType {
+    domain
+        .clone()
+        .or_else(|| known_domain(get_entry_status(label).domain))
+        .unwrap_or(DomainType::RequestorDomain)
+}
+
+fn print_list(filter: Option<&str>, json: bool) -> Result<(), String> {
+    let running = list_all();
+
+    // A short list otherwise looks like jobs are missing
+    for (domain, e) in list_errors() {
+        eprintln!("launchk: cannot list {} domain: {}", domain, e);
+    }
+    let plists = LABEL_TO_ENTRY_CONFIG
+        .read()
+        .map_err(|_| "Cannot read plists".to_string())?;
+
+    let mut labels: Vec<&String> = plists
+        .keys()
+        .chain(running.iter().filter(|r| !plists.contains_key(*r)))
+        .filter(|l| {
+            filter
+                .map(|f| l.to_ascii_lowercase().contains(&f.to_ascii_lowercase()))
+                .unwrap_or(true)
+        })
+        .collect();
+    labels.sort();
+
+    let entries: Vec<ListEntry> = labels
+        .into_iter()
+        .map(|label| {
+            let status = get_entry_status(label);
+
+            ListEntry {
+                label: label.clone(),
+                pid: status.pid,
+                loaded: running.contains(label),
+                domain: status.domain.to_string(),
+                session: status.limit_load_to_session_type.to_string(),
+                job_type: status
+                    .plist
+                    .as_ref()
+                    .map(|p| format!("{}/{}", p.entry_location, p.entry_type)),
+                plist_path: status.plist.map(|p| p.plist_path),
+            }
+        })
+        .collect();
+
+    if json {
+        let out = serde_json::to_string_pretty(&entries).map_err(|e| e.to_string())?;
+        println!("{}", out);
+        return Ok(());
+    }
+
+    let rows: Vec<Vec<String>> = entries
+        .into_iter()
+        .map(|entry| {
+            let pid = if entry.pid > 0 {
+                entry.pid.to_string()
+            } else {
+                "-".to_string()
+            };
+
+            vec![
+                pid,
+                if entry.loaded { "yes" } else { "no" }.to_string(),
+                entry.session,
+                entry.job_type.unwrap_or("-".to_string()),
+                entry.label,
+            ]
+        })
+        .collect();
+
+    // e.g. piped into head
+    match write_columns(&["PID", "Loaded", "Session", "Job Type", "Label"], &rows) {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => result.map_err(|e| e.to_string()),
+    }
+}
+
+/// Space padded columns, the last one left as-is so labels are never cut
+fn write_columns(header: &[&str], rows: &[Vec<String>]) -> io::Result<()> {
+    let mut widths: Vec<usize> = header.iter().map(|h| h.chars().count()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let header: Vec<String> = header.iter().map(|h| h.to_string()).collect();
+
+    for row in std::iter::once(header.as_slice()).chain(rows.iter().map(Vec::as_slice)) {
+        let (last, padded) = row.split_last().expect("Must have columns");
+
+        for (cell, width) in padded.iter().zip(widths.iter()) {
+            write!(out, "{:<width$}  ", cell, width = width)?;
+        }
+
+        writeln!(out, "{}", last)?;
+    }
+
+    Ok(())
+}
+
+fn print_ok(json: bool) -> Result<(), String> {
+    if json {
+        println!("{}", serde_json::json!({ "ok": true }));
+    }
+
+    Ok(())
+}
+
+/// Write shmem contents as-is, or as a JSON string keyed by command
+fn print_bytes(key: &str, buf: &[u8], json: bool) -> Result<(), String> {
+    if json {
+        let mut out = serde_json::Map::new();
+        out.insert(
+            key.to_string(),
+            String::from_utf8_lossy(buf).to_string().into(),
+        );
+        println!("{}", serde_json::Value::Object(out));
+        return Ok(());
+    }
+
+    io::stdout().write_all(buf).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_script_line, CliCommand};
+    use xpc_sys::enums::DomainType;
+
+    #[test]
+    fn script_lines() {
+        assert_eq!(parse_script_line("  # comment").unwrap(), None);
+        assert_eq!(parse_script_line("").unwrap(), None);
+
+        let load = parse_script_line("load com.example.agent gui/501")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            load.command,
+            CliCommand::Load("com.example.agent".to_string())
+        );
+        assert_eq!(load.domain, Some(DomainType::Gui));
+        assert_eq!(load.handle, Some(501));
+
+        let disable = parse_script_line("disable com.example.agent")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            disable.command,
+            CliCommand::Disable("com.example.agent".to_string())
+        );
+
+        let enable = parse_script_line("enable gui/502/com.example.agent")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            enable.command,
+            CliCommand::Enable("com.example.agent".to_string())
+        );
+        assert_eq!(enable.domain, Some(DomainType::Gui));
+        assert_eq!(enable.handle, Some(502));
+
+        assert!(parse_script_line("dumpstate").is_err());
+        assert!(parse_script_line("load").is_err());
+    }
+}
@@ -0,0 +1,240 @@
+/// Columns the service list can show
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum ServiceColumn {
+    Label,
+    Domain,
+    Session,
+    JobType,
+    Pid,
+    Cpu,
+    Memory,
+    Status,
+    Enabled,
+    ExitCode,
+    Restarts,
+    Path,
+}
+
+pub static ALL_COLUMNS: [ServiceColumn; 12] = [
+    ServiceColumn::Label,
+    ServiceColumn::Domain,
+    ServiceColumn::Session,
+    ServiceColumn::JobType,
+    ServiceColumn::Pid,
+    ServiceColumn::Cpu,
+    ServiceColumn::Memory,
+    ServiceColumn::Status,
+    ServiceColumn::Enabled,
+    ServiceColumn::ExitCode,
+    ServiceColumn::Restarts,
+    ServiceColumn::Path,
+];
+
+/// A column with its width, None to share remaining space
+pub type ColumnLayout = (ServiceColumn, Option<usize>);
+
+impl ServiceColumn {
+    /// Name in config and the picker
+    pub fn key(&self) -> &'static str {
+        match self {
+            ServiceColumn::Label => "label",
+            ServiceColumn::Domain => "domain",
+            ServiceColumn::Session => "session",
+            ServiceColumn::JobType => "job_type",
+            ServiceColumn::Pid => "pid",
+            ServiceColumn::Cpu => "cpu",
+            ServiceColumn::Memory => "memory",
+            ServiceColumn::Status => "status",
+            ServiceColumn::Enabled => "enabled",
+            ServiceColumn::ExitCode => "exit_code",
+            ServiceColumn::Restarts => "restarts",
+            ServiceColumn::Path => "path",
+        }
+    }
+
+    pub fn header(&self) -> &'static str {
+        match self {
+            ServiceColumn::Label => "Name",
+            ServiceColumn::Domain => "Domain",
+            ServiceColumn::Session => "Session",
+            ServiceColumn::JobType => "Job Type",
+            ServiceColumn::Pid => "PID",
+            ServiceColumn::Cpu => "CPU%",
+            ServiceColumn::Memory => "Memory",
+            ServiceColumn::Status => "Status",
+            ServiceColumn::Enabled => "Enabled",
+            ServiceColumn::ExitCode => "Exit",
+            ServiceColumn::Restarts => "Restarts",
+            ServiceColumn::Path => "Path",
+        }
+    }
+
+    pub fn default_width(&self) -> Option<usize> {
+        match self {
+            ServiceColumn::Label | ServiceColumn::Path => None,
+            ServiceColumn::Domain => Some(12),
+            ServiceColumn::Session => Some(12),
+            ServiceColumn::JobType => Some(14),
+            ServiceColumn::Pid => Some(6),
+            ServiceColumn::Cpu => Some(7),
+            ServiceColumn::Memory => Some(9),
+            ServiceColumn::Status => Some(9),
+            ServiceColumn::Enabled => Some(8),
+            ServiceColumn::ExitCode => Some(5),
+            // Count and an 8 minute sparkline
+            ServiceColumn::Restarts => Some(13),
+        }
+    }
+
+    fn from_key(key: &str) -> Option<ServiceColumn> {
+        ALL_COLUMNS.iter().copied().find(|c| c.key() == key)
+    }
+}
+
+fn parse_column(key: &str) -> Result<ServiceColumn, String> {
+    ServiceColumn::from_key(key).ok_or_else(|| {
+        format!(
+            "Unknown column {}, expected one of {}",
+            key,
+            ALL_COLUMNS
+                .iter()
+                .map(ServiceColumn::key)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    })
+}
+
+/// The layout before columns were configurable
+pub fn default_columns() -> Vec<ColumnLayout> {
+    [
+        ServiceColumn::Label,
+        ServiceColumn::Session,
+        ServiceColumn::JobType,
+        ServiceColumn::Pid,
+        ServiceColumn::Cpu,
+        ServiceColumn::Memory,
+        ServiceColumn::Status,
+        ServiceColumn::Enabled,
+        ServiceColumn::Path,
+    ]
+    .iter()
+    .map(|c| (*c, c.default_width()))
+    .collect()
+}
+
+/// In order, each "name", "name:width" or "name:*" to share remaining
+/// space. Empty for the default layout.
+pub fn parse_columns(spec: &[String]) -> Result<Vec<ColumnLayout>, String> {
+    if spec.is_empty() {
+        return Ok(default_columns());
+    }
+
+    let mut columns: Vec<ColumnLayout> = vec![];
+
+    for entry in spec {
+        let (key, width) = match entry.split_once(':') {
+            Some((key, width)) => (key, Some(width)),
+            None => (entry.as_str(), None),
+        };
+
+        let column = parse_column(key)?;
+
+        let width = match width {
+            None => column.default_width(),
+            Some("*") => None,
+            Some(w) => match w.parse::<usize>() {
+                Ok(w) if w > 1 => Some(w),
+                _ => return Err(format!("Invalid width for column {}", key)),
+            },
+        };
+
+        if columns.iter().any(|(c, _)| *c == column) {
+            return Err(format!("Column {} listed twice", key));
+        }
+
+        columns.push((column, width));
+    }
+
+    if !columns.iter().any(|(c, _)| *c == ServiceColumn::Label) {
+        return Err("columns must include label".to_string());
+    }
+
+    Ok(columns)
+}
+
+/// "name" or "name:desc" to (column, descending?)
+pub fn parse_sort(spec: &str) -> Result<(ServiceColumn, bool), String> {
+    let (key, descending) = match spec.split_once(':') {
+        Some((key, "desc")) => (key, true),
+        Some((key, "asc")) => (key, false),
+        Some(_) => return Err(format!("Invalid sort {}, expected name[:asc|:desc]", spec)),
+        None => (spec, false),
+    };
+
+    Ok((parse_column(key)?, descending))
+}
+
+/// parse_columns spec for columns, empty for the default layout
+pub fn columns_spec(columns: &[ColumnLayout]) -> Vec<String> {
+    if columns == default_columns().as_slice() {
+        return vec![];
+    }
+
+    columns
+        .iter()
+        .map(|(column, width)| match width {
+            w if *w == column.default_width() => column.key().to_string(),
+            Some(w) => format!("{}:{}", column.key(), w),
+            None => format!("{}:*", column.key()),
+        })
+        .collect()
+}
+
+/// parse_sort spec, e.g. "pid:desc"
+pub fn sort_spec((column, descending): (ServiceColumn, bool)) -> String {
+    match descending {
+        true => format!("{}:desc", column.key()),
+        false => column.key().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        columns_spec, default_columns, parse_columns, parse_sort, sort_spec, ServiceColumn,
+    };
+
+    #[test]
+    fn parse_column_layout() {
+        assert_eq!(parse_columns(&[]), Ok(default_columns()));
+
+        let spec: Vec<String> = ["label", "pid:8", "exit_code", "domain:*"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        let columns = parse_columns(&spec).unwrap();
+        assert_eq!(
+            columns,
+            vec![
+                (ServiceColumn::Label, None),
+                (ServiceColumn::Pid, Some(8)),
+                (ServiceColumn::ExitCode, Some(5)),
+                (ServiceColumn::Domain, None),
+            ]
+        );
+
+        assert!(parse_columns(&["pid".to_string()]).is_err());
+        assert!(parse_columns(&["label".to_string(), "uptime".to_string()]).is_err());
+        assert!(parse_columns(&["label".to_string(), "label".to_string()]).is_err());
+
+        assert_eq!(parse_sort("pid"), Ok((ServiceColumn::Pid, false)));
+        assert_eq!(parse_sort("cpu:desc"), Ok((ServiceColumn::Cpu, true)));
+        assert!(parse_sort("pid:up").is_err());
+
+        assert_eq!(columns_spec(&default_columns()), Vec::<String>::new());
+        assert_eq!(parse_columns(&columns_spec(&columns)), Ok(columns));
+        assert_eq!(sort_spec((ServiceColumn::Cpu, true)), "cpu:desc");
+    }
+}
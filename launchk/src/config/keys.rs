@@ -0,0 +1,175 @@
+use cursive::event::{Event, Key};
+
+use crate::config::Config;
+
+/// Keys the omnibox or service list already use, cannot be remapped.
+/// 1-9 switch [[profiles]].
+const RESERVED_CHARS: &str = "/:sguadlexnqbworh[ABCD 123456789";
+
+/// Ctrl combos the omnibox uses: clear, history search, quit
+const RESERVED_CTRL: &str = "urc";
+
+/// Built-in (command, key), config [bindings] replaces a command's keys
+pub static DEFAULT_BINDINGS: [(&str, &str); 13] = [
+    ("kickstart", "k"),
+    ("start", "S"),
+    ("stop", "X"),
+    ("info", "i"),
+    ("log", "L"),
+    ("view", "v"),
+    ("copylabel", "y"),
+    ("copypath", "Y"),
+    ("copyprocinfo", "P"),
+    ("reload", "R"),
+    ("quickload", "O"),
+    ("target", "T"),
+    ("help", "?"),
+];
+
+fn named_key(name: &str) -> Option<Key> {
+    let key = match name.to_ascii_lowercase().as_str() {
+        "ins" | "insert" => Key::Ins,
+        "del" | "delete" => Key::Del,
+        f => {
+            let n: u8 = f.strip_prefix('f')?.parse().ok()?;
+            if !(1..=12).contains(&n) {
+                return None;
+            }
+            Key::from_f(n)
+        }
+    };
+
+    Some(key)
+}
+
+/// A character (any script), F1-F12, Ins or Del, optionally prefixed
+/// with ctrl-, alt- or shift-, e.g. "K", "é", "F5", "ctrl-k", "alt-F2"
+pub fn parse_key(binding: &str) -> Result<Event, String> {
+    let invalid = || format!("Cannot bind key {:?}", binding);
+
+    let (mods, key) = match binding.rfind(['-', '+']) {
+        Some(i) if i > 0 && i + 1 < binding.len() => (&binding[..i], &binding[i + 1..]),
+        _ => ("", binding),
+    };
+
+    let mut ctrl = false;
+    let mut alt = false;
+    let mut shift = false;
+
+    for m in mods.split(['-', '+']).filter(|m| !m.is_empty()) {
+        match m.to_ascii_lowercase().as_str() {
+            "ctrl" => ctrl = true,
+            "alt" | "opt" => alt = true,
+            "shift" => shift = true,
+            _ => return Err(invalid()),
+        }
+    }
+
+    let mut chars = key.chars();
+    let event = match (chars.next(), chars.next(), ctrl, alt, shift) {
+        (Some(c), None, false, false, false) => Event::Char(c),
+        (Some(c), None, true, false, false) if c.is_ascii_alphabetic() => {
+            Event::CtrlChar(c.to_ascii_lowercase())
+        }
+        (Some(c), None, false, true, false) => Event::AltChar(c),
+        (Some(_), None, _, _, _) => return Err(invalid()),
+        _ => {
+            let named = named_key(key).ok_or_else(invalid)?;
+
+            match (ctrl, alt, shift) {
+                (false, false, false) => Event::Key(named),
+                (true, false, false) => Event::Ctrl(named),
+                (false, true, false) => Event::Alt(named),
+                (false, false, true) => Event::Shift(named),
+                (true, true, false) => Event::CtrlAlt(named),
+                (true, false, true) => Event::CtrlShift(named),
+                (false, true, true) => Event::AltShift(named),
+                (true, true, true) => return Err(invalid()),
+            }
+        }
+    };
+
+    if is_reserved(&event) {
+        return Err(invalid());
+    }
+
+    Ok(event)
+}
+
+fn is_reserved(event: &Event) -> bool {
+    match event {
+        Event::Char(c) => RESERVED_CHARS.contains(*c),
+        Event::CtrlChar(c) => RESERVED_CTRL.contains(*c),
+        _ => false,
+    }
+}
+
+/// (key, command name) in effect: defaults for commands [bindings] doesn't
+/// mention and keys nothing in config took, then config's own
+pub fn active_bindings(config: &Config) -> Vec<(Event, String)> {
+    let configured: Vec<(Event, String)> = config
+        .key_bindings()
+        .into_iter()
+        .filter_map(|(key, name)| Some((parse_key(key).ok()?, name.to_string())))
+        .collect();
+
+    let mut bindings: Vec<(Event, String)> = DEFAULT_BINDINGS
+        .iter()
+        .filter(|(name, _)| !config.bindings.contains_key(*name))
+        .filter_map(|(name, key)| Some((parse_key(key).ok()?, name.to_string())))
+        .filter(|(event, _)| !configured.iter().any(|(e, _)| e == event))
+        .collect();
+
+    bindings.extend(configured);
+    bindings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{active_bindings, parse_key};
+    use crate::config::Config;
+    use cursive::event::{Event, Key};
+
+    #[test]
+    fn parse_keys() {
+        assert_eq!(parse_key("K"), Ok(Event::Char('K')));
+        assert_eq!(parse_key("ö"), Ok(Event::Char('ö')));
+        assert_eq!(parse_key("-"), Ok(Event::Char('-')));
+        assert_eq!(parse_key("F5"), Ok(Event::Key(Key::F5)));
+        assert_eq!(parse_key("ctrl-K"), Ok(Event::CtrlChar('k')));
+        assert_eq!(parse_key("alt+x"), Ok(Event::AltChar('x')));
+        assert_eq!(parse_key("ctrl-shift-F12"), Ok(Event::CtrlShift(Key::F12)));
+        assert_eq!(parse_key("alt-Del"), Ok(Event::Alt(Key::Del)));
+        assert!(parse_key("s").is_err());
+        assert!(parse_key("ctrl-u").is_err());
+        assert!(parse_key("F13").is_err());
+        assert!(parse_key("hyper-k").is_err());
+    }
+
+    #[test]
+    fn config_replaces_defaults() {
+        let config = Config::parse(
+            r##"
+            [keys]
+            k = "kill"
+
+            [bindings]
+            info = ["I", "F2"]
+            "##,
+        )
+        .unwrap();
+
+        let bindings = active_bindings(&config);
+        let bound = |event: Event| {
+            bindings
+                .iter()
+                .find(|(e, _)| *e == event)
+                .map(|(_, n)| n.as_str())
+        };
+
+        assert_eq!(bound(Event::Char('k')), Some("kill"));
+        assert_eq!(bound(Event::Char('i')), None);
+        assert_eq!(bound(Event::Key(Key::F2)), Some("info"));
+        assert_eq!(bound(Event::Char('S')), Some("start"));
+    }
+}
@@ -0,0 +1,392 @@
+pub mod columns;
+pub mod keys;
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::RwLock;
+use std::time::Duration;
+
+use cursive::event::Event;
+use serde::Deserialize;
+use toml::Value;
+
+use crate::launchd::job_type_filter::{JobTypeFilter, JOB_TYPE_FILTER_KEYS};
+
+use self::columns::{parse_columns, parse_sort};
+use self::keys::{active_bindings, parse_key};
+
+lazy_static! {
+    static ref CONFIG: RwLock<Config> = RwLock::new(Config::default());
+    /// active_bindings(&CONFIG), parsed once per reload instead of per key
+    static ref BINDINGS: RwLock<Vec<(Event, String)>> =
+        RwLock::new(active_bindings(&Config::default()));
+}
+
+/// ~/.config/launchk/config.toml
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Initial [sguadlexnqbworh] filter, e.g. "ul"
+    pub job_type_filter: Option<String>,
+    /// Overridden by LAUNCHK_POLL_INTERVAL_MS
+    pub refresh_interval_ms: Option<u64>,
+    /// Extra tries for a query failing with EAGAIN, EINTR or EPIPE,
+    /// default 2, 0 to turn off
+    pub retry_attempts: Option<u32>,
+    /// Wait before the first retry, doubled each time, default 50
+    pub retry_backoff_ms: Option<u64>,
+    /// Overrides $EDITOR, may include arguments e.g. "code --wait"
+    pub editor: Option<String>,
+    /// dark (default), light or solarized
+    pub color_scheme: Option<String>,
+    /// Merged over the color scheme, same format as tui/style.toml
+    pub theme: Option<Value>,
+    /// Hotkey to omnibox command name, e.g. K = "kill"
+    pub keys: HashMap<String, String>,
+    /// Omnibox command name to its keys, replacing the defaults, e.g.
+    /// info = ["I", "F2"]
+    pub bindings: HashMap<String, KeyList>,
+    /// Extra labels needing typed confirmation to unload, disable or
+    /// kill, exact or a prefix ending in *, e.g. "com.example.*"
+    pub protected: Vec<String>,
+    /// Service list columns in order, e.g. ["label", "pid:8", "path:*"]
+    pub columns: Vec<String>,
+    /// Plists are copied here before edit or disable, defaults to
+    /// ~/.config/launchk/backups
+    pub backup_dir: Option<String>,
+    /// Keep restart and exit history in stats.json next to this file
+    pub persist_stats: bool,
+    /// Save filters, sort, columns and the selected job on exit and
+    /// restore them next time, default true. --no-state for one run
+    pub restore_state: Option<bool>,
+    /// [[profiles]] switched with 1-9 in order, or :profile <name>
+    pub profiles: Vec<ViewProfile>,
+}
+
+/// Saved filter, sort and columns, e.g. "my-agents"
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ViewProfile {
+    pub name: String,
+    /// As typed after /, e.g. "apple status:running"
+    pub filter: String,
+    /// Same letters as the top level job_type_filter
+    pub job_type_filter: Option<String>,
+    /// "pid" or "pid:desc", none for the default order
+    pub sort: Option<String>,
+    /// Keeps the current columns when empty
+    pub columns: Vec<String>,
+}
+
+impl ViewProfile {
+    pub fn job_type_filter(&self) -> JobTypeFilter {
+        self.job_type_filter
+            .as_deref()
+            .and_then(|f| parse_job_type_filter(f).ok())
+            .unwrap_or_else(JobTypeFilter::launchk_default)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if let Some(filter) = &self.job_type_filter {
+            parse_job_type_filter(filter)?;
+        }
+
+        if let Some(sort) = &self.sort {
+            parse_sort(sort)?;
+        }
+
+        if !self.columns.is_empty() {
+            parse_columns(&self.columns)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// One key or several, e.g. "ctrl-k" or ["K", "F9"]
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum KeyList {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeyList {
+    pub fn keys(&self) -> Vec<&str> {
+        match self {
+            KeyList::One(k) => vec![k.as_str()],
+            KeyList::Many(keys) => keys.iter().map(String::as_str).collect(),
+        }
+    }
+}
+
+impl Config {
+    pub fn parse(text: &str) -> Result<Config, String> {
+        let config: Config = toml::from_str(text).map_err(|e| e.to_string())?;
+
+        if let Some(filter) = &config.job_type_filter {
+            parse_job_type_filter(filter)?;
+        }
+
+        if config.refresh_interval_ms == Some(0) {
+            return Err("refresh_interval_ms must be > 0".to_string());
+        }
+
+        let mut bound = HashMap::new();
+
+        for (key, name) in config.key_bindings() {
+            match bound.insert(parse_key(key)?, name) {
+                Some(other) if other != name => {
+                    return Err(format!("{} is bound to both {} and {}", key, other, name))
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(p) = config
+            .protected
+            .iter()
+            .find(|p| p.is_empty() || p.trim_end_matches('*').contains('*'))
+        {
+            return Err(format!("Invalid protected pattern {:?}", p));
+        }
+
+        for (i, profile) in config.profiles.iter().enumerate() {
+            if profile.name.is_empty() {
+                return Err(format!("Profile {} has no name", i + 1));
+            }
+
+            if config.profiles[..i].iter().any(|p| p.name == profile.name) {
+                return Err(format!("Profile {} listed twice", profile.name));
+            }
+
+            profile
+                .validate()
+                .map_err(|e| format!("Profile {}: {}", profile.name, e))?;
+        }
+
+        Ok(config)
+    }
+
+    pub fn job_type_filter(&self) -> JobTypeFilter {
+        self.job_type_filter
+            .as_deref()
+            .and_then(|f| parse_job_type_filter(f).ok())
+            .unwrap_or_else(JobTypeFilter::launchk_default)
+    }
+
+    pub fn restore_state(&self) -> bool {
+        self.restore_state.unwrap_or(true)
+    }
+
+    pub fn profile(&self, name: &str) -> Option<&ViewProfile> {
+        self.profiles.iter().find(|p| p.name == name)
+    }
+
+    /// (key, command name) from [bindings] and [keys]
+    pub fn key_bindings(&self) -> Vec<(&str, &str)> {
+        let mut bindings: Vec<(&str, &str)> = self
+            .bindings
+            .iter()
+            .flat_map(|(name, keys)| keys.keys().into_iter().map(move |k| (k, name.as_str())))
+            .collect();
+
+        bindings.extend(
+            self.keys
+                .iter()
+                .map(|(k, name)| (k.as_str(), name.as_str())),
+        );
+        bindings.sort();
+        bindings
+    }
+
+    /// Built-in theme with the [theme] table merged over it
+    pub fn theme_toml(&self, base: &str) -> String {
+        let mut theme: Value = base.parse().expect("Must parse built-in theme");
+
+        if let Some(overrides) = &self.theme {
+            merge(&mut theme, overrides);
+        }
+
+        theme.to_string()
+    }
+}
+
+fn merge(base: &mut Value, overrides: &Value) {
+    match (base, overrides) {
+        (Value::Table(base), Value::Table(overrides)) => {
+            for (k, v) in overrides {
+                match base.get_mut(k) {
+                    Some(existing) => merge(existing, v),
+                    None => {
+                        base.insert(k.clone(), v.clone());
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides.clone(),
+    }
+}
+
+/// Same letters as the omnibox job type filter
+pub fn parse_job_type_filter(filter: &str) -> Result<JobTypeFilter, String> {
+    let mut jtf = JobTypeFilter::default();
+
+    for c in filter.chars() {
+        let (mask, _, _) = JOB_TYPE_FILTER_KEYS
+            .iter()
+            .find(|(_, key, _)| *key == c)
+            .ok_or_else(|| format!("Invalid job_type_filter {}", filter))?;
+
+        jtf.insert(*mask);
+    }
+
+    Ok(jtf)
+}
+
+const POLL_INTERVAL_ENV: &str = "LAUNCHK_POLL_INTERVAL_MS";
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Poll interval from LAUNCHK_POLL_INTERVAL_MS, then config
+pub fn poll_interval() -> Duration {
+    env::var(POLL_INTERVAL_ENV)
+        .ok()
+        .and_then(|ms| ms.parse().ok())
+        .filter(|ms| *ms > 0)
+        .or(config().refresh_interval_ms)
+        .map(Duration::from_millis)
+        .unwrap_or(DEFAULT_POLL_INTERVAL)
+}
+
+pub fn config_path() -> PathBuf {
+    env::var("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| {
+            PathBuf::from(env::var("HOME").expect("Must read $HOME")).join(".config")
+        })
+        .join("launchk")
+        .join("config.toml")
+}
+
+/// $XDG_STATE_HOME/launchk, or ~/Library/Application Support/launchk
+pub fn state_dir() -> PathBuf {
+    env::var("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| {
+            PathBuf::from(env::var("HOME").expect("Must read $HOME"))
+                .join("Library")
+                .join("Application Support")
+        })
+        .join("launchk")
+}
+
+/// Current config
+pub fn config() -> Config {
+    CONFIG.read().expect("Must read config").clone()
+}
+
+/// Read config from disk, a missing file means defaults. Keeps
+/// the previous config on error.
+pub fn reload() -> Result<(), String> {
+    let path = config_path();
+
+    let config = match fs::read_to_string(&path) {
+        Ok(text) => Config::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))?,
+        Err(e) if e.kind() == ErrorKind::NotFound => Config::default(),
+        Err(e) => return Err(format!("{}: {}", path.display(), e)),
+    };
+
+    *BINDINGS.write().expect("Must update bindings") = active_bindings(&config);
+    *CONFIG.write().expect("Must update config") = config;
+    Ok(())
+}
+
+/// (key, command name) in effect, see keys::active_bindings
+pub fn bindings() -> Vec<(Event, String)> {
+    BINDINGS.read().expect("Must read bindings").clone()
+}
+
+/// Command name bound to a key
+pub fn bound_command(event: &Event) -> Option<String> {
+    BINDINGS
+        .read()
+        .expect("Must read bindings")
+        .iter()
+        .find(|(e, _)| e == event)
+        .map(|(_, name)| name.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Config;
+    use crate::launchd::job_type_filter::JobTypeFilter;
+
+    #[test]
+    fn parse_config() {
+        let config = Config::parse(
+            r##"
+            job_type_filter = "ua"
+            refresh_interval_ms = 500
+
+            [keys]
+            K = "kill"
+
+            [bindings]
+            info = "F2"
+            stop = ["ctrl-x", "é"]
+
+            [theme.colors]
+            background = "#000000"
+
+            [[profiles]]
+            name = "my-agents"
+            filter = "com.example"
+            job_type_filter = "ua"
+            sort = "pid:desc"
+            "##,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.job_type_filter(),
+            JobTypeFilter::USER | JobTypeFilter::AGENT
+        );
+        assert_eq!(
+            config.key_bindings(),
+            vec![
+                ("F2", "info"),
+                ("K", "kill"),
+                ("ctrl-x", "stop"),
+                ("é", "stop")
+            ]
+        );
+
+        let profile = config.profile("my-agents").unwrap();
+        assert_eq!(profile.filter, "com.example");
+        assert_eq!(
+            profile.job_type_filter(),
+            JobTypeFilter::USER | JobTypeFilter::AGENT
+        );
+
+        let theme = config
+            .theme_toml("shadow = false\n[colors]\nbackground = \"#151515\"\nview = \"#151515\"\n");
+        assert!(theme.contains("background = \"#000000\""));
+        assert!(theme.contains("view = \"#151515\""));
+    }
+
+    #[test]
+    fn reject_bad_config() {
+        assert!(Config::parse("job_type_filter = \"z\"").is_err());
+        assert!(Config::parse("[keys]\ns = \"kill\"").is_err());
+        assert!(Config::parse("[keys]\n1 = \"kill\"").is_err());
+        assert!(Config::parse("[bindings]\nstop = \"9\"").is_err());
+        assert!(Config::parse("[keys]\nK = \"kill\"\n[bindings]\nstop = \"K\"").is_err());
+        assert!(Config::parse("typo = 1").is_err());
+        assert!(Config::parse("protected = [\"com.*.vpn\"]").is_err());
+        assert!(Config::parse("[[profiles]]\nname = \"a\"\n[[profiles]]\nname = \"a\"").is_err());
+        assert!(Config::parse("[[profiles]]\nname = \"a\"\nsort = \"uptime\"").is_err());
+    }
+}
@@ -0,0 +1,579 @@
+use std::fmt;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+use plist::{Dictionary, Value};
+use xpc_sys::code_signing::{signature_kind, signature_summary, SignatureKind, SignatureSummary};
+
+use crate::launchd::plist::{
+    program, LaunchdEntryLocation, LaunchdEntryType, LaunchdPlist, LABEL_TO_ENTRY_CONFIG,
+};
+
+/// Owner and mode of a file
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct FileOwner {
+    pub uid: u32,
+    pub gid: u32,
+    pub mode: u32,
+}
+
+impl FileOwner {
+    fn read(path: &str) -> Option<Self> {
+        let meta = fs::metadata(path).ok()?;
+
+        Some(Self {
+            uid: meta.uid(),
+            gid: meta.gid(),
+            mode: meta.mode(),
+        })
+    }
+
+    /// Owned by someone other than root, or group (not wheel) or world writable
+    fn user_writable(&self) -> bool {
+        self.uid != 0 || self.mode & 0o002 != 0 || (self.mode & 0o020 != 0 && self.gid != 0)
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum AuditReason {
+    /// Runs as root, but a user can rewrite its plist
+    WritablePlist(FileOwner),
+    /// Runs as root, but a user can replace its program
+    WritableProgram(String, FileOwner),
+    Unsigned(String, SignatureKind),
+    /// Label looks like Apple's, but is not shipped in /System
+    AppleLookalike,
+    /// RunAtLoad and KeepAlive, from a dot file or directory
+    HiddenPersistent(String),
+}
+
+impl AuditReason {
+    /// Worth looking at first
+    pub fn is_high(&self) -> bool {
+        matches!(
+            self,
+            AuditReason::WritablePlist(_)
+                | AuditReason::WritableProgram(_, _)
+                | AuditReason::HiddenPersistent(_)
+        )
+    }
+}
+
+impl fmt::Display for AuditReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuditReason::WritablePlist(o) => write!(
+                f,
+                "runs as root, plist writable (uid {}, mode {:o})",
+                o.uid,
+                o.mode & 0o7777
+            ),
+            AuditReason::WritableProgram(p, o) => write!(
+                f,
+                "runs as root, program {} writable (uid {}, mode {:o})",
+                p,
+                o.uid,
+                o.mode & 0o7777
+            ),
+            AuditReason::Unsigned(p, SignatureKind::AdHoc) => {
+                write!(f, "program {} is ad-hoc signed", p)
+            }
+            AuditReason::Unsigned(p, _) => write!(f, "program {} is unsigned", p),
+            AuditReason::AppleLookalike => write!(f, "Apple-like label outside /System"),
+            AuditReason::HiddenPersistent(p) => {
+                write!(f, "RunAtLoad and KeepAlive with hidden program {}", p)
+            }
+        }
+    }
+}
+
+/// A job with at least one reason to look closer
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AuditFinding {
+    pub label: String,
+    pub plist_path: String,
+    pub reasons: Vec<AuditReason>,
+}
+
+impl AuditFinding {
+    pub fn is_high(&self) -> bool {
+        self.reasons.iter().any(AuditReason::is_high)
+    }
+}
+
+/// What audit_job looks at besides the plist, read from disk when auditing
+#[derive(Debug, Clone, Default)]
+pub struct AuditContext {
+    pub plist_owner: Option<FileOwner>,
+    pub program_owner: Option<FileOwner>,
+    pub signature: Option<SignatureKind>,
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
+/// com.apple.* outside /System, or a near miss like com.appIe.*
+pub fn mimics_apple(label: &str, location: &LaunchdEntryLocation) -> bool {
+    let mut parts = label.splitn(3, '.');
+    let (first, second) = match (parts.next(), parts.next()) {
+        (Some(f), Some(s)) => (f.to_lowercase(), s.to_lowercase()),
+        _ => return false,
+    };
+
+    if label.starts_with("com.apple.") {
+        return *location != LaunchdEntryLocation::System;
+    }
+
+    let distance = edit_distance(&first, "com") + edit_distance(&second, "apple");
+    (1..=2).contains(&distance)
+}
+
+fn is_hidden(path: &str) -> bool {
+    Path::new(path).components().any(|c| {
+        c.as_os_str()
+            .to_str()
+            .map(|s| s.starts_with('.') && s != "." && s != "..")
+            .unwrap_or(false)
+    })
+}
+
+/// Daemons run as root unless UserName says otherwise
+fn runs_as_root(plist: &LaunchdPlist, dict: &Dictionary) -> bool {
+    plist.entry_type == LaunchdEntryType::Daemon
+        && dict
+            .get("UserName")
+            .and_then(Value::as_string)
+            .map(|u| u == "root")
+            .unwrap_or(true)
+}
+
+/// Reasons a job looks like suspicious persistence
+pub fn audit_job(
+    label: &str,
+    plist: &LaunchdPlist,
+    dict: &Dictionary,
+    context: &AuditContext,
+) -> Vec<AuditReason> {
+    let mut reasons = vec![];
+    let program = program(dict);
+    let privileged = runs_as_root(plist, dict);
+
+    if let Some(owner) = context
+        .plist_owner
+        .filter(|o| privileged && o.user_writable())
+    {
+        reasons.push(AuditReason::WritablePlist(owner));
+    }
+
+    if let (Some(program), Some(owner)) = (&program, context.program_owner) {
+        if privileged && owner.user_writable() {
+            reasons.push(AuditReason::WritableProgram(program.clone(), owner));
+        }
+    }
+
+    if let (Some(program), Some(kind)) = (&program, context.signature) {
+        if kind != SignatureKind::Signed {
+            reasons.push(AuditReason::Unsigned(program.clone(), kind));
+        }
+    }
+
+    if mimics_apple(label, &plist.entry_location) {
+        reasons.push(AuditReason::AppleLookalike);
+    }
+
+    let flag = |key: &str| match dict.get(key) {
+        Some(Value::Boolean(b)) => *b,
+        Some(Value::Dictionary(_)) => true,
+        _ => false,
+    };
+
+    if let Some(program) = program.filter(|p| is_hidden(p)) {
+        if flag("RunAtLoad") && flag("KeepAlive") {
+            reasons.push(AuditReason::HiddenPersistent(program));
+        }
+    }
+
+    reasons
+}
+
+/// Every job outside /System (SIP protects it) with something to flag,
+/// the most suspicious first
+pub fn audit() -> Vec<AuditFinding> {
+    let plists: Vec<(String, LaunchdPlist)> = LABEL_TO_ENTRY_CONFIG
+        .read()
+        .map(|m| m.iter().map(|(l, p)| (l.clone(), p.clone())).collect())
+        .unwrap_or_default();
+
+    let mut findings: Vec<AuditFinding> = plists
+        .into_iter()
+        .filter(|(_, p)| p.entry_location != LaunchdEntryLocation::System)
+        .filter_map(|(label, plist)| {
+            let value = Value::from_file(&plist.plist_path).ok()?;
+            let dict = value.as_dictionary()?;
+            let program = program(dict);
+
+            let context = AuditContext {
+                plist_owner: FileOwner::read(&plist.plist_path),
+                program_owner: program.as_deref().and_then(FileOwner::read),
+                signature: program.as_deref().and_then(|p| signature_kind(p).ok()),
+            };
+
+            let reasons = audit_job(&label, &plist, dict, &context);
+
+            Some(AuditFinding {
+                label,
+                plist_path: plist.plist_path,
+                reasons,
+            })
+            .filter(|f| !f.reasons.is_empty())
+        })
+        .collect();
+
+    findings.sort_by(|a, b| {
+        b.is_high()
+            .cmp(&a.is_high())
+            .then(b.reasons.len().cmp(&a.reasons.len()))
+            .then(a.label.cmp(&b.label))
+    });
+
+    findings
+}
+
+/// Where Apple's own programs live, /usr/local is not one of them.
+/// Third-party agents use them too (/bin/sh, /usr/bin/open).
+static APPLE_PATHS: [&str; 7] = [
+    "/System/",
+    "/usr/bin/",
+    "/usr/libexec/",
+    "/usr/sbin/",
+    "/bin/",
+    "/sbin/",
+    "/Library/Apple/",
+];
+
+/// Run whatever their arguments say, so their own path says nothing
+static LAUNCHERS: [&str; 12] = [
+    "sh",
+    "bash",
+    "zsh",
+    "dash",
+    "ksh",
+    "csh",
+    "tcsh",
+    "env",
+    "open",
+    "osascript",
+    "ruby",
+    "node",
+];
+
+/// Shell, interpreter or open, by file name (python3.11 etc. included)
+fn is_launcher(program: &str) -> bool {
+    let name = Path::new(program)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    LAUNCHERS.contains(&name.as_str())
+        || ["python", "perl", "php"]
+            .iter()
+            .any(|i| name.starts_with(i))
+}
+
+/// An agent that doesn't look like Apple's
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ThirdPartyAgent {
+    pub label: String,
+    pub plist_path: String,
+    /// e.g. Team ID ABCDE12345
+    pub reason: String,
+}
+
+/// Why an agent looks like it isn't Apple's, None if it does. A program
+/// in APPLE_PATHS, or signed without a Team ID, is only trusted for a
+/// com.apple label, and never for a shell, interpreter or open, which
+/// are judged by what they run (args, ProgramArguments after argv[0]).
+pub fn third_party_reason(
+    label: &str,
+    program: Option<&str>,
+    args: &[String],
+    signature: Option<&SignatureSummary>,
+) -> Option<String> {
+    let apple_label = label.starts_with("com.apple.");
+
+    if let Some(launcher) = program.filter(|p| is_launcher(p)) {
+        return Some(match args.iter().find(|a| !a.starts_with('-')) {
+            Some(target) => format!("{} runs {}", launcher, target),
+            None => format!("runs {}", launcher),
+        });
+    }
+
+    let apple_path = program.is_some_and(|p| APPLE_PATHS.iter().any(|a| p.starts_with(a)));
+
+    match signature {
+        Some(SignatureSummary {
+            team_id: Some(team_id),
+            ..
+        }) => Some(format!("Team ID {}", team_id)),
+        _ if apple_label && apple_path => None,
+        Some(SignatureSummary {
+            kind: SignatureKind::Signed,
+            team_id: None,
+        }) if apple_label => None,
+        Some(SignatureSummary { kind, .. }) if *kind != SignatureKind::Signed => {
+            Some(format!("{} program", kind_name(*kind)))
+        }
+        _ => Some(match program {
+            Some(p) if apple_path => format!("{} under a non-Apple label", p),
+            Some(p) => format!("program {}", p),
+            None => "no program".to_string(),
+        }),
+    }
+}
+
+fn kind_name(kind: SignatureKind) -> &'static str {
+    match kind {
+        SignatureKind::Unsigned => "unsigned",
+        SignatureKind::AdHoc => "ad-hoc signed",
+        SignatureKind::Signed => "signed",
+    }
+}
+
+/// Agents outside /System that don't look like Apple's, by label
+pub fn third_party_agents() -> Vec<ThirdPartyAgent> {
+    let plists: Vec<(String, LaunchdPlist)> = LABEL_TO_ENTRY_CONFIG
+        .read()
+        .map(|m| m.iter().map(|(l, p)| (l.clone(), p.clone())).collect())
+        .unwrap_or_default();
+
+    let mut agents: Vec<ThirdPartyAgent> = plists
+        .into_iter()
+        .filter(|(_, p)| {
+            p.entry_type == LaunchdEntryType::Agent
+                && p.entry_location != LaunchdEntryLocation::System
+        })
+        .filter_map(|(label, plist)| {
+            let value = Value::from_file(&plist.plist_path).ok()?;
+            let dict = value.as_dictionary()?;
+            let program = program(dict);
+            let args: Vec<String> = dict
+                .get("ProgramArguments")
+                .and_then(Value::as_array)
+                .map(|a| {
+                    a.iter()
+                        .skip(1)
+                        .filter_map(Value::as_string)
+                        .map(String::from)
+                        .collect()
+                })
+                .unwrap_or_default();
+            let signature = program.as_deref().and_then(|p| signature_summary(p).ok());
+
+            let reason = third_party_reason(&label, program.as_deref(), &args, signature.as_ref())?;
+
+            Some(ThirdPartyAgent {
+                label,
+                plist_path: plist.plist_path,
+                reason,
+            })
+        })
+        .collect();
+
+    agents.sort_by(|a, b| a.label.cmp(&b.label));
+    agents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        audit_job, mimics_apple, third_party_reason, AuditContext, AuditReason, FileOwner,
+    };
+    use crate::launchd::plist::{LaunchdEntryLocation, LaunchdEntryType, LaunchdPlist};
+    use plist::Dictionary;
+    use xpc_sys::code_signing::{SignatureKind, SignatureSummary};
+
+    #[test]
+    fn third_party_agents() {
+        let signed = |team_id: Option<&str>| SignatureSummary {
+            kind: SignatureKind::Signed,
+            team_id: team_id.map(String::from),
+        };
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+
+        assert_eq!(
+            third_party_reason("com.apple.Siri.agent", Some("/System/Library/x"), &[], None),
+            None
+        );
+        assert_eq!(
+            third_party_reason(
+                "com.apple.SafariBookmarksSyncAgent",
+                Some("/Library/Apple/x"),
+                &[],
+                Some(&signed(None))
+            ),
+            None
+        );
+        assert_eq!(
+            third_party_reason(
+                "com.example.updater",
+                Some("/Applications/Example.app/Contents/MacOS/updater"),
+                &[],
+                Some(&signed(Some("ABCDE12345")))
+            )
+            .as_deref(),
+            Some("Team ID ABCDE12345")
+        );
+        assert_eq!(
+            third_party_reason(
+                "com.apple.updater",
+                Some("/Users/me/.hidden/updater"),
+                &[],
+                Some(&SignatureSummary {
+                    kind: SignatureKind::AdHoc,
+                    team_id: None
+                })
+            )
+            .as_deref(),
+            Some("ad-hoc signed program")
+        );
+        assert_eq!(
+            third_party_reason("com.example.script", Some("/usr/local/bin/job"), &[], None)
+                .as_deref(),
+            Some("program /usr/local/bin/job")
+        );
+
+        // Apple's binaries running someone else's job
+        assert_eq!(
+            third_party_reason(
+                "com.example.backup",
+                Some("/bin/sh"),
+                &args(&["-c", "~/backup.sh"]),
+                Some(&signed(None))
+            )
+            .as_deref(),
+            Some("/bin/sh runs ~/backup.sh")
+        );
+        assert_eq!(
+            third_party_reason(
+                "local.sync",
+                Some("/usr/bin/python3"),
+                &args(&["/Users/me/sync.py"]),
+                Some(&signed(None))
+            )
+            .as_deref(),
+            Some("/usr/bin/python3 runs /Users/me/sync.py")
+        );
+        assert_eq!(
+            third_party_reason(
+                "com.apple.fake",
+                Some("/usr/bin/open"),
+                &args(&["-a", "Foo.app"]),
+                Some(&signed(None))
+            )
+            .as_deref(),
+            Some("/usr/bin/open runs Foo.app")
+        );
+        assert_eq!(
+            third_party_reason(
+                "com.example.awake",
+                Some("/usr/bin/caffeinate"),
+                &args(&["-i"]),
+                Some(&signed(None))
+            )
+            .as_deref(),
+            Some("/usr/bin/caffeinate under a non-Apple label")
+        );
+    }
+
+    #[test]
+    fn apple_lookalikes() {
+        assert!(mimics_apple(
+            "com.apple.updater",
+            &LaunchdEntryLocation::User
+        ));
+        assert!(!mimics_apple(
+            "com.apple.Finder",
+            &LaunchdEntryLocation::System
+        ));
+        assert!(mimics_apple(
+            "com.appIe.updater",
+            &LaunchdEntryLocation::Global
+        ));
+        assert!(mimics_apple(
+            "corn.apple.updater",
+            &LaunchdEntryLocation::Global
+        ));
+        assert!(!mimics_apple(
+            "com.example.agent",
+            &LaunchdEntryLocation::User
+        ));
+        assert!(!mimics_apple(
+            "org.apache.httpd",
+            &LaunchdEntryLocation::Global
+        ));
+    }
+
+    #[test]
+    fn audit_daemon() {
+        let plist = LaunchdPlist {
+            entry_type: LaunchdEntryType::Daemon,
+            entry_location: LaunchdEntryLocation::Global,
+            plist_path: "/Library/LaunchDaemons/com.example.helper.plist".to_string(),
+            readonly: false,
+            session_types: vec![],
+        };
+
+        let mut dict = Dictionary::new();
+        dict.insert("Program".to_string(), "/Users/me/.cache/helper".into());
+        dict.insert("RunAtLoad".to_string(), true.into());
+        dict.insert("KeepAlive".to_string(), true.into());
+
+        let user_owned = FileOwner {
+            uid: 501,
+            gid: 20,
+            mode: 0o100644,
+        };
+
+        let context = AuditContext {
+            plist_owner: Some(FileOwner {
+                uid: 0,
+                gid: 0,
+                mode: 0o100644,
+            }),
+            program_owner: Some(user_owned),
+            signature: Some(SignatureKind::AdHoc),
+        };
+
+        let reasons = audit_job("com.example.helper", &plist, &dict, &context);
+        assert_eq!(
+            reasons,
+            vec![
+                AuditReason::WritableProgram("/Users/me/.cache/helper".to_string(), user_owned),
+                AuditReason::Unsigned("/Users/me/.cache/helper".to_string(), SignatureKind::AdHoc),
+                AuditReason::HiddenPersistent("/Users/me/.cache/helper".to_string()),
+            ]
+        );
+
+        dict.insert("UserName".to_string(), "nobody".into());
+        assert_eq!(
+            audit_job("com.example.helper", &plist, &dict, &context).len(),
+            2
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use std::cmp::Reverse;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::{config, config_path};
+
+/// Configured backup_dir, or backups next to config.toml
+pub fn backup_dir() -> PathBuf {
+    config()
+        .backup_dir
+        .map(PathBuf::from)
+        .unwrap_or_else(|| config_path().with_file_name("backups"))
+}
+
+/// A copy of a job's plist, at <backup dir>/<label>/<epoch seconds>.plist
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Backup {
+    pub path: String,
+    /// Seconds since the epoch
+    pub taken: i64,
+}
+
+/// Copy plist_path aside before it is changed
+pub fn backup_plist(label: &str, plist_path: &str) -> Result<Backup, String> {
+    let dir = backup_dir().join(label);
+    fs::create_dir_all(&dir).map_err(|e| format!("{}: {}", dir.to_string_lossy(), e))?;
+
+    let taken = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Must get ts")
+        .as_secs() as i64;
+
+    let path = dir.join(format!("{}.plist", taken));
+    fs::copy(plist_path, &path).map_err(|e| format!("Cannot back up {}: {}", plist_path, e))?;
+
+    Ok(Backup {
+        path: path.to_string_lossy().to_string(),
+        taken,
+    })
+}
+
+/// Backups of label, newest first
+pub fn list_backups(label: &str) -> Vec<Backup> {
+    let entries = match fs::read_dir(backup_dir().join(label)) {
+        Ok(e) => e,
+        Err(_) => return vec![],
+    };
+
+    let mut backups: Vec<Backup> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let path = e.path();
+            let taken = path
+                .file_name()?
+                .to_str()?
+                .strip_suffix(".plist")?
+                .parse()
+                .ok()?;
+
+            Some(Backup {
+                path: path.to_string_lossy().to_string(),
+                taken,
+            })
+        })
+        .collect();
+
+    backups.sort_by_key(|b| Reverse(b.taken));
+    backups
+}
+
+/// Put backup_path back at plist_path, backing up what is there first so
+/// a restore can be undone the same way
+pub fn restore_backup(label: &str, backup_path: &str, plist_path: &str) -> Result<(), String> {
+    plist::Value::from_file(backup_path)
+        .map_err(|e| format!("Backup {} is not a plist: {}", backup_path, e))?;
+
+    if Path::new(plist_path).exists() {
+        backup_plist(label, plist_path)?;
+    }
+
+    fs::copy(backup_path, plist_path)
+        .map(|_| ())
+        .map_err(|e| format!("Cannot restore {}: {}", plist_path, e))
+}
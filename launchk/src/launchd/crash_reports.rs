@@ -0,0 +1,84 @@
+use std::cmp::Reverse;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+/// How far back a report still counts as recent
+const RECENT: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// A .ips (macOS 12+) or .crash file written by ReportCrash
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CrashReport {
+    pub path: String,
+    pub modified: SystemTime,
+}
+
+fn report_dirs() -> Vec<String> {
+    let mut dirs = vec!["/Library/Logs/DiagnosticReports".to_string()];
+
+    if let Ok(home) = env::var("HOME") {
+        dirs.insert(0, format!("{}/Library/Logs/DiagnosticReports", home));
+    }
+
+    dirs
+}
+
+/// Reports are named <process>-<date>.ips or <process>_<date>_<host>.crash
+fn is_report_for(file_name: &str, process: &str) -> bool {
+    let is_report = file_name.ends_with(".ips") || file_name.ends_with(".crash");
+
+    is_report
+        && file_name
+            .strip_prefix(process)
+            .map(|rest| rest.starts_with('-') || rest.starts_with('_'))
+            .unwrap_or(false)
+}
+
+/// Recent crash reports for an executable, newest first
+pub fn crash_reports(executable: &str) -> Vec<CrashReport> {
+    let process = match Path::new(executable).file_name() {
+        Some(p) => p.to_string_lossy().to_string(),
+        None => return vec![],
+    };
+    let since = SystemTime::now() - RECENT;
+
+    let mut reports: Vec<CrashReport> = report_dirs()
+        .iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(|e| e.ok())
+        .filter(|e| is_report_for(&e.file_name().to_string_lossy(), &process))
+        .filter_map(|e| {
+            let modified = e.metadata().ok()?.modified().ok()?;
+
+            Some(CrashReport {
+                path: e.path().to_string_lossy().to_string(),
+                modified,
+            })
+        })
+        .filter(|r| r.modified >= since)
+        .collect();
+
+    reports.sort_by_key(|r| Reverse(r.modified));
+    reports
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_report_for;
+
+    #[test]
+    fn report_names() {
+        assert!(is_report_for(
+            "mDNSResponder-2023-05-01-101010.ips",
+            "mDNSResponder"
+        ));
+        assert!(is_report_for(
+            "helper_2021-01-01-101010_host.crash",
+            "helper"
+        ));
+        assert!(!is_report_for("helperd-2023-05-01-101010.ips", "helper"));
+        assert!(!is_report_for("helper-2023-05-01-101010.diag", "helper"));
+    }
+}
@@ -0,0 +1,180 @@
+use std::ffi::CString;
+use std::fmt;
+use std::str::FromStr;
+
+use xpc_sys::enums::DomainType;
+
+/// launchctl domain target, e.g. system, gui/501, user/501
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct DomainTarget {
+    pub domain_type: DomainType,
+    /// uid, asid or pid depending on domain type, 0 if not needed
+    pub handle: u64,
+}
+
+impl DomainTarget {
+    pub fn new(domain_type: DomainType, handle: u64) -> Self {
+        Self {
+            domain_type,
+            handle,
+        }
+    }
+}
+
+/// Resolve e.g. gui/501 into a domain type and handle, as taken by load
+/// and unload. system has no handle.
+pub fn resolve_domain_target(target: &str) -> Result<(DomainType, Option<u64>), String> {
+    let DomainTarget {
+        domain_type,
+        handle,
+    } = target.parse()?;
+
+    match domain_type {
+        DomainType::System => Ok((domain_type, None)),
+        _ => Ok((domain_type, Some(handle))),
+    }
+}
+
+/// Resolve a user name for the user domains, e.g. gui/alice
+fn uid_for_name(name: &str) -> Option<u64> {
+    let name = CString::new(name).ok()?;
+    let passwd = unsafe { libc::getpwnam(name.as_ptr()) };
+
+    if passwd.is_null() {
+        None
+    } else {
+        Some(unsafe { (*passwd).pw_uid } as u64)
+    }
+}
+
+/// launchctl service target, e.g. gui/502/com.example.agent or
+/// system/com.example.daemon, into its domain target and label
+pub fn parse_service_target(s: &str) -> Result<(DomainTarget, String), String> {
+    let (target, label) = match s.strip_prefix("system/") {
+        Some(label) => ("system".to_string(), label),
+        None => match s.splitn(3, '/').collect::<Vec<_>>().as_slice() {
+            [name, handle, label] => (format!("{}/{}", name, handle), *label),
+            _ => return Err(format!("Invalid service target {}", s)),
+        },
+    };
+
+    if label.is_empty() {
+        return Err(format!("Service target {} has no label", s));
+    }
+
+    Ok((target.parse()?, label.to_string()))
+}
+
+impl FromStr for DomainTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, '/');
+        let name = parts.next().unwrap_or("");
+        let handle = parts.next();
+
+        let domain_type = match name.parse::<DomainType>() {
+            Ok(DomainType::System) if handle.is_none() => {
+                return Ok(DomainTarget::new(DomainType::System, 0))
+            }
+            Ok(DomainType::System)
+            | Ok(DomainType::RequestorDomain)
+            | Ok(DomainType::RequestorUserDomain)
+            | Err(_) => return Err(format!("Invalid domain target {}", s)),
+            Ok(domain_type) => domain_type,
+        };
+
+        let handle = handle.ok_or_else(|| format!("Domain target {} requires a handle", s))?;
+        let handle = match (handle.parse::<u64>(), &domain_type) {
+            (Ok(h), _) => Some(h),
+            (Err(_), DomainType::User | DomainType::UserLogin | DomainType::Gui) => {
+                uid_for_name(handle)
+            }
+            _ => None,
+        };
+
+        handle
+            .map(|h| DomainTarget::new(domain_type, h))
+            .ok_or_else(|| format!("Invalid handle in domain target {}", s))
+    }
+}
+
+impl fmt::Display for DomainTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self.domain_type {
+            DomainType::System => return write!(f, "system"),
+            DomainType::User => "user",
+            DomainType::UserLogin => "login",
+            DomainType::Session => "session",
+            DomainType::PID => "pid",
+            DomainType::Gui => "gui",
+            ref other => return write!(f, "{}", other),
+        };
+
+        write!(f, "{}/{}", name, self.handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_service_target, resolve_domain_target, DomainTarget};
+    use xpc_sys::enums::DomainType;
+
+    #[test]
+    fn parse_domain_target() {
+        let gui: DomainTarget = "gui/501".parse().unwrap();
+
+        assert_eq!(gui, DomainTarget::new(DomainType::Gui, 501));
+        assert_eq!(gui.to_string(), "gui/501");
+        assert_eq!(
+            "system".parse::<DomainTarget>().unwrap().domain_type,
+            DomainType::System
+        );
+        assert!("gui".parse::<DomainTarget>().is_err());
+        assert!("nope/1".parse::<DomainTarget>().is_err());
+        assert!("system/1".parse::<DomainTarget>().is_err());
+        assert!("requestor/1".parse::<DomainTarget>().is_err());
+    }
+
+    #[test]
+    fn resolve_targets() {
+        assert_eq!(
+            resolve_domain_target("session/100008"),
+            Ok((DomainType::Session, Some(100008)))
+        );
+        assert_eq!(
+            resolve_domain_target("pid/42"),
+            Ok((DomainType::PID, Some(42)))
+        );
+        assert_eq!(
+            resolve_domain_target("system"),
+            Ok((DomainType::System, None))
+        );
+        assert!(resolve_domain_target("user/launchk-nobody").is_err());
+        assert_eq!(
+            resolve_domain_target("gui/root"),
+            Ok((DomainType::Gui, Some(0)))
+        );
+        assert!(resolve_domain_target("pid/root").is_err());
+    }
+
+    #[test]
+    fn parse_service_targets() {
+        assert_eq!(
+            parse_service_target("gui/502/com.example.agent"),
+            Ok((
+                DomainTarget::new(DomainType::Gui, 502),
+                "com.example.agent".to_string()
+            ))
+        );
+        assert_eq!(
+            parse_service_target("system/com.example.daemon"),
+            Ok((
+                DomainTarget::new(DomainType::System, 0),
+                "com.example.daemon".to_string()
+            ))
+        );
+        assert!(parse_service_target("com.example.agent").is_err());
+        assert!(parse_service_target("gui/502/").is_err());
+    }
+}
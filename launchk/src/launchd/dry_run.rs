@@ -0,0 +1,148 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use std::process::Command;
+
+use plist::{Dictionary, Value};
+
+/// PATH launchd gives jobs (_PATH_STDPATH)
+const LAUNCHD_PATH: &str = "/usr/bin:/bin:/usr/sbin:/sbin";
+/// Passed through from launchk, like launchd does for agents
+const INHERITED: [&str; 5] = ["HOME", "USER", "LOGNAME", "SHELL", "TMPDIR"];
+
+/// How launchd would exec a job, to run it once directly
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DryRun {
+    /// Program, otherwise the first of ProgramArguments
+    pub program: String,
+    /// ProgramArguments, argv[0] included
+    pub args: Vec<String>,
+    /// EnvironmentVariables over the inherited ones
+    pub env: BTreeMap<String, String>,
+    pub working_dir: Option<String>,
+    /// UserName, the dry run still runs as us
+    pub user: Option<String>,
+}
+
+impl DryRun {
+    pub fn from_plist(dict: &Dictionary) -> Result<Self, String> {
+        let string = |key: &str| dict.get(key).and_then(Value::as_string).map(String::from);
+
+        let args: Vec<String> = dict
+            .get("ProgramArguments")
+            .and_then(Value::as_array)
+            .map(|a| {
+                a.iter()
+                    .filter_map(Value::as_string)
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let program = string("Program")
+            .or_else(|| args.first().cloned())
+            .ok_or_else(|| "No Program or ProgramArguments".to_string())?;
+
+        // Program without ProgramArguments gets itself as argv[0]
+        let args = if args.is_empty() {
+            vec![program.clone()]
+        } else {
+            args
+        };
+
+        let mut env: BTreeMap<String, String> = INHERITED
+            .iter()
+            .filter_map(|k| env::var(k).ok().map(|v| (k.to_string(), v)))
+            .collect();
+        env.insert("PATH".to_string(), LAUNCHD_PATH.to_string());
+
+        if let Some(vars) = dict
+            .get("EnvironmentVariables")
+            .and_then(Value::as_dictionary)
+        {
+            for (k, v) in vars {
+                if let Some(v) = v.as_string() {
+                    env.insert(k.clone(), v.to_string());
+                }
+            }
+        }
+
+        Ok(Self {
+            program,
+            args,
+            env,
+            working_dir: string("WorkingDirectory"),
+            user: string("UserName"),
+        })
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let plist = Value::from_file(path.as_ref())
+            .map_err(|e| format!("{}: {}", path.as_ref().display(), e))?;
+        let dict = plist
+            .as_dictionary()
+            .ok_or_else(|| format!("{}: not a dictionary", path.as_ref().display()))?;
+
+        Self::from_plist(dict)
+    }
+
+    /// Shell-like command line, for the view's status line
+    pub fn command_line(&self) -> String {
+        let line = self.args.join(" ");
+
+        match &self.working_dir {
+            Some(dir) => format!("cd {} && {}", dir, line),
+            None => line,
+        }
+    }
+
+    /// Program with only the environment launchd would give it
+    pub fn command(&self) -> Command {
+        let mut command = Command::new(&self.program);
+        command
+            .arg0(&self.args[0])
+            .args(&self.args[1..])
+            .env_clear()
+            .envs(&self.env);
+
+        if let Some(dir) = &self.working_dir {
+            command.current_dir(dir);
+        }
+
+        command
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use plist::{Dictionary, Value};
+
+    use super::DryRun;
+
+    #[test]
+    fn dry_run_from_plist() {
+        let mut dict = Dictionary::new();
+        dict.insert(
+            "ProgramArguments".to_string(),
+            Value::Array(vec!["/bin/sh".into(), "-c".into(), "echo $GREETING".into()]),
+        );
+        dict.insert("WorkingDirectory".to_string(), "/tmp".into());
+
+        let mut vars = Dictionary::new();
+        vars.insert("GREETING".to_string(), "hi".into());
+        vars.insert("PATH".to_string(), "/opt/bin".into());
+        dict.insert("EnvironmentVariables".to_string(), Value::Dictionary(vars));
+
+        let run = DryRun::from_plist(&dict).unwrap();
+        assert_eq!(run.program, "/bin/sh");
+        assert_eq!(run.env.get("GREETING").map(String::as_str), Some("hi"));
+        assert_eq!(run.env.get("PATH").map(String::as_str), Some("/opt/bin"));
+        assert_eq!(run.command_line(), "cd /tmp && /bin/sh -c echo $GREETING");
+
+        dict.insert("Program".to_string(), "/usr/bin/env".into());
+        assert_eq!(DryRun::from_plist(&dict).unwrap().program, "/usr/bin/env");
+
+        assert!(DryRun::from_plist(&Dictionary::new()).is_err());
+    }
+}
@@ -0,0 +1,301 @@
+use std::fmt;
+
+use plist::{Dictionary, Value};
+use xpc_sys::{
+    rs_bootstrap_check, rs_bootstrap_strerror, BOOTSTRAP_NOT_PRIVILEGED, BOOTSTRAP_UNKNOWN_SERVICE,
+};
+
+use crate::launchd::print_parser::StateSection;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum EndpointKind {
+    Socket,
+    MachService,
+}
+
+/// Result of looking a Mach service up from launchk's bootstrap namespace
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Reachability {
+    Reachable,
+    /// Not registered, or in a domain we can't see (another user's)
+    Unknown,
+    NotPrivileged,
+    Failed(String),
+}
+
+impl Reachability {
+    pub fn from_lookup(result: Result<(), i32>) -> Self {
+        match result {
+            Ok(()) => Reachability::Reachable,
+            Err(kr) if kr == BOOTSTRAP_UNKNOWN_SERVICE as i32 => Reachability::Unknown,
+            Err(kr) if kr == BOOTSTRAP_NOT_PRIVILEGED as i32 => Reachability::NotPrivileged,
+            Err(kr) => Reachability::Failed(rs_bootstrap_strerror(kr)),
+        }
+    }
+
+    /// bootstrap_look_up the name, this does not launch the job
+    pub fn lookup(service: &str) -> Self {
+        Self::from_lookup(rs_bootstrap_check(service))
+    }
+}
+
+impl fmt::Display for Reachability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reachability::Reachable => write!(f, "reachable"),
+            Reachability::Unknown => write!(f, "unreachable (unknown service)"),
+            Reachability::NotPrivileged => write!(f, "unreachable (not privileged)"),
+            Reachability::Failed(e) => write!(f, "unreachable ({})", e),
+        }
+    }
+}
+
+/// A socket or Mach service a job declares or launchd holds for it
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Endpoint {
+    pub kind: EndpointKind,
+    /// Sockets dict key, or Mach service name
+    pub name: String,
+    /// Socket type, e.g. stream or dgram
+    pub socket_type: Option<String>,
+    /// Socket path, or [node:]port
+    pub address: Option<String>,
+    /// From print output, None if launchd did not say
+    pub active: Option<bool>,
+    /// Declared in the plist (false: only seen at runtime)
+    pub declared: bool,
+    /// Mach services, once looked up
+    pub reachable: Option<Reachability>,
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = match self.active {
+            Some(true) => "active",
+            Some(false) => "inactive",
+            None if self.declared => "not loaded",
+            None => "-",
+        };
+
+        match self.kind {
+            EndpointKind::Socket => write!(
+                f,
+                "{:<16} {:<7} {:<32} {}",
+                self.name,
+                self.socket_type.as_deref().unwrap_or("stream"),
+                self.address.as_deref().unwrap_or("-"),
+                state
+            ),
+            EndpointKind::MachService => write!(
+                f,
+                "{:<48} {:<10} {}",
+                self.name,
+                state,
+                self.reachable
+                    .as_ref()
+                    .map(|r| r.to_string())
+                    .unwrap_or_default()
+            ),
+        }
+    }
+}
+
+fn flag(section: &StateSection, key: &str) -> Option<bool> {
+    section.value(key).map(|v| v == "1" || v == "true")
+}
+
+/// SockPathName, or SockNodeName:SockServiceName
+fn plist_address(socket: &Dictionary) -> Option<String> {
+    let string = |key: &str| socket.get(key).and_then(Value::as_string);
+
+    if let Some(path) = string("SockPathName") {
+        return Some(path.to_string());
+    }
+
+    let port = socket.get("SockServiceName").and_then(|p| match p {
+        Value::String(s) => Some(s.clone()),
+        Value::Integer(i) => Some(i.to_string()),
+        _ => None,
+    })?;
+
+    Some(match string("SockNodeName") {
+        Some(node) => format!("{}:{}", node, port),
+        None => port,
+    })
+}
+
+/// print output names the address in a few ways depending on family
+fn runtime_address(socket: &StateSection) -> Option<String> {
+    ["path", "port", "service name", "node name"]
+        .iter()
+        .find_map(|k| socket.value(k))
+        .map(String::from)
+}
+
+/// Sockets and MachServices from a plist, with launchd's view of them
+/// from the job's print or dumpstate block
+pub fn job_endpoints(plist: Option<&Value>, section: Option<&StateSection>) -> Vec<Endpoint> {
+    let dict = plist.and_then(Value::as_dictionary);
+    let mut endpoints: Vec<Endpoint> = vec![];
+
+    // A socket key can hold one dict or an array of them (e.g. IPv4 and 6)
+    let declared_sockets = dict
+        .and_then(|d| d.get("Sockets"))
+        .and_then(Value::as_dictionary);
+
+    for (name, value) in declared_sockets.into_iter().flatten() {
+        let sockets: Vec<&Dictionary> = match value {
+            Value::Dictionary(d) => vec![d],
+            Value::Array(a) => a.iter().filter_map(Value::as_dictionary).collect(),
+            _ => vec![],
+        };
+
+        for socket in sockets {
+            endpoints.push(Endpoint {
+                kind: EndpointKind::Socket,
+                name: name.clone(),
+                socket_type: socket
+                    .get("SockType")
+                    .and_then(Value::as_string)
+                    .map(String::from),
+                address: plist_address(socket),
+                active: None,
+                declared: true,
+                reachable: None,
+            });
+        }
+    }
+
+    let declared_services = dict
+        .and_then(|d| d.get("MachServices"))
+        .and_then(Value::as_dictionary);
+
+    for name in declared_services.into_iter().flat_map(|d| d.keys()) {
+        endpoints.push(Endpoint {
+            kind: EndpointKind::MachService,
+            name: name.clone(),
+            socket_type: None,
+            address: None,
+            active: None,
+            declared: true,
+            reachable: None,
+        });
+    }
+
+    let runtime = [
+        (EndpointKind::Socket, "sockets"),
+        (EndpointKind::MachService, "endpoints"),
+    ];
+
+    for (kind, block) in runtime.iter() {
+        let children = section
+            .and_then(|s| s.section(block))
+            .map(|b| b.sections.as_slice())
+            .unwrap_or_default();
+
+        for child in children {
+            let active = flag(child, "active");
+            let mut matched = false;
+
+            for endpoint in endpoints
+                .iter_mut()
+                .filter(|e| e.kind == *kind && e.name == child.name)
+            {
+                endpoint.active = active;
+                matched = true;
+            }
+
+            if !matched {
+                endpoints.push(Endpoint {
+                    kind: *kind,
+                    name: child.name.clone(),
+                    socket_type: child.value("type").map(String::from),
+                    address: runtime_address(child),
+                    active,
+                    declared: false,
+                    reachable: None,
+                });
+            }
+        }
+    }
+
+    endpoints
+}
+
+/// Look up each Mach service, marking whether a client could reach it
+pub fn check_reachability(endpoints: &mut [Endpoint]) {
+    for endpoint in endpoints
+        .iter_mut()
+        .filter(|e| e.kind == EndpointKind::MachService)
+    {
+        endpoint.reachable = Some(Reachability::lookup(&endpoint.name));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use xpc_sys::{BOOTSTRAP_NO_MEMORY, BOOTSTRAP_UNKNOWN_SERVICE};
+
+    use super::{job_endpoints, EndpointKind, Reachability};
+    use crate::launchd::print_parser::parse;
+    use plist::{Dictionary, Value};
+
+    static PRINT: &str = "gui/501/com.example.agent = {
+	sockets = {
+		\"Listeners\" = {
+			type = stream
+			active = 1
+		}
+	}
+	endpoints = {
+		\"com.example.agent.xpc\" = {
+			port = 0x1a03
+			active = 0
+		}
+		\"com.example.agent.extra\" = {
+			active = 1
+		}
+	}
+}
+";
+
+    #[test]
+    fn merge_declared_and_runtime() {
+        let mut listener = Dictionary::new();
+        listener.insert("SockServiceName".to_string(), 8080.into());
+        listener.insert("SockNodeName".to_string(), "localhost".into());
+
+        let mut sockets = Dictionary::new();
+        sockets.insert("Listeners".to_string(), Value::Dictionary(listener));
+
+        let mut services = Dictionary::new();
+        services.insert("com.example.agent.xpc".to_string(), true.into());
+
+        let mut dict = Dictionary::new();
+        dict.insert("Sockets".to_string(), Value::Dictionary(sockets));
+        dict.insert("MachServices".to_string(), Value::Dictionary(services));
+
+        let state = parse(PRINT);
+        let endpoints = job_endpoints(Some(&Value::Dictionary(dict)), Some(&state.sections[0]));
+
+        assert_eq!(endpoints.len(), 3);
+        assert_eq!(endpoints[0].kind, EndpointKind::Socket);
+        assert_eq!(endpoints[0].address.as_deref(), Some("localhost:8080"));
+        assert_eq!(endpoints[0].active, Some(true));
+        assert_eq!(endpoints[1].active, Some(false));
+        assert!(!endpoints[2].declared);
+    }
+
+    #[test]
+    fn reachability_from_lookup() {
+        assert_eq!(Reachability::from_lookup(Ok(())), Reachability::Reachable);
+        assert_eq!(
+            Reachability::from_lookup(Err(BOOTSTRAP_UNKNOWN_SERVICE as i32)),
+            Reachability::Unknown
+        );
+        assert!(matches!(
+            Reachability::from_lookup(Err(BOOTSTRAP_NO_MEMORY as i32)),
+            Reachability::Failed(_)
+        ));
+    }
+}
@@ -1,19 +1,458 @@
-use std::collections::HashMap;
-use std::convert::TryInto;
-use std::sync::Mutex;
-use std::time::{Duration, SystemTime};
+use std::collections::{HashMap, HashSet};
+use std::mem::MaybeUninit;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
 
+use crate::launchd::domain_target::DomainTarget;
+use crate::launchd::job_detail::{dumpstate_jobs, DumpstateJob};
 use crate::launchd::plist::LaunchdPlist;
-use crate::launchd::query::find_in_all;
+use crate::launchd::print_parser::StateSection;
+use crate::launchd::query::{
+    disabled_overrides, find_in_all, list_target, list_user, LoadedService, ServiceEntry,
+};
+use crate::launchd::status_cache::StatusCache;
 
 use xpc_sys::enums::{DomainType, SessionType};
-use xpc_sys::traits::xpc_value::TryXPCValue;
 
 const ENTRY_INFO_QUERY_TTL: Duration = Duration::from_secs(15);
+/// New PIDs seen within this long count towards churn
+const CHURN_WINDOW: Duration = Duration::from_secs(60);
+/// This many spawns in CHURN_WINDOW means launchd keeps respawning a job
+const CHURN_SPAWNS: usize = 3;
+/// launchd's ThrottleInterval when neither dumpstate nor the plist say
+const DEFAULT_THROTTLE_INTERVAL: u64 = 10;
 
 lazy_static! {
-    pub static ref ENTRY_STATUS_CACHE: Mutex<HashMap<String, LaunchdEntryStatus>> =
-        Mutex::new(HashMap::new());
+    pub static ref ENTRY_STATUS_CACHE: StatusCache<LaunchdEntryStatus> =
+        StatusCache::new(ENTRY_INFO_QUERY_TTL);
+    /// Latest usage of running jobs by PID, updated when polling
+    static ref PROC_USAGE: RwLock<HashMap<i64, ProcUsage>> = RwLock::new(HashMap::new());
+    /// Override database (label -> disabled) merged over domains, updated
+    /// when polling
+    static ref OVERRIDES: RwLock<HashMap<String, bool>> = RwLock::new(HashMap::new());
+    /// Plist paths (for jobs not in the standard directories) and exit info
+    /// from dumpstate
+    static ref DUMPSTATE_JOBS: RwLock<HashMap<String, DumpstateJob>> = RwLock::new(HashMap::new());
+    /// Last exit status by label, updated when polling
+    static ref LAST_EXIT: RwLock<HashMap<String, i64>> = RwLock::new(HashMap::new());
+    /// Recent spawns by label, updated when polling
+    static ref SPAWNS: Mutex<HashMap<String, SpawnHistory>> = Mutex::new(HashMap::new());
+    /// uid the list is scoped to, with labels in its gui and user domains
+    static ref USER_SCOPE: RwLock<Option<(u32, HashSet<String>)>> = RwLock::new(None);
+    /// Domain target load, unload, enable and disable go to, with labels
+    /// loaded in it. None asks for a domain each time.
+    static ref DOMAIN_TARGET: RwLock<Option<(DomainTarget, HashSet<String>)>> = RwLock::new(None);
+    /// Previous (CPU time in ns, when) by PID
+    static ref CPU_SAMPLES: Mutex<HashMap<i64, (u64, Instant)>> = Mutex::new(HashMap::new());
+    /// rusage times are in mach absolute time units (not ns on Apple Silicon)
+    static ref TIMEBASE: (u64, u64) = mach_timebase();
+}
+
+/// (numer, denom), libc deprecates these for the mach2 crate's copies
+#[allow(deprecated)]
+fn mach_timebase() -> (u64, u64) {
+    let mut info = libc::mach_timebase_info { numer: 0, denom: 0 };
+    unsafe { libc::mach_timebase_info(&mut info) };
+
+    if info.denom == 0 {
+        (1, 1)
+    } else {
+        (info.numer as u64, info.denom as u64)
+    }
+}
+
+/// CPU and memory of a running job
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub struct ProcUsage {
+    /// CPU% * 10 since the previous sample, can exceed 1000 with many cores
+    pub cpu_permille: u64,
+    /// Resident set size in bytes
+    pub rss: u64,
+}
+
+/// (CPU time in ns, RSS) from proc_pid_rusage
+fn rusage(pid: i64) -> Option<(u64, u64)> {
+    let mut info = MaybeUninit::<libc::rusage_info_v2>::uninit();
+
+    let err = unsafe {
+        libc::proc_pid_rusage(
+            pid as libc::c_int,
+            libc::RUSAGE_INFO_V2,
+            info.as_mut_ptr() as *mut libc::rusage_info_t,
+        )
+    };
+
+    if err != 0 {
+        return None;
+    }
+
+    let info = unsafe { info.assume_init() };
+    let (numer, denom) = *TIMEBASE;
+    let cpu_ns = (info.ri_user_time + info.ri_system_time) * numer / denom;
+
+    Some((cpu_ns, info.ri_resident_size))
+}
+
+/// Sample usage for running PIDs, dropping anything that exited
+pub fn sample_proc_usage(pids: &[i64]) {
+    let now = Instant::now();
+    let mut samples = CPU_SAMPLES.lock().expect("Must sample");
+    let mut usage = HashMap::new();
+
+    for &pid in pids.iter().filter(|p| **p > 0) {
+        let (cpu_ns, rss) = match rusage(pid) {
+            Some(r) => r,
+            None => continue,
+        };
+
+        let cpu_permille = samples
+            .get(&pid)
+            .map(|(last_ns, last)| {
+                let elapsed = now.duration_since(*last).as_nanos() as u64;
+                cpu_ns.saturating_sub(*last_ns) * 1000 / elapsed.max(1)
+            })
+            .unwrap_or(0);
+
+        samples.insert(pid, (cpu_ns, now));
+        usage.insert(pid, ProcUsage { cpu_permille, rss });
+    }
+
+    samples.retain(|pid, _| usage.contains_key(pid));
+    *PROC_USAGE.write().expect("Must update usage") = usage;
+}
+
+/// Re-read print-disabled for all domains
+pub fn refresh_overrides() {
+    let overrides = disabled_overrides();
+    *OVERRIDES.write().expect("Must update overrides") = overrides;
+}
+
+/// Some(disabled) if label has an override, jobs without one are enabled
+pub fn get_override(label: &str) -> Option<bool> {
+    OVERRIDES.read().ok()?.get(label).copied()
+}
+
+/// Would launchd refuse to load label because of a disable override?
+pub fn is_disabled(label: &str) -> bool {
+    get_override(label).unwrap_or(false)
+}
+
+/// Keep exit statuses from the latest list
+pub fn record_last_exit(entries: &[ServiceEntry]) {
+    let last_exit = entries
+        .iter()
+        .filter_map(|e| e.status.map(|s| (e.label.clone(), s)))
+        .collect();
+
+    *LAST_EXIT.write().expect("Must update last exit") = last_exit;
+}
+
+/// Last exit status, None if not loaded
+pub fn get_last_exit(label: &str) -> Option<i64> {
+    LAST_EXIT.read().ok()?.get(label).copied()
+}
+
+/// PIDs seen for a job between polls
+#[derive(Debug, Clone, Default)]
+struct SpawnHistory {
+    pid: i64,
+    /// When a new PID was seen, within CHURN_WINDOW
+    spawns: Vec<Instant>,
+    /// When the PID went away
+    exited: Option<Instant>,
+}
+
+/// Note PID changes from the latest list
+pub fn track_spawns(entries: &[ServiceEntry]) {
+    let now = Instant::now();
+    let mut spawns = SPAWNS.lock().expect("Must track spawns");
+
+    for entry in entries {
+        let history = spawns.entry(entry.label.clone()).or_default();
+
+        if entry.pid != history.pid {
+            if entry.pid > 0 {
+                history.spawns.push(now);
+                history.exited = None;
+            } else {
+                history.exited = Some(now);
+            }
+
+            history.pid = entry.pid;
+        }
+
+        history
+            .spawns
+            .retain(|t| now.duration_since(*t) < CHURN_WINDOW);
+    }
+
+    let labels: HashSet<&String> = entries.iter().map(|e| &e.label).collect();
+    spawns.retain(|label, _| labels.contains(label));
+}
+
+/// Respawned CHURN_SPAWNS times within CHURN_WINDOW
+pub fn is_churning(label: &str) -> bool {
+    SPAWNS
+        .lock()
+        .ok()
+        .and_then(|s| s.get(label).map(|h| h.spawns.len() >= CHURN_SPAWNS))
+        .unwrap_or(false)
+}
+
+/// Until launchd may spawn label again, if it is throttled (per dumpstate)
+/// or churning and has exited. Zero once the interval has passed.
+pub fn throttle_remaining(label: &str, exit: Option<&ExitInfo>) -> Option<Duration> {
+    let throttled = exit.map(|e| e.throttled).unwrap_or(false) || is_churning(label);
+    if !throttled {
+        return None;
+    }
+
+    let interval = exit
+        .and_then(|e| e.throttle_interval)
+        .unwrap_or(DEFAULT_THROTTLE_INTERVAL);
+
+    let exited = SPAWNS.lock().ok()?.get(label)?.exited?;
+    Some(Duration::from_secs(interval).saturating_sub(exited.elapsed()))
+}
+
+/// Re-read plist paths and exit info from dumpstate, slow so polled less
+/// often
+pub fn refresh_dumpstate_jobs() {
+    match dumpstate_jobs() {
+        Ok(jobs) => *DUMPSTATE_JOBS.write().expect("Must update jobs") = jobs,
+        Err(e) => log::debug!("[refresh_dumpstate_jobs]: {}", e),
+    }
+}
+
+/// Plist path launchd loaded label from, if dumpstate had one
+pub fn dumpstate_plist_path(label: &str) -> Option<String> {
+    DUMPSTATE_JOBS.read().ok()?.get(label)?.plist_path.clone()
+}
+
+/// How label last exited, as of the last dumpstate
+pub fn get_exit_info(label: &str) -> Option<ExitInfo> {
+    DUMPSTATE_JOBS
+        .read()
+        .ok()?
+        .get(label)
+        .map(|j| j.exit.clone())
+}
+
+/// Signals that mean the program crashed rather than was stopped: SIGILL,
+/// SIGTRAP, SIGABRT, SIGEMT, SIGFPE, SIGBUS, SIGSEGV, SIGSYS
+const CRASH_SIGNALS: [i64; 8] = [4, 5, 6, 7, 8, 10, 11, 12];
+
+/// Last exit from a job's print or dumpstate block
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct ExitInfo {
+    /// None if it never exited
+    pub code: Option<i64>,
+    /// e.g. 11 from `last terminating signal = Segmentation fault: 11`
+    pub signal: Option<i64>,
+    /// launchd is holding off respawning it
+    pub throttled: bool,
+    /// Seconds launchd waits between spawns
+    pub throttle_interval: Option<u64>,
+}
+
+impl ExitInfo {
+    /// Stopped by SIGSEGV, SIGABRT etc.
+    pub fn crashed(&self) -> bool {
+        self.signal
+            .map(|s| CRASH_SIGNALS.contains(&s))
+            .unwrap_or(false)
+    }
+}
+
+impl From<&StateSection> for ExitInfo {
+    fn from(section: &StateSection) -> Self {
+        // e.g. "0", "78: Function not implemented" or "(never exited)"
+        let code = section
+            .value("last exit code")
+            .and_then(|c| c.split(':').next())
+            .and_then(|c| c.trim().parse().ok());
+
+        let signal = section
+            .value("last terminating signal")
+            .and_then(|s| s.rsplit(':').next())
+            .and_then(|s| s.trim().parse().ok());
+
+        // print says "state", older dumpstate "job state"
+        let throttled = ["state", "job state"]
+            .iter()
+            .filter_map(|k| section.value(k))
+            .any(|s| s == "spawn scheduled" || s.contains("throttled"));
+
+        // e.g. "10 seconds"
+        let throttle_interval = section
+            .value("throttle interval")
+            .and_then(|i| i.split_whitespace().next())
+            .and_then(|i| i.parse().ok());
+
+        Self {
+            code,
+            signal,
+            throttled,
+            throttle_interval,
+        }
+    }
+}
+
+/// What a row's status glyph shows
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum JobState {
+    Running,
+    /// Loaded, not running, and the last exit (if any) was clean
+    ExitedOk,
+    /// Non-zero exit, or stopped by a signal like SIGKILL
+    ExitedError,
+    Crashed,
+    Throttled,
+    Disabled,
+    NotLoaded,
+}
+
+impl JobState {
+    pub fn glyph(&self) -> &'static str {
+        match self {
+            JobState::Running => "●",
+            JobState::ExitedOk => "✔",
+            JobState::ExitedError => "✘",
+            JobState::Crashed => "⚡",
+            JobState::Throttled => "⏳",
+            JobState::Disabled => "⊘",
+            JobState::NotLoaded => "○",
+        }
+    }
+
+    /// For status: filters, e.g. status:crashed
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "running" => Some(JobState::Running),
+            "ok" | "exited" => Some(JobState::ExitedOk),
+            "error" | "failed" => Some(JobState::ExitedError),
+            "crashed" => Some(JobState::Crashed),
+            "throttled" => Some(JobState::Throttled),
+            "disabled" => Some(JobState::Disabled),
+            "unloaded" => Some(JobState::NotLoaded),
+            _ => None,
+        }
+    }
+
+    /// From the polled list status (negative for a signal, as in
+    /// `launchctl list`) and dumpstate's exit info
+    pub fn new(
+        loaded: bool,
+        enabled: bool,
+        pid: i64,
+        last_exit: Option<i64>,
+        exit: Option<&ExitInfo>,
+    ) -> Self {
+        if loaded && pid > 0 {
+            return JobState::Running;
+        }
+
+        if !enabled {
+            return JobState::Disabled;
+        }
+
+        if !loaded {
+            return JobState::NotLoaded;
+        }
+
+        if exit.map(|e| e.throttled).unwrap_or(false) {
+            return JobState::Throttled;
+        }
+
+        let signal = last_exit
+            .filter(|s| *s < 0)
+            .map(|s| -s)
+            .or_else(|| exit.and_then(|e| e.signal));
+
+        match (signal, last_exit.or_else(|| exit.and_then(|e| e.code))) {
+            (Some(s), _) if CRASH_SIGNALS.contains(&s) => JobState::Crashed,
+            (Some(_), _) => JobState::ExitedError,
+            (None, Some(code)) if code != 0 => JobState::ExitedError,
+            _ => JobState::ExitedOk,
+        }
+    }
+}
+
+/// Scope the list to one user's agents, None for everything
+pub fn set_user_scope(uid: Option<u32>) {
+    let scope = uid.map(|uid| (uid, list_user(uid)));
+    *USER_SCOPE.write().expect("Must update user scope") = scope;
+}
+
+/// Re-list the scoped user's domains
+pub fn refresh_user_scope() {
+    let uid = USER_SCOPE
+        .read()
+        .ok()
+        .and_then(|s| s.as_ref().map(|(uid, _)| *uid));
+
+    if uid.is_some() {
+        set_user_scope(uid);
+    }
+}
+
+/// Labels for the scoped user, None if not scoped
+pub fn user_scope() -> Option<HashSet<String>> {
+    USER_SCOPE
+        .read()
+        .ok()?
+        .as_ref()
+        .map(|(_, labels)| labels.clone())
+}
+
+fn target_labels(target: &DomainTarget) -> HashSet<String> {
+    list_target(target).unwrap_or_else(|e| {
+        log::error!(
+            "[entry_status/target_labels]: error {}, target, {}",
+            e,
+            target
+        );
+        HashSet::new()
+    })
+}
+
+/// Send load, unload, enable and disable to target, None to ask
+pub fn set_domain_target(target: Option<DomainTarget>) {
+    let target = target.map(|t| {
+        let labels = target_labels(&t);
+        (t, labels)
+    });
+
+    *DOMAIN_TARGET.write().expect("Must update domain target") = target;
+}
+
+/// Re-list the selected domain target
+pub fn refresh_domain_target() {
+    if let Some(target) = domain_target() {
+        set_domain_target(Some(target));
+    }
+}
+
+pub fn domain_target() -> Option<DomainTarget> {
+    DOMAIN_TARGET
+        .read()
+        .ok()?
+        .as_ref()
+        .map(|(target, _)| target.clone())
+}
+
+/// Labels loaded in the selected domain target, None if not selected
+pub fn domain_target_labels() -> Option<HashSet<String>> {
+    DOMAIN_TARGET
+        .read()
+        .ok()?
+        .as_ref()
+        .map(|(_, labels)| labels.clone())
+}
+
+/// Usage from the last sample, if pid is running
+pub fn get_proc_usage(pid: i64) -> Option<ProcUsage> {
+    PROC_USAGE.read().ok()?.get(&pid).copied()
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
@@ -23,7 +462,11 @@ pub struct LaunchdEntryStatus {
     pub domain: DomainType,
     // So, there is a pid_t, but it's i32, and the XPC response has an i64?
     pub pid: i64,
-    tick: SystemTime,
+    /// No disable override, independent of loaded. Refreshed on every
+    /// get_entry_status, overrides are polled separately.
+    pub enabled: bool,
+    /// Keys of launchd's reply we could not read, shown as placeholders
+    pub unparseable: Vec<&'static str>,
 }
 
 impl Default for LaunchdEntryStatus {
@@ -33,7 +476,8 @@ impl Default for LaunchdEntryStatus {
             domain: DomainType::Unknown,
             plist: None,
             pid: 0,
-            tick: SystemTime::now(),
+            enabled: true,
+            unparseable: vec![],
         }
     }
 }
@@ -41,22 +485,17 @@ impl Default for LaunchdEntryStatus {
 /// Get entry info for label
 pub fn get_entry_status<S: Into<String>>(label: S) -> LaunchdEntryStatus {
     let label_string = label.into();
-    let mut cache = ENTRY_STATUS_CACHE.try_lock().unwrap();
-
-    if cache.contains_key(label_string.as_str()) {
-        let item = cache.get(label_string.as_str()).unwrap().clone();
 
-        if item.tick.elapsed().unwrap() > ENTRY_INFO_QUERY_TTL {
-            cache.remove(label_string.as_str());
-            drop(cache);
-            return get_entry_status(label_string);
-        }
-
-        return item;
+    if let Some(item) = ENTRY_STATUS_CACHE.get(&label_string) {
+        return LaunchdEntryStatus {
+            enabled: !is_disabled(&label_string),
+            ..item
+        };
     }
 
+    let generation = ENTRY_STATUS_CACHE.generation();
     let meta = build_entry_status(&label_string);
-    cache.insert(label_string, meta.clone());
+    ENTRY_STATUS_CACHE.insert(label_string, meta.clone(), generation);
     meta
 }
 
@@ -64,19 +503,30 @@ fn build_entry_status<S: Into<String>>(label: S) -> LaunchdEntryStatus {
     let label_string = label.into();
     let response = find_in_all(label_string.clone());
 
-    let pid: i64 = response
-        .as_ref()
-        .map_err(|e| e.clone())
-        .and_then(|(_, r)| r.get(&["service", "PID"]))
-        .and_then(|o| o.xpc_value())
-        .unwrap_or(0);
-
-    let limit_load_to_session_type = response
-        .as_ref()
-        .map_err(|e| e.clone())
-        .and_then(|(_, r)| r.get(&["service", "LimitLoadToSessionType"]))
-        .and_then(|o| o.try_into())
-        .unwrap_or(SessionType::Unknown);
+    // Not loaded is an error from find_in_all, a reply without a readable
+    // "service" is not
+    let LoadedService {
+        pid,
+        limit_load_to_session_type,
+        unparseable,
+    } = match &response {
+        Ok((_, r)) => r
+            .get_as_dictionary(["service"])
+            .and_then(|s| s.parse())
+            .unwrap_or_else(|e| {
+                log::warn!("[entry_status]: {} unreadable, {}", label_string, e);
+                LoadedService {
+                    pid: 0,
+                    limit_load_to_session_type: SessionType::Unknown,
+                    unparseable: vec!["service"],
+                }
+            }),
+        Err(_) => LoadedService {
+            pid: 0,
+            limit_load_to_session_type: SessionType::Unknown,
+            unparseable: vec![],
+        },
+    };
 
     let domain = response
         .as_ref()
@@ -90,6 +540,66 @@ fn build_entry_status<S: Into<String>>(label: S) -> LaunchdEntryStatus {
         domain,
         plist: entry_config,
         pid,
-        tick: SystemTime::now(),
+        enabled: !is_disabled(&label_string),
+        unparseable,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ExitInfo, JobState};
+    use crate::launchd::print_parser::parse;
+
+    #[test]
+    fn exit_info_and_state() {
+        let state = parse(
+            "gui/501/com.example.agent = {
+	job state = spawn scheduled
+	throttle interval = 30 seconds
+	last exit code = 78: Function not implemented
+	last terminating signal = Segmentation fault: 11
+}
+",
+        );
+        let exit = ExitInfo::from(&state.sections[0]);
+
+        assert_eq!(
+            exit,
+            ExitInfo {
+                code: Some(78),
+                signal: Some(11),
+                throttled: true,
+                throttle_interval: Some(30),
+            }
+        );
+
+        assert_eq!(
+            JobState::new(true, true, 0, None, Some(&exit)),
+            JobState::Throttled
+        );
+        assert_eq!(
+            JobState::new(true, true, 0, Some(-11), None),
+            JobState::Crashed
+        );
+        assert_eq!(
+            JobState::new(true, true, 0, Some(-9), None),
+            JobState::ExitedError
+        );
+        assert_eq!(
+            JobState::new(true, true, 0, Some(0), None),
+            JobState::ExitedOk
+        );
+        assert_eq!(
+            JobState::new(true, false, 42, None, None),
+            JobState::Running
+        );
+        assert_eq!(
+            JobState::new(false, false, 0, None, None),
+            JobState::Disabled
+        );
+        assert_eq!(
+            JobState::new(false, true, 0, None, None),
+            JobState::NotLoaded
+        );
     }
 }
@@ -0,0 +1,219 @@
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::launchd::query::ServiceEntry;
+
+/// A change between two polls of list_all_detailed, for launchk --events
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum JobEvent {
+    Loaded {
+        label: String,
+        domain: String,
+    },
+    Unloaded {
+        label: String,
+        domain: String,
+    },
+    Started {
+        label: String,
+        domain: String,
+        pid: i64,
+    },
+    /// status is launchctl list's, negative for a signal
+    Exited {
+        label: String,
+        domain: String,
+        pid: i64,
+        status: Option<i64>,
+    },
+    /// Exited and started again between polls
+    PidChanged {
+        label: String,
+        domain: String,
+        old_pid: i64,
+        pid: i64,
+    },
+}
+
+impl JobEvent {
+    pub fn label(&self) -> &str {
+        match self {
+            JobEvent::Loaded { label, .. }
+            | JobEvent::Unloaded { label, .. }
+            | JobEvent::Started { label, .. }
+            | JobEvent::Exited { label, .. }
+            | JobEvent::PidChanged { label, .. } => label,
+        }
+    }
+}
+
+/// A JSON line: seconds since epoch and the event
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct EventLine {
+    pub time: u64,
+    #[serde(flatten)]
+    pub event: JobEvent,
+}
+
+impl From<JobEvent> for EventLine {
+    fn from(event: JobEvent) -> Self {
+        EventLine {
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            event,
+        }
+    }
+}
+
+/// Entries by (label, domain), a label can be loaded in several domains
+fn by_job(entries: &[ServiceEntry]) -> BTreeMap<(String, String), &ServiceEntry> {
+    entries
+        .iter()
+        .map(|e| ((e.label.clone(), e.domain.to_string()), e))
+        .collect()
+}
+
+/// Events going from older to newer, unloads (and their exits) first
+pub fn job_events(older: &[ServiceEntry], newer: &[ServiceEntry]) -> Vec<JobEvent> {
+    let older = by_job(older);
+    let newer = by_job(newer);
+    let mut events = vec![];
+
+    for ((label, domain), old) in &older {
+        let (label, domain) = (label.clone(), domain.clone());
+
+        let new = match newer.get(&(label.clone(), domain.clone())) {
+            Some(new) => new,
+            None => {
+                if old.pid > 0 {
+                    events.push(JobEvent::Exited {
+                        label: label.clone(),
+                        domain: domain.clone(),
+                        pid: old.pid,
+                        status: None,
+                    });
+                }
+                events.push(JobEvent::Unloaded { label, domain });
+                continue;
+            }
+        };
+
+        match (old.pid, new.pid) {
+            (0, 0) => (),
+            (0, pid) => events.push(JobEvent::Started { label, domain, pid }),
+            (pid, 0) => events.push(JobEvent::Exited {
+                label,
+                domain,
+                pid,
+                status: new.status,
+            }),
+            (old_pid, pid) if old_pid != pid => events.push(JobEvent::PidChanged {
+                label,
+                domain,
+                old_pid,
+                pid,
+            }),
+            _ => (),
+        }
+    }
+
+    for ((label, domain), new) in &newer {
+        if older.contains_key(&(label.clone(), domain.clone())) {
+            continue;
+        }
+
+        events.push(JobEvent::Loaded {
+            label: label.clone(),
+            domain: domain.clone(),
+        });
+
+        if new.pid > 0 {
+            events.push(JobEvent::Started {
+                label: label.clone(),
+                domain: domain.clone(),
+                pid: new.pid,
+            });
+        }
+    }
+
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{job_events, JobEvent};
+    use crate::launchd::query::ServiceEntry;
+    use xpc_sys::enums::DomainType;
+
+    fn entry(label: &str, pid: i64, status: Option<i64>) -> ServiceEntry {
+        ServiceEntry {
+            label: label.to_string(),
+            domain: DomainType::System,
+            pid,
+            status,
+            plist_path: None,
+            unparseable: vec![],
+        }
+    }
+
+    #[test]
+    fn events_between_polls() {
+        let older = vec![
+            entry("com.example.exits", 10, None),
+            entry("com.example.respawns", 11, None),
+            entry("com.example.unloads", 12, None),
+            entry("com.example.starts", 0, Some(0)),
+        ];
+        let newer = vec![
+            entry("com.example.exits", 0, Some(-9)),
+            entry("com.example.respawns", 20, None),
+            entry("com.example.starts", 21, None),
+            entry("com.example.loads", 0, None),
+        ];
+
+        let domain = DomainType::System.to_string();
+        let events = job_events(&older, &newer);
+
+        assert_eq!(
+            events,
+            vec![
+                JobEvent::Exited {
+                    label: "com.example.exits".to_string(),
+                    domain: domain.clone(),
+                    pid: 10,
+                    status: Some(-9),
+                },
+                JobEvent::PidChanged {
+                    label: "com.example.respawns".to_string(),
+                    domain: domain.clone(),
+                    old_pid: 11,
+                    pid: 20,
+                },
+                JobEvent::Started {
+                    label: "com.example.starts".to_string(),
+                    domain: domain.clone(),
+                    pid: 21,
+                },
+                JobEvent::Exited {
+                    label: "com.example.unloads".to_string(),
+                    domain: domain.clone(),
+                    pid: 12,
+                    status: None,
+                },
+                JobEvent::Unloaded {
+                    label: "com.example.unloads".to_string(),
+                    domain: domain.clone(),
+                },
+                JobEvent::Loaded {
+                    label: "com.example.loads".to_string(),
+                    domain,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::{BTreeMap, HashMap};
+use std::ptr::slice_from_raw_parts;
+
+use xpc_sys::code_signing::{code_signature, CodeSignature};
+
+use crate::launchd::backup::{list_backups, Backup};
+use crate::launchd::crash_reports::{crash_reports, CrashReport};
+use crate::launchd::domain_target::DomainTarget;
+use crate::launchd::endpoints::{check_reachability, job_endpoints, Endpoint};
+use crate::launchd::entry_status::ExitInfo;
+use crate::launchd::events::EventLine;
+use crate::launchd::job_stats::job_history;
+use crate::launchd::limits::{plist_limits, Limit};
+use crate::launchd::print_parser::{parse, StateSection};
+use crate::launchd::procinfo::ProcInfo;
+use crate::launchd::query::{blame, dumpstate, limits, procinfo_parsed};
+use crate::launchd::schedule::JobSchedule;
+use crate::launchd::signing::executable_path;
+use crate::launchd::triggers::{launch_triggers, Trigger};
+use crate::launchd::watch_paths::{watched_paths, WatchedPath};
+
+/// A job's section of dumpstate, plus procinfo if it is running
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct JobDetail {
+    /// Service target, e.g. gui/501/com.apple.Spotlight
+    pub target: String,
+    pub state: Option<String>,
+    pub pid: Option<String>,
+    pub program: Option<String>,
+    pub plist_path: Option<String>,
+    pub last_exit_code: Option<String>,
+    pub arguments: Vec<String>,
+    pub environment: Vec<(String, String)>,
+    pub mach_services: Vec<String>,
+    /// Everything else from dumpstate
+    pub section: StateSection,
+    /// procinfo output for the running process
+    pub process: Option<ProcInfo>,
+    /// Domain the job is in, e.g. gui/501
+    pub domain_target: Option<DomainTarget>,
+    /// launchctl setenv variables of the domain
+    pub domain_environment: Vec<(String, String)>,
+    /// Reason launchd last started the job
+    pub blame: Option<String>,
+    /// StartInterval or StartCalendarInterval from the plist
+    pub schedule: Option<JobSchedule>,
+    /// Sockets and MachServices, declared and at runtime
+    pub endpoints: Vec<Endpoint>,
+    /// What will start it next
+    pub triggers: Vec<Trigger>,
+    /// WatchPaths and QueueDirectories, as they are on disk now
+    pub watched_paths: Vec<WatchedPath>,
+    /// Soft/HardResourceLimits from the plist
+    pub limits: Vec<(&'static str, Limit)>,
+    /// launchctl limit, what jobs get without their own
+    pub launchd_limits: BTreeMap<String, Limit>,
+    /// Executable of the running process and its code signature
+    pub executable: Option<String>,
+    pub signature: Option<Result<CodeSignature, String>>,
+    /// Copies taken before edit or disable, newest first
+    pub backups: Vec<Backup>,
+    /// Recent reports for the program if it last exited by crashing,
+    /// newest first
+    pub crash_reports: Vec<CrashReport>,
+    /// Starts, exits and PID changes seen while polling, oldest first
+    pub history: Vec<EventLine>,
+}
+
+impl From<StateSection> for JobDetail {
+    fn from(section: StateSection) -> Self {
+        let owned = |key: &str| section.value(key).map(String::from);
+
+        let environment = ["default environment", "environment"]
+            .iter()
+            .filter_map(|name| section.section(name))
+            .flat_map(|s| s.values.clone())
+            .collect();
+
+        let mach_services = section
+            .section("endpoints")
+            .map(|s| s.sections.iter().map(|e| e.name.clone()).collect())
+            .unwrap_or_default();
+
+        Self {
+            target: section.name.clone(),
+            state: owned("state"),
+            pid: owned("pid"),
+            program: owned("program"),
+            plist_path: owned("path"),
+            last_exit_code: owned("last exit code"),
+            arguments: section
+                .section("arguments")
+                .map(|s| s.items.clone())
+                .unwrap_or_default(),
+            environment,
+            mach_services,
+            process: None,
+            domain_target: section
+                .name
+                .rsplit_once('/')
+                .and_then(|(domain, _)| domain.parse().ok()),
+            domain_environment: vec![],
+            blame: None,
+            schedule: None,
+            endpoints: vec![],
+            triggers: vec![],
+            watched_paths: vec![],
+            limits: vec![],
+            launchd_limits: BTreeMap::new(),
+            executable: None,
+            signature: None,
+            backups: vec![],
+            crash_reports: vec![],
+            history: vec![],
+            section,
+        }
+    }
+}
+
+/// Is this dumpstate block name for label (in any domain)?
+pub fn is_job_target(target: &str, label: &str) -> bool {
+    target == label || target.ends_with(&format!("/{}", label))
+}
+
+/// dumpstate parsed into domain and service blocks
+pub fn parsed_dumpstate() -> Result<StateSection, String> {
+    let (size, shmem) = dumpstate().map_err(|e| e.to_string())?;
+    let text =
+        String::from_utf8_lossy(unsafe { &*slice_from_raw_parts(shmem.region as *mut u8, size) })
+            .to_string();
+
+    Ok(parse(&text))
+}
+
+/// What the background dumpstate poll keeps for each job
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct DumpstateJob {
+    pub plist_path: Option<String>,
+    pub exit: ExitInfo,
+}
+
+/// Label -> plist path and exit info for every job in dumpstate
+pub fn dumpstate_jobs() -> Result<HashMap<String, DumpstateJob>, String> {
+    let state = parsed_dumpstate()?;
+
+    Ok(state
+        .sections
+        .iter()
+        .filter(|s| s.name.parse::<DomainTarget>().is_err())
+        .filter_map(|s| {
+            let (_, label) = s.name.rsplit_once('/')?;
+            let job = DumpstateJob {
+                plist_path: s.value("path").map(String::from),
+                exit: ExitInfo::from(s),
+            };
+
+            Some((label.to_string(), job))
+        })
+        .collect())
+}
+
+/// Find label in dumpstate, and attach procinfo if pid > 0
+pub fn job_detail<S: AsRef<str>>(label: S, pid: i64) -> Result<JobDetail, String> {
+    let label = label.as_ref();
+    let state = parsed_dumpstate()?;
+
+    let mut detail: JobDetail = state
+        .sections
+        .iter()
+        .find(|s| is_job_target(&s.name, label))
+        .cloned()
+        .map(JobDetail::from)
+        .ok_or_else(|| format!("{} not found in dumpstate", label))?;
+
+    if let Some(target) = &detail.domain_target {
+        detail.domain_environment = state
+            .section(&target.to_string())
+            .and_then(|d| d.section("environment"))
+            .map(|e| e.values.clone())
+            .unwrap_or_default();
+    }
+
+    let plist = detail
+        .plist_path
+        .as_ref()
+        .and_then(|p| plist::Value::from_file(p).ok());
+
+    detail.schedule = plist.as_ref().and_then(JobSchedule::from_plist);
+    detail.endpoints = job_endpoints(plist.as_ref(), Some(&detail.section));
+    check_reachability(&mut detail.endpoints);
+    detail.triggers = launch_triggers(plist.as_ref(), &detail.endpoints, detail.schedule.as_ref());
+    detail.watched_paths = watched_paths(plist.as_ref());
+    detail.limits = plist_limits(plist.as_ref());
+    detail.launchd_limits = limits().unwrap_or_default();
+    detail.backups = list_backups(label);
+    detail.history = job_history(label);
+
+    // launchd keeps the reason after the job exits
+    detail.blame = detail
+        .domain_target
+        .as_ref()
+        .and_then(|t| blame(label, t).ok());
+
+    if ExitInfo::from(&detail.section).crashed() {
+        detail.crash_reports = detail
+            .program
+            .as_deref()
+            .map(crash_reports)
+            .unwrap_or_default();
+    }
+
+    if pid > 0 {
+        detail.process = procinfo_parsed(pid).ok();
+
+        detail.executable = executable_path(pid)
+            .or_else(|| detail.process.as_ref().and_then(|p| p.program_path.clone()));
+        detail.signature = detail.executable.as_deref().map(code_signature);
+    }
+
+    Ok(detail)
+}
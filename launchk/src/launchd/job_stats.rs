@@ -0,0 +1,202 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{config, config_path};
+use crate::launchd::events::{job_events, EventLine, JobEvent};
+use crate::launchd::query::ServiceEntry;
+
+/// Events kept per label
+const MAX_EVENTS: usize = 100;
+/// Sparkline of starts per minute, over the last 8 minutes
+const SPARK_BUCKETS: usize = 8;
+const SPARK_BUCKET_SECS: u64 = 60;
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// PID changes, restarts and exits of a job while launchk was watching
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct JobStats {
+    /// Started again after exiting, or a new PID between polls
+    pub restarts: u64,
+    /// Oldest first
+    pub events: VecDeque<EventLine>,
+}
+
+lazy_static! {
+    static ref STATS: Mutex<HashMap<String, JobStats>> = Mutex::new(load());
+    /// The last list, to diff the next one against
+    static ref PREVIOUS: Mutex<Option<Vec<ServiceEntry>>> = Mutex::new(None);
+}
+
+fn stats_path() -> PathBuf {
+    config_path().with_file_name("stats.json")
+}
+
+/// Stats from the last run, if persist_stats is on
+fn load() -> HashMap<String, JobStats> {
+    if !config().persist_stats {
+        return HashMap::new();
+    }
+
+    fs::read_to_string(stats_path())
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+fn save(stats: &HashMap<String, JobStats>) {
+    let path = stats_path();
+    let result = serde_json::to_string(stats)
+        .map_err(|e| e.to_string())
+        .and_then(|json| fs::write(&path, json).map_err(|e| e.to_string()));
+
+    if let Err(e) = result {
+        log::error!("[job_stats/save]: {:?} {}", path, e);
+    }
+}
+
+impl JobStats {
+    fn record(&mut self, line: EventLine) {
+        let exited_before = self
+            .events
+            .iter()
+            .any(|e| matches!(e.event, JobEvent::Exited { .. }));
+
+        match line.event {
+            JobEvent::PidChanged { .. } => self.restarts += 1,
+            JobEvent::Started { .. } if exited_before => self.restarts += 1,
+            _ => (),
+        }
+
+        self.events.push_back(line);
+
+        while self.events.len() > MAX_EVENTS {
+            self.events.pop_front();
+        }
+    }
+
+    /// Starts per minute, oldest first, as of now (seconds since epoch)
+    pub fn sparkline(&self, now: u64) -> String {
+        let mut buckets = [0_usize; SPARK_BUCKETS];
+
+        for line in &self.events {
+            let started = matches!(
+                line.event,
+                JobEvent::Started { .. } | JobEvent::PidChanged { .. }
+            );
+
+            let bucket = (now.saturating_sub(line.time) / SPARK_BUCKET_SECS) as usize;
+            if started && bucket < SPARK_BUCKETS {
+                buckets[SPARK_BUCKETS - 1 - bucket] += 1;
+            }
+        }
+
+        let max = buckets.iter().copied().max().unwrap_or(0).max(1);
+
+        buckets
+            .iter()
+            .map(|n| SPARKS[(n * (SPARKS.len() - 1)).div_ceil(max)])
+            .collect()
+    }
+}
+
+/// Diff the latest list against the last one, updating each job's stats
+pub fn record_stats(entries: &[ServiceEntry]) {
+    let older = PREVIOUS
+        .lock()
+        .expect("Must read previous list")
+        .replace(entries.to_vec());
+
+    // Everything would look newly started on the first poll
+    let events = match older {
+        Some(older) => job_events(&older, entries),
+        None => return,
+    };
+
+    if events.is_empty() {
+        return;
+    }
+
+    let mut stats = STATS.lock().expect("Must update stats");
+
+    for event in events {
+        stats
+            .entry(event.label().to_string())
+            .or_default()
+            .record(EventLine::from(event));
+    }
+
+    if config().persist_stats {
+        save(&stats);
+    }
+}
+
+/// (restarts, sparkline) for the service list, None until anything
+/// happened to label
+pub fn restart_summary(label: &str) -> Option<(u64, String)> {
+    let stats = STATS.try_lock().ok()?;
+    let job = stats.get(label)?;
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    Some((job.restarts, job.sparkline(now)))
+}
+
+/// Everything recorded for label, oldest first
+pub fn job_history(label: &str) -> Vec<EventLine> {
+    STATS
+        .lock()
+        .ok()
+        .and_then(|s| s.get(label).map(|j| j.events.iter().cloned().collect()))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JobStats;
+    use crate::launchd::events::{EventLine, JobEvent};
+
+    fn line(time: u64, event: JobEvent) -> EventLine {
+        EventLine { time, event }
+    }
+
+    #[test]
+    fn count_restarts() {
+        let (label, domain) = ("com.example.flappy".to_string(), "gui/501".to_string());
+        let started = |pid| JobEvent::Started {
+            label: label.clone(),
+            domain: domain.clone(),
+            pid,
+        };
+        let exited = |pid| JobEvent::Exited {
+            label: label.clone(),
+            domain: domain.clone(),
+            pid,
+            status: Some(1),
+        };
+
+        let mut stats = JobStats::default();
+        stats.record(line(0, started(10)));
+        stats.record(line(10, exited(10)));
+        stats.record(line(20, started(11)));
+        stats.record(line(
+            420,
+            JobEvent::PidChanged {
+                label: label.clone(),
+                domain: domain.clone(),
+                old_pid: 11,
+                pid: 12,
+            },
+        ));
+
+        assert_eq!(stats.restarts, 2);
+        assert_eq!(stats.sparkline(479), "█▁▁▁▁▁▁▅");
+    }
+}
@@ -1,6 +1,8 @@
 use std::fmt;
 use std::fmt::Formatter;
 
+use xpc_sys::enums::SessionType;
+
 bitflags! {
     #[derive(Clone, Copy, Default, Eq, PartialEq, Hash)]
     /// Bitmask for filtering on the job type, which is a mix
@@ -12,46 +14,72 @@ bitflags! {
         const AGENT  = (1 << 4);
         const DAEMON = (1 << 5);
         const LOADED = (1 << 6);
+        /// In the disabled override database (print-disabled)
+        const DISABLED = (1 << 7);
+        /// Running from an unsigned or ad-hoc signed binary
+        const UNSIGNED = (1 << 8);
+        /// No disable override, complements DISABLED
+        const ENABLED = (1 << 9);
+        /// LimitLoadToSessionType, agents without one are Aqua
+        const AQUA = (1 << 10);
+        const BACKGROUND = (1 << 11);
+        const LOGINWINDOW = (1 << 12);
+        const STANDARDIO = (1 << 13);
+        /// Plist in the standard directories but not loaded
+        const ORPHANED = (1 << 14);
+        /// Loaded, but no plist on disk or in dumpstate
+        const GHOST = (1 << 15);
     }
 }
 
+/// (mask, omnibox key, name) in display order
+pub static JOB_TYPE_FILTER_KEYS: [(JobTypeFilter, char, &str); 15] = [
+    (JobTypeFilter::SYSTEM, 's', "system"),
+    (JobTypeFilter::GLOBAL, 'g', "global"),
+    (JobTypeFilter::USER, 'u', "user"),
+    (JobTypeFilter::AGENT, 'a', "agent"),
+    (JobTypeFilter::DAEMON, 'd', "daemon"),
+    (JobTypeFilter::LOADED, 'l', "loaded"),
+    (JobTypeFilter::ENABLED, 'e', "enabled"),
+    (JobTypeFilter::DISABLED, 'x', "disabled"),
+    (JobTypeFilter::UNSIGNED, 'n', "unsigned"),
+    (JobTypeFilter::AQUA, 'q', "aqua"),
+    (JobTypeFilter::BACKGROUND, 'b', "background"),
+    (JobTypeFilter::LOGINWINDOW, 'w', "loginwindow"),
+    (JobTypeFilter::STANDARDIO, 'o', "stdio"),
+    (JobTypeFilter::ORPHANED, 'r', "orphaned"),
+    (JobTypeFilter::GHOST, 'h', "ghost"),
+];
+
 impl JobTypeFilter {
     pub fn launchk_default() -> Self {
         let mut jtf = JobTypeFilter::default();
         jtf.toggle(JobTypeFilter::LOADED);
         jtf
     }
+
+    /// Bit for a LimitLoadToSessionType, System has none as it is the
+    /// daemons' default
+    pub fn for_session_type(session_type: &SessionType) -> Self {
+        match session_type {
+            SessionType::Aqua => JobTypeFilter::AQUA,
+            SessionType::Background => JobTypeFilter::BACKGROUND,
+            SessionType::LoginWindow => JobTypeFilter::LOGINWINDOW,
+            SessionType::StandardIO => JobTypeFilter::STANDARDIO,
+            _ => JobTypeFilter::empty(),
+        }
+    }
 }
 
 /// Represent the bitmask as a string for easy TUI check for styling
 /// hotkey status
 impl fmt::Display for JobTypeFilter {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        let mut display = format!("");
-
-        if (*self & JobTypeFilter::SYSTEM) == JobTypeFilter::SYSTEM {
-            display.push('s');
-        }
-
-        if (*self & JobTypeFilter::GLOBAL) == JobTypeFilter::GLOBAL {
-            display.push('g');
-        }
-
-        if (*self & JobTypeFilter::USER) == JobTypeFilter::USER {
-            display.push('u');
-        }
-
-        if (*self & JobTypeFilter::AGENT) == JobTypeFilter::AGENT {
-            display.push('a');
-        }
-
-        if (*self & JobTypeFilter::DAEMON) == JobTypeFilter::DAEMON {
-            display.push('d');
-        }
-
-        if (*self & JobTypeFilter::LOADED) == JobTypeFilter::LOADED {
-            display.push('l');
-        }
+        let display: String = JOB_TYPE_FILTER_KEYS
+            .iter()
+            .filter(|(mask, _, _)| self.contains(*mask))
+            .map(|(_, key, _)| key)
+            .collect();
 
         write!(f, "{}", display)
     }
@@ -66,6 +94,15 @@ impl fmt::Debug for JobTypeFilter {
             JobTypeFilter::AGENT => write!(f, "AGENT"),
             JobTypeFilter::DAEMON => write!(f, "DAEMON"),
             JobTypeFilter::LOADED => write!(f, "LOADED"),
+            JobTypeFilter::ENABLED => write!(f, "ENABLED"),
+            JobTypeFilter::DISABLED => write!(f, "DISABLED"),
+            JobTypeFilter::UNSIGNED => write!(f, "UNSIGNED"),
+            JobTypeFilter::AQUA => write!(f, "AQUA"),
+            JobTypeFilter::BACKGROUND => write!(f, "BACKGROUND"),
+            JobTypeFilter::LOGINWINDOW => write!(f, "LOGINWINDOW"),
+            JobTypeFilter::STANDARDIO => write!(f, "STANDARDIO"),
+            JobTypeFilter::ORPHANED => write!(f, "ORPHANED"),
+            JobTypeFilter::GHOST => write!(f, "GHOST"),
             _ => Ok(()),
         }
     }
@@ -0,0 +1,150 @@
+use std::fmt;
+
+use plist::{Dictionary, Value};
+
+/// (launchctl limit name, Soft/HardResourceLimits key)
+pub const RESOURCES: [(&str, &str); 9] = [
+    ("cpu", "CPU"),
+    ("filesize", "FileSize"),
+    ("data", "Data"),
+    ("stack", "Stack"),
+    ("core", "Core"),
+    ("rss", "ResidentSetSize"),
+    ("memlock", "MemoryLock"),
+    ("maxproc", "NumberOfProcesses"),
+    ("maxfiles", "NumberOfFiles"),
+];
+
+/// RLIM_INFINITY
+pub const UNLIMITED: u64 = i64::MAX as u64;
+
+/// setrlimit() soft and hard values, None if not set
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct Limit {
+    pub soft: Option<u64>,
+    pub hard: Option<u64>,
+}
+
+fn limit_value(value: Option<u64>) -> String {
+    match value {
+        None => "-".to_string(),
+        Some(v) if v >= UNLIMITED => "unlimited".to_string(),
+        Some(v) => v.to_string(),
+    }
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", limit_value(self.soft), limit_value(self.hard))
+    }
+}
+
+/// "unlimited" or a number, as launchctl limit takes them
+pub fn parse_limit_value(value: &str) -> Result<u64, String> {
+    match value {
+        "unlimited" => Ok(UNLIMITED),
+        v => v
+            .parse()
+            .map_err(|_| format!("{} is not a number or unlimited", v)),
+    }
+}
+
+/// launchctl limit name from a plist key or the name itself
+pub fn resource_name(name: &str) -> Option<&'static str> {
+    RESOURCES
+        .iter()
+        .find(|(n, key)| *n == name || *key == name)
+        .map(|(n, _)| *n)
+}
+
+fn plist_values(dict: &Dictionary, key: &str) -> Vec<(&'static str, u64)> {
+    let limits = match dict.get(key).and_then(Value::as_dictionary) {
+        Some(l) => l,
+        None => return vec![],
+    };
+
+    RESOURCES
+        .iter()
+        .filter_map(|(name, key)| {
+            let value = limits.get(key)?;
+            let value = value
+                .as_unsigned_integer()
+                .or_else(|| value.as_signed_integer().map(|i| i.max(0) as u64))?;
+
+            Some((*name, value))
+        })
+        .collect()
+}
+
+/// SoftResourceLimits and HardResourceLimits, in RESOURCES order
+pub fn plist_limits(plist: Option<&Value>) -> Vec<(&'static str, Limit)> {
+    let dict = match plist.and_then(Value::as_dictionary) {
+        Some(d) => d,
+        None => return vec![],
+    };
+
+    let soft = plist_values(dict, "SoftResourceLimits");
+    let hard = plist_values(dict, "HardResourceLimits");
+    let find = |values: &[(&str, u64)], name: &str| {
+        values.iter().find(|(n, _)| *n == name).map(|(_, v)| *v)
+    };
+
+    RESOURCES
+        .iter()
+        .map(|(name, _)| {
+            let limit = Limit {
+                soft: find(&soft, name),
+                hard: find(&hard, name),
+            };
+
+            (*name, limit)
+        })
+        .filter(|(_, limit)| *limit != Limit::default())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_limit_value, plist_limits, resource_name, Limit, UNLIMITED};
+    use plist::{Dictionary, Value};
+
+    #[test]
+    fn limits_from_plist() {
+        let mut soft = Dictionary::new();
+        soft.insert("NumberOfFiles".to_string(), 1024.into());
+
+        let mut hard = Dictionary::new();
+        hard.insert("NumberOfFiles".to_string(), 4096.into());
+        hard.insert("Core".to_string(), 0.into());
+
+        let mut dict = Dictionary::new();
+        dict.insert("SoftResourceLimits".to_string(), Value::Dictionary(soft));
+        dict.insert("HardResourceLimits".to_string(), Value::Dictionary(hard));
+
+        let limits = plist_limits(Some(&Value::Dictionary(dict)));
+        assert_eq!(
+            limits,
+            vec![
+                (
+                    "core",
+                    Limit {
+                        soft: None,
+                        hard: Some(0)
+                    }
+                ),
+                (
+                    "maxfiles",
+                    Limit {
+                        soft: Some(1024),
+                        hard: Some(4096)
+                    }
+                ),
+            ]
+        );
+        assert_eq!(limits[1].1.to_string(), "1024/4096");
+
+        assert_eq!(parse_limit_value("unlimited"), Ok(UNLIMITED));
+        assert!(parse_limit_value("lots").is_err());
+        assert_eq!(resource_name("NumberOfProcesses"), Some("maxproc"));
+    }
+}
@@ -0,0 +1,303 @@
+use std::fmt;
+use std::process::Command;
+
+use xpc_sys::enums::DomainType;
+
+use crate::launchd::domain_target::DomainTarget;
+
+/// Type of a Background Task Management record
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum LoginItemKind {
+    /// An app that has login items or agents embedded in it
+    App,
+    /// Opened at login (SMAppService.loginItem, or System Settings)
+    LoginItem,
+    Agent,
+    Daemon,
+    /// Groups items by Team ID
+    Developer,
+    Other(String),
+}
+
+impl From<&str> for LoginItemKind {
+    fn from(kind: &str) -> Self {
+        match kind {
+            "app" => LoginItemKind::App,
+            "login item" => LoginItemKind::LoginItem,
+            "agent" => LoginItemKind::Agent,
+            "daemon" => LoginItemKind::Daemon,
+            "developer" => LoginItemKind::Developer,
+            other => LoginItemKind::Other(other.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for LoginItemKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoginItemKind::App => write!(f, "app"),
+            LoginItemKind::LoginItem => write!(f, "login item"),
+            LoginItemKind::Agent => write!(f, "agent"),
+            LoginItemKind::Daemon => write!(f, "daemon"),
+            LoginItemKind::Developer => write!(f, "developer"),
+            LoginItemKind::Other(kind) => write!(f, "{}", kind),
+        }
+    }
+}
+
+/// One record from sfltool dumpbtm (macOS 13+)
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct LoginItem {
+    /// Owner of the record, from "Records for UID"
+    pub uid: Option<u32>,
+    pub name: String,
+    pub developer: Option<String>,
+    pub team_id: Option<String>,
+    pub kind: LoginItemKind,
+    pub enabled: bool,
+    pub allowed: bool,
+    /// <type number>.<bundle id or label>
+    pub identifier: String,
+    pub url: Option<String>,
+    pub executable: Option<String>,
+    pub bundle_id: Option<String>,
+    /// Identifier of the app it is embedded in
+    pub parent: Option<String>,
+}
+
+impl LoginItem {
+    fn new(uid: Option<u32>) -> Self {
+        Self {
+            uid,
+            name: String::new(),
+            developer: None,
+            team_id: None,
+            kind: LoginItemKind::Other(String::new()),
+            enabled: false,
+            allowed: false,
+            identifier: String::new(),
+            url: None,
+            executable: None,
+            bundle_id: None,
+            parent: None,
+        }
+    }
+
+    /// launchd label of an agent or daemon, the identifier without its
+    /// type prefix
+    pub fn label(&self) -> Option<&str> {
+        match self.kind {
+            LoginItemKind::Agent | LoginItemKind::Daemon => self
+                .identifier
+                .split_once('.')
+                .map(|(_, label)| label)
+                .or(Some(&self.identifier)),
+            _ => None,
+        }
+    }
+
+    /// Where launchctl enable/disable applies, None for items only
+    /// System Settings can change
+    pub fn domain_target(&self) -> Option<DomainTarget> {
+        match (&self.kind, self.uid) {
+            (LoginItemKind::Daemon, _) => Some(DomainTarget::new(DomainType::System, 0)),
+            (LoginItemKind::Agent, Some(uid)) if uid > 0 => {
+                Some(DomainTarget::new(DomainType::Gui, uid as u64))
+            }
+            _ => None,
+        }
+    }
+
+    /// The .app this comes from: its own URL, or its parent's
+    pub fn source_app(&self, items: &[LoginItem]) -> Option<String> {
+        let own = self.url.as_deref().and_then(app_bundle);
+
+        own.or_else(|| {
+            let parent = self.parent.as_ref()?;
+            items
+                .iter()
+                .find(|i| &i.identifier == parent)
+                .and_then(|i| i.url.as_deref())
+                .and_then(app_bundle)
+        })
+    }
+}
+
+/// Path of the .app in a file:// URL, e.g. file:///Applications/Docker.app/
+fn app_bundle(url: &str) -> Option<String> {
+    let path = percent_decode(url.strip_prefix("file://").unwrap_or(url));
+    let end = path.find(".app/").map(|i| i + 4).or_else(|| {
+        if path.ends_with(".app") {
+            Some(path.len())
+        } else {
+            None
+        }
+    })?;
+
+    Some(path[..end].to_string())
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                out.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&out).to_string()
+}
+
+/// Parse sfltool dumpbtm, records are "#N:" followed by "Key: value"
+/// lines
+pub fn parse_dumpbtm(text: &str) -> Vec<LoginItem> {
+    let mut items = vec![];
+    let mut uid = None;
+    let mut current: Option<LoginItem> = None;
+
+    for line in text.lines().map(str::trim) {
+        if let Some(rest) = line.strip_prefix("Records for UID ") {
+            uid = rest.split_whitespace().next().and_then(|u| u.parse().ok());
+            items.extend(current.take());
+            continue;
+        }
+
+        // "#1:" starts a record, "#1: 16.com.example" is an embedded item
+        if line.starts_with('#') && line.ends_with(':') {
+            items.extend(current.replace(LoginItem::new(uid)));
+            continue;
+        }
+
+        let (item, (key, value)) = match (current.as_mut(), line.split_once(": ")) {
+            (Some(item), Some(kv)) => (item, kv),
+            _ => continue,
+        };
+        let value = value.trim();
+
+        match key {
+            "Name" => item.name = value.to_string(),
+            "Developer Name" => item.developer = Some(value.to_string()),
+            "Team Identifier" => item.team_id = Some(value.to_string()),
+            "Type" => {
+                let kind = value.split(" (").next().unwrap_or(value);
+                item.kind = LoginItemKind::from(kind);
+            }
+            "Disposition" => {
+                let flags = value
+                    .split(']')
+                    .next()
+                    .unwrap_or("")
+                    .trim_start_matches('[');
+                let flags: Vec<&str> = flags.split(',').map(str::trim).collect();
+
+                item.enabled = flags.contains(&"enabled");
+                item.allowed = flags.contains(&"allowed");
+            }
+            "Identifier" => item.identifier = value.to_string(),
+            "URL" => item.url = Some(value.to_string()),
+            "Executable Path" => item.executable = Some(value.to_string()),
+            "Bundle Identifier" => item.bundle_id = Some(value.to_string()),
+            "Parent Identifier" => item.parent = Some(value.to_string()),
+            _ => (),
+        }
+    }
+
+    items.extend(current);
+    items
+}
+
+/// Login items and app-embedded agents/daemons registered with Background
+/// Task Management, needs root
+pub fn login_items() -> Result<Vec<LoginItem>, String> {
+    let output = Command::new("sfltool")
+        .arg("dumpbtm")
+        .output()
+        .map_err(|e| format!("Cannot run sfltool (macOS 13+): {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "sfltool dumpbtm failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(parse_dumpbtm(&String::from_utf8_lossy(&output.stdout))
+        .into_iter()
+        .filter(|i| i.kind != LoginItemKind::Developer)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_dumpbtm, LoginItemKind};
+
+    const DUMPBTM: &str = "
+========================
+ Records for UID 501 : 6B29D2E7-0000-0000-0000-000000000000
+========================
+
+ ServiceManagement migrated: true
+
+ Items:
+
+ #1:
+                 UUID: 2C2C3E0A-0000-0000-0000-000000000000
+                 Name: Docker
+       Developer Name: Docker Inc
+      Team Identifier: 9BNSXJN65R
+                 Type: app (0x2)
+          Disposition: [enabled, allowed, visible, notified] (11)
+           Identifier: 2.com.docker.docker
+                  URL: file:///Applications/Docker%20Desktop.app/
+    Bundle Identifier: com.docker.docker
+    Embedded Item Identifiers:
+      #1: 16.com.docker.vmnetd
+
+ #2:
+                 Name: com.docker.helper
+                 Type: agent (0x8)
+          Disposition: [disabled, allowed, visible, notified] (10)
+           Identifier: 8.com.docker.helper
+                  URL: Contents/Library/LaunchAgents/com.docker.helper.plist
+    Parent Identifier: 2.com.docker.docker
+";
+
+    #[test]
+    fn dumpbtm_records() {
+        let items = parse_dumpbtm(DUMPBTM);
+        assert_eq!(items.len(), 2);
+
+        let (app, agent) = (&items[0], &items[1]);
+        assert_eq!(app.kind, LoginItemKind::App);
+        assert_eq!(app.team_id.as_deref(), Some("9BNSXJN65R"));
+        assert!(app.enabled && app.allowed);
+        assert_eq!(app.label(), None);
+
+        assert_eq!(agent.uid, Some(501));
+        assert!(!agent.enabled);
+        assert_eq!(agent.label(), Some("com.docker.helper"));
+        assert_eq!(
+            agent.source_app(&items).as_deref(),
+            Some("/Applications/Docker Desktop.app")
+        );
+        assert_eq!(
+            agent.domain_target().map(|t| t.to_string()).as_deref(),
+            Some("gui/501")
+        );
+    }
+}
@@ -0,0 +1,252 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::launchd::domain_target::DomainTarget;
+use crate::launchd::job_detail::{is_job_target, parsed_dumpstate};
+use crate::launchd::print_parser::StateSection;
+use crate::launchd::procinfo::ProcInfo;
+use crate::launchd::query::{list_all_detailed, procinfo_parsed, resolve_service};
+
+/// Which jobs publish which MachServices, and which jobs reference
+/// them elsewhere in their dumpstate block (e.g. event channels)
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct MachServiceGraph {
+    /// service name -> job targets publishing it
+    providers: BTreeMap<String, BTreeSet<String>>,
+    /// service name -> job targets holding an endpoint to it
+    consumers: BTreeMap<String, BTreeSet<String>>,
+}
+
+/// Relationships for one job
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct MachServiceLinks {
+    /// (service, jobs consuming it)
+    pub provides: Vec<(String, Vec<String>)>,
+    /// (service, jobs providing it)
+    pub consumes: Vec<(String, Vec<String>)>,
+}
+
+/// Service blocks are <domain target>/<label>, e.g. gui/501/com.apple.Finder
+fn is_service_section(section: &StateSection) -> bool {
+    section.name.parse::<DomainTarget>().is_err()
+        && section
+            .name
+            .rsplit_once('/')
+            .map(|(domain, _)| domain.parse::<DomainTarget>().is_ok())
+            .unwrap_or(false)
+}
+
+fn published(section: &StateSection) -> impl Iterator<Item = &String> {
+    section
+        .section("endpoints")
+        .into_iter()
+        .flat_map(|e| e.sections.iter().map(|s| &s.name))
+}
+
+/// Every name or value in a block, for matching against service names
+fn references<'a>(section: &'a StateSection, out: &mut Vec<&'a str>) {
+    out.extend(section.items.iter().map(|i| i.trim_matches('"')));
+
+    for (key, value) in &section.values {
+        out.push(key);
+        out.push(value.trim_matches('"'));
+    }
+
+    for child in &section.sections {
+        out.push(&child.name);
+        references(child, out);
+    }
+}
+
+impl MachServiceGraph {
+    pub fn from_state(state: &StateSection) -> Self {
+        let mut graph = MachServiceGraph::default();
+        let services: Vec<&StateSection> = state
+            .sections
+            .iter()
+            .filter(|s| is_service_section(s))
+            .collect();
+
+        for section in &services {
+            for name in published(section) {
+                graph
+                    .providers
+                    .entry(name.clone())
+                    .or_default()
+                    .insert(section.name.clone());
+            }
+        }
+
+        for section in &services {
+            let own: BTreeSet<&String> = published(section).collect();
+            let mut refs = vec![];
+
+            for child in section.sections.iter().filter(|s| s.name != "endpoints") {
+                refs.push(child.name.as_str());
+                references(child, &mut refs);
+            }
+
+            for (key, value) in &section.values {
+                refs.push(key);
+                refs.push(value.trim_matches('"'));
+            }
+
+            for name in refs {
+                if own.iter().any(|o| o.as_str() == name) || !graph.providers.contains_key(name) {
+                    continue;
+                }
+
+                graph
+                    .consumers
+                    .entry(name.to_string())
+                    .or_default()
+                    .insert(section.name.clone());
+            }
+        }
+
+        graph
+    }
+
+    /// Provides/consumes for label in any domain
+    pub fn links(&self, label: &str) -> MachServiceLinks {
+        let is_job = |target: &String| is_job_target(target, label);
+
+        let related = |map: &BTreeMap<String, BTreeSet<String>>, service: &String| {
+            map.get(service)
+                .map(|jobs| jobs.iter().cloned().collect())
+                .unwrap_or_default()
+        };
+
+        let provides = self
+            .providers
+            .iter()
+            .filter(|(_, jobs)| jobs.iter().any(is_job))
+            .map(|(service, _)| (service.clone(), related(&self.consumers, service)))
+            .collect();
+
+        let consumes = self
+            .consumers
+            .iter()
+            .filter(|(_, jobs)| jobs.iter().any(is_job))
+            .map(|(service, _)| (service.clone(), related(&self.providers, service)))
+            .collect();
+
+        MachServiceLinks { provides, consumes }
+    }
+
+    /// Job targets publishing service
+    pub fn providers_of(&self, service: &str) -> Vec<String> {
+        self.providers
+            .get(service)
+            .map(|jobs| jobs.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Who hosts a Mach service, and its process if running
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ServiceOwner {
+    pub label: String,
+    pub pid: i64,
+    pub process: Option<ProcInfo>,
+}
+
+/// resolveport on our own send right, falling back to dumpstate endpoints
+/// (e.g. when the service is in a domain we cannot look up from). The
+/// owner is then examined by PID.
+pub fn whohas(service: &str) -> Result<Vec<ServiceOwner>, String> {
+    let labels = match resolve_service(service) {
+        Ok(label) => vec![label],
+        Err(e) => {
+            log::debug!("[mach_graph/whohas]: resolveport {}: {}", service, e);
+
+            mach_service_graph()?
+                .providers_of(service)
+                .into_iter()
+                .filter_map(|target| target.rsplit_once('/').map(|(_, l)| l.to_string()))
+                .collect()
+        }
+    };
+
+    if labels.is_empty() {
+        return Err(format!("No job publishes {}", service));
+    }
+
+    let running = list_all_detailed();
+
+    Ok(labels
+        .into_iter()
+        .map(|label| {
+            let pid = running
+                .iter()
+                .find(|e| e.label == label)
+                .map(|e| e.pid)
+                .unwrap_or(0);
+
+            let process = Some(pid)
+                .filter(|p| *p > 0)
+                .and_then(|p| procinfo_parsed(p).ok());
+
+            ServiceOwner {
+                label,
+                pid,
+                process,
+            }
+        })
+        .collect())
+}
+
+/// Build the graph from current dumpstate
+pub fn mach_service_graph() -> Result<MachServiceGraph, String> {
+    parsed_dumpstate().map(|state| MachServiceGraph::from_state(&state))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MachServiceGraph;
+    use crate::launchd::print_parser::parse;
+
+    static DUMPSTATE: &str = r#"gui/501 = {
+	type = User
+}
+
+gui/501/com.example.server = {
+	endpoints = {
+		"com.example.server.xpc" = {
+			port = 0x4503
+		}
+	}
+}
+
+gui/501/com.example.client = {
+	event channels = {
+		"com.example.server.xpc" = {
+			port = 0x1a03
+		}
+	}
+}
+"#;
+
+    #[test]
+    fn provides_and_consumes() {
+        let graph = MachServiceGraph::from_state(&parse(DUMPSTATE));
+
+        let server = graph.links("com.example.server");
+        assert_eq!(
+            server.provides,
+            vec![(
+                "com.example.server.xpc".to_string(),
+                vec!["gui/501/com.example.client".to_string()]
+            )]
+        );
+        assert!(server.consumes.is_empty());
+
+        let client = graph.links("com.example.client");
+        assert_eq!(
+            client.consumes,
+            vec![(
+                "com.example.server.xpc".to_string(),
+                vec!["gui/501/com.example.server".to_string()]
+            )]
+        );
+    }
+}
@@ -3,6 +3,9 @@ use xpc_sys::traits::query_builder::QueryBuilder;
 
 // A bunch of XPCDictionary 'protos' that can be extended to make XPC queries
 
+/// kickstart -k: kill the running instance before restarting
+pub const KICKSTART_KILL: u64 = 1 << 1;
+
 lazy_static! {
     /// launchctl list [name]
     pub static ref LIST_SERVICES: XPCDictionary = XPCDictionary::new()
@@ -35,6 +38,35 @@ lazy_static! {
         .entry("enable", false)
         .entry("no-einprogress", true);
 
+    /// launchctl submit -l <label> -- <program> [args], a job without a plist
+    /// in the requestor's domain. Takes the job dictionary as "request".
+    pub static ref SUBMIT: XPCDictionary = XPCDictionary::new()
+        .entry("routine", 812 as u64)
+        .entry("subsystem", 3 as u64)
+        .entry("handle", 0 as u64)
+        .entry("legacy", true);
+
+    /// launchctl remove <label>
+    pub static ref REMOVE: XPCDictionary = XPCDictionary::new()
+        .entry("routine", 816 as u64)
+        .entry("subsystem", 3 as u64)
+        .entry("handle", 0 as u64)
+        .entry("legacy", true);
+
+    /// launchctl bootstrap <domain-target> [path]
+    pub static ref BOOTSTRAP_PATHS: XPCDictionary = XPCDictionary::new()
+        .with_domain_port_as_bootstrap_port()
+        .entry("routine", 800 as u64)
+        .entry("subsystem", 3 as u64)
+        .entry("enable", false)
+        .entry("no-einprogress", true);
+
+    /// launchctl bootout <service-target>
+    pub static ref BOOTOUT_NAMES: XPCDictionary = XPCDictionary::new()
+        .with_domain_port_as_bootstrap_port()
+        .entry("routine", 801 as u64)
+        .entry("subsystem", 3 as u64)
+        .entry("no-einprogress", true);
 
     /// launchctl enable
     pub static ref ENABLE_NAMES: XPCDictionary = XPCDictionary::new()
@@ -50,6 +82,95 @@ lazy_static! {
         .entry("routine", 809 as u64)
         .entry("subsystem", 3 as u64);
 
+    /// launchctl kickstart [-k] <service-target>
+    pub static ref KICKSTART: XPCDictionary = XPCDictionary::new()
+        .entry("subsystem", 2 as u64)
+        .entry("routine", 702 as u64);
+
+    /// launchctl start <label>
+    pub static ref START: XPCDictionary = XPCDictionary::new()
+        .entry("subsystem", 3 as u64)
+        .entry("routine", 813 as u64);
+
+    /// launchctl stop <label>
+    pub static ref STOP: XPCDictionary = XPCDictionary::new()
+        .entry("subsystem", 3 as u64)
+        .entry("routine", 814 as u64);
+
+    /// launchctl kill <signal> <service-target>
+    pub static ref KILL: XPCDictionary = XPCDictionary::new()
+        .entry("subsystem", 2 as u64)
+        .entry("routine", 701 as u64);
+
+    /// launchctl debug <service-target>, applies to the next launch only
+    pub static ref DEBUG: XPCDictionary = XPCDictionary::new()
+        .entry("subsystem", 2 as u64)
+        .entry("routine", 704 as u64);
+
+    /// launchctl resolveport <owner-pid> <port-name>
+    pub static ref RESOLVEPORT: XPCDictionary = XPCDictionary::new()
+        .entry("subsystem", 2 as u64)
+        .entry("routine", 706 as u64);
+
+    /// launchctl manageruid, the uid of the launchd answering us
+    pub static ref MANAGERUID: XPCDictionary = XPCDictionary::new()
+        .entry("subsystem", 6 as u64)
+        .entry("routine", 301 as u64);
+
+    /// launchctl managerpid
+    pub static ref MANAGERPID: XPCDictionary = XPCDictionary::new()
+        .entry("subsystem", 6 as u64)
+        .entry("routine", 302 as u64);
+
+    /// launchctl managername, e.g. System or Aqua
+    pub static ref MANAGERNAME: XPCDictionary = XPCDictionary::new()
+        .entry("subsystem", 6 as u64)
+        .entry("routine", 303 as u64);
+
+    /// launchctl version, e.g. Darwin Bootstrapper Version 7.0.0
+    pub static ref VERSION: XPCDictionary = XPCDictionary::new()
+        .entry("subsystem", 3 as u64)
+        .entry("routine", 825 as u64);
+
+    /// launchctl setenv <key> <value>, unsetenv <key> (null value)
+    pub static ref SETENV: XPCDictionary = XPCDictionary::new()
+        .entry("subsystem", 3 as u64)
+        .entry("routine", 819 as u64);
+
+    /// launchctl getenv <key>
+    pub static ref GETENV: XPCDictionary = XPCDictionary::new()
+        .entry("subsystem", 3 as u64)
+        .entry("routine", 820 as u64);
+
+    /// launchctl blame <service-target>
+    pub static ref BLAME: XPCDictionary = XPCDictionary::new()
+        .entry("subsystem", 2 as u64)
+        .entry("routine", 707 as u64);
+
+    /// launchctl print-cache, the XPC service cache seen by a process.
+    /// Sent with type 5 (pid) and the process as handle
+    pub static ref PRINT_CACHE: XPCDictionary = XPCDictionary::new()
+        .entry("subsystem", 3 as u64)
+        .entry("routine", 803 as u64);
+
+    /// launchctl uncache <service-name>, the next lookup of "name" from
+    /// the process bypasses the cache. Sent with type 5 (pid) like
+    /// print-cache, launchd answers ENOTSUP where it has no such routine
+    pub static ref UNCACHE: XPCDictionary = XPCDictionary::new()
+        .entry("subsystem", 3 as u64)
+        .entry("routine", 804 as u64);
+
+    /// launchctl print-disabled <domain-target>
+    pub static ref PRINT_DISABLED: XPCDictionary = XPCDictionary::new()
+        .entry("subsystem", 3 as u64)
+        .entry("routine", 830 as u64);
+
+    /// launchctl print <domain-target>
+    /// Requires a shmem xpc_object_t member, see XPCShmem for more details
+    pub static ref PRINT: XPCDictionary = XPCDictionary::new()
+        .entry("subsystem", 3 as u64)
+        .entry("routine", 828 as u64);
+
     /// launchctl dumpstate
     /// Requires a shmem xpc_object_t member, see XPCShmem for more details
     pub static ref DUMPSTATE: XPCDictionary = XPCDictionary::new()
@@ -59,15 +180,22 @@ lazy_static! {
         .with_handle_or_default(None);
 
     /// launchctl dumpjpcategory
-    /// Requires a FD".entry("fd", 1 as RawFd)"
+    /// Requires an XPCFd ".entry("fd", &fd)", see query::dumpjpcategory
     pub static ref DUMPJPCATEGORY: XPCDictionary = XPCDictionary::new()
         .entry("subsystem", 3 as u64)
         .entry("routine", 837 as u64)
         .entry("type", 1 as u64)
         .with_handle_or_default(None);
 
+    /// launchctl limit [name [soft [hard]]], launchd's own rlimits which
+    /// its jobs inherit. "limits" is name -> {soft, hard}, sent to set them
+    pub static ref LIMIT: XPCDictionary = XPCDictionary::new()
+        .entry("subsystem", 3 as u64)
+        .entry("routine", 818 as u64)
+        .with_handle_or_default(None);
+
     /// launchctl procinfo
-    /// Requires a FD".entry("fd", 1 as RawFd)"
+    /// Requires a shmem, or an XPCFd ".entry("fd", &fd)"
     pub static ref PROCINFO: XPCDictionary = XPCDictionary::new()
         .entry("subsystem", 2 as u64)
         .entry("routine", 708 as u64);
@@ -1,10 +1,44 @@
 pub mod message;
 
+pub mod audit;
+pub mod backup;
+pub mod crash_reports;
+
 /// queries (sorta?)
 pub mod query;
 
+pub mod domain_target;
+pub mod dry_run;
+pub mod endpoints;
 pub mod entry_status;
+pub mod events;
+pub mod job_detail;
+pub mod job_stats;
 pub mod job_type_filter;
+pub mod limits;
+pub mod login_items;
+pub mod mach_graph;
+pub mod new_job;
+pub mod overrides;
+pub mod pipe;
+pub mod preflight;
+pub mod process_tree;
+pub mod procinfo;
+pub mod protected;
+pub mod raw_xpc;
+pub mod schedule;
+pub mod session;
+pub mod signing;
+pub mod snapshot;
+pub mod status_cache;
+pub mod templates;
+pub mod triggers;
+pub mod watch_paths;
 
 /// plist management
 pub mod plist;
+pub mod plist_diff;
+pub mod plist_lint;
+
+/// launchctl print style output parser
+pub mod print_parser;
@@ -0,0 +1,271 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use plist::{Dictionary, Value};
+
+use crate::launchd::plist::{
+    LaunchdEntryLocation, LaunchdEntryType, ADMIN_LAUNCH_AGENTS, ADMIN_LAUNCH_DAEMONS,
+    USER_LAUNCH_AGENTS,
+};
+use crate::launchd::plist_lint::lint;
+use crate::launchd::templates::parse_dictionary;
+
+/// StartCalendarInterval keys
+static CALENDAR_KEYS: [&str; 5] = ["Minute", "Hour", "Day", "Weekday", "Month"];
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Schedule {
+    None,
+    /// StartInterval in seconds
+    Interval(u64),
+    /// StartCalendarInterval, e.g. Hour=3 Minute=30
+    Calendar(BTreeMap<String, i64>),
+}
+
+impl Schedule {
+    /// Empty, seconds, or space separated Key=value calendar fields
+    pub fn parse(schedule: &str) -> Result<Schedule, String> {
+        let schedule = schedule.trim();
+
+        if schedule.is_empty() {
+            return Ok(Schedule::None);
+        }
+
+        if let Ok(seconds) = schedule.parse::<u64>() {
+            return Ok(Schedule::Interval(seconds));
+        }
+
+        schedule
+            .split_whitespace()
+            .map(|field| {
+                let (key, value) = field
+                    .split_once('=')
+                    .ok_or_else(|| format!("Invalid schedule field {}", field))?;
+
+                if !CALENDAR_KEYS.contains(&key) {
+                    return Err(format!(
+                        "Unknown calendar key {}, expected one of {}",
+                        key,
+                        CALENDAR_KEYS.join(", ")
+                    ));
+                }
+
+                value
+                    .parse::<i64>()
+                    .map(|v| (key.to_string(), v))
+                    .map_err(|_| format!("Invalid value for {}", key))
+            })
+            .collect::<Result<BTreeMap<String, i64>, String>>()
+            .map(Schedule::Calendar)
+    }
+}
+
+/// Split like a shell would for simple cases, quotes group words
+pub fn split_args(args: &str) -> Vec<String> {
+    let mut result = vec![];
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    let mut in_word = false;
+
+    for c in args.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '"') | (None, '\'') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    result.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+
+    if in_word {
+        result.push(current);
+    }
+
+    result
+}
+
+/// Answers from the new job wizard
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct NewJob {
+    pub label: String,
+    pub program_arguments: Vec<String>,
+    pub run_at_load: bool,
+    pub schedule: Schedule,
+    pub stdout_path: Option<String>,
+    pub stderr_path: Option<String>,
+    /// User or Global
+    pub location: LaunchdEntryLocation,
+    pub entry_type: LaunchdEntryType,
+    /// Rendered template XML, the answers above are added to it
+    pub template: Option<String>,
+}
+
+impl NewJob {
+    pub fn to_plist(&self) -> Value {
+        let mut dict = self
+            .template
+            .as_deref()
+            .and_then(|xml| parse_dictionary(xml).ok())
+            .unwrap_or_default();
+
+        dict.insert("Label".to_string(), self.label.clone().into());
+
+        // Templates bring their own Program or ProgramArguments
+        if self.template.is_none() || !self.program_arguments.is_empty() {
+            dict.insert(
+                "ProgramArguments".to_string(),
+                Value::Array(
+                    self.program_arguments
+                        .iter()
+                        .map(|a| a.clone().into())
+                        .collect(),
+                ),
+            );
+        }
+
+        if self.run_at_load {
+            dict.insert("RunAtLoad".to_string(), true.into());
+        }
+
+        match &self.schedule {
+            Schedule::None => {}
+            Schedule::Interval(seconds) => {
+                dict.insert("StartInterval".to_string(), (*seconds).into());
+            }
+            Schedule::Calendar(fields) => {
+                let calendar: Dictionary = fields
+                    .iter()
+                    .map(|(k, v)| (k.clone(), Value::from(*v)))
+                    .collect();
+                dict.insert("StartCalendarInterval".to_string(), calendar.into());
+            }
+        }
+
+        if let Some(path) = &self.stdout_path {
+            dict.insert("StandardOutPath".to_string(), path.clone().into());
+        }
+
+        if let Some(path) = &self.stderr_path {
+            dict.insert("StandardErrorPath".to_string(), path.clone().into());
+        }
+
+        dict.into()
+    }
+
+    /// What write() would put on disk
+    pub fn to_xml(&self) -> Result<String, String> {
+        let mut xml = vec![];
+        self.to_plist()
+            .to_writer_xml(&mut xml)
+            .map_err(|e| e.to_string())?;
+
+        String::from_utf8(xml).map_err(|e| e.to_string())
+    }
+
+    /// <directory for location and type>/<label>.plist
+    pub fn path(&self) -> Result<PathBuf, String> {
+        let dir = match (&self.location, &self.entry_type) {
+            (LaunchdEntryLocation::User, LaunchdEntryType::Agent) => USER_LAUNCH_AGENTS.as_str(),
+            (LaunchdEntryLocation::Global, LaunchdEntryType::Agent) => ADMIN_LAUNCH_AGENTS,
+            (LaunchdEntryLocation::Global, LaunchdEntryType::Daemon) => ADMIN_LAUNCH_DAEMONS,
+            _ => {
+                return Err(
+                    "Jobs can only be created as user agents, or global agents and daemons"
+                        .to_string(),
+                )
+            }
+        };
+
+        Ok(PathBuf::from(dir).join(format!("{}.plist", self.label)))
+    }
+
+    /// Lint and write, never overwriting an existing plist
+    pub fn write(&self) -> Result<PathBuf, String> {
+        if self.label.is_empty() || self.label.contains('/') {
+            return Err(format!("Invalid label {:?}", self.label));
+        }
+
+        let plist = self.to_plist();
+        let lint = lint(&plist);
+
+        if !lint.errors.is_empty() {
+            return Err(lint.errors.join(", "));
+        }
+
+        let path = self.path()?;
+        if path.exists() {
+            return Err(format!("{} already exists", path.display()));
+        }
+
+        plist
+            .to_file_xml(&path)
+            .map_err(|e| format!("Cannot write {}: {}", path.display(), e))?;
+
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{split_args, NewJob, Schedule};
+    use crate::launchd::plist::{LaunchdEntryLocation, LaunchdEntryType};
+    use crate::launchd::plist_lint::lint;
+
+    #[test]
+    fn parse_schedule() {
+        assert_eq!(Schedule::parse(""), Ok(Schedule::None));
+        assert_eq!(Schedule::parse("300"), Ok(Schedule::Interval(300)));
+
+        match Schedule::parse("Hour=3 Minute=30") {
+            Ok(Schedule::Calendar(fields)) => {
+                assert_eq!(fields.get("Hour"), Some(&3));
+                assert_eq!(fields.get("Minute"), Some(&30));
+            }
+            other => panic!("Unexpected {:?}", other),
+        }
+
+        assert!(Schedule::parse("Hours=3").is_err());
+    }
+
+    #[test]
+    fn split_quoted_args() {
+        assert_eq!(
+            split_args("/bin/sh -c 'echo hi' \"a b\""),
+            vec!["/bin/sh", "-c", "echo hi", "a b"]
+        );
+    }
+
+    #[test]
+    fn new_job_plist_lints() {
+        let job = NewJob {
+            label: "com.example.job".to_string(),
+            program_arguments: vec!["/usr/bin/true".to_string()],
+            run_at_load: true,
+            schedule: Schedule::parse("Hour=3").unwrap(),
+            stdout_path: Some("/tmp/job.log".to_string()),
+            stderr_path: None,
+            location: LaunchdEntryLocation::Global,
+            entry_type: LaunchdEntryType::Daemon,
+            template: None,
+        };
+
+        let lint = lint(&job.to_plist());
+        assert!(lint.errors.is_empty());
+        assert!(lint.warnings.is_empty());
+        assert_eq!(
+            job.path().unwrap().to_string_lossy(),
+            "/Library/LaunchDaemons/com.example.job.plist"
+        );
+    }
+}
@@ -0,0 +1,285 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use xpc_sys::enums::DomainType;
+use xpc_sys::rs_geteuid;
+
+use crate::launchd::domain_target::DomainTarget;
+use crate::launchd::plist::{for_label, LaunchdEntryLocation};
+use crate::launchd::preflight::{Blocker, Operation, Preflight};
+use crate::launchd::protected::is_protected;
+use crate::launchd::query::{logged_in_users, print_disabled, set_enabled_in};
+
+/// launchctl print-disabled for each domain, keyed by domain target
+/// (e.g. system, gui/501) then label, true if disabled
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Overrides {
+    /// Seconds since epoch
+    pub taken_at: u64,
+    pub domains: BTreeMap<String, BTreeMap<String, bool>>,
+}
+
+/// An enable or disable needed to match an exported file
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct OverrideChange {
+    pub target: String,
+    pub label: String,
+    pub disabled: bool,
+}
+
+impl fmt::Display for OverrideChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verb = if self.disabled { "disable" } else { "enable" };
+        write!(f, "{} {}/{}", verb, self.target, self.label)
+    }
+}
+
+impl OverrideChange {
+    /// What would refuse it, checked like disable from the service list.
+    /// Labels without a plist in our own gui or user domain are ours to
+    /// change.
+    pub fn blocker(&self, preflight: &Preflight) -> Option<Blocker> {
+        let target: DomainTarget = self.target.parse().ok()?;
+        let plist = for_label(self.label.clone());
+        let ours = match target.domain_type {
+            DomainType::Gui | DomainType::User => target.handle == preflight.euid as u64,
+            _ => false,
+        };
+        let location = plist
+            .as_ref()
+            .map(|p| p.entry_location.clone())
+            .or(ours.then_some(LaunchdEntryLocation::User));
+
+        preflight
+            .check(Operation::Change, location.as_ref(), None)
+            .err()
+    }
+
+    /// Disables a protected job, which needs its label typed
+    pub fn disables_protected(&self) -> bool {
+        self.disabled
+            && self
+                .target
+                .parse::<DomainTarget>()
+                .map(|t| is_protected(&self.label, &t.domain_type))
+                .unwrap_or(false)
+    }
+
+    pub fn apply(&self) -> Result<(), String> {
+        let target: DomainTarget = self.target.parse()?;
+
+        set_enabled_in(&self.label, &target, !self.disabled)
+            .map(|_| ())
+            .map_err(|e| format!("{}: {}", self, e))
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// system, and gui/user for us or (as root) every logged in user
+fn override_domains() -> Vec<DomainTarget> {
+    let uids: Vec<u64> = match rs_geteuid() {
+        0 => logged_in_users().keys().map(|uid| *uid as u64).collect(),
+        uid => vec![uid as u64],
+    };
+
+    let mut targets = vec![DomainTarget::new(DomainType::System, 0)];
+
+    for uid in uids {
+        targets.push(DomainTarget::new(DomainType::Gui, uid));
+        targets.push(DomainTarget::new(DomainType::User, uid));
+    }
+
+    targets
+}
+
+impl Overrides {
+    pub fn capture() -> Overrides {
+        let domains = override_domains()
+            .into_iter()
+            .filter_map(|target| match print_disabled(&target) {
+                Ok(o) => Some((target.to_string(), o.into_iter().collect())),
+                Err(e) => {
+                    log::error!("[overrides/capture]: {}: {}", target, e);
+                    None
+                }
+            })
+            .collect();
+
+        Overrides {
+            taken_at: now(),
+            domains,
+        }
+    }
+
+    /// Just labels in target as they are now, so applying the result
+    /// later undoes enabling or disabling them
+    pub fn capture_labels(target: &DomainTarget, labels: &[String]) -> Result<Overrides, String> {
+        let disabled = print_disabled(target).map_err(|e| format!("{}: {}", target, e))?;
+        let labels = labels
+            .iter()
+            .map(|l| (l.clone(), disabled.get(l).copied().unwrap_or(false)))
+            .collect();
+
+        Ok(Overrides {
+            taken_at: now(),
+            domains: BTreeMap::from([(target.to_string(), labels)]),
+        })
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, json).map_err(|e| format!("Cannot write {}: {}", path, e))
+    }
+
+    pub fn read(path: &str) -> Result<Overrides, String> {
+        let json = fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+        serde_json::from_str(&json).map_err(|e| format!("Invalid overrides {}: {}", path, e))
+    }
+
+    /// What to enable or disable so current matches self, and what of it
+    /// preflight refuses with why. Labels missing from self are left alone.
+    pub fn checked_changes_from(
+        &self,
+        current: &Overrides,
+        preflight: &Preflight,
+    ) -> (Vec<OverrideChange>, Vec<String>) {
+        let mut blocked = vec![];
+        let changes = self
+            .changes_from(current)
+            .into_iter()
+            .filter(|c| match c.blocker(preflight) {
+                Some(b) => {
+                    blocked.push(format!("{}: {}", c, b));
+                    false
+                }
+                None => true,
+            })
+            .collect();
+
+        (changes, blocked)
+    }
+
+    /// What to enable or disable so current matches self. Labels missing
+    /// from self are left alone.
+    pub fn changes_from(&self, current: &Overrides) -> Vec<OverrideChange> {
+        self.domains
+            .iter()
+            .flat_map(|(target, labels)| {
+                let now = current.domains.get(target);
+
+                labels.iter().filter_map(move |(label, disabled)| {
+                    let is_disabled = now.and_then(|n| n.get(label)).copied().unwrap_or(false);
+
+                    if is_disabled == *disabled {
+                        return None;
+                    }
+
+                    Some(OverrideChange {
+                        target: target.clone(),
+                        label: label.clone(),
+                        disabled: *disabled,
+                    })
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OverrideChange, Overrides};
+    use crate::launchd::preflight::{Blocker, Preflight};
+
+    fn overrides(entries: &[(&str, &str, bool)]) -> Overrides {
+        let mut o = Overrides::default();
+
+        for (target, label, disabled) in entries {
+            o.domains
+                .entry(target.to_string())
+                .or_default()
+                .insert(label.to_string(), *disabled);
+        }
+
+        o
+    }
+
+    #[test]
+    fn changes_to_match() {
+        let exported = overrides(&[
+            ("system", "com.example.daemon", true),
+            ("gui/501", "com.example.agent", false),
+            ("gui/501", "com.example.updater", true),
+        ]);
+        let current = overrides(&[
+            ("system", "com.example.daemon", true),
+            ("gui/501", "com.example.agent", true),
+            ("gui/501", "com.example.other", true),
+        ]);
+
+        let changes = exported.changes_from(&current);
+        assert_eq!(
+            changes,
+            vec![
+                OverrideChange {
+                    target: "gui/501".to_string(),
+                    label: "com.example.agent".to_string(),
+                    disabled: false
+                },
+                OverrideChange {
+                    target: "gui/501".to_string(),
+                    label: "com.example.updater".to_string(),
+                    disabled: true
+                },
+            ]
+        );
+        assert_eq!(
+            changes[1].to_string(),
+            "disable gui/501/com.example.updater"
+        );
+    }
+
+    #[test]
+    fn blocked_changes() {
+        let user = Preflight {
+            euid: 501,
+            sip: true,
+        };
+        let change = |target: &str, label: &str| OverrideChange {
+            target: target.to_string(),
+            label: label.to_string(),
+            disabled: true,
+        };
+
+        assert_eq!(change("gui/501", "com.example.agent").blocker(&user), None);
+        assert_eq!(
+            change("system", "com.example.daemon").blocker(&user),
+            Some(Blocker::NeedsSudo(None))
+        );
+        assert_eq!(
+            change("gui/502", "com.example.agent").blocker(&user),
+            Some(Blocker::NeedsSudo(None))
+        );
+        assert!(change("system", "com.apple.WindowServer").disables_protected());
+        assert!(!change("gui/501", "com.example.agent").disables_protected());
+
+        let exported = overrides(&[
+            ("gui/501", "com.example.agent", true),
+            ("system", "com.example.daemon", true),
+        ]);
+        let (changes, blocked) = exported.checked_changes_from(&Overrides::default(), &user);
+        assert_eq!(changes, vec![change("gui/501", "com.example.agent")]);
+        assert_eq!(
+            blocked,
+            vec!["disable system/com.example.daemon: needs sudo, no plist found for it"]
+        );
+    }
+}
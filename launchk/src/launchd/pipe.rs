@@ -0,0 +1,268 @@
+use std::cell::RefCell;
+use std::convert::TryInto;
+use std::rc::Rc;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use xpc_sys::objects::xpc_dictionary::XPCDictionary;
+use xpc_sys::objects::xpc_error::XPCError;
+use xpc_sys::traits::xpc_pipeable::{check_reply, XPCPipeResult, XPCPipeable};
+
+use crate::config::config;
+
+const DEFAULT_RETRY_ATTEMPTS: u32 = 2;
+const DEFAULT_RETRY_BACKOFF: Duration = Duration::from_millis(50);
+
+/// Retries across all queries per second, so a launchd that stays busy
+/// gets errors rather than a storm of retries
+const MAX_RETRIES_PER_SECOND: u32 = 20;
+
+lazy_static! {
+    static ref RETRY_BUDGET: Mutex<(Instant, u32)> = Mutex::new((Instant::now(), 0));
+}
+
+/// Where queries go, launchd's bootstrap pipe unless a test swaps in
+/// a MockPipe
+pub trait LaunchdPipe {
+    fn pipe_routine(&self, query: &XPCDictionary) -> XPCPipeResult;
+}
+
+pub struct BootstrapPipe;
+
+impl LaunchdPipe for BootstrapPipe {
+    fn pipe_routine(&self, query: &XPCDictionary) -> XPCPipeResult {
+        XPCPipeable::pipe_routine(query)
+    }
+}
+
+thread_local! {
+    // Per thread, so tests with different mocks can run in parallel
+    static PIPE: RefCell<Rc<dyn LaunchdPipe>> = RefCell::new(Rc::new(BootstrapPipe));
+}
+
+/// Puts the previous pipe back when dropped, even if f panics
+#[cfg(test)]
+struct RestorePipe(Option<Rc<dyn LaunchdPipe>>);
+
+#[cfg(test)]
+impl Drop for RestorePipe {
+    fn drop(&mut self) {
+        if let Some(previous) = self.0.take() {
+            PIPE.with(|p| p.replace(previous));
+        }
+    }
+}
+
+/// Run f with this thread's queries going to pipe
+#[cfg(test)]
+pub fn with_pipe<T>(pipe: Rc<dyn LaunchdPipe>, f: impl FnOnce() -> T) -> T {
+    let _restore = RestorePipe(Some(PIPE.with(|p| p.replace(pipe))));
+    f()
+}
+
+/// Sent once as is through this thread's LaunchdPipe, the reply not
+/// checked for errors (the xpc console shows them)
+pub fn send_raw(query: &XPCDictionary) -> XPCPipeResult {
+    PIPE.with(|p| p.borrow().clone()).pipe_routine(query)
+}
+
+/// pipe_routine_with_error_handling, through this thread's LaunchdPipe
+pub trait LaunchdRoutine {
+    /// Sent once: launchd may have acted before a transient error, so
+    /// resending could e.g. signal or bootstrap twice
+    fn launchd_routine(&self) -> Result<XPCDictionary, XPCError>;
+
+    /// Read-only routines (list, print, getenv...), retried on
+    /// transient errors
+    fn launchd_query(&self) -> Result<XPCDictionary, XPCError>;
+}
+
+impl LaunchdRoutine for XPCDictionary {
+    fn launchd_routine(&self) -> Result<XPCDictionary, XPCError> {
+        send(self, false)
+    }
+
+    fn launchd_query(&self) -> Result<XPCDictionary, XPCError> {
+        send(self, true)
+    }
+}
+
+fn send(query: &XPCDictionary, retry: bool) -> Result<XPCDictionary, XPCError> {
+    let pipe = PIPE.with(|p| p.borrow().clone());
+    let mut retries: Option<(u32, Duration)> = None;
+
+    loop {
+        let err = match pipe.pipe_routine(query) {
+            Err(e) if retry && is_transient(&e) => e,
+            reply => return check_reply(reply?.try_into()?),
+        };
+
+        // Only read config once something went wrong
+        let (left, backoff) = retries.get_or_insert_with(retry_policy);
+
+        if *left == 0 || !take_retry() {
+            return Err(err);
+        }
+
+        log::warn!("[pipe/send]: {}, retrying in {:?}", err, backoff);
+        thread::sleep(*backoff);
+
+        *left -= 1;
+        *backoff *= 2;
+    }
+}
+
+/// Worth another try, unlike e.g. EPERM or ENOENT
+pub fn is_transient(err: &XPCError) -> bool {
+    match err {
+        XPCError::PipeErrno(errno) => [libc::EAGAIN, libc::EINTR, libc::EPIPE].contains(errno),
+        _ => false,
+    }
+}
+
+/// (attempts, first backoff) from config
+fn retry_policy() -> (u32, Duration) {
+    let config = config();
+
+    (
+        config.retry_attempts.unwrap_or(DEFAULT_RETRY_ATTEMPTS),
+        config
+            .retry_backoff_ms
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_RETRY_BACKOFF),
+    )
+}
+
+fn take_retry() -> bool {
+    let mut budget = RETRY_BUDGET.lock().expect("Must lock retry budget");
+    let (window, used) = &mut *budget;
+
+    if window.elapsed() >= Duration::from_secs(1) {
+        *window = Instant::now();
+        *used = 0;
+    }
+
+    if *used >= MAX_RETRIES_PER_SECOND {
+        log::error!("[pipe/take_retry]: Retry limit reached, not retrying");
+        return false;
+    }
+
+    *used += 1;
+    true
+}
+
+#[cfg(test)]
+pub mod mock {
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+
+    use xpc_sys::objects::xpc_dictionary::XPCDictionary;
+    use xpc_sys::objects::xpc_error::XPCError;
+    use xpc_sys::objects::xpc_object::XPCObject;
+    use xpc_sys::traits::xpc_pipeable::XPCPipeResult;
+    use xpc_sys::traits::xpc_value::TryXPCValue;
+
+    use super::LaunchdPipe;
+
+    /// Canned replies by routine, keeping every query sent
+    #[derive(Default)]
+    pub struct MockPipe {
+        replies: HashMap<u64, XPCDictionary>,
+        /// Returned in order before any reply
+        failures: RefCell<Vec<XPCError>>,
+        pub sent: RefCell<Vec<XPCDictionary>>,
+    }
+
+    impl MockPipe {
+        pub fn fail(self, err: XPCError) -> Self {
+            self.failures.borrow_mut().push(err);
+            self
+        }
+
+        pub fn reply(mut self, routine: u64, reply: XPCDictionary) -> Self {
+            self.replies.insert(routine, reply);
+            self
+        }
+    }
+
+    impl LaunchdPipe for MockPipe {
+        fn pipe_routine(&self, query: &XPCDictionary) -> XPCPipeResult {
+            self.sent.borrow_mut().push(query.clone());
+
+            let mut failures = self.failures.borrow_mut();
+            if !failures.is_empty() {
+                return Err(failures.remove(0));
+            }
+
+            let routine: u64 = query.get(["routine"])?.xpc_value()?;
+            self.replies
+                .get(&routine)
+                .map(XPCObject::from)
+                .ok_or_else(|| XPCError::PipeError(format!("No reply for routine {}", routine)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use xpc_sys::objects::xpc_dictionary::XPCDictionary;
+    use xpc_sys::objects::xpc_error::XPCError;
+    use xpc_sys::traits::query_builder::QueryBuilder;
+
+    use super::mock::MockPipe;
+    use super::{with_pipe, LaunchdRoutine};
+    use crate::launchd::message::KILL;
+
+    fn query() -> XPCDictionary {
+        XPCDictionary::new().entry("routine", 303_u64)
+    }
+
+    #[test]
+    fn retry_transient_errors() {
+        let pipe = Rc::new(
+            MockPipe::default()
+                .fail(XPCError::PipeErrno(libc::EINTR))
+                .reply(303, XPCDictionary::new().entry("name", "Aqua")),
+        );
+
+        assert!(with_pipe(pipe.clone(), || query().launchd_query()).is_ok());
+        assert_eq!(pipe.sent.borrow().len(), 2);
+
+        let pipe = Rc::new(
+            MockPipe::default()
+                .fail(XPCError::PipeErrno(libc::EPERM))
+                .reply(303, XPCDictionary::new()),
+        );
+
+        assert_eq!(
+            with_pipe(pipe.clone(), || query().launchd_query()).err(),
+            Some(XPCError::PipeErrno(libc::EPERM))
+        );
+        assert_eq!(pipe.sent.borrow().len(), 1);
+    }
+
+    #[test]
+    fn never_resend_changes() {
+        let pipe = Rc::new(
+            MockPipe::default()
+                .fail(XPCError::PipeErrno(libc::EPIPE))
+                .reply(701, XPCDictionary::new()),
+        );
+
+        let kill = || {
+            XPCDictionary::new()
+                .extend(&KILL)
+                .entry("signal", 9_i64)
+                .launchd_routine()
+        };
+
+        assert_eq!(
+            with_pipe(pipe.clone(), kill).err(),
+            Some(XPCError::PipeErrno(libc::EPIPE))
+        );
+        assert_eq!(pipe.sent.borrow().len(), 1);
+    }
+}
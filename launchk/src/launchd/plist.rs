@@ -1,11 +1,14 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::fmt;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::{Once, RwLock};
 
+use crate::config::config;
+use crate::launchd::backup::{backup_plist, Backup};
 use crate::launchd::job_type_filter::JobTypeFilter;
+use crate::launchd::plist_lint::{lint, PlistLint};
 use notify_debouncer_mini::{new_debouncer, notify::RecursiveMode, DebounceEventResult};
 use std::fs::File;
 use std::io::Read;
@@ -13,16 +16,21 @@ use std::process::Command;
 use std::sync::mpsc::{channel, Receiver, Sender};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::runtime::Handle;
+use xpc_sys::enums::SessionType;
 
 pub static PLIST_MAP_INIT: Once = Once::new();
 
 lazy_static! {
     pub static ref LABEL_TO_ENTRY_CONFIG: RwLock<HashMap<String, LaunchdPlist>> =
         RwLock::new(HashMap::new());
-    static ref EDITOR: String = env::var("EDITOR").unwrap_or("vim".to_string());
     static ref TMP_DIR: String = env::var("TMPDIR").unwrap_or("/tmp".to_string());
-    static ref USER_LAUNCH_AGENTS: String =
+    pub static ref USER_LAUNCH_AGENTS: String =
         env::var("HOME").expect("Must read $HOME") + "/Library/LaunchAgents";
+    /// Plist paths written to since their job was loaded
+    static ref CHANGED_SINCE_LOAD: RwLock<HashSet<String>> = RwLock::new(HashSet::new());
+    /// Plist path -> modified time and its program, read when first asked for
+    static ref PROGRAMS: RwLock<HashMap<String, (SystemTime, Option<String>)>> =
+        RwLock::new(HashMap::new());
 }
 
 /*
@@ -70,11 +78,13 @@ pub struct LaunchdPlist {
     pub entry_location: LaunchdEntryLocation,
     pub plist_path: String,
     pub readonly: bool,
+    /// LimitLoadToSessionType, empty if not set
+    pub session_types: Vec<SessionType>,
 }
 
 // TODO: This should be somewhere else
 impl LaunchdPlist {
-    pub fn job_type_filter(&self, is_loaded: bool) -> JobTypeFilter {
+    pub fn job_type_filter(&self, is_loaded: bool, is_disabled: bool) -> JobTypeFilter {
         let mut jtf = JobTypeFilter::default();
 
         match self.entry_location {
@@ -92,6 +102,21 @@ impl LaunchdPlist {
             jtf.toggle(JobTypeFilter::LOADED);
         }
 
+        if is_disabled {
+            jtf.toggle(JobTypeFilter::DISABLED);
+        } else {
+            jtf.toggle(JobTypeFilter::ENABLED);
+        }
+
+        // launchd loads agents without a session type into Aqua
+        if self.session_types.is_empty() && self.entry_type == LaunchdEntryType::Agent {
+            jtf.insert(JobTypeFilter::AQUA);
+        }
+
+        for session_type in &self.session_types {
+            jtf.insert(JobTypeFilter::for_session_type(session_type));
+        }
+
         jtf
     }
 }
@@ -133,18 +158,34 @@ async fn fsnotify_subscriber() {
             .filter_map(|e| path_if_plist(&e.path))
             .collect();
 
+        CHANGED_SINCE_LOAD
+            .write()
+            .expect("Must mark changed")
+            .extend(paths.iter().map(|p| p.to_string_lossy().to_string()));
+
         insert_plists(paths.into_iter());
     }
 }
 
 fn build_label_map_entry(plist_path: PathBuf) -> Option<(String, LaunchdPlist)> {
     let path_string = plist_path.to_string_lossy().to_string();
-    let label = plist::Value::from_file(&path_string).ok()?;
-    let label = label
-        .as_dictionary()
+    let plist = plist::Value::from_file(&path_string).ok()?;
+    let dict = plist.as_dictionary();
+    let label = dict
         .and_then(|d| d.get("Label"))
         .and_then(|v| v.as_string());
 
+    // A string or an array of them
+    let session_types = match dict.and_then(|d| d.get("LimitLoadToSessionType")) {
+        Some(plist::Value::String(s)) => vec![SessionType::from(s.clone())],
+        Some(plist::Value::Array(a)) => a
+            .iter()
+            .filter_map(|v| v.as_string())
+            .map(|s| SessionType::from(s.to_string()))
+            .collect(),
+        _ => vec![],
+    };
+
     let entry_type = if path_string.starts_with(ADMIN_LAUNCH_DAEMONS)
         || path_string.starts_with(SYSTEM_LAUNCH_DAEMONS)
     {
@@ -173,6 +214,7 @@ fn build_label_map_entry(plist_path: PathBuf) -> Option<(String, LaunchdPlist)>
                 .metadata()
                 .map(|m| m.permissions().readonly())
                 .unwrap_or(true),
+            session_types,
         },
     ))
 }
@@ -190,7 +232,7 @@ fn path_if_plist(path: &PathBuf) -> Option<PathBuf> {
     }
 }
 
-fn insert_plists(plists: impl Iterator<Item = PathBuf>) {
+pub fn insert_plists(plists: impl Iterator<Item = PathBuf>) {
     let mut label_map = LABEL_TO_ENTRY_CONFIG.write().expect("Must update");
 
     for plist_path in plists {
@@ -209,8 +251,16 @@ fn insert_plists(plists: impl Iterator<Item = PathBuf>) {
 /// usually match the label property. Still looking for
 /// a way to do dumpstate, dumpjpcategory without parsing the string
 pub fn init_plist_map(runtime_handle: &Handle) {
+    load_plist_map();
+
+    // Spawn fsnotify subscriber
+    runtime_handle.spawn(fsnotify_subscriber());
+}
+
+/// Plists in the standard directories
+fn plist_paths() -> Vec<PathBuf> {
     let dirs = [
-        &USER_LAUNCH_AGENTS,
+        USER_LAUNCH_AGENTS.as_str(),
         ADMIN_LAUNCH_AGENTS,
         SYSTEM_LAUNCH_AGENTS,
         ADMIN_LAUNCH_DAEMONS,
@@ -218,17 +268,113 @@ pub fn init_plist_map(runtime_handle: &Handle) {
     ];
 
     // Get all the plists from everywhere into one stream
-    let plists = dirs
-        .iter()
+    dirs.iter()
         .filter_map(|&dirname| fs::read_dir(Path::new(dirname)).ok())
         .flat_map(|rd| rd.map(|e| e.ok()))
         .flatten()
-        .filter_map(|d| path_if_plist(&d.path()));
+        .filter_map(|d| path_if_plist(&d.path()))
+        .collect()
+}
 
-    insert_plists(plists);
+/// Read all plists from the standard directories once, without
+/// watching for changes (e.g. for CLI usage)
+pub fn load_plist_map() {
+    insert_plists(plist_paths().into_iter());
+}
 
-    // Spawn fsnotify subscriber
-    runtime_handle.spawn(fsnotify_subscriber());
+/// Re-read every plist, e.g. after changes fsnotify missed, reporting
+/// (plists read, total). on_progress returning false stops early.
+/// Returns how many were read.
+pub fn rescan_plists<F: FnMut(usize, usize) -> bool>(mut on_progress: F) -> Result<usize, String> {
+    const BATCH: usize = 50;
+
+    let plists = plist_paths();
+
+    for (i, batch) in plists.chunks(BATCH).enumerate() {
+        insert_plists(batch.iter().cloned());
+
+        if !on_progress(i * BATCH + batch.len(), plists.len()) {
+            return Err("Cancelled".to_string());
+        }
+    }
+
+    // Deleted while nobody was watching
+    LABEL_TO_ENTRY_CONFIG
+        .write()
+        .expect("Must update")
+        .retain(|_, p| Path::new(&p.plist_path).exists());
+
+    Ok(plists.len())
+}
+
+/// Has the plist changed on disk while its job was loaded?
+pub fn changed_since_load(plist_path: &str) -> bool {
+    CHANGED_SINCE_LOAD
+        .read()
+        .map(|c| c.contains(plist_path))
+        .unwrap_or(false)
+}
+
+/// Job was (re)loaded from plist_path
+pub fn clear_changed(plist_path: &str) {
+    CHANGED_SINCE_LOAD
+        .write()
+        .expect("Must clear changed")
+        .remove(plist_path);
+}
+
+/// Forget changes to plists of jobs that are not loaded, loading them
+/// reads what is on disk anyway
+pub fn retain_changed(loaded: &HashSet<String>) {
+    let label_map = match LABEL_TO_ENTRY_CONFIG.read() {
+        Ok(m) => m,
+        Err(_) => return,
+    };
+
+    let loaded_paths: HashSet<&str> = loaded
+        .iter()
+        .filter_map(|l| label_map.get(l))
+        .map(|p| p.plist_path.as_str())
+        .collect();
+
+    CHANGED_SINCE_LOAD
+        .write()
+        .expect("Must update changed")
+        .retain(|p| loaded_paths.contains(p.as_str()));
+}
+
+/// Program, or the first of ProgramArguments
+pub fn program(dict: &plist::Dictionary) -> Option<String> {
+    dict.get("Program")
+        .and_then(plist::Value::as_string)
+        .or_else(|| {
+            dict.get("ProgramArguments")
+                .and_then(plist::Value::as_array)
+                .and_then(|a| a.first())
+                .and_then(plist::Value::as_string)
+        })
+        .map(String::from)
+}
+
+/// Program a plist on disk runs, reread only when the file changes
+pub fn plist_program(plist_path: &str) -> Option<String> {
+    let modified = fs::metadata(plist_path).and_then(|m| m.modified()).ok()?;
+
+    match PROGRAMS.read().ok()?.get(plist_path) {
+        Some((at, program)) if *at == modified => return program.clone(),
+        _ => (),
+    }
+
+    let found = plist::Value::from_file(plist_path)
+        .ok()
+        .and_then(|v| v.as_dictionary().and_then(program));
+
+    PROGRAMS
+        .write()
+        .expect("Must update programs")
+        .insert(plist_path.to_string(), (modified, found.clone()));
+
+    found
 }
 
 /// Get plist for a label
@@ -237,25 +383,50 @@ pub fn for_label<S: Into<String>>(label: S) -> Option<LaunchdPlist> {
     label_map.get(label.into().as_str()).map(|c| c.clone())
 }
 
+/// Check magic, binary plists start with bplist00
+pub fn is_binary_plist(path: &str) -> Result<bool, String> {
+    let mut file = File::open(path).map_err(|_| "Couldn't read file".to_string())?;
+
+    let mut magic_buf: [u8; 8] = [0; 8];
+    file.read_exact(&mut magic_buf)
+        .map_err(|_| "Couldn't read magic".to_string())?;
+
+    Ok(magic_buf == PLIST_MAGIC.as_bytes())
+}
+
+/// Plist contents as XML, binary plists are converted
+pub fn plist_xml(path: &str) -> Result<String, String> {
+    let plist = plist::Value::from_file(path).map_err(|e| e.to_string())?;
+    let mut xml: Vec<u8> = Vec::new();
+
+    plist.to_writer_xml(&mut xml).map_err(|e| e.to_string())?;
+    String::from_utf8(xml).map_err(|e| e.to_string())
+}
+
+/// Editor command, config overrides $EDITOR
+fn editor() -> String {
+    config()
+        .editor
+        .or_else(|| env::var("EDITOR").ok())
+        .unwrap_or("vim".to_string())
+}
+
 /// Given a LaunchdPlist, start editor pointing to temporary file
 /// and replace on exit. Uses plist crate to validate changes and
-/// help show contents for binary encoded files
-pub fn edit_and_replace(plist_meta: &LaunchdPlist) -> Result<(), String> {
+/// help show contents for binary encoded files. Lint errors refuse
+/// the changes, the backup of the previous plist and lint warnings
+/// are returned.
+pub fn edit_and_replace(
+    label: &str,
+    plist_meta: &LaunchdPlist,
+) -> Result<(Backup, Vec<String>), String> {
     if plist_meta.readonly {
         return Err("plist is read-only!".to_string());
     }
 
-    let mut file =
-        File::open(&plist_meta.plist_path).map_err(|_| "Couldn't read file".to_string())?;
-
     // We want to write back in the correct format,
     // can't assume we can safely write XML everywhere?
-    let mut magic_buf: [u8; 8] = [0; 8];
-    file.read_exact(&mut magic_buf)
-        .map_err(|_| "Couldn't read magic".to_string())?;
-    let is_binary = std::str::from_utf8(&magic_buf)
-        .map_err(|_| "Couldn't read magic".to_string())?
-        == PLIST_MAGIC;
+    let is_binary = is_binary_plist(&plist_meta.plist_path)?;
 
     // plist -> validate with crate -> temp file
     let og_plist = plist::Value::from_file(&plist_meta.plist_path).map_err(|e| e.to_string())?;
@@ -267,14 +438,17 @@ pub fn edit_and_replace(plist_meta: &LaunchdPlist) -> Result<(), String> {
         .to_file_xml(&temp_path)
         .map_err(|e| e.to_string())?;
 
-    // Start $EDITOR
-    let exit = Command::new(&*EDITOR)
+    // Start editor from config, or $EDITOR
+    let editor = editor();
+    let mut editor_args = editor.split_whitespace();
+    let exit = Command::new(editor_args.next().unwrap_or("vim"))
+        .args(editor_args)
         .arg(&temp_path)
         .status()
-        .map_err(|e| format!("{} failed: {}", &*EDITOR, e.to_string()))?;
+        .map_err(|e| format!("{} failed: {}", editor, e.to_string()))?;
 
     if !exit.success() {
-        return Err(format!("{} did not exit successfully", &*EDITOR));
+        return Err(format!("{} did not exit successfully", editor));
     }
 
     // temp file -> validate with crate -> original
@@ -285,6 +459,23 @@ pub fn edit_and_replace(plist_meta: &LaunchdPlist) -> Result<(), String> {
         return Err("No changes made".to_string());
     }
 
+    let PlistLint { errors, warnings } = lint(&plist);
+    if !errors.is_empty() {
+        return Err(format!(
+            "Changes not saved (edits kept in {}): {}",
+            temp_path.to_string_lossy(),
+            errors.join(", ")
+        ));
+    }
+
+    let backup = backup_plist(label, &plist_meta.plist_path).map_err(|e| {
+        format!(
+            "Changes not saved (edits kept in {}): {}",
+            temp_path.to_string_lossy(),
+            e
+        )
+    })?;
+
     let writer = if is_binary {
         plist::Value::to_file_binary
     } else {
@@ -293,5 +484,5 @@ pub fn edit_and_replace(plist_meta: &LaunchdPlist) -> Result<(), String> {
 
     writer(&plist, &plist_meta.plist_path).map_err(|e| e.to_string())?;
 
-    Ok(())
+    Ok((backup, warnings))
 }
@@ -0,0 +1,260 @@
+use std::collections::BTreeMap;
+
+use plist::Value;
+
+use crate::launchd::job_detail::{is_job_target, parsed_dumpstate};
+use crate::launchd::print_parser::StateSection;
+
+/// Plist keys that can be read back from launchctl print output
+pub static COMPARED_KEYS: [&str; 12] = [
+    "Program",
+    "ProgramArguments",
+    "WorkingDirectory",
+    "StandardInPath",
+    "StandardOutPath",
+    "StandardErrorPath",
+    "EnvironmentVariables",
+    "StartInterval",
+    "RunAtLoad",
+    "KeepAlive",
+    "ExitTimeOut",
+    "MachServices",
+];
+
+/// One key as launchd has it, and as the plist on disk has it
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct KeyDiff {
+    pub key: String,
+    pub loaded: Option<String>,
+    pub on_disk: Option<String>,
+}
+
+impl KeyDiff {
+    pub fn differs(&self) -> bool {
+        self.loaded != self.on_disk
+    }
+}
+
+/// A flag in e.g. `properties = keepalive | runatload`
+fn has_property(section: &StateSection, property: &str) -> bool {
+    section
+        .value("properties")
+        .map(|p| p.split('|').any(|f| f.trim() == property))
+        .unwrap_or(false)
+}
+
+fn sorted_pairs(pairs: impl Iterator<Item = (String, String)>) -> Option<String> {
+    let pairs: BTreeMap<String, String> = pairs.collect();
+
+    if pairs.is_empty() {
+        None
+    } else {
+        Some(
+            pairs
+                .iter()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>()
+                .join(", "),
+        )
+    }
+}
+
+fn sorted_names(names: impl Iterator<Item = String>) -> Option<String> {
+    let mut names: Vec<String> = names.collect();
+    names.sort();
+
+    Some(names.join(", ")).filter(|n| !n.is_empty())
+}
+
+/// Effective configuration from a job's dumpstate or print block
+pub fn loaded_config(section: &StateSection) -> BTreeMap<String, String> {
+    let owned = |key: &str| section.value(key).map(String::from);
+    let flag = |property: &str| Some(has_property(section, property).to_string());
+
+    let values = [
+        ("Program", owned("program")),
+        (
+            "ProgramArguments",
+            section
+                .section("arguments")
+                .map(|a| a.items.join(" "))
+                .filter(|a| !a.is_empty()),
+        ),
+        ("WorkingDirectory", owned("working directory")),
+        ("StandardInPath", owned("stdin path")),
+        ("StandardOutPath", owned("stdout path")),
+        ("StandardErrorPath", owned("stderr path")),
+        (
+            "EnvironmentVariables",
+            section
+                .section("environment")
+                .and_then(|e| sorted_pairs(e.values.iter().cloned())),
+        ),
+        (
+            "StartInterval",
+            section
+                .value("run interval")
+                .map(|i| i.trim_end_matches(" seconds").to_string()),
+        ),
+        ("RunAtLoad", flag("runatload")),
+        ("KeepAlive", flag("keepalive")),
+        ("ExitTimeOut", owned("exit timeout")),
+        (
+            "MachServices",
+            section
+                .section("endpoints")
+                .and_then(|e| sorted_names(e.sections.iter().map(|s| s.name.clone()))),
+        ),
+    ];
+
+    values
+        .iter()
+        .filter_map(|(k, v)| v.clone().map(|v| (k.to_string(), v)))
+        .collect()
+}
+
+fn display_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Boolean(b) => b.to_string(),
+        Value::Integer(i) => i.to_string(),
+        Value::Real(r) => r.to_string(),
+        Value::Array(a) => a.iter().map(display_value).collect::<Vec<_>>().join(" "),
+        Value::Dictionary(d) => {
+            sorted_pairs(d.iter().map(|(k, v)| (k.clone(), display_value(v)))).unwrap_or_default()
+        }
+        other => format!("{:?}", other),
+    }
+}
+
+/// The same keys from a plist, with launchd's defaults filled in so they
+/// compare like for like
+pub fn disk_config(plist: &Value) -> BTreeMap<String, String> {
+    let dict = match plist.as_dictionary() {
+        Some(d) => d,
+        None => return BTreeMap::new(),
+    };
+
+    let mut config: BTreeMap<String, String> = COMPARED_KEYS
+        .iter()
+        .filter_map(|k| dict.get(k).map(|v| (k.to_string(), display_value(v))))
+        .collect();
+
+    // launchd runs ProgramArguments[0] without Program, and vice versa
+    let first_argument = dict
+        .get("ProgramArguments")
+        .and_then(Value::as_array)
+        .and_then(|a| a.first())
+        .and_then(Value::as_string)
+        .map(String::from);
+
+    if let Some(program) = dict
+        .get("Program")
+        .and_then(Value::as_string)
+        .map(String::from)
+        .or(first_argument)
+    {
+        config
+            .entry("Program".to_string())
+            .or_insert(program.clone());
+        config
+            .entry("ProgramArguments".to_string())
+            .or_insert(program);
+    }
+
+    // A KeepAlive dict (conditions) still means kept alive
+    if let Some(Value::Dictionary(_)) = dict.get("KeepAlive") {
+        config.insert("KeepAlive".to_string(), "true".to_string());
+    }
+
+    if let Some(Value::Dictionary(services)) = dict.get("MachServices") {
+        if let Some(names) = sorted_names(services.keys().cloned()) {
+            config.insert("MachServices".to_string(), names);
+        }
+    }
+
+    for key in ["RunAtLoad", "KeepAlive"].iter() {
+        config
+            .entry(key.to_string())
+            .or_insert_with(|| "false".to_string());
+    }
+
+    config
+}
+
+/// Every compared key present on either side
+pub fn diff(loaded: &BTreeMap<String, String>, on_disk: &BTreeMap<String, String>) -> Vec<KeyDiff> {
+    COMPARED_KEYS
+        .iter()
+        .filter(|k| loaded.contains_key(**k) || on_disk.contains_key(**k))
+        .map(|k| KeyDiff {
+            key: k.to_string(),
+            loaded: loaded.get(*k).cloned(),
+            on_disk: on_disk.get(*k).cloned(),
+        })
+        .collect()
+}
+
+/// Compare a loaded job with its plist, returns the plist path and keys
+pub fn plist_diff(label: &str, plist_path: Option<&str>) -> Result<(String, Vec<KeyDiff>), String> {
+    let state = parsed_dumpstate()?;
+    let section = state
+        .sections
+        .iter()
+        .find(|s| is_job_target(&s.name, label))
+        .ok_or_else(|| format!("{} is not loaded", label))?;
+
+    let path = section
+        .value("path")
+        .or(plist_path)
+        .ok_or_else(|| format!("No plist for {}", label))?
+        .to_string();
+
+    let plist = Value::from_file(&path).map_err(|e| format!("{}: {}", path, e))?;
+
+    Ok((path, diff(&loaded_config(section), &disk_config(&plist))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff, disk_config, loaded_config};
+    use crate::launchd::print_parser::parse;
+    use plist::{Dictionary, Value};
+
+    static PRINT: &str = "gui/501/com.example.agent = {
+	path = /Users/me/Library/LaunchAgents/com.example.agent.plist
+	program = /usr/local/bin/agent
+	arguments = {
+		/usr/local/bin/agent
+		--verbose
+	}
+	stdout path = /tmp/agent.log
+	run interval = 300 seconds
+	properties = runatload | inferred program
+}
+";
+
+    #[test]
+    fn diff_loaded_and_disk() {
+        let state = parse(PRINT);
+        let loaded = loaded_config(&state.sections[0]);
+
+        let mut dict = Dictionary::new();
+        dict.insert(
+            "ProgramArguments".to_string(),
+            Value::Array(vec!["/usr/local/bin/agent".into(), "--verbose".into()]),
+        );
+        dict.insert("StandardOutPath".to_string(), "/tmp/agent.log".into());
+        dict.insert("StartInterval".to_string(), 600.into());
+        dict.insert("RunAtLoad".to_string(), true.into());
+
+        let diffs = diff(&loaded, &disk_config(&Value::Dictionary(dict)));
+        let differing: Vec<&str> = diffs
+            .iter()
+            .filter(|d| d.differs())
+            .map(|d| d.key.as_str())
+            .collect();
+
+        assert_eq!(differing, vec!["ProgramArguments", "StartInterval"]);
+    }
+}
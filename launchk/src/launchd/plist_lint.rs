@@ -0,0 +1,386 @@
+use std::path::Path;
+
+use plist::{Dictionary, Value};
+use serde::Serialize;
+
+/// Expected type of a launchd.plist(5) key
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum KeyType {
+    String,
+    Integer,
+    Boolean,
+    Dictionary,
+    StringArray,
+    StringOrStringArray,
+    BooleanOrDictionary,
+    DictionaryOrArray,
+    /// Known key, contents not checked
+    Any,
+}
+
+static KNOWN_KEYS: [(&str, KeyType); 60] = [
+    ("Label", KeyType::String),
+    ("Disabled", KeyType::Boolean),
+    ("UserName", KeyType::String),
+    ("GroupName", KeyType::String),
+    ("inetdCompatibility", KeyType::Dictionary),
+    ("LimitLoadToHosts", KeyType::StringArray),
+    ("LimitLoadFromHosts", KeyType::StringArray),
+    ("LimitLoadToSessionType", KeyType::StringOrStringArray),
+    ("LimitLoadToHardware", KeyType::Dictionary),
+    ("LimitLoadFromHardware", KeyType::Dictionary),
+    ("Program", KeyType::String),
+    ("BundleProgram", KeyType::String),
+    ("ProgramArguments", KeyType::StringArray),
+    ("EnableGlobbing", KeyType::Boolean),
+    ("EnableTransactions", KeyType::Boolean),
+    ("EnablePressuredExit", KeyType::Boolean),
+    ("OnDemand", KeyType::Boolean),
+    ("ServiceIPC", KeyType::Boolean),
+    ("KeepAlive", KeyType::BooleanOrDictionary),
+    ("RunAtLoad", KeyType::Boolean),
+    ("RootDirectory", KeyType::String),
+    ("WorkingDirectory", KeyType::String),
+    ("EnvironmentVariables", KeyType::Dictionary),
+    ("Umask", KeyType::Any),
+    ("TimeOut", KeyType::Integer),
+    ("ExitTimeOut", KeyType::Integer),
+    ("ThrottleInterval", KeyType::Integer),
+    ("InitGroups", KeyType::Boolean),
+    ("WatchPaths", KeyType::StringArray),
+    ("QueueDirectories", KeyType::StringArray),
+    ("StartOnMount", KeyType::Boolean),
+    ("StartInterval", KeyType::Integer),
+    ("StartCalendarInterval", KeyType::DictionaryOrArray),
+    ("StandardInPath", KeyType::String),
+    ("StandardOutPath", KeyType::String),
+    ("StandardErrorPath", KeyType::String),
+    ("Debug", KeyType::Boolean),
+    ("WaitForDebugger", KeyType::Boolean),
+    ("SoftResourceLimits", KeyType::Dictionary),
+    ("HardResourceLimits", KeyType::Dictionary),
+    ("Nice", KeyType::Integer),
+    ("ProcessType", KeyType::String),
+    ("AbandonProcessGroup", KeyType::Boolean),
+    ("LowPriorityIO", KeyType::Boolean),
+    ("LowPriorityBackgroundIO", KeyType::Boolean),
+    ("MaterializeDatalessFiles", KeyType::Boolean),
+    ("LaunchOnlyOnce", KeyType::Boolean),
+    ("MachServices", KeyType::Dictionary),
+    ("Sockets", KeyType::Dictionary),
+    ("LaunchEvents", KeyType::Dictionary),
+    ("SessionCreate", KeyType::Boolean),
+    ("LegacyTimers", KeyType::Boolean),
+    ("AssociatedBundleIdentifiers", KeyType::StringOrStringArray),
+    ("HopefullyExitsFirst", KeyType::Boolean),
+    ("HopefullyExitsLast", KeyType::Boolean),
+    ("POSIXSpawnType", KeyType::String),
+    ("JetsamProperties", KeyType::Dictionary),
+    ("EnableTransactionsAsync", KeyType::Any),
+    ("CFBundleIdentifier", KeyType::Any),
+    ("_ManagedBy", KeyType::Any),
+];
+
+/// Keys launchd ignores or that launchd.plist(5) says to avoid, and why
+static DEPRECATED_KEYS: [(&str, &str); 5] = [
+    ("OnDemand", "use KeepAlive"),
+    ("ServiceIPC", "ignored by launchd"),
+    ("LimitLoadToHosts", "ignored by launchd"),
+    ("LimitLoadFromHosts", "ignored by launchd"),
+    (
+        "inetdCompatibility",
+        "avoid for new jobs, use Sockets and accept connections",
+    ),
+];
+
+/// Keys that can't be used together
+static EXCLUSIVE_KEYS: [(&str, &str); 2] =
+    [("Program", "BundleProgram"), ("OnDemand", "KeepAlive")];
+
+/// StartCalendarInterval keys and their ranges
+static CALENDAR_KEYS: [(&str, i64, i64); 5] = [
+    ("Minute", 0, 59),
+    ("Hour", 0, 23),
+    ("Day", 1, 31),
+    ("Weekday", 0, 7),
+    ("Month", 1, 12),
+];
+
+/// Problems found by lint
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize)]
+pub struct PlistLint {
+    /// launchd would refuse (or misinterpret) the plist
+    pub errors: Vec<String>,
+    /// Probably a typo, e.g. unknown keys
+    pub warnings: Vec<String>,
+}
+
+fn is_string_array(value: &Value) -> bool {
+    value
+        .as_array()
+        .map(|a| a.iter().all(|v| v.as_string().is_some()))
+        .unwrap_or(false)
+}
+
+fn matches_type(value: &Value, key_type: KeyType) -> bool {
+    match key_type {
+        KeyType::String => value.as_string().is_some(),
+        KeyType::Integer => value.as_signed_integer().is_some(),
+        KeyType::Boolean => value.as_boolean().is_some(),
+        KeyType::Dictionary => value.as_dictionary().is_some(),
+        KeyType::StringArray => is_string_array(value),
+        KeyType::StringOrStringArray => value.as_string().is_some() || is_string_array(value),
+        KeyType::BooleanOrDictionary => {
+            value.as_boolean().is_some() || value.as_dictionary().is_some()
+        }
+        KeyType::DictionaryOrArray => value.as_dictionary().is_some() || value.as_array().is_some(),
+        KeyType::Any => true,
+    }
+}
+
+fn lint_calendar_interval(interval: &Value, result: &mut PlistLint) {
+    let dict = match interval.as_dictionary() {
+        Some(d) => d,
+        None => {
+            result
+                .errors
+                .push("StartCalendarInterval entries should be Dictionary".to_string());
+            return;
+        }
+    };
+
+    if dict.is_empty() {
+        result
+            .warnings
+            .push("Empty StartCalendarInterval runs every minute".to_string());
+    }
+
+    for (key, value) in dict.iter() {
+        let (_, min, max) = match CALENDAR_KEYS.iter().find(|(k, _, _)| k == key) {
+            Some(range) => range,
+            None => {
+                result
+                    .errors
+                    .push(format!("Unknown StartCalendarInterval key {}", key));
+                continue;
+            }
+        };
+
+        match value.as_signed_integer() {
+            Some(v) if v >= *min && v <= *max => {}
+            Some(v) => result.errors.push(format!(
+                "StartCalendarInterval {} {} is not in {}-{}",
+                key, v, min, max
+            )),
+            None => result
+                .errors
+                .push(format!("StartCalendarInterval {} should be Integer", key)),
+        }
+    }
+}
+
+/// Program, or ProgramArguments[0] when absolute, must exist to spawn
+fn lint_program_path(dict: &Dictionary, result: &mut PlistLint) {
+    let program = dict
+        .get("Program")
+        .and_then(Value::as_string)
+        .or_else(|| {
+            dict.get("ProgramArguments")
+                .and_then(Value::as_array)
+                .and_then(|a| a.first())
+                .and_then(Value::as_string)
+        })
+        .filter(|p| p.starts_with('/'));
+
+    if let Some(program) = program {
+        if !Path::new(program).exists() {
+            result
+                .errors
+                .push(format!("Program {} does not exist", program));
+        }
+    }
+}
+
+impl PlistLint {
+    /// "error: ..." then "warning: ..."
+    pub fn lines(&self) -> Vec<String> {
+        self.errors
+            .iter()
+            .map(|e| format!("error: {}", e))
+            .chain(self.warnings.iter().map(|w| format!("warning: {}", w)))
+            .collect()
+    }
+}
+
+/// launchctl-style checks on a launchd plist
+pub fn lint(plist: &Value) -> PlistLint {
+    let mut result = PlistLint::default();
+
+    let dict = match plist.as_dictionary() {
+        Some(d) => d,
+        None => {
+            result.errors.push("Root must be a dictionary".to_string());
+            return result;
+        }
+    };
+
+    if dict.get("Label").is_none() {
+        result.errors.push("Missing Label".to_string());
+    }
+
+    if ["Program", "ProgramArguments", "BundleProgram"]
+        .iter()
+        .all(|k| dict.get(k).is_none())
+    {
+        result
+            .errors
+            .push("Missing Program or ProgramArguments".to_string());
+    }
+
+    if let Some(args) = dict.get("ProgramArguments").and_then(|a| a.as_array()) {
+        if args.is_empty() {
+            result.errors.push("ProgramArguments is empty".to_string());
+        }
+    }
+
+    for (key, value) in dict.iter() {
+        match KNOWN_KEYS.iter().find(|(k, _)| k == key) {
+            Some((_, key_type)) if !matches_type(value, *key_type) => result
+                .errors
+                .push(format!("{} should be {:?}", key, key_type)),
+            Some(_) => {}
+            None => result.warnings.push(format!("Unknown key {}", key)),
+        }
+
+        if let Some((_, why)) = DEPRECATED_KEYS.iter().find(|(k, _)| k == key) {
+            result
+                .warnings
+                .push(format!("{} is deprecated, {}", key, why));
+        }
+    }
+
+    for (a, b) in EXCLUSIVE_KEYS.iter() {
+        if dict.contains_key(a) && dict.contains_key(b) {
+            result
+                .errors
+                .push(format!("{} and {} can't be used together", a, b));
+        }
+    }
+
+    if dict.contains_key("StartInterval") && dict.contains_key("StartCalendarInterval") {
+        result
+            .warnings
+            .push("StartInterval and StartCalendarInterval both start the job".to_string());
+    }
+
+    if let Some(inetd) = dict
+        .get("inetdCompatibility")
+        .and_then(Value::as_dictionary)
+    {
+        for (key, value) in inetd.iter() {
+            match key.as_str() {
+                "Wait" if value.as_boolean().is_none() => result
+                    .errors
+                    .push("inetdCompatibility Wait should be Boolean".to_string()),
+                "Wait" => {}
+                other => result
+                    .warnings
+                    .push(format!("Unknown inetdCompatibility key {}", other)),
+            }
+        }
+    }
+
+    match dict.get("StartCalendarInterval") {
+        Some(Value::Array(intervals)) => intervals
+            .iter()
+            .for_each(|i| lint_calendar_interval(i, &mut result)),
+        Some(interval @ Value::Dictionary(_)) => lint_calendar_interval(interval, &mut result),
+        _ => {}
+    }
+
+    lint_program_path(dict, &mut result);
+
+    result
+}
+
+/// Parse and lint a plist on disk
+pub fn lint_file<P: AsRef<Path>>(path: P) -> Result<PlistLint, String> {
+    Value::from_file(path)
+        .map(|v| lint(&v))
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use plist::{Dictionary, Value};
+
+    use super::lint;
+
+    #[test]
+    fn lint_type_mismatches() {
+        let mut dict = Dictionary::new();
+        dict.insert("Label".to_string(), "com.example".into());
+        dict.insert("ProgramArguments".to_string(), "/bin/true".into());
+        dict.insert("StartInterval".to_string(), "60".into());
+        dict.insert("RunAtLaod".to_string(), true.into());
+
+        let result = lint(&Value::Dictionary(dict));
+
+        assert_eq!(
+            result.errors,
+            vec![
+                "ProgramArguments should be StringArray",
+                "StartInterval should be Integer"
+            ]
+        );
+        assert_eq!(result.warnings, vec!["Unknown key RunAtLaod"]);
+    }
+
+    #[test]
+    fn lint_deprecated_and_calendar() {
+        let mut calendar = Dictionary::new();
+        calendar.insert("Hour".to_string(), 24.into());
+        calendar.insert("Minute".to_string(), 30.into());
+        calendar.insert("Second".to_string(), 0.into());
+
+        let mut dict = Dictionary::new();
+        dict.insert("Label".to_string(), "com.example".into());
+        dict.insert("Program".to_string(), "/nonexistent/launchk-lint".into());
+        dict.insert("OnDemand".to_string(), false.into());
+        dict.insert("KeepAlive".to_string(), true.into());
+        dict.insert(
+            "StartCalendarInterval".to_string(),
+            Value::Array(vec![Value::Dictionary(calendar)]),
+        );
+
+        let result = lint(&Value::Dictionary(dict));
+
+        assert_eq!(
+            result.errors,
+            vec![
+                "OnDemand and KeepAlive can't be used together",
+                "StartCalendarInterval Hour 24 is not in 0-23",
+                "Unknown StartCalendarInterval key Second",
+                "Program /nonexistent/launchk-lint does not exist",
+            ]
+        );
+        assert_eq!(
+            result.warnings,
+            vec!["OnDemand is deprecated, use KeepAlive"]
+        );
+        assert_eq!(
+            result.lines()[0],
+            "error: OnDemand and KeepAlive can't be used together"
+        );
+    }
+
+    #[test]
+    fn lint_missing_label() {
+        let mut dict = Dictionary::new();
+        dict.insert("Program".to_string(), "/usr/bin/true".into());
+
+        let result = lint(&Value::Dictionary(dict));
+
+        assert_eq!(result.errors, vec!["Missing Label"]);
+    }
+}
@@ -0,0 +1,169 @@
+use std::ffi::CString;
+use std::fmt;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+
+use xpc_sys::csr::sip_enabled;
+use xpc_sys::rs_geteuid;
+
+use crate::launchd::plist::{LaunchdEntryLocation, LaunchdPlist};
+
+/// What is about to be done to a job
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Operation {
+    /// Load, kickstart, kill etc., root only outside ~/Library/LaunchAgents
+    Control,
+    /// Unload, enable, disable or remove, which SIP also refuses for /System
+    Change,
+    /// Replace the plist (edit, restore a backup), then reload it
+    Write,
+}
+
+/// Why launchd or the filesystem would refuse, found before trying
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Blocker {
+    /// In /Library or /System (or no plist found) and we are not root
+    NeedsSudo(Option<LaunchdEntryLocation>),
+    /// In /System with System Integrity Protection on, even for root
+    Sip,
+    /// We cannot write the plist, owned by this uid
+    PlistNotWritable(u32),
+}
+
+impl Blocker {
+    /// Sudo would get past it
+    pub fn sudo_helps(&self) -> bool {
+        match self {
+            Blocker::NeedsSudo(_) => true,
+            Blocker::PlistNotWritable(owner) => *owner == 0,
+            Blocker::Sip => false,
+        }
+    }
+}
+
+impl fmt::Display for Blocker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Blocker::NeedsSudo(Some(LaunchdEntryLocation::Global)) => {
+                write!(f, "needs sudo, its plist is in /Library")
+            }
+            Blocker::NeedsSudo(Some(_)) => write!(f, "needs sudo, it is a system job"),
+            Blocker::NeedsSudo(None) => write!(f, "needs sudo, no plist found for it"),
+            Blocker::Sip => write!(
+                f,
+                "blocked by SIP, it is in /System and System Integrity Protection is on"
+            ),
+            Blocker::PlistNotWritable(0) => write!(f, "plist owned by root, edit will fail"),
+            Blocker::PlistNotWritable(uid) => {
+                write!(f, "plist owned by uid {}, edit will fail", uid)
+            }
+        }
+    }
+}
+
+/// Who we are and what the system allows
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Preflight {
+    pub euid: u32,
+    pub sip: bool,
+}
+
+impl Preflight {
+    pub fn current() -> Self {
+        Self {
+            euid: rs_geteuid(),
+            sip: sip_enabled(),
+        }
+    }
+
+    /// The first thing that would fail. writable is (can write, owner) of
+    /// the plist, only needed for Write.
+    pub fn check(
+        &self,
+        op: Operation,
+        location: Option<&LaunchdEntryLocation>,
+        writable: Option<(bool, u32)>,
+    ) -> Result<(), Blocker> {
+        let in_system = location == Some(&LaunchdEntryLocation::System);
+
+        if self.sip && in_system && op != Operation::Control {
+            return Err(Blocker::Sip);
+        }
+
+        if op == Operation::Write {
+            if let Some((false, owner)) = writable {
+                return Err(Blocker::PlistNotWritable(owner));
+            }
+        }
+
+        if self.euid != 0 && location != Some(&LaunchdEntryLocation::User) {
+            return Err(Blocker::NeedsSudo(location.cloned()));
+        }
+
+        Ok(())
+    }
+
+    /// check for a job's plist, reading its owner from disk
+    pub fn check_job(&self, op: Operation, plist: Option<&LaunchdPlist>) -> Result<(), Blocker> {
+        let writable = plist.filter(|_| op == Operation::Write).and_then(|p| {
+            let owner = fs::metadata(&p.plist_path).ok()?.uid();
+            Some((can_write(&p.plist_path), owner))
+        });
+
+        self.check(op, plist.map(|p| &p.entry_location), writable)
+    }
+}
+
+/// access(2), which also knows about groups and ACLs
+fn can_write(path: &str) -> bool {
+    CString::new(path)
+        .map(|p| unsafe { libc::access(p.as_ptr(), libc::W_OK) } == 0)
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Blocker, Operation, Preflight};
+    use crate::launchd::plist::LaunchdEntryLocation;
+
+    #[test]
+    fn preflight_blockers() {
+        let user = Preflight {
+            euid: 501,
+            sip: true,
+        };
+        let root = Preflight { euid: 0, sip: true };
+
+        let system = Some(&LaunchdEntryLocation::System);
+        let global = Some(&LaunchdEntryLocation::Global);
+        let home = Some(&LaunchdEntryLocation::User);
+
+        assert_eq!(
+            user.check(Operation::Control, global, None),
+            Err(Blocker::NeedsSudo(Some(LaunchdEntryLocation::Global)))
+        );
+        assert_eq!(
+            user.check(Operation::Control, None, None),
+            Err(Blocker::NeedsSudo(None))
+        );
+        assert_eq!(root.check(Operation::Control, system, None), Ok(()));
+        assert_eq!(
+            root.check(Operation::Change, system, None),
+            Err(Blocker::Sip)
+        );
+        assert_eq!(
+            user.check(Operation::Write, home, Some((false, 0))),
+            Err(Blocker::PlistNotWritable(0))
+        );
+        assert_eq!(
+            user.check(Operation::Write, home, Some((true, 501))),
+            Ok(())
+        );
+        assert_eq!(
+            user.check(Operation::Write, global, Some((true, 501))),
+            Err(Blocker::NeedsSudo(Some(LaunchdEntryLocation::Global)))
+        );
+        assert!(Blocker::PlistNotWritable(0).sudo_helps());
+        assert!(!Blocker::Sip.sudo_helps());
+    }
+}
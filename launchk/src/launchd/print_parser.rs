@@ -0,0 +1,148 @@
+/// A `name = { ... }` block from launchctl print, procinfo or dumpstate output
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct StateSection {
+    pub name: String,
+    /// `key = value` and `key => value` lines
+    pub values: Vec<(String, String)>,
+    /// Lines without a key, e.g. members of `arguments = {`
+    pub items: Vec<String>,
+    pub sections: Vec<StateSection>,
+}
+
+impl StateSection {
+    /// First value for key
+    pub fn value(&self, key: &str) -> Option<&str> {
+        self.values
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// First child section with name
+    pub fn section(&self, name: &str) -> Option<&StateSection> {
+        self.sections.iter().find(|s| s.name == name)
+    }
+}
+
+/// Parse output into a tree. The returned root section has no name, and
+/// holds any top level values (procinfo) or blocks (dumpstate).
+pub fn parse(text: &str) -> StateSection {
+    let mut stack: Vec<StateSection> = vec![StateSection::default()];
+
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(name) = line
+            .strip_suffix("=> {")
+            .or_else(|| line.strip_suffix("= {"))
+        {
+            stack.push(StateSection {
+                name: unquote(name.trim()),
+                ..Default::default()
+            });
+            continue;
+        }
+
+        if line == "}" {
+            close_section(&mut stack);
+            continue;
+        }
+
+        let current = stack.last_mut().expect("Must have root");
+        match split_key_value(line) {
+            Some(kv) => current.values.push(kv),
+            None => current.items.push(line.to_string()),
+        }
+    }
+
+    // Output may be truncated (e.g. shmem too small)
+    while stack.len() > 1 {
+        close_section(&mut stack);
+    }
+
+    stack.pop().expect("Must have root")
+}
+
+fn close_section(stack: &mut Vec<StateSection>) {
+    if stack.len() < 2 {
+        return;
+    }
+
+    let done = stack.pop().expect("Must have section");
+    stack
+        .last_mut()
+        .expect("Must have parent")
+        .sections
+        .push(done);
+}
+
+fn split_key_value(line: &str) -> Option<(String, String)> {
+    let (key, value) = line.split_once(" => ").or_else(|| line.split_once(" = "))?;
+
+    Some((unquote(key.trim()), value.trim().to_string()))
+}
+
+fn unquote(s: &str) -> String {
+    s.trim_matches('"').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+
+    static PRINT: &str = r#"gui/501/com.example.agent = {
+	active count = 1
+	path = /Users/me/Library/LaunchAgents/com.example.agent.plist
+	state = running
+
+	arguments = {
+		/usr/local/bin/agent
+		--verbose
+	}
+
+	environment = {
+		PATH => /usr/bin:/bin
+	}
+
+	endpoints = {
+		"com.example.agent.xpc" = {
+			port = 0x4503
+			active = 1
+		}
+	}
+}
+"#;
+
+    #[test]
+    fn parse_print_service() {
+        let root = parse(PRINT);
+        let job = root.section("gui/501/com.example.agent").unwrap();
+
+        assert_eq!(job.value("state"), Some("running"));
+        assert_eq!(
+            job.section("arguments").unwrap().items,
+            vec!["/usr/local/bin/agent", "--verbose"]
+        );
+        assert_eq!(
+            job.section("environment").unwrap().value("PATH"),
+            Some("/usr/bin:/bin")
+        );
+        assert_eq!(
+            job.section("endpoints").unwrap().sections[0].name,
+            "com.example.agent.xpc"
+        );
+    }
+
+    #[test]
+    fn parse_truncated() {
+        let root = parse("system = {\n\tservices = {\n\t\t0 - com.example");
+        let system = root.section("system").unwrap();
+
+        assert_eq!(
+            system.section("services").unwrap().items,
+            vec!["0 - com.example"]
+        );
+    }
+}
@@ -0,0 +1,168 @@
+use std::path::Path;
+
+use crate::launchd::query::list_all_detailed;
+use crate::launchd::signing::executable_path;
+
+/// Deep enough for helpers of helpers, and stops a runaway recursion
+const MAX_DEPTH: usize = 8;
+
+/// A process and everything it spawned
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ProcessNode {
+    pub pid: i32,
+    pub name: String,
+    pub children: Vec<ProcessNode>,
+}
+
+/// proc_listchildpids, retried with a bigger buffer while it fills up
+pub fn child_pids(pid: i32) -> Vec<i32> {
+    let mut capacity = 64;
+
+    loop {
+        let mut buffer: Vec<i32> = vec![0; capacity];
+        let count = unsafe {
+            libc::proc_listchildpids(
+                pid,
+                buffer.as_mut_ptr() as *mut libc::c_void,
+                (capacity * std::mem::size_of::<i32>()) as libc::c_int,
+            )
+        };
+
+        if count <= 0 {
+            return vec![];
+        }
+
+        if (count as usize) < capacity {
+            buffer.truncate(count as usize);
+            return buffer.into_iter().filter(|p| *p > 0).collect();
+        }
+
+        capacity *= 2;
+    }
+}
+
+/// proc_name, or the executable's file name
+pub fn process_name(pid: i32) -> String {
+    let mut buffer = vec![0u8; 256];
+    let len = unsafe {
+        libc::proc_name(
+            pid,
+            buffer.as_mut_ptr() as *mut libc::c_void,
+            buffer.len() as u32,
+        )
+    };
+
+    if len > 0 {
+        buffer.truncate(len as usize);
+        return String::from_utf8_lossy(&buffer).to_string();
+    }
+
+    executable_path(pid as i64)
+        .and_then(|p| {
+            Path::new(&p)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+        })
+        .unwrap_or_else(|| "?".to_string())
+}
+
+fn build(pid: i32, depth: usize) -> ProcessNode {
+    let children = if depth < MAX_DEPTH {
+        child_pids(pid)
+            .into_iter()
+            .map(|child| build(child, depth + 1))
+            .collect()
+    } else {
+        vec![]
+    };
+
+    ProcessNode {
+        pid,
+        name: process_name(pid),
+        children,
+    }
+}
+
+/// A job's process and its descendants
+pub fn process_tree(pid: i32) -> ProcessNode {
+    build(pid, 0)
+}
+
+/// (label, tree) for running jobs that have spawned something
+pub fn job_trees() -> Result<Vec<(String, ProcessNode)>, String> {
+    let trees = list_all_detailed()
+        .into_iter()
+        .filter(|e| e.pid > 0)
+        .map(|e| (e.label, process_tree(e.pid as i32)))
+        .filter(|(_, tree)| !tree.children.is_empty())
+        .collect();
+
+    Ok(trees)
+}
+
+impl ProcessNode {
+    /// (pid, line) with ├─ └─ guides, the root first
+    pub fn lines(&self) -> Vec<(i32, String)> {
+        let mut lines = vec![(self.pid, format!("{} {}", self.pid, self.name))];
+        self.child_lines("", &mut lines);
+        lines
+    }
+
+    fn child_lines(&self, prefix: &str, lines: &mut Vec<(i32, String)>) {
+        for (i, child) in self.children.iter().enumerate() {
+            let last = i == self.children.len() - 1;
+            let (branch, indent) = if last {
+                ("└─ ", "   ")
+            } else {
+                ("├─ ", "│  ")
+            };
+
+            lines.push((
+                child.pid,
+                format!("{}{}{} {}", prefix, branch, child.pid, child.name),
+            ));
+            child.child_lines(&format!("{}{}", prefix, indent), lines);
+        }
+    }
+
+    pub fn descendants(&self) -> usize {
+        self.children.iter().map(|c| 1 + c.descendants()).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProcessNode;
+
+    fn node(pid: i32, name: &str, children: Vec<ProcessNode>) -> ProcessNode {
+        ProcessNode {
+            pid,
+            name: name.to_string(),
+            children,
+        }
+    }
+
+    #[test]
+    fn tree_lines() {
+        let tree = node(
+            100,
+            "daemon",
+            vec![
+                node(101, "helper", vec![node(103, "worker", vec![])]),
+                node(102, "helper", vec![]),
+            ],
+        );
+
+        let lines: Vec<String> = tree.lines().into_iter().map(|(_, l)| l).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "100 daemon",
+                "├─ 101 helper",
+                "│  └─ 103 worker",
+                "└─ 102 helper",
+            ]
+        );
+        assert_eq!(tree.descendants(), 3);
+    }
+}
@@ -0,0 +1,101 @@
+use serde::Serialize;
+
+use crate::launchd::print_parser::StateSection;
+
+/// launchctl procinfo output, the parts worth a name
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize)]
+pub struct ProcInfo {
+    pub program_path: Option<String>,
+    pub arguments: Vec<String>,
+    pub environment: Vec<(String, String)>,
+    pub cputypes: Vec<String>,
+    /// e.g. valid, followed by flags like restrict or library validation
+    pub code_signing: Option<String>,
+    pub code_signing_flags: Vec<String>,
+    /// Every top level key and value
+    pub values: Vec<(String, String)>,
+}
+
+/// Items, or values keyed [0], [1]... in order
+fn vector(section: Option<&StateSection>) -> Vec<String> {
+    let section = match section {
+        Some(s) => s,
+        None => return vec![],
+    };
+
+    let indexed = section
+        .values
+        .iter()
+        .filter(|(k, _)| k.starts_with('['))
+        .map(|(_, v)| v.clone());
+
+    section.items.iter().cloned().chain(indexed).collect()
+}
+
+impl From<StateSection> for ProcInfo {
+    fn from(root: StateSection) -> Self {
+        let owned = |key: &str| root.value(key).map(String::from);
+
+        let cputypes = root
+            .values
+            .iter()
+            .filter(|(k, _)| k.contains("cputype") || k.contains("cpu type"))
+            .map(|(_, v)| v.clone())
+            .chain(vector(root.section("cputypes")))
+            .collect();
+
+        Self {
+            program_path: owned("program path"),
+            arguments: vector(root.section("argument vector")),
+            environment: root
+                .section("environment vector")
+                .map(|s| s.values.clone())
+                .unwrap_or_default(),
+            cputypes,
+            code_signing: owned("code signing info"),
+            // Flags are indented under code signing info, without braces
+            code_signing_flags: root.items.clone(),
+            values: root.values,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProcInfo;
+    use crate::launchd::print_parser::parse;
+
+    static PROCINFO: &str = "program path = /usr/local/bin/agent
+argument count = 2
+argument vector = {
+	[0] = /usr/local/bin/agent
+	[1] = --verbose
+}
+environment vector = {
+	PATH => /usr/bin:/bin
+}
+bsd proc info = {
+	pid = 123
+}
+code signing info = valid
+	restrict
+	library validation
+";
+
+    #[test]
+    fn parse_procinfo() {
+        let info = ProcInfo::from(parse(PROCINFO));
+
+        assert_eq!(info.program_path.as_deref(), Some("/usr/local/bin/agent"));
+        assert_eq!(info.arguments, vec!["/usr/local/bin/agent", "--verbose"]);
+        assert_eq!(
+            info.environment,
+            vec![("PATH".to_string(), "/usr/bin:/bin".to_string())]
+        );
+        assert_eq!(info.code_signing.as_deref(), Some("valid"));
+        assert_eq!(
+            info.code_signing_flags,
+            vec!["restrict", "library validation"]
+        );
+    }
+}
@@ -0,0 +1,60 @@
+use xpc_sys::enums::DomainType;
+
+use crate::config::config;
+use crate::launchd::plist::{LaunchdEntryLocation, LaunchdPlist};
+
+/// Session critical whatever domain they are seen in
+static CRITICAL_LABELS: [&str; 10] = [
+    "com.apple.WindowServer",
+    "com.apple.loginwindow",
+    "com.apple.coreservicesd",
+    "com.apple.securityd",
+    "com.apple.opendirectoryd",
+    "com.apple.logd",
+    "com.apple.configd",
+    "com.apple.notifyd",
+    "com.apple.diskarbitrationd",
+    "com.apple.mDNSResponder",
+];
+
+/// Exact label, or a prefix ending in *
+pub fn matches_pattern(pattern: &str, label: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => label.starts_with(prefix),
+        None => pattern == label,
+    }
+}
+
+/// Needs typed confirmation before unload, disable or kill: the critical
+/// list, Apple daemons in the system domain (children of launchd), and
+/// anything matching `protected` in config
+pub fn is_protected(label: &str, domain: &DomainType) -> bool {
+    CRITICAL_LABELS.contains(&label)
+        || (*domain == DomainType::System && label.starts_with("com.apple."))
+        || config().protected.iter().any(|p| matches_pattern(p, label))
+}
+
+/// Loaded from /System, so launchd refuses to unload, disable or enable it
+/// while SIP is on (see xpc_sys::csr::sip_enabled)
+pub fn is_sip_protected(plist: Option<&LaunchdPlist>) -> bool {
+    plist
+        .map(|p| p.entry_location == LaunchdEntryLocation::System)
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_protected, matches_pattern};
+    use xpc_sys::enums::DomainType;
+
+    #[test]
+    fn protected_labels() {
+        assert!(matches_pattern("com.example.*", "com.example.vpn"));
+        assert!(!matches_pattern("com.example.vpn", "com.example.vpnd"));
+
+        assert!(is_protected("com.apple.WindowServer", &DomainType::Unknown));
+        assert!(is_protected("com.apple.backupd", &DomainType::System));
+        assert!(!is_protected("com.apple.Spotlight", &DomainType::Gui));
+        assert!(!is_protected("com.example.agent", &DomainType::System));
+    }
+}
@@ -1,22 +1,42 @@
 use crate::launchd::message::{
-    DISABLE_NAMES, DUMPJPCATEGORY, DUMPSTATE, ENABLE_NAMES, LIST_SERVICES, LOAD_PATHS, PROCINFO,
-    UNLOAD_PATHS,
+    BLAME, BOOTOUT_NAMES, BOOTSTRAP_PATHS, DEBUG, DISABLE_NAMES, DUMPJPCATEGORY, DUMPSTATE,
+    ENABLE_NAMES, GETENV, KICKSTART, KICKSTART_KILL, KILL, LIMIT, LIST_SERVICES, LOAD_PATHS,
+    MANAGERNAME, MANAGERPID, MANAGERUID, PRINT, PRINT_CACHE, PRINT_DISABLED, PROCINFO, REMOVE,
+    RESOLVEPORT, SETENV, START, STOP, SUBMIT, UNCACHE, UNLOAD_PATHS, VERSION,
 };
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::convert::TryFrom;
+use std::ffi::CStr;
+use std::fmt::Display;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+use std::ptr::slice_from_raw_parts;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use std::thread;
+
+use serde::{Serialize, Serializer};
 
 use xpc_sys::{
-    objects::xpc_shmem::XPCShmem,
-    rs_geteuid,
-    traits::{xpc_pipeable::XPCPipeable, xpc_value::TryXPCValue},
-    MAP_SHARED,
+    mach_port_deallocate, mach_port_t, mach_task_self_, objects::xpc_object::XPCObject,
+    objects::xpc_shmem::XPCShmem, rs_bootstrap_look_up, rs_geteuid, rs_getpid, rs_sysctlbyname,
+    traits::xpc_value::TryXPCValue, xpc_null_create, MAP_SHARED,
 };
 
+use crate::launchd::domain_target::DomainTarget;
 use crate::launchd::entry_status::ENTRY_STATUS_CACHE;
+use crate::launchd::limits::Limit;
+use crate::launchd::pipe::LaunchdRoutine;
+use crate::launchd::plist::LABEL_TO_ENTRY_CONFIG;
+use crate::launchd::print_parser::parse;
+use crate::launchd::procinfo::ProcInfo;
 use std::iter::FromIterator;
 use xpc_sys::objects::xpc_dictionary::XPCDictionary;
-use xpc_sys::objects::xpc_error::XPCError;
+use xpc_sys::objects::xpc_error::{launchd_error_message, XPCError};
+use xpc_sys::objects::xpc_fd::XPCFd;
 use xpc_sys::traits::query_builder::QueryBuilder;
+use xpc_sys::traits::xpc_deserialize::{Field, FromXPCDictionary};
 
 use xpc_sys::enums::{DomainType, SessionType};
 
@@ -28,7 +48,7 @@ pub fn find_in_all<S: Into<String>>(label: S) -> Result<(DomainType, XPCDictiona
             .extend(&LIST_SERVICES)
             .entry("type", domain_type)
             .entry("name", label_string.clone())
-            .pipe_routine_with_error_handling();
+            .launchd_query();
 
         if response.is_ok() {
             return response.map(|r| (domain_type.into(), r));
@@ -44,11 +64,184 @@ pub fn list(domain_type: DomainType, name: Option<String>) -> Result<XPCDictiona
         .extend(&LIST_SERVICES)
         .with_domain_type_or_default(Some(domain_type))
         .entry_if_present("name", name)
-        .pipe_routine_with_error_handling()
+        .launchd_query()
 }
 
-/// Query for jobs across all domain types
-pub fn list_all() -> HashSet<String> {
+/// Query for jobs in a domain that needs a handle, e.g. gui/501
+pub fn list_target(target: &DomainTarget) -> Result<HashSet<String>, XPCError> {
+    let XPCDictionary(services) = XPCDictionary::new()
+        .extend(&LIST_SERVICES)
+        .with_domain_type_or_default(Some(target.domain_type.clone()))
+        .with_handle_or_default(Some(target.handle))
+        .launchd_query()
+        .and_then(|d| d.get_as_dictionary(["services"]))?;
+
+    Ok(services.into_keys().collect())
+}
+
+/// XPC services in a process's domain (pid/<pid>), e.g. ones embedded
+/// in an app, sorted by label
+pub fn list_pid_domain(pid: u64) -> Result<Vec<(String, ListedService)>, XPCError> {
+    let services = XPCDictionary::new()
+        .extend(&LIST_SERVICES)
+        .with_domain_type_or_default(Some(DomainType::PID))
+        .with_handle_or_default(Some(pid))
+        .launchd_query()?
+        .parse_map_lossy::<ListedService, _, _>(["services"])?;
+
+    let (services, errors) = services;
+
+    // Still listed, just without pid or status
+    let unreadable = errors.into_iter().map(|(label, e)| {
+        log::warn!("[query/list_pid_domain]: {} unreadable, {}", label, e);
+        let svc = ListedService {
+            pid: 0,
+            status: None,
+            unparseable: vec!["service"],
+        };
+        (label, svc)
+    });
+
+    let mut services: Vec<(String, ListedService)> =
+        services.into_iter().chain(unreadable).collect();
+    services.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    Ok(services)
+}
+
+/// Labels in a user's gui and user domains
+pub fn list_user(uid: u32) -> HashSet<String> {
+    vec![DomainType::Gui, DomainType::User]
+        .into_iter()
+        .filter_map(|t| {
+            let target = DomainTarget::new(t, uid as u64);
+            let labels = list_target(&target);
+
+            if let Err(e) = &labels {
+                log::error!("[query/list_user]: error {}, domain, {}", e, target);
+            }
+
+            labels.ok()
+        })
+        .flatten()
+        .collect()
+}
+
+/// uid to user name, for everyone with a login session in utmpx
+pub fn logged_in_users() -> BTreeMap<u32, String> {
+    let mut users = BTreeMap::new();
+
+    unsafe {
+        libc::setutxent();
+
+        loop {
+            let entry = libc::getutxent();
+            if entry.is_null() {
+                break;
+            }
+
+            if (*entry).ut_type != libc::USER_PROCESS {
+                continue;
+            }
+
+            let passwd = libc::getpwnam((*entry).ut_user.as_ptr());
+            if passwd.is_null() {
+                continue;
+            }
+
+            let name = CStr::from_ptr((*passwd).pw_name)
+                .to_string_lossy()
+                .to_string();
+            users.insert((*passwd).pw_uid, name);
+        }
+
+        libc::endutxent();
+    }
+
+    users
+}
+
+/// A job from list, with what launchctl list shows for it
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct ServiceEntry {
+    pub label: String,
+    #[serde(serialize_with = "serialize_display")]
+    pub domain: DomainType,
+    pub pid: i64,
+    /// Last exit status, None if not loaded
+    pub status: Option<i64>,
+    pub plist_path: Option<String>,
+    /// Keys launchd sent in a shape we cannot read, their fields are
+    /// defaults. "service" if the whole entry was unreadable.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unparseable: Vec<&'static str>,
+}
+
+/// Keys of fields that were present but unreadable
+fn unparseable_keys(fields: &[(&'static str, bool)]) -> Vec<&'static str> {
+    fields
+        .iter()
+        .filter(|(_, unparseable)| *unparseable)
+        .map(|(key, _)| *key)
+        .collect()
+}
+
+/// A value of list's "services", keyed by label. Fields are read
+/// leniently so one odd job does not fail its whole domain.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ListedService {
+    pub pid: i64,
+    /// Last exit status
+    pub status: Option<i64>,
+    pub unparseable: Vec<&'static str>,
+}
+
+impl FromXPCDictionary for ListedService {
+    fn from_xpc_dictionary(dict: &XPCDictionary) -> Result<Self, XPCError> {
+        let pid: Field<i64> = dict.field(["pid"]);
+        let status: Field<i64> = dict.field(["status"]);
+
+        Ok(ListedService {
+            unparseable: unparseable_keys(&[
+                ("pid", pid.is_unparseable()),
+                ("status", status.is_unparseable()),
+            ]),
+            pid: pid.value().unwrap_or(0),
+            status: status.value(),
+        })
+    }
+}
+
+/// list's "service" when asking for one label
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct LoadedService {
+    pub pid: i64,
+    pub limit_load_to_session_type: SessionType,
+    pub unparseable: Vec<&'static str>,
+}
+
+impl FromXPCDictionary for LoadedService {
+    fn from_xpc_dictionary(dict: &XPCDictionary) -> Result<Self, XPCError> {
+        let pid: Field<i64> = dict.field(["PID"]);
+        let session: Field<SessionType> = dict.field(["LimitLoadToSessionType"]);
+
+        Ok(LoadedService {
+            unparseable: unparseable_keys(&[
+                ("PID", pid.is_unparseable()),
+                ("LimitLoadToSessionType", session.is_unparseable()),
+            ]),
+            pid: pid.value().unwrap_or(0),
+            limit_load_to_session_type: session.value().unwrap_or(SessionType::Unknown),
+        })
+    }
+}
+
+fn serialize_display<T: Display, S: Serializer>(value: &T, s: S) -> Result<S::Ok, S::Error> {
+    s.collect_str(value)
+}
+
+/// Domains we can list without a handle
+fn listable_domains() -> Vec<DomainType> {
     let mut everything = vec![
         DomainType::System,
         DomainType::RequestorUserDomain,
@@ -59,29 +252,103 @@ pub fn list_all() -> HashSet<String> {
         everything.push(DomainType::User);
     }
 
-    let list = everything
-        .iter()
-        .filter_map(|t| {
-            let svc_for_type = list(t.clone(), None)
-                .and_then(|d| d.get_as_dictionary(&["services"]))
-                .map(|XPCDictionary(ref hm)| hm.keys().map(|k| k.clone()).collect());
-
-            if svc_for_type.is_err() {
-                log::error!(
-                    "[query/list_all]: poll error {}, domain, {}",
-                    svc_for_type.err().unwrap(),
-                    t
-                );
+    everything
+}
+
+lazy_static! {
+    /// Domains the last list_all or list_all_detailed could not enumerate
+    static ref LIST_ERRORS: RwLock<Vec<(DomainType, XPCError)>> = RwLock::new(vec![]);
+}
+
+/// Domains that failed to list last time, e.g. System without access
+pub fn list_errors() -> Vec<(DomainType, XPCError)> {
+    LIST_ERRORS.read().expect("Must read list errors").clone()
+}
+
+/// list each listable domain, remembering the ones that fail
+fn list_each_domain<T, F>(read: F) -> Vec<(DomainType, T)>
+where
+    F: Fn(XPCDictionary) -> Result<T, XPCError>,
+{
+    let mut errors = vec![];
+
+    let listed = listable_domains()
+        .into_iter()
+        .filter_map(|t| match list(t.clone(), None).and_then(&read) {
+            Ok(services) => Some((t, services)),
+            Err(e) => {
+                log::error!("[query/list_each_domain]: error {}, domain, {}", e, t);
+                errors.push((t, e));
                 None
-            } else {
-                svc_for_type.ok()
             }
         })
-        .flat_map(|k: Vec<String>| k.into_iter());
+        .collect();
+
+    *LIST_ERRORS.write().expect("Must record list errors") = errors;
+    listed
+}
+
+/// Query for jobs across all domain types
+pub fn list_all() -> HashSet<String> {
+    let list = list_each_domain(|d| d.get_as_dictionary(&["services"]))
+        .into_iter()
+        .flat_map(|(_, XPCDictionary(hm))| hm.into_keys());
 
     HashSet::from_iter(list)
 }
 
+/// Query for jobs across all domain types, with pid and status
+pub fn list_all_detailed() -> Vec<ServiceEntry> {
+    let plists = LABEL_TO_ENTRY_CONFIG.read().ok();
+
+    let mut entries: Vec<ServiceEntry> =
+        list_each_domain(|d| d.parse_map_lossy::<ListedService, _, _>(["services"]))
+            .into_iter()
+            .flat_map(|(domain, (services, errors))| {
+                let plists = &plists;
+
+                // Still listed, just without pid or status
+                let unreadable = errors.into_iter().map(|(label, e)| {
+                    log::warn!("[query/list_all_detailed]: {} unreadable, {}", label, e);
+                    let svc = ListedService {
+                        pid: 0,
+                        status: None,
+                        unparseable: vec!["service"],
+                    };
+                    (label, svc)
+                });
+
+                services
+                    .into_iter()
+                    .chain(unreadable.collect::<Vec<_>>())
+                    .map(move |(label, svc)| ServiceEntry {
+                        plist_path: plists
+                            .as_ref()
+                            .and_then(|p| p.get(&label))
+                            .map(|p| p.plist_path.clone()),
+                        domain: domain.clone(),
+                        pid: svc.pid,
+                        status: svc.status,
+                        unparseable: svc.unparseable,
+                        label,
+                    })
+            })
+            .collect();
+
+    entries.sort_by(|a, b| a.label.cmp(&b.label));
+    entries
+}
+
+/// For requests that change what is loaded. The label's row goes now, and
+/// anything cached while the request ran is discarded after, as other rows
+/// (the same label in another domain, overrides) can change too
+fn changing_loaded<T>(label: &str, request: impl FnOnce() -> T) -> T {
+    ENTRY_STATUS_CACHE.invalidate(label);
+    let result = request();
+    ENTRY_STATUS_CACHE.invalidate_all();
+    result
+}
+
 pub fn load<S: Into<String>>(
     label: S,
     plist_path: S,
@@ -89,18 +356,15 @@ pub fn load<S: Into<String>>(
     session: Option<SessionType>,
     handle: Option<u64>,
 ) -> Result<XPCDictionary, XPCError> {
-    ENTRY_STATUS_CACHE
-        .lock()
-        .expect("Must invalidate")
-        .remove(&label.into());
-
-    XPCDictionary::new()
-        .extend(&LOAD_PATHS)
-        .with_domain_type_or_default(domain_type)
-        .with_session_type_or_default(session)
-        .with_handle_or_default(handle)
-        .entry("paths", vec![plist_path.into()])
-        .pipe_routine_with_error_handling()
+    changing_loaded(&label.into(), || {
+        XPCDictionary::new()
+            .extend(&LOAD_PATHS)
+            .with_domain_type_or_default(domain_type)
+            .with_session_type_or_default(session)
+            .with_handle_or_default(handle)
+            .entry("paths", vec![plist_path.into()])
+            .launchd_routine()
+    })
 }
 
 pub fn unload<S: Into<String>>(
@@ -110,98 +374,914 @@ pub fn unload<S: Into<String>>(
     session: Option<SessionType>,
     handle: Option<u64>,
 ) -> Result<XPCDictionary, XPCError> {
-    ENTRY_STATUS_CACHE
-        .lock()
-        .expect("Must invalidate")
-        .remove(&label.into());
+    changing_loaded(&label.into(), || {
+        XPCDictionary::new()
+            .extend(&UNLOAD_PATHS)
+            .with_domain_type_or_default(domain_type)
+            .with_session_type_or_default(session)
+            .with_handle_or_default(handle)
+            .entry("paths", vec![plist_path.into()])
+            .launchd_routine()
+    })
+}
+
+/// launchctl submit: run program with args as label, without a plist.
+/// launchd keeps it alive until removed.
+pub fn submit<S: Into<String>>(
+    label: S,
+    program: S,
+    args: Vec<String>,
+) -> Result<XPCDictionary, XPCError> {
+    let label_string = label.into();
+
+    let mut program_arguments = vec![program.into()];
+    program_arguments.extend(args);
+
+    let request = XPCDictionary::new()
+        .entry("Label", label_string.clone())
+        .entry("ProgramArguments", program_arguments)
+        .entry("KeepAlive", true);
+
+    changing_loaded(&label_string, || {
+        XPCDictionary::new()
+            .extend(&SUBMIT)
+            .with_domain_type_or_default(None)
+            .entry("request", request)
+            .launchd_routine()
+    })
+}
+
+/// launchctl remove: unload label from the requestor's domain, the
+/// only way to tear down a submitted job
+pub fn remove<S: Into<String>>(label: S) -> Result<XPCDictionary, XPCError> {
+    let label_string = label.into();
+
+    changing_loaded(&label_string, || {
+        XPCDictionary::new()
+            .extend(&REMOVE)
+            .with_domain_type_or_default(None)
+            .entry("name", label_string.clone())
+            .launchd_routine()
+    })
+}
+
+/// Darwin 15 (OS X 10.11) replaced load/unload with bootstrap/bootout
+const BOOTSTRAP_MIN_DARWIN: u64 = 15;
+
+lazy_static! {
+    static ref HAS_BOOTSTRAP: bool = unsafe { rs_sysctlbyname("kern.osrelease") }
+        .ok()
+        .and_then(|r| r.split('.').next().and_then(|major| major.parse().ok()))
+        .map(|major: u64| major >= BOOTSTRAP_MIN_DARWIN)
+        .unwrap_or(false);
+}
+
+/// Can use bootstrap/bootout instead of the legacy load/unload
+pub fn has_bootstrap() -> bool {
+    *HAS_BOOTSTRAP
+}
+
+/// launchctl bootstrap <domain-target> <path>
+pub fn bootstrap<S: Into<String>>(
+    label: S,
+    plist_path: S,
+    target: &DomainTarget,
+    session: Option<SessionType>,
+) -> Result<XPCDictionary, XPCError> {
+    changing_loaded(&label.into(), || {
+        XPCDictionary::new()
+            .extend(&BOOTSTRAP_PATHS)
+            .with_domain_type_or_default(Some(target.domain_type.clone()))
+            .with_session_type_or_default(session)
+            .with_handle_or_default(Some(target.handle))
+            .entry("paths", vec![plist_path.into()])
+            .launchd_routine()
+    })
+}
+
+/// launchctl bootout <domain-target>/<label>
+pub fn bootout<S: Into<String>>(
+    label: S,
+    target: &DomainTarget,
+) -> Result<XPCDictionary, XPCError> {
+    let label_string = label.into();
+
+    changing_loaded(&label_string, || {
+        XPCDictionary::new()
+            .extend(&BOOTOUT_NAMES)
+            .with_domain_type_or_default(Some(target.domain_type.clone()))
+            .with_handle_or_default(Some(target.handle))
+            .entry("name", label_string.clone())
+            .launchd_routine()
+    })
+}
+
+/// enable a job, in another user's domain with a handle (e.g. gui/502
+/// as root), otherwise the requestor's
+pub fn enable<S: Into<String>>(
+    label: S,
+    domain_type: DomainType,
+    handle: Option<u64>,
+) -> Result<XPCDictionary, XPCError> {
+    set_enabled(label.into(), domain_type, handle, true)
+}
+
+/// disable a job, see enable
+pub fn disable<S: Into<String>>(
+    label: S,
+    domain_type: DomainType,
+    handle: Option<u64>,
+) -> Result<XPCDictionary, XPCError> {
+    set_enabled(label.into(), domain_type, handle, false)
+}
+
+fn set_enabled(
+    label: String,
+    domain_type: DomainType,
+    handle: Option<u64>,
+    enabled: bool,
+) -> Result<XPCDictionary, XPCError> {
+    let names = if enabled {
+        &*ENABLE_NAMES
+    } else {
+        &*DISABLE_NAMES
+    };
 
     XPCDictionary::new()
-        .extend(&UNLOAD_PATHS)
-        .with_domain_type_or_default(domain_type)
-        .with_session_type_or_default(session)
+        .extend(names)
+        .with_domain_type_or_default(Some(domain_type))
+        .entry("name", label.clone())
+        .entry("names", vec![label])
         .with_handle_or_default(handle)
-        .entry("paths", vec![plist_path.into()])
-        .pipe_routine_with_error_handling()
+        .launchd_routine()
 }
 
-pub fn enable<S: Into<String>>(
+/// enable or disable in a domain target, e.g. gui/501 for another
+/// user's agent
+pub fn set_enabled_in(
+    label: &str,
+    target: &DomainTarget,
+    enabled: bool,
+) -> Result<XPCDictionary, XPCError> {
+    set_enabled(
+        label.to_string(),
+        target.domain_type.clone(),
+        Some(target.handle),
+        enabled,
+    )
+}
+
+/// Start a job if not running, optionally killing the running
+/// instance first so it is restarted (launchctl kickstart -k)
+pub fn kickstart<S: Into<String>>(
     label: S,
     domain_type: DomainType,
+    kill_first: bool,
 ) -> Result<XPCDictionary, XPCError> {
     let label_string = label.into();
 
+    ENTRY_STATUS_CACHE.invalidate(&label_string);
+
     XPCDictionary::new()
-        .extend(&ENABLE_NAMES)
+        .extend(&KICKSTART)
         .with_domain_type_or_default(Some(domain_type))
-        .entry("name", label_string.clone())
-        .entry("names", vec![label_string])
         .with_handle_or_default(None)
-        .pipe_routine_with_error_handling()
+        .entry("name", label_string)
+        .entry("flags", if kill_first { KICKSTART_KILL } else { 0 })
+        .launchd_routine()
 }
 
-pub fn disable<S: Into<String>>(
+/// launchctl resolveport: label of the job holding the receive right
+/// for a port name in owner_pid
+pub fn resolveport(owner_pid: i64, port: mach_port_t) -> Result<String, XPCError> {
+    XPCDictionary::new()
+        .extend(&RESOLVEPORT)
+        .entry("pid", owner_pid)
+        .entry("port", port as u64)
+        .launchd_query()?
+        .get(["name"])?
+        .xpc_value()
+}
+
+/// Label of the job hosting a Mach service, by looking it up and
+/// resolving our own send right
+pub fn resolve_service(service: &str) -> Result<String, XPCError> {
+    let port = rs_bootstrap_look_up(service).map_err(XPCError::QueryError)?;
+    let label = resolveport(rs_getpid() as i64, port);
+
+    unsafe { mach_port_deallocate(mach_task_self_, port) };
+
+    label
+}
+
+/// The launchd we are talking to, None where a query failed
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize)]
+pub struct LaunchdManager {
+    pub uid: Option<u64>,
+    pub pid: Option<i64>,
+    /// e.g. System, or Aqua for a per-user launchd
+    pub name: Option<String>,
+    pub version: Option<String>,
+    /// RELEASE, DEVELOPMENT or DEBUG (launchctl variant)
+    pub variant: Option<String>,
+}
+
+/// e.g. Aqua (uid 501, pid 1)
+impl Display for LaunchdManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let unknown = || "?".to_string();
+
+        write!(
+            f,
+            "{} (uid {}, pid {})",
+            self.name.clone().unwrap_or_else(unknown),
+            self.uid.map(|u| u.to_string()).unwrap_or_else(unknown),
+            self.pid.map(|p| p.to_string()).unwrap_or_else(unknown),
+        )
+    }
+}
+
+fn manager_value<T>(message: &XPCDictionary, key: &str) -> Result<T, XPCError>
+where
+    XPCObject: TryXPCValue<T>,
+{
+    XPCDictionary::new()
+        .extend(message)
+        .with_domain_type_or_default(None)
+        .launchd_query()?
+        .get([key])?
+        .xpc_value()
+}
+
+/// The build in the version string, e.g. DEVELOPMENT from
+/// "...; root:libxpc_executables-2236.140.2~11/launchd/DEVELOPMENT_ARM64E"
+pub fn launchd_variant(version: &str) -> Option<String> {
+    let (_, build) = version.trim().rsplit_once("/launchd/")?;
+    let variant = build.split('_').next()?;
+
+    Some(variant.to_string()).filter(|v| !v.is_empty())
+}
+
+/// launchctl managername, e.g. System, Aqua or Background
+pub fn manager_name() -> Result<String, XPCError> {
+    manager_value(&MANAGERNAME, "name")
+}
+
+/// launchctl manageruid
+pub fn manager_uid() -> Result<u64, XPCError> {
+    manager_value(&MANAGERUID, "uid")
+}
+
+/// launchctl managerpid
+pub fn manager_pid() -> Result<i64, XPCError> {
+    manager_value(&MANAGERPID, "pid")
+}
+
+/// launchctl manageruid, managerpid, managername and version
+pub fn launchd_manager() -> LaunchdManager {
+    let version: Option<String> = manager_value(&VERSION, "version").ok();
+
+    LaunchdManager {
+        uid: manager_uid().ok(),
+        pid: manager_pid().ok(),
+        name: manager_name().ok(),
+        variant: version.as_deref().and_then(launchd_variant),
+        version,
+    }
+}
+
+/// Overrides for the next launch of a job, see launchctl debug
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct DebugOptions {
+    /// Run this instead of the job's Program
+    pub program_path: Option<String>,
+    /// Start suspended until a debugger attaches
+    pub wait_for_debugger: bool,
+    pub stdin_path: Option<String>,
+    /// Appended to, created if missing
+    pub stdout_path: Option<String>,
+    pub stderr_path: Option<String>,
+    pub environment: Vec<(String, String)>,
+}
+
+fn open_fd(path: &Option<String>, write: bool) -> Result<Option<File>, XPCError> {
+    path.as_ref()
+        .map(|p| {
+            OpenOptions::new()
+                .read(!write)
+                .append(write)
+                .create(write)
+                .open(p)
+                .map_err(|e| XPCError::IOError(format!("{}: {}", p, e)))
+        })
+        .transpose()
+}
+
+/// Configure a one-shot debug launch of a loaded job (launchctl debug)
+pub fn debug<S: Into<String>>(
+    label: S,
+    domain_type: DomainType,
+    options: &DebugOptions,
+) -> Result<XPCDictionary, XPCError> {
+    // launchd dups the fds, ours only need to outlive the call
+    let stdin = open_fd(&options.stdin_path, false)?;
+    let stdout = open_fd(&options.stdout_path, true)?;
+    let stderr = open_fd(&options.stderr_path, true)?;
+
+    let environment: HashMap<String, Arc<XPCObject>> = options
+        .environment
+        .iter()
+        .map(|(k, v)| (k.clone(), Arc::new(XPCObject::from(v.as_str()))))
+        .collect();
+
+    XPCDictionary::new()
+        .extend(&DEBUG)
+        .with_domain_type_or_default(Some(domain_type))
+        .with_handle_or_default(None)
+        .entry("name", label.into())
+        .entry_if_present("program", options.program_path.clone())
+        .entry("wait-for-debugger", options.wait_for_debugger)
+        .entry_if_present("stdin", stdin.as_ref().map(File::as_raw_fd))
+        .entry_if_present("stdout", stdout.as_ref().map(File::as_raw_fd))
+        .entry_if_present("stderr", stderr.as_ref().map(File::as_raw_fd))
+        .entry("environment", XPCDictionary(environment))
+        .launchd_routine()
+}
+
+/// Start a loaded job without kickstart semantics (launchctl start)
+pub fn start<S: Into<String>>(
     label: S,
     domain_type: DomainType,
 ) -> Result<XPCDictionary, XPCError> {
     let label_string = label.into();
 
+    ENTRY_STATUS_CACHE.invalidate(&label_string);
+
+    XPCDictionary::new()
+        .extend(&START)
+        .with_domain_type_or_default(Some(domain_type))
+        .with_handle_or_default(None)
+        .entry("name", label_string)
+        .launchd_routine()
+}
+
+/// Stop a running job but leave it loaded (launchctl stop)
+pub fn stop<S: Into<String>>(label: S, domain_type: DomainType) -> Result<XPCDictionary, XPCError> {
+    let label_string = label.into();
+
+    ENTRY_STATUS_CACHE.invalidate(&label_string);
+
     XPCDictionary::new()
-        .extend(&DISABLE_NAMES)
+        .extend(&STOP)
         .with_domain_type_or_default(Some(domain_type))
-        .entry("name", label_string.clone())
-        .entry("names", vec![label_string])
         .with_handle_or_default(None)
-        .pipe_routine_with_error_handling()
+        .entry("name", label_string)
+        .launchd_routine()
+}
+
+/// Send a signal to a job (launchctl kill)
+pub fn kill_signal<S: Into<String>>(
+    label: S,
+    domain_type: DomainType,
+    signal: i32,
+) -> Result<XPCDictionary, XPCError> {
+    let label_string = label.into();
+
+    ENTRY_STATUS_CACHE.invalidate(&label_string);
+
+    XPCDictionary::new()
+        .extend(&KILL)
+        .with_domain_type_or_default(Some(domain_type))
+        .with_handle_or_default(None)
+        .entry("name", label_string)
+        .entry("signal", signal as i64)
+        .launchd_routine()
+}
+
+/// Set an environment variable for jobs started in a domain (launchctl setenv)
+pub fn setenv<S: Into<String>>(
+    key: S,
+    value: S,
+    target: &DomainTarget,
+) -> Result<XPCDictionary, XPCError> {
+    let mut envvars = HashMap::new();
+    envvars.insert(key.into(), Arc::new(XPCObject::from(value.into())));
+
+    XPCDictionary::new()
+        .extend(&SETENV)
+        .with_domain_type_or_default(Some(target.domain_type.clone()))
+        .with_handle_or_default(Some(target.handle))
+        .entry("envvars", XPCDictionary(envvars))
+        .launchd_routine()
+}
+
+/// Remove an environment variable from a domain (launchctl unsetenv)
+pub fn unsetenv<S: Into<String>>(key: S, target: &DomainTarget) -> Result<XPCDictionary, XPCError> {
+    let mut envvars = HashMap::new();
+    envvars.insert(
+        key.into(),
+        Arc::new(XPCObject::from(unsafe { xpc_null_create() })),
+    );
+
+    XPCDictionary::new()
+        .extend(&SETENV)
+        .with_domain_type_or_default(Some(target.domain_type.clone()))
+        .with_handle_or_default(Some(target.handle))
+        .entry("envvars", XPCDictionary(envvars))
+        .launchd_routine()
+}
+
+/// Read an environment variable from a domain (launchctl getenv)
+pub fn getenv<S: Into<String>>(key: S, target: &DomainTarget) -> Result<String, XPCError> {
+    XPCDictionary::new()
+        .extend(&GETENV)
+        .with_domain_type_or_default(Some(target.domain_type.clone()))
+        .with_handle_or_default(Some(target.handle))
+        .entry("envvar", key.into())
+        .launchd_query()?
+        .get(["value"])?
+        .xpc_value()
+}
+
+impl FromXPCDictionary for Limit {
+    fn from_xpc_dictionary(dict: &XPCDictionary) -> Result<Self, XPCError> {
+        Ok(Limit {
+            soft: dict.optional_value(["soft"])?,
+            hard: dict.optional_value(["hard"])?,
+        })
+    }
+}
+
+/// launchctl limit, launchd's resource limits by name (e.g. maxfiles)
+pub fn limits() -> Result<BTreeMap<String, Limit>, XPCError> {
+    XPCDictionary::new()
+        .extend(&LIMIT)
+        .with_domain_type_or_default(None)
+        .launchd_query()?
+        .parse_map::<Limit, _, _>(["limits"])
+        .map(BTreeMap::from_iter)
+}
+
+/// launchctl limit <name> <soft> <hard>, needs root for the system domain
+pub fn set_limit(name: &str, limit: Limit) -> Result<XPCDictionary, XPCError> {
+    let mut values = HashMap::new();
+    let mut entry = |key: &str, value: Option<u64>| {
+        if let Some(v) = value {
+            values.insert(key.to_string(), Arc::new(XPCObject::from(v)));
+        }
+    };
+    entry("soft", limit.soft);
+    entry("hard", limit.hard);
+
+    let mut limits = HashMap::new();
+    limits.insert(
+        name.to_string(),
+        Arc::new(XPCObject::from(XPCDictionary(values))),
+    );
+
+    XPCDictionary::new()
+        .extend(&LIMIT)
+        .with_domain_type_or_default(None)
+        .entry("limits", XPCDictionary(limits))
+        .launchd_routine()
+}
+
+/// Why launchd started a job, e.g. "speculative", "xpc event" (launchctl blame)
+pub fn blame<S: Into<String>>(label: S, target: &DomainTarget) -> Result<String, XPCError> {
+    XPCDictionary::new()
+        .extend(&BLAME)
+        .with_domain_type_or_default(Some(target.domain_type.clone()))
+        .with_handle_or_default(Some(target.handle))
+        .entry("name", label.into())
+        .launchd_query()?
+        .get(["reason"])?
+        .xpc_value()
+}
+
+/// launchctl print-disabled <domain-target>, label -> disabled. Enabled
+/// entries are overrides too, they only mean something was re-enabled.
+pub fn print_disabled(target: &DomainTarget) -> Result<HashMap<String, bool>, XPCError> {
+    let XPCDictionary(overrides) = XPCDictionary::new()
+        .extend(&PRINT_DISABLED)
+        .with_domain_type_or_default(Some(target.domain_type.clone()))
+        .with_handle_or_default(Some(target.handle))
+        .launchd_query()
+        .and_then(|r| r.get_as_dictionary(["disabled"]))?;
+
+    Ok(overrides
+        .into_iter()
+        .filter_map(|(label, disabled)| disabled.xpc_value().ok().map(|d| (label, d)))
+        .collect())
+}
+
+/// launchctl print-cache for pid's domain, launchk's own by default. The
+/// reply is described as is, its shape differs between releases.
+pub fn print_cache(pid: Option<i64>) -> Result<String, XPCError> {
+    let pid = pid.unwrap_or_else(|| rs_getpid() as i64);
+    let reply = XPCDictionary::new()
+        .extend(&PRINT_CACHE)
+        .with_domain_type_or_default(Some(DomainType::PID))
+        .with_handle_or_default(Some(pid as u64))
+        .launchd_query()?;
+
+    Ok(XPCObject::from(&reply).to_string())
+}
+
+/// launchctl uncache <service-name> for pid's domain, launchk's own by
+/// default. Sent once, it changes the cache.
+pub fn uncache<S: Into<String>>(service: S, pid: Option<i64>) -> Result<XPCDictionary, XPCError> {
+    let service = service.into();
+    if service.is_empty() {
+        return Err(XPCError::ValueError("No service name".to_string()));
+    }
+
+    let pid = pid.unwrap_or_else(|| rs_getpid() as i64);
+
+    XPCDictionary::new()
+        .extend(&UNCACHE)
+        .with_domain_type_or_default(Some(DomainType::PID))
+        .with_handle_or_default(Some(pid as u64))
+        .entry("name", service)
+        .launchd_routine()
+        .map_err(|e| match e {
+            XPCError::QueryError(m) if m == launchd_error_message(libc::ENOTSUP as i64) => {
+                XPCError::QueryError("uncache is not supported by this launchd".to_string())
+            }
+            e => e,
+        })
+}
+
+/// Overrides from every domain we can list, label -> disabled. Disabled
+/// in any domain wins.
+pub fn disabled_overrides() -> HashMap<String, bool> {
+    let mut merged: HashMap<String, bool> = HashMap::new();
+
+    for t in listable_domains() {
+        let overrides = match print_disabled(&DomainTarget::new(t.clone(), 0)) {
+            Ok(o) => o,
+            Err(e) => {
+                log::error!("[query/disabled_overrides]: error {}, domain, {}", e, t);
+                continue;
+            }
+        };
+
+        for (label, disabled) in overrides {
+            *merged.entry(label).or_insert(false) |= disabled;
+        }
+    }
+
+    merged
+}
+
+/// Labels disabled in any domain we can list
+pub fn disabled_labels() -> HashSet<String> {
+    disabled_overrides()
+        .into_iter()
+        .filter(|(_, d)| *d)
+        .map(|(label, _)| label)
+        .collect()
+}
+
+/// Largest region handed to launchd, output is truncated past this
+const SHMEM_MAX_SIZE: usize = 0x10000000;
+
+/// Region size that last held the whole output, so polls don't regrow
+static SHMEM_SIZE: AtomicUsize = AtomicUsize::new(0x400000);
+
+/// Run a routine that writes into a shmem region, doubling the region
+/// and re-issuing while launchd fills it (bytes-written == size). Each
+/// region is deallocated when dropped, including on error
+fn shmem_routine<F>(query: F) -> Result<(usize, XPCShmem), XPCError>
+where
+    F: Fn(&XPCShmem) -> XPCDictionary,
+{
+    let mut size = SHMEM_SIZE.load(Ordering::Relaxed);
+
+    loop {
+        let shmem =
+            XPCShmem::new_task_self(size, i32::try_from(MAP_SHARED).expect("Must conv flags"))?;
+
+        let response = query(&shmem).launchd_query()?;
+        let bytes_written: u64 = response.get(["bytes-written"])?.xpc_value()?;
+        let bytes_written = usize::try_from(bytes_written).unwrap().min(size);
+
+        if bytes_written < size {
+            SHMEM_SIZE.fetch_max(size, Ordering::Relaxed);
+            return Ok((bytes_written, shmem));
+        }
+
+        if size >= SHMEM_MAX_SIZE {
+            log::warn!("shmem output truncated at {} bytes", size);
+            return Ok((bytes_written, shmem));
+        }
+
+        size = (size * 2).min(SHMEM_MAX_SIZE);
+    }
 }
 
 /// Create a shared shmem region for the XPC routine to write
 /// dumpstate contents into, and return the bytes written and
 /// shmem region
 pub fn dumpstate() -> Result<(usize, XPCShmem), XPCError> {
-    let shmem = XPCShmem::new_task_self(
-        0x1400000,
-        i32::try_from(MAP_SHARED).expect("Must conv flags"),
-    )?;
+    shmem_routine(|shmem| {
+        XPCDictionary::new()
+            .extend(&DUMPSTATE)
+            .entry("shmem", &shmem.xpc_object)
+    })
+}
 
-    let response = XPCDictionary::new()
-        .extend(&DUMPSTATE)
-        .entry("shmem", &shmem.xpc_object)
-        .pipe_routine_with_error_handling()?;
+/// Write dumpstate to a file in chunks, reporting (bytes written, total)
+/// as it goes. Returns the total. on_progress returning false stops and
+/// removes the partial file.
+pub fn dumpstate_to_file<F: FnMut(usize, usize) -> bool>(
+    path: &str,
+    mut on_progress: F,
+) -> Result<usize, String> {
+    const CHUNK: usize = 0x100000;
+
+    let (size, shmem) = dumpstate().map_err(|e| e.to_string())?;
+    let bytes = unsafe { &*slice_from_raw_parts(shmem.region as *const u8, size) };
+    let mut file = File::create(path).map_err(|e| format!("Cannot write {}: {}", path, e))?;
+
+    for (i, chunk) in bytes.chunks(CHUNK).enumerate() {
+        file.write_all(chunk)
+            .map_err(|e| format!("Cannot write {}: {}", path, e))?;
+        if !on_progress((i * CHUNK + chunk.len()).min(size), size) {
+            drop(file);
+            fs::remove_file(path).unwrap_or(());
+            return Err("Cancelled".to_string());
+        }
+    }
 
-    let bytes_written: u64 = response.get(&["bytes-written"])?.xpc_value()?;
+    Ok(size)
+}
 
-    Ok((usize::try_from(bytes_written).unwrap(), shmem))
+/// launchctl print <domain-target> into shmem, like dumpstate for one domain
+pub fn print_domain(
+    domain_type: DomainType,
+    handle: Option<u64>,
+) -> Result<(usize, XPCShmem), XPCError> {
+    shmem_routine(|shmem| {
+        XPCDictionary::new()
+            .extend(&PRINT)
+            .with_domain_type_or_default(Some(domain_type.clone()))
+            .with_handle_or_default(handle)
+            .entry("shmem", &shmem.xpc_object)
+    })
 }
 
-pub fn dumpjpcategory() -> Result<(usize, XPCShmem), XPCError> {
-    let shmem = XPCShmem::new_task_self(
-        0x1400000,
-        i32::try_from(MAP_SHARED).expect("Must conv flags"),
-    )?;
+/// launchctl print <service-target>, e.g. pid/123/com.example.xpcservice
+pub fn print_service(target: &DomainTarget, name: &str) -> Result<(usize, XPCShmem), XPCError> {
+    shmem_routine(|shmem| {
+        XPCDictionary::new()
+            .extend(&PRINT)
+            .with_domain_type_or_default(Some(target.domain_type.clone()))
+            .with_handle_or_default(Some(target.handle))
+            .entry("name", name)
+            .entry("shmem", &shmem.xpc_object)
+    })
+}
 
+/// launchctl dumpjpcategory, launchd writes the report to a pipe we read
+/// on another thread (so a full pipe cannot block it)
+pub fn dumpjpcategory() -> Result<Vec<u8>, XPCError> {
+    let mut fds: [RawFd; 2] = [-1; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        return Err(XPCError::QueryError("Cannot create pipe".to_string()));
+    }
+
+    let [read_fd, write_fd] = fds;
+    let reader = thread::spawn(move || {
+        let mut buf = vec![];
+        unsafe { File::from_raw_fd(read_fd) }
+            .read_to_end(&mut buf)
+            .map(|_| buf)
+    });
+
+    // The request holds a dup of write_fd until it is dropped here
+    let write_fd = unsafe { XPCFd::from_raw_fd(write_fd) };
     let response = XPCDictionary::new()
         .extend(&DUMPJPCATEGORY)
-        .entry("shmem", &shmem.xpc_object)
-        .pipe_routine_with_error_handling()?;
+        .entry("fd", &write_fd)
+        .launchd_routine();
 
-    let bytes_written: u64 = response.get(&["bytes-written"])?.xpc_value()?;
+    // EOF for the reader once launchd is done with its copy
+    drop(write_fd);
 
-    Ok((usize::try_from(bytes_written).unwrap(), shmem))
+    let output = reader
+        .join()
+        .map_err(|_| XPCError::QueryError("dumpjpcategory reader panicked".to_string()))?
+        .map_err(|e| XPCError::QueryError(e.to_string()));
+
+    response?;
+    output
 }
 
 pub fn procinfo(pid: i64) -> Result<(usize, XPCShmem), XPCError> {
-    let shmem = XPCShmem::new_task_self(
-        0x1400000,
-        i32::try_from(MAP_SHARED).expect("Must conv flags"),
-    )?;
+    shmem_routine(|shmem| {
+        XPCDictionary::new()
+            .extend(&PROCINFO)
+            .entry("shmem", &shmem.xpc_object)
+            .entry("pid", pid)
+    })
+}
 
-    let response = XPCDictionary::new()
-        .extend(&PROCINFO)
-        .entry("shmem", &shmem.xpc_object)
-        .entry("pid", pid)
-        .pipe_routine_with_error_handling()?;
+/// procinfo parsed, shmem is managed here
+pub fn procinfo_parsed(pid: i64) -> Result<ProcInfo, XPCError> {
+    let (size, shmem) = procinfo(pid)?;
+    let text =
+        String::from_utf8_lossy(unsafe { &*slice_from_raw_parts(shmem.region as *mut u8, size) });
+
+    Ok(ProcInfo::from(parse(&text)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use xpc_sys::objects::xpc_dictionary::XPCDictionary;
+    use xpc_sys::objects::xpc_error::{launchd_error_message, XPCError};
+    use xpc_sys::traits::query_builder::QueryBuilder;
+    use xpc_sys::traits::xpc_value::TryXPCValue;
 
-    let bytes_written: u64 = response.get(&["bytes-written"])?.xpc_value()?;
+    use super::{
+        launchd_variant, list_pid_domain, list_target, manager_name, print_service, uncache,
+        ListedService,
+    };
+    use crate::launchd::domain_target::DomainTarget;
+    use crate::launchd::pipe::mock::MockPipe;
+    use crate::launchd::pipe::with_pipe;
+    use xpc_sys::enums::DomainType;
+
+    #[test]
+    fn query_mock_pipe() {
+        let services = XPCDictionary::new()
+            .entry("com.example.a", XPCDictionary::new())
+            .entry("com.example.b", XPCDictionary::new());
+
+        let pipe = Rc::new(
+            MockPipe::default()
+                .reply(303, XPCDictionary::new().entry("name", "Aqua"))
+                .reply(815, XPCDictionary::new().entry("services", &services)),
+        );
+
+        let (name, labels) = with_pipe(pipe.clone(), || {
+            (
+                manager_name(),
+                list_target(&DomainTarget::new(DomainType::Gui, 501)),
+            )
+        });
+
+        assert_eq!(name, Ok("Aqua".to_string()));
+
+        let mut labels: Vec<String> = labels.unwrap().into_iter().collect();
+        labels.sort();
+        assert_eq!(labels, vec!["com.example.a", "com.example.b"]);
+
+        let sent = pipe.sent.borrow();
+        let handle: u64 = sent[1].get(["handle"]).unwrap().xpc_value().unwrap();
+        assert_eq!(handle, 501);
+    }
+
+    #[test]
+    fn query_mock_pipe_errors() {
+        let pipe =
+            Rc::new(MockPipe::default().reply(303, XPCDictionary::new().entry("error", 113_i64)));
+
+        assert_eq!(
+            with_pipe(pipe.clone(), manager_name),
+            Err(XPCError::QueryError(launchd_error_message(113)))
+        );
+        assert!(matches!(
+            with_pipe(pipe, || list_target(&DomainTarget::new(
+                DomainType::Gui,
+                501
+            ))),
+            Err(XPCError::PipeError(_))
+        ));
+    }
 
-    Ok((usize::try_from(bytes_written).unwrap(), shmem))
+    #[test]
+    fn pid_domain_keeps_unreadable() {
+        let services = XPCDictionary::new()
+            .entry(
+                "com.example.b",
+                XPCDictionary::new()
+                    .entry("pid", 12_i64)
+                    .entry("status", 0_i64),
+            )
+            .entry("com.example.a", "not a dictionary");
+
+        let pipe = Rc::new(
+            MockPipe::default().reply(815, XPCDictionary::new().entry("services", &services)),
+        );
+
+        let services = with_pipe(pipe.clone(), || list_pid_domain(77)).unwrap();
+        assert_eq!(
+            services,
+            vec![
+                (
+                    "com.example.a".to_string(),
+                    ListedService {
+                        pid: 0,
+                        status: None,
+                        unparseable: vec!["service"],
+                    }
+                ),
+                (
+                    "com.example.b".to_string(),
+                    ListedService {
+                        pid: 12,
+                        status: Some(0),
+                        unparseable: vec![],
+                    }
+                ),
+            ]
+        );
+
+        let handle: u64 = pipe.sent.borrow()[0]
+            .get(["handle"])
+            .unwrap()
+            .xpc_value()
+            .unwrap();
+        assert_eq!(handle, 77);
+    }
+
+    #[test]
+    fn print_service_mock_pipe() {
+        let pipe = Rc::new(
+            MockPipe::default().reply(828, XPCDictionary::new().entry("bytes-written", 12_u64)),
+        );
+
+        let target = DomainTarget::new(DomainType::PID, 123);
+        let (written, _) = with_pipe(pipe.clone(), || {
+            print_service(&target, "com.example.xpcservice")
+        })
+        .unwrap();
+        assert_eq!(written, 12);
+
+        let sent = pipe.sent.borrow();
+        assert_eq!(sent.len(), 1);
+
+        let name: String = sent[0].get(["name"]).unwrap().xpc_value().unwrap();
+        let handle: u64 = sent[0].get(["handle"]).unwrap().xpc_value().unwrap();
+        assert_eq!((name.as_str(), handle), ("com.example.xpcservice", 123));
+        assert!(sent[0].get(["shmem"]).is_ok());
+
+        let failing = Rc::new(
+            MockPipe::default().reply(828, XPCDictionary::new().entry("error", libc::ESRCH as i64)),
+        );
+        assert_eq!(
+            with_pipe(failing, || print_service(&target, "com.example.xpcservice")).err(),
+            Some(XPCError::QueryError(launchd_error_message(
+                libc::ESRCH as i64
+            )))
+        );
+    }
+
+    #[test]
+    fn uncache_sent_once() {
+        let pipe = Rc::new(
+            MockPipe::default()
+                .fail(XPCError::PipeErrno(libc::EAGAIN))
+                .reply(804, XPCDictionary::new()),
+        );
+
+        assert_eq!(
+            with_pipe(pipe.clone(), || uncache("com.example.xpc", Some(42))).err(),
+            Some(XPCError::PipeErrno(libc::EAGAIN))
+        );
+
+        let sent = pipe.sent.borrow();
+        assert_eq!(sent.len(), 1);
+
+        let name: String = sent[0].get(["name"]).unwrap().xpc_value().unwrap();
+        let handle: u64 = sent[0].get(["handle"]).unwrap().xpc_value().unwrap();
+        assert_eq!((name.as_str(), handle), ("com.example.xpc", 42));
+
+        let unsupported = Rc::new(MockPipe::default().reply(
+            804,
+            XPCDictionary::new().entry("error", libc::ENOTSUP as i64),
+        ));
+        assert_eq!(
+            with_pipe(unsupported, || uncache("com.example.xpc", None)).err(),
+            Some(XPCError::QueryError(
+                "uncache is not supported by this launchd".to_string()
+            ))
+        );
+        assert!(uncache("", None).is_err());
+    }
+
+    #[test]
+    fn variant_from_version() {
+        assert_eq!(
+            launchd_variant("Darwin Bootstrapper Version 7.0.0: Tue Jan 10 2023; root:libxpc_executables-2236.140.2~11/launchd/RELEASE_ARM64E").as_deref(),
+            Some("RELEASE")
+        );
+        assert_eq!(
+            launchd_variant("root:libxpc_executables-1/launchd/DEVELOPMENT_X86_64").as_deref(),
+            Some("DEVELOPMENT")
+        );
+        assert_eq!(launchd_variant("Darwin Bootstrapper Version 7.0.0"), None);
+    }
 }
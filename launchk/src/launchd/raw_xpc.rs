@@ -0,0 +1,286 @@
+use std::iter::Peekable;
+use std::str::Chars;
+use std::sync::Arc;
+
+use xpc_sys::objects::xpc_array::XPCArray;
+use xpc_sys::objects::xpc_dictionary::XPCDictionary;
+use xpc_sys::objects::xpc_object::XPCObject;
+use xpc_sys::traits::query_builder::QueryBuilder;
+
+use crate::launchd::pipe::send_raw;
+
+/// A value typed into the xpc console, before it becomes an XPCObject
+#[derive(Debug, Clone, PartialEq)]
+pub enum RawValue {
+    Dictionary(Vec<(String, RawValue)>),
+    Array(Vec<RawValue>),
+    String(String),
+    Int64(i64),
+    UInt64(u64),
+    Double(f64),
+    Bool(bool),
+}
+
+fn dictionary(entries: &[(String, RawValue)]) -> XPCDictionary {
+    entries.iter().fold(XPCDictionary::new(), |dict, (k, v)| {
+        dict.entry(k.as_str(), XPCObject::from(v))
+    })
+}
+
+impl From<&RawValue> for XPCObject {
+    fn from(value: &RawValue) -> Self {
+        match value {
+            RawValue::Dictionary(entries) => dictionary(entries).into(),
+            RawValue::Array(values) => XPCArray::from(
+                values
+                    .iter()
+                    .map(|v| Arc::new(XPCObject::from(v)))
+                    .collect::<Vec<_>>(),
+            )
+            .into(),
+            RawValue::String(s) => s.as_str().into(),
+            RawValue::Int64(i) => (*i).into(),
+            RawValue::UInt64(u) => (*u).into(),
+            RawValue::Double(d) => (*d).into(),
+            RawValue::Bool(b) => (*b).into(),
+        }
+    }
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.peek().is_some_and(|c| c.is_whitespace()) {
+            self.chars.next();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+        match self.chars.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(format!("Expected {} but found {}", expected, c)),
+            None => Err(format!("Expected {} but input ended", expected)),
+        }
+    }
+
+    /// Letters, digits and . - _ + for bare keys, numbers and true/false
+    fn word(&mut self) -> String {
+        let mut word = String::new();
+
+        while let Some(&c) = self.chars.peek() {
+            if !(c.is_alphanumeric() || "._-+".contains(c)) {
+                break;
+            }
+            word.push(c);
+            self.chars.next();
+        }
+
+        word
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut s = String::new();
+
+        loop {
+            match self.chars.next() {
+                Some('"') => return Ok(s),
+                Some('\\') => match self.chars.next() {
+                    Some('n') => s.push('\n'),
+                    Some('t') => s.push('\t'),
+                    Some(c) => s.push(c),
+                    None => break,
+                },
+                Some(c) => s.push(c),
+                None => break,
+            }
+        }
+
+        Err("Unterminated string".to_string())
+    }
+
+    fn key(&mut self) -> Result<String, String> {
+        self.skip_whitespace();
+
+        if self.chars.peek() == Some(&'"') {
+            return self.string();
+        }
+
+        match self.word() {
+            w if w.is_empty() => Err("Expected a key".to_string()),
+            w => Ok(w),
+        }
+    }
+
+    /// Items until close, separated by commas, trailing comma allowed
+    fn items<T, F>(&mut self, close: char, mut item: F) -> Result<Vec<T>, String>
+    where
+        F: FnMut(&mut Self) -> Result<T, String>,
+    {
+        let mut items = vec![];
+
+        loop {
+            self.skip_whitespace();
+            if self.chars.peek() == Some(&close) {
+                self.chars.next();
+                return Ok(items);
+            }
+
+            items.push(item(self)?);
+
+            self.skip_whitespace();
+            match self.chars.peek() {
+                Some(',') => {
+                    self.chars.next();
+                }
+                Some(&c) if c == close => {}
+                Some(c) => return Err(format!("Expected , or {} but found {}", close, c)),
+                None => return Err(format!("Expected {} but input ended", close)),
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<RawValue, String> {
+        self.skip_whitespace();
+
+        match self.chars.peek() {
+            Some('{') => {
+                self.chars.next();
+                let entries = self.items('}', |p| {
+                    let key = p.key()?;
+                    p.skip_whitespace();
+                    match p.chars.next() {
+                        Some(':') | Some('=') => {}
+                        _ => return Err(format!("Expected : after {}", key)),
+                    }
+                    Ok((key, p.value()?))
+                })?;
+                Ok(RawValue::Dictionary(entries))
+            }
+            Some('[') => {
+                self.chars.next();
+                Ok(RawValue::Array(self.items(']', |p| p.value())?))
+            }
+            Some('"') => Ok(RawValue::String(self.string()?)),
+            Some(_) => parse_word(&self.word()),
+            None => Err("Expected a value but input ended".to_string()),
+        }
+    }
+}
+
+/// true, false, or a number: 1 is uint64, -1 int64 and 1.5 a double. An
+/// i or u suffix (1i, 1u) picks the integer type.
+fn parse_word(word: &str) -> Result<RawValue, String> {
+    let invalid = || format!("Invalid value {:?}", word);
+
+    match word {
+        "" => Err("Expected a value".to_string()),
+        "true" => Ok(RawValue::Bool(true)),
+        "false" => Ok(RawValue::Bool(false)),
+        w if w.ends_with('i') => w[..w.len() - 1]
+            .parse()
+            .map(RawValue::Int64)
+            .map_err(|_| invalid()),
+        w if w.ends_with('u') => w[..w.len() - 1]
+            .parse()
+            .map(RawValue::UInt64)
+            .map_err(|_| invalid()),
+        w if w.contains('.') => w.parse().map(RawValue::Double).map_err(|_| invalid()),
+        w if w.starts_with('-') => w.parse().map(RawValue::Int64).map_err(|_| invalid()),
+        w => w.parse().map(RawValue::UInt64).map_err(|_| invalid()),
+    }
+}
+
+/// Parse e.g. `{subsystem: 3, routine: 815, type: 1, handle: 0}`. Keys may
+/// be bare or quoted, `=` works as well as `:`.
+pub fn parse(input: &str) -> Result<RawValue, String> {
+    let mut parser = Parser {
+        chars: input.chars().peekable(),
+    };
+
+    let value = parser.value()?;
+
+    parser.skip_whitespace();
+    if let Some(c) = parser.chars.next() {
+        return Err(format!("Unexpected {} after value", c));
+    }
+
+    Ok(value)
+}
+
+/// Send a dictionary to launchd and describe the reply, errors included
+pub fn send(input: &str) -> Result<String, String> {
+    let query = match parse(input)? {
+        RawValue::Dictionary(entries) => dictionary(&entries),
+        _ => {
+            return Err("launchd takes a dictionary, e.g. {subsystem: 3, routine: 815}".to_string())
+        }
+    };
+
+    let reply = send_raw(&query).map_err(|e| e.to_string())?;
+
+    Ok(reply.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use xpc_sys::objects::xpc_dictionary::XPCDictionary;
+    use xpc_sys::traits::xpc_value::TryXPCValue;
+
+    use super::{parse, send, RawValue};
+    use crate::launchd::pipe::mock::MockPipe;
+    use crate::launchd::pipe::with_pipe;
+
+    #[test]
+    fn parse_console_syntax() {
+        let value =
+            parse(r#"{subsystem: 3, "routine": 815, handle = -1, names: ["a b", true, 1.5, 2i],}"#)
+                .unwrap();
+
+        assert_eq!(
+            value,
+            RawValue::Dictionary(vec![
+                ("subsystem".to_string(), RawValue::UInt64(3)),
+                ("routine".to_string(), RawValue::UInt64(815)),
+                ("handle".to_string(), RawValue::Int64(-1)),
+                (
+                    "names".to_string(),
+                    RawValue::Array(vec![
+                        RawValue::String("a b".to_string()),
+                        RawValue::Bool(true),
+                        RawValue::Double(1.5),
+                        RawValue::Int64(2),
+                    ])
+                ),
+            ])
+        );
+
+        assert!(parse("{subsystem 3}").is_err());
+        assert!(parse("{a: \"open}").is_err());
+        assert!(parse("{a: 1} x").is_err());
+        assert!(parse("{a: nope}").is_err());
+    }
+
+    #[test]
+    fn send_through_pipe() {
+        let pipe = Rc::new(MockPipe::default().reply(815, XPCDictionary::new()));
+
+        assert!(with_pipe(pipe.clone(), || send(
+            "{subsystem: 3, routine: 815, handle: 501}"
+        ))
+        .is_ok());
+        assert!(with_pipe(pipe.clone(), || send("[1, 2]")).is_err());
+
+        let sent = pipe.sent.borrow();
+        assert_eq!(sent.len(), 1);
+
+        let handle: u64 = sent[0].get(["handle"]).unwrap().xpc_value().unwrap();
+        assert_eq!(handle, 501);
+    }
+}
@@ -0,0 +1,317 @@
+use std::fmt;
+use std::mem::MaybeUninit;
+
+use plist::Value;
+
+/// Days searched for the next fire time, enough for Month=2 Day=29
+const MAX_SEARCH_DAYS: i64 = 366 * 8;
+
+static WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+/// One StartCalendarInterval dict, missing keys match anything
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct CalendarInterval {
+    pub minute: Option<i64>,
+    pub hour: Option<i64>,
+    pub day: Option<i64>,
+    /// 0 and 7 are Sunday
+    pub weekday: Option<i64>,
+    pub month: Option<i64>,
+}
+
+/// StartInterval or StartCalendarInterval from a job's plist
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum JobSchedule {
+    Interval(u64),
+    Calendar(Vec<CalendarInterval>),
+}
+
+/// Local wall clock time, to the minute
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+pub struct LocalTime {
+    /// Days since 1970-01-01
+    pub days: i64,
+    pub hour: i64,
+    pub minute: i64,
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+    era * 146097 + doe - 719468
+}
+
+/// (year, month, day) for days since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+impl LocalTime {
+    pub fn now() -> LocalTime {
+        LocalTime::from_epoch(unsafe { libc::time(std::ptr::null_mut()) })
+    }
+
+    /// Local time for seconds since the epoch
+    pub fn from_epoch(seconds: libc::time_t) -> LocalTime {
+        let mut tm = MaybeUninit::<libc::tm>::uninit();
+        let tm = unsafe {
+            libc::localtime_r(&seconds, tm.as_mut_ptr());
+            tm.assume_init()
+        };
+
+        LocalTime {
+            days: days_from_civil(
+                tm.tm_year as i64 + 1900,
+                tm.tm_mon as i64 + 1,
+                tm.tm_mday as i64,
+            ),
+            hour: tm.tm_hour as i64,
+            minute: tm.tm_min as i64,
+        }
+    }
+
+    /// 0 is Sunday
+    pub fn weekday(&self) -> i64 {
+        (self.days + 4).rem_euclid(7)
+    }
+
+    fn minutes(&self) -> i64 {
+        self.days * 1440 + self.hour * 60 + self.minute
+    }
+
+    /// e.g. 2h 5m, ignoring DST changes in between
+    pub fn until(&self, later: &LocalTime) -> String {
+        let minutes = later.minutes() - self.minutes();
+
+        match (minutes / 1440, minutes % 1440 / 60, minutes % 60) {
+            (0, 0, m) => format!("{}m", m),
+            (0, h, m) => format!("{}h {}m", h, m),
+            (d, h, _) => format!("{}d {}h", d, h),
+        }
+    }
+}
+
+impl fmt::Display for LocalTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (year, month, day) = civil_from_days(self.days);
+
+        write!(
+            f,
+            "{} {:04}-{:02}-{:02} {:02}:{:02}",
+            WEEKDAYS[self.weekday() as usize],
+            year,
+            month,
+            day,
+            self.hour,
+            self.minute
+        )
+    }
+}
+
+impl CalendarInterval {
+    fn from_dict(dict: &plist::Dictionary) -> CalendarInterval {
+        let field = |key: &str| dict.get(key).and_then(Value::as_signed_integer);
+
+        CalendarInterval {
+            minute: field("Minute"),
+            hour: field("Hour"),
+            day: field("Day"),
+            weekday: field("Weekday"),
+            month: field("Month"),
+        }
+    }
+
+    /// Like cron, Day and Weekday together match when either does
+    fn matches_day(&self, days: i64) -> bool {
+        let (_, month, day) = civil_from_days(days);
+        let weekday = (days + 4).rem_euclid(7);
+
+        let on_day = self.day.map(|d| d == day);
+        let on_weekday = self.weekday.map(|w| w % 7 == weekday);
+
+        self.month.map(|m| m == month).unwrap_or(true)
+            && match (on_day, on_weekday) {
+                (Some(d), Some(w)) => d || w,
+                (d, w) => d.unwrap_or(true) && w.unwrap_or(true),
+            }
+    }
+
+    /// Every (hour, minute) this fires at on a matching day
+    fn times(&self) -> Vec<(i64, i64)> {
+        let hours: Vec<i64> = self.hour.map(|h| vec![h]).unwrap_or((0..24).collect());
+        let minutes: Vec<i64> = self.minute.map(|m| vec![m]).unwrap_or((0..60).collect());
+
+        hours
+            .iter()
+            .flat_map(|h| minutes.iter().map(move |m| (*h, *m)))
+            .collect()
+    }
+}
+
+impl fmt::Display for CalendarInterval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fields: Vec<String> = [
+            ("Month", self.month),
+            ("Day", self.day),
+            ("Weekday", self.weekday),
+            ("Hour", self.hour),
+            ("Minute", self.minute),
+        ]
+        .iter()
+        .filter_map(|(k, v)| v.map(|v| format!("{}={}", k, v)))
+        .collect();
+
+        if fields.is_empty() {
+            write!(f, "Every minute")
+        } else {
+            write!(f, "{}", fields.join(" "))
+        }
+    }
+}
+
+impl JobSchedule {
+    /// StartCalendarInterval (a dict or array of dicts) wins over StartInterval
+    pub fn from_plist(plist: &Value) -> Option<JobSchedule> {
+        let dict = plist.as_dictionary()?;
+
+        match dict.get("StartCalendarInterval") {
+            Some(Value::Dictionary(d)) => {
+                return Some(JobSchedule::Calendar(vec![CalendarInterval::from_dict(d)]))
+            }
+            Some(Value::Array(a)) => {
+                return Some(JobSchedule::Calendar(
+                    a.iter()
+                        .filter_map(Value::as_dictionary)
+                        .map(CalendarInterval::from_dict)
+                        .collect(),
+                ))
+            }
+            _ => (),
+        }
+
+        dict.get("StartInterval")
+            .and_then(Value::as_unsigned_integer)
+            .map(JobSchedule::Interval)
+    }
+
+    /// Next count fire times after from, empty for StartInterval since
+    /// that counts from when the job was loaded
+    pub fn next_fire_times(&self, from: &LocalTime, count: usize) -> Vec<LocalTime> {
+        let intervals = match self {
+            JobSchedule::Calendar(intervals) => intervals,
+            JobSchedule::Interval(_) => return vec![],
+        };
+
+        let mut fire_times = vec![];
+
+        for days in from.days..from.days + MAX_SEARCH_DAYS {
+            let mut times: Vec<(i64, i64)> = intervals
+                .iter()
+                .filter(|i| i.matches_day(days))
+                .flat_map(|i| i.times())
+                .collect();
+
+            times.sort_unstable();
+            times.dedup();
+
+            fire_times.extend(
+                times
+                    .into_iter()
+                    .map(|(hour, minute)| LocalTime { days, hour, minute })
+                    .filter(|t| t > from)
+                    .take(count - fire_times.len()),
+            );
+
+            if fire_times.len() >= count {
+                break;
+            }
+        }
+
+        fire_times
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{civil_from_days, days_from_civil, CalendarInterval, JobSchedule, LocalTime};
+
+    #[test]
+    fn civil_round_trip() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11017);
+        assert_eq!(civil_from_days(11017), (2000, 3, 1));
+        assert_eq!(civil_from_days(days_from_civil(2024, 2, 29)), (2024, 2, 29));
+    }
+
+    #[test]
+    fn next_calendar_fires() {
+        // Wed 2026-10-14 12:00
+        let from = LocalTime {
+            days: days_from_civil(2026, 10, 14),
+            hour: 12,
+            minute: 0,
+        };
+
+        let daily = JobSchedule::Calendar(vec![CalendarInterval {
+            hour: Some(3),
+            minute: Some(30),
+            ..Default::default()
+        }]);
+
+        let fires = daily.next_fire_times(&from, 2);
+        assert_eq!(fires[0].to_string(), "Thu 2026-10-15 03:30");
+        assert_eq!(fires[1].to_string(), "Fri 2026-10-16 03:30");
+        assert_eq!(from.until(&fires[0]), "15h 30m");
+
+        let sundays = JobSchedule::Calendar(vec![CalendarInterval {
+            weekday: Some(7),
+            hour: Some(0),
+            minute: Some(0),
+            ..Default::default()
+        }]);
+
+        assert_eq!(
+            sundays.next_fire_times(&from, 1)[0].to_string(),
+            "Sun 2026-10-18 00:00"
+        );
+
+        // The 20th or any Sunday, whichever comes first
+        let either = JobSchedule::Calendar(vec![CalendarInterval {
+            day: Some(20),
+            weekday: Some(0),
+            hour: Some(0),
+            minute: Some(0),
+            ..Default::default()
+        }]);
+
+        let fires: Vec<String> = either
+            .next_fire_times(&from, 3)
+            .iter()
+            .map(|t| t.to_string())
+            .collect();
+        assert_eq!(
+            fires,
+            vec![
+                "Sun 2026-10-18 00:00",
+                "Tue 2026-10-20 00:00",
+                "Sun 2026-10-25 00:00"
+            ]
+        );
+    }
+}
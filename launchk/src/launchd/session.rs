@@ -0,0 +1,124 @@
+use std::env;
+use std::fmt;
+
+use xpc_sys::enums::{DomainType, SessionType};
+use xpc_sys::rs_geteuid;
+
+use crate::launchd::query::{manager_name, manager_uid};
+
+lazy_static! {
+    static ref CURRENT: LaunchSession = LaunchSession::detect();
+}
+
+/// Where launchk runs, which decides where agents go by default: the GUI
+/// domain in a login session, the user domain over SSH (no Aqua there)
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum LaunchSession {
+    Aqua,
+    Background,
+    LoginWindow,
+    Ssh,
+    /// The system launchd, e.g. root without a login session
+    System,
+}
+
+impl fmt::Display for LaunchSession {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            LaunchSession::Aqua => "Aqua",
+            LaunchSession::Background => "Background",
+            LaunchSession::LoginWindow => "LoginWindow",
+            LaunchSession::Ssh => "SSH",
+            LaunchSession::System => "System",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+impl LaunchSession {
+    /// From launchctl managername and whether sshd started us. sshd
+    /// sessions are Background, unless attached to a GUI login (e.g. tmux)
+    fn from_manager(name: Option<&str>, ssh: bool) -> Self {
+        match name {
+            Some("Aqua") => LaunchSession::Aqua,
+            _ if ssh => LaunchSession::Ssh,
+            Some("Background") => LaunchSession::Background,
+            Some("LoginWindow") => LaunchSession::LoginWindow,
+            _ => LaunchSession::System,
+        }
+    }
+
+    fn detect() -> Self {
+        let ssh = env::var_os("SSH_CONNECTION").is_some() || env::var_os("SSH_TTY").is_some();
+        let session = Self::from_manager(manager_name().ok().as_deref(), ssh);
+
+        log::info!(
+            "[session]: {} (manager uid {:?})",
+            session,
+            manager_uid().ok()
+        );
+        session
+    }
+
+    /// Detected once, on first use
+    pub fn current() -> Self {
+        *CURRENT
+    }
+
+    /// Domain and handle for agents when nothing else says
+    pub fn default_domain(&self) -> (DomainType, Option<u64>) {
+        let uid = rs_geteuid() as u64;
+
+        match self {
+            LaunchSession::Aqua | LaunchSession::LoginWindow => (DomainType::Gui, Some(uid)),
+            LaunchSession::Background | LaunchSession::Ssh => (DomainType::User, Some(uid)),
+            LaunchSession::System => (DomainType::System, None),
+        }
+    }
+
+    /// default_domain without a handle, relative to launchk (the
+    /// requestor) as domain_session_prompt offers
+    pub fn requestor_domain(&self) -> DomainType {
+        match self {
+            LaunchSession::Aqua | LaunchSession::LoginWindow => DomainType::RequestorDomain,
+            LaunchSession::Background | LaunchSession::Ssh => DomainType::RequestorUserDomain,
+            LaunchSession::System => DomainType::System,
+        }
+    }
+
+    /// Session type for agents whose plist does not limit it
+    pub fn default_session_type(&self) -> SessionType {
+        match self {
+            LaunchSession::Aqua => SessionType::Aqua,
+            LaunchSession::LoginWindow => SessionType::LoginWindow,
+            LaunchSession::Background | LaunchSession::Ssh => SessionType::Background,
+            LaunchSession::System => SessionType::System,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LaunchSession;
+
+    #[test]
+    fn session_from_manager() {
+        assert_eq!(
+            LaunchSession::from_manager(Some("Aqua"), true),
+            LaunchSession::Aqua
+        );
+        assert_eq!(
+            LaunchSession::from_manager(Some("Background"), true),
+            LaunchSession::Ssh
+        );
+        assert_eq!(
+            LaunchSession::from_manager(Some("Background"), false),
+            LaunchSession::Background
+        );
+        assert_eq!(
+            LaunchSession::from_manager(None, false),
+            LaunchSession::System
+        );
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use xpc_sys::code_signing::{signature_summary, SignatureKind, SignatureSummary};
+
+lazy_static! {
+    /// Executable path -> signature, binaries are only read once
+    static ref PATH_SIGNATURES: RwLock<HashMap<String, SignatureSummary>> =
+        RwLock::new(HashMap::new());
+    /// Running PID -> its executable, updated when polling
+    static ref PID_EXECUTABLES: RwLock<HashMap<i64, Executable>> =
+        RwLock::new(HashMap::new());
+}
+
+#[derive(Debug, Clone)]
+struct Executable {
+    path: String,
+    /// None if it could not be read
+    signature: Option<SignatureSummary>,
+}
+
+/// Executable of a running process
+pub fn executable_path(pid: i64) -> Option<String> {
+    let mut buffer = vec![0u8; libc::PROC_PIDPATHINFO_MAXSIZE as usize];
+
+    let len = unsafe {
+        libc::proc_pidpath(
+            pid as libc::c_int,
+            buffer.as_mut_ptr() as *mut libc::c_void,
+            buffer.len() as u32,
+        )
+    };
+
+    if len <= 0 {
+        return None;
+    }
+
+    buffer.truncate(len as usize);
+    String::from_utf8(buffer).ok()
+}
+
+/// Look up executables and signatures for running PIDs, dropping anything
+/// that exited
+pub fn refresh_signatures(pids: &[i64]) {
+    let mut paths = PATH_SIGNATURES.write().expect("Must update signatures");
+    let mut executables = HashMap::new();
+
+    for &pid in pids.iter().filter(|p| **p > 0) {
+        let path = match executable_path(pid) {
+            Some(p) => p,
+            None => continue,
+        };
+
+        let signature = match paths.get(&path) {
+            Some(signature) => Some(signature.clone()),
+            None => match signature_summary(&path) {
+                Ok(signature) => Some(paths.entry(path.clone()).or_insert(signature).clone()),
+                Err(e) => {
+                    log::error!("[signing/refresh_signatures]: {}", e);
+                    None
+                }
+            },
+        };
+
+        executables.insert(pid, Executable { path, signature });
+    }
+
+    *PID_EXECUTABLES.write().expect("Must update signatures") = executables;
+}
+
+fn with_executable<T>(pid: i64, f: impl FnOnce(&Executable) -> Option<T>) -> Option<T> {
+    PID_EXECUTABLES.read().ok()?.get(&pid).and_then(f)
+}
+
+/// Running from an unsigned or ad-hoc signed binary
+pub fn is_untrusted(pid: i64) -> bool {
+    with_executable(pid, |e| e.signature.as_ref().map(|s| s.kind))
+        .map(|k| k != SignatureKind::Signed)
+        .unwrap_or(false)
+}
+
+/// Executable of a running job, as of the last poll
+pub fn running_executable(pid: i64) -> Option<String> {
+    with_executable(pid, |e| Some(e.path.clone()))
+}
+
+/// Team ID its executable is signed with, as of the last poll
+pub fn running_team_id(pid: i64) -> Option<String> {
+    with_executable(pid, |e| e.signature.as_ref()?.team_id.clone())
+}
@@ -0,0 +1,196 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::launchd::plist::LABEL_TO_ENTRY_CONFIG;
+use crate::launchd::query::{disabled_labels, list_all_detailed};
+
+/// A job as seen when the snapshot was taken
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct JobSnapshot {
+    /// Domain it is loaded in, None if not loaded
+    pub domain: Option<String>,
+    pub pid: i64,
+    pub disabled: bool,
+    pub plist_path: Option<String>,
+}
+
+impl JobSnapshot {
+    fn loaded(&self) -> bool {
+        self.domain.is_some()
+    }
+}
+
+/// list_all + per-job status, keyed by label
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Seconds since epoch
+    pub taken_at: u64,
+    pub jobs: BTreeMap<String, JobSnapshot>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum SnapshotChange {
+    Added(String),
+    Removed(String),
+    Loaded(String),
+    Unloaded(String),
+    Enabled(String),
+    Disabled(String),
+    // (label, old path, new path)
+    PlistMoved(String, Option<String>, Option<String>),
+}
+
+impl fmt::Display for SnapshotChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotChange::Added(l) => write!(f, "+ added     {}", l),
+            SnapshotChange::Removed(l) => write!(f, "- removed   {}", l),
+            SnapshotChange::Loaded(l) => write!(f, "  loaded    {}", l),
+            SnapshotChange::Unloaded(l) => write!(f, "  unloaded  {}", l),
+            SnapshotChange::Enabled(l) => write!(f, "  enabled   {}", l),
+            SnapshotChange::Disabled(l) => write!(f, "  disabled  {}", l),
+            SnapshotChange::PlistMoved(l, old, new) => write!(
+                f,
+                "  plist     {}: {} -> {}",
+                l,
+                old.as_deref().unwrap_or("-"),
+                new.as_deref().unwrap_or("-")
+            ),
+        }
+    }
+}
+
+impl Snapshot {
+    /// Loaded jobs in every domain we can list, and every plist found
+    pub fn capture() -> Snapshot {
+        let disabled = disabled_labels();
+        let mut jobs = BTreeMap::new();
+
+        for entry in list_all_detailed() {
+            jobs.insert(
+                entry.label.clone(),
+                JobSnapshot {
+                    domain: Some(entry.domain.to_string()),
+                    pid: entry.pid,
+                    disabled: disabled.contains(&entry.label),
+                    plist_path: entry.plist_path,
+                },
+            );
+        }
+
+        if let Ok(plists) = LABEL_TO_ENTRY_CONFIG.read() {
+            for (label, plist) in plists.iter() {
+                jobs.entry(label.clone()).or_insert_with(|| JobSnapshot {
+                    domain: None,
+                    pid: 0,
+                    disabled: disabled.contains(label),
+                    plist_path: Some(plist.plist_path.clone()),
+                });
+            }
+        }
+
+        Snapshot {
+            taken_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            jobs,
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(path, json).map_err(|e| format!("Cannot write {}: {}", path, e))
+    }
+
+    pub fn read(path: &str) -> Result<Snapshot, String> {
+        let json = fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+        serde_json::from_str(&json).map_err(|e| format!("Invalid snapshot {}: {}", path, e))
+    }
+
+    /// What changed going from self to newer, by label. PIDs are ignored.
+    pub fn diff(&self, newer: &Snapshot) -> Vec<SnapshotChange> {
+        let mut changes = vec![];
+
+        for (label, old) in &self.jobs {
+            let new = match newer.jobs.get(label) {
+                Some(new) => new,
+                None => {
+                    changes.push(SnapshotChange::Removed(label.clone()));
+                    continue;
+                }
+            };
+
+            match (old.loaded(), new.loaded()) {
+                (false, true) => changes.push(SnapshotChange::Loaded(label.clone())),
+                (true, false) => changes.push(SnapshotChange::Unloaded(label.clone())),
+                _ => (),
+            }
+
+            match (old.disabled, new.disabled) {
+                (true, false) => changes.push(SnapshotChange::Enabled(label.clone())),
+                (false, true) => changes.push(SnapshotChange::Disabled(label.clone())),
+                _ => (),
+            }
+
+            if old.plist_path != new.plist_path {
+                changes.push(SnapshotChange::PlistMoved(
+                    label.clone(),
+                    old.plist_path.clone(),
+                    new.plist_path.clone(),
+                ));
+            }
+        }
+
+        changes.extend(
+            newer
+                .jobs
+                .keys()
+                .filter(|l| !self.jobs.contains_key(*l))
+                .map(|l| SnapshotChange::Added(l.clone())),
+        );
+
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{JobSnapshot, Snapshot, SnapshotChange};
+
+    fn job(loaded: bool, disabled: bool) -> JobSnapshot {
+        JobSnapshot {
+            domain: Some("System".to_string()).filter(|_| loaded),
+            pid: 0,
+            disabled,
+            plist_path: None,
+        }
+    }
+
+    #[test]
+    fn diff_snapshots() {
+        let mut old = Snapshot::default();
+        old.jobs.insert("gone".to_string(), job(true, false));
+        old.jobs.insert("same".to_string(), job(true, false));
+        old.jobs.insert("toggled".to_string(), job(false, false));
+
+        let mut new = Snapshot::default();
+        new.jobs.insert("same".to_string(), job(true, false));
+        new.jobs.insert("toggled".to_string(), job(true, true));
+        new.jobs.insert("persist".to_string(), job(true, false));
+
+        assert_eq!(
+            old.diff(&new),
+            vec![
+                SnapshotChange::Removed("gone".to_string()),
+                SnapshotChange::Loaded("toggled".to_string()),
+                SnapshotChange::Disabled("toggled".to_string()),
+                SnapshotChange::Added("persist".to_string()),
+            ]
+        );
+    }
+}
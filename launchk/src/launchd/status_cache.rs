@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+struct CacheEntry<V> {
+    value: V,
+    inserted: Instant,
+    generation: u64,
+}
+
+struct Entries<V> {
+    values: HashMap<String, CacheEntry<V>>,
+    /// Generation each key was last invalidated at, since invalidate_all
+    invalidated: HashMap<String, u64>,
+}
+
+/// Values by label that expire after a TTL, or all at once when the
+/// generation moves on (e.g. after a load changes a domain). Reads and
+/// inserts never block: a busy lock is a miss, or an insert skipped.
+pub struct StatusCache<V> {
+    entries: RwLock<Entries<V>>,
+    /// Moves on at every invalidation, of one key or all
+    generation: AtomicU64,
+    /// Generation of the last invalidate_all, older entries are stale
+    epoch: AtomicU64,
+    ttl: Duration,
+}
+
+impl<V: Clone> StatusCache<V> {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            entries: RwLock::new(Entries {
+                values: HashMap::new(),
+                invalidated: HashMap::new(),
+            }),
+            generation: AtomicU64::new(0),
+            epoch: AtomicU64::new(0),
+            ttl,
+        }
+    }
+
+    /// Take before building a value, and pass to insert, so an invalidation
+    /// that lands while building isn't undone
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Fresh value, None if missing, expired, stale or the lock is busy
+    pub fn get(&self, key: &str) -> Option<V> {
+        let entries = self.entries.try_read().ok()?;
+        let entry = entries.values.get(key)?;
+
+        if entry.generation < self.epoch.load(Ordering::Acquire)
+            || entry.inserted.elapsed() > self.ttl
+        {
+            return None;
+        }
+
+        Some(entry.value.clone())
+    }
+
+    pub fn insert(&self, key: String, value: V, generation: u64) {
+        if generation < self.epoch.load(Ordering::Acquire) {
+            return;
+        }
+
+        if let Ok(mut entries) = self.entries.try_write() {
+            // Built before this key was invalidated, e.g. a poll in flight
+            // during a load
+            if entries
+                .invalidated
+                .get(&key)
+                .is_some_and(|g| generation < *g)
+            {
+                return;
+            }
+
+            entries.values.insert(
+                key,
+                CacheEntry {
+                    value,
+                    inserted: Instant::now(),
+                    generation,
+                },
+            );
+        }
+    }
+
+    pub fn invalidate(&self, key: &str) {
+        let mut entries = self.entries.write().expect("Must invalidate");
+        let generation = self.generation.fetch_add(1, Ordering::AcqRel) + 1;
+
+        entries.values.remove(key);
+        entries.invalidated.insert(key.to_string(), generation);
+    }
+
+    /// Everything cached so far is stale
+    pub fn invalidate_all(&self) {
+        let generation = self.generation.fetch_add(1, Ordering::AcqRel) + 1;
+        self.epoch.store(generation, Ordering::Release);
+
+        // Drop what can't be read again, if nobody is busy with it
+        if let Ok(mut entries) = self.entries.try_write() {
+            entries.values.retain(|_, e| e.generation >= generation);
+            entries.invalidated.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StatusCache;
+    use std::time::Duration;
+
+    #[test]
+    fn expiry_and_generations() {
+        let cache = StatusCache::new(Duration::from_secs(60));
+
+        let generation = cache.generation();
+        cache.insert("a".to_string(), 1, generation);
+        cache.insert("b".to_string(), 2, generation);
+        assert_eq!(cache.get("a"), Some(1));
+
+        // A poll started before the invalidate can't put a back
+        let polling = cache.generation();
+        cache.invalidate("a");
+        assert_eq!(cache.get("a"), None);
+        assert_eq!(cache.get("b"), Some(2));
+        cache.insert("a".to_string(), 1, polling);
+        assert_eq!(cache.get("a"), None);
+        cache.insert("b".to_string(), 5, polling);
+        assert_eq!(cache.get("b"), Some(5));
+        cache.insert("a".to_string(), 6, cache.generation());
+        assert_eq!(cache.get("a"), Some(6));
+
+        // Built before the invalidation, so never cached
+        let before = cache.generation();
+        cache.invalidate_all();
+        assert_eq!(cache.get("b"), None);
+        cache.insert("b".to_string(), 3, before);
+        assert_eq!(cache.get("b"), None);
+
+        cache.insert("b".to_string(), 4, cache.generation());
+        assert_eq!(cache.get("b"), Some(4));
+
+        let expired = StatusCache::new(Duration::from_secs(0));
+        expired.insert("a".to_string(), 1, 0);
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(expired.get("a"), None);
+    }
+}
@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use std::io::Cursor;
+
+use plist::{Dictionary, Value};
+
+use crate::launchd::plist::LaunchdEntryType;
+
+/// A plist with {{variable}} placeholders for the new job wizard.
+/// {{label}} is always filled in from the wizard's label.
+pub struct Template {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// (variable, prompt, default)
+    pub variables: &'static [(&'static str, &'static str, &'static str)],
+    /// Preselected in the wizard
+    pub entry_type: LaunchdEntryType,
+    xml: &'static str,
+}
+
+pub static TEMPLATES: [Template; 4] = [
+    Template {
+        name: "periodic",
+        description: "Periodic script runner (StartInterval)",
+        variables: &[
+            ("command", "Shell command", "/usr/local/bin/backup.sh"),
+            ("interval", "Every N seconds", "3600"),
+        ],
+        entry_type: LaunchdEntryType::Agent,
+        xml: include_str!("templates/periodic.plist"),
+    },
+    Template {
+        name: "watch-path",
+        description: "Path watcher (WatchPaths)",
+        variables: &[
+            ("path", "Path to watch, no ~", "/Users/Shared/Inbox"),
+            (
+                "command",
+                "Shell command run on change",
+                "/usr/local/bin/process-inbox.sh",
+            ),
+        ],
+        entry_type: LaunchdEntryType::Agent,
+        xml: include_str!("templates/watch_path.plist"),
+    },
+    Template {
+        name: "socket-server",
+        description: "Socket-activated server (Sockets)",
+        variables: &[
+            (
+                "program",
+                "Server, checks in with launch_activate_socket(\"Listener\")",
+                "/usr/local/bin/server",
+            ),
+            ("host", "Listen on", "localhost"),
+            ("port", "TCP port or service name", "8080"),
+        ],
+        entry_type: LaunchdEntryType::Daemon,
+        xml: include_str!("templates/socket_server.plist"),
+    },
+    Template {
+        name: "login-agent",
+        description: "Login agent, restarted if it fails (RunAtLoad, KeepAlive)",
+        variables: &[(
+            "program",
+            "Program to run at login",
+            "/Applications/Example.app/Contents/MacOS/Example",
+        )],
+        entry_type: LaunchdEntryType::Agent,
+        xml: include_str!("templates/login_agent.plist"),
+    },
+];
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+impl Template {
+    /// Plist XML with every {{variable}} replaced by its (escaped) value
+    pub fn render(&self, values: &HashMap<String, String>) -> Result<String, String> {
+        let mut xml = self.xml.to_string();
+
+        for (name, value) in values {
+            xml = xml.replace(&format!("{{{{{}}}}}", name), &escape_xml(value));
+        }
+
+        if let Some(start) = xml.find("{{") {
+            let name: String = xml[start + 2..].chars().take_while(|c| *c != '}').collect();
+            return Err(format!("No value for {} in template {}", name, self.name));
+        }
+
+        parse_dictionary(&xml).map_err(|e| format!("Template {}: {}", self.name, e))?;
+        Ok(xml)
+    }
+}
+
+/// Top level dictionary of a rendered template
+pub fn parse_dictionary(xml: &str) -> Result<Dictionary, String> {
+    Value::from_reader_xml(Cursor::new(xml.as_bytes()))
+        .map_err(|e| e.to_string())?
+        .into_dictionary()
+        .ok_or_else(|| "Not a dictionary".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use plist::Value;
+
+    use super::{parse_dictionary, TEMPLATES};
+    use crate::launchd::plist_lint::lint;
+
+    #[test]
+    fn templates_render_and_lint() {
+        for template in &TEMPLATES {
+            let mut values: HashMap<String, String> = template
+                .variables
+                .iter()
+                .map(|(name, _, default)| (name.to_string(), default.to_string()))
+                .collect();
+
+            assert!(template.render(&values).is_err(), "{}", template.name);
+
+            values.insert("label".to_string(), "com.example.<job>".to_string());
+            let dict = parse_dictionary(&template.render(&values).unwrap()).unwrap();
+
+            assert_eq!(
+                dict.get("Label").and_then(Value::as_string),
+                Some("com.example.<job>")
+            );
+            assert!(lint(&dict.into()).errors.is_empty(), "{}", template.name);
+        }
+    }
+}
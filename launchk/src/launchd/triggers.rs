@@ -0,0 +1,232 @@
+use std::fmt;
+
+use plist::{Dictionary, Value};
+
+use crate::launchd::endpoints::{Endpoint, EndpointKind};
+use crate::launchd::schedule::JobSchedule;
+
+/// Something that makes launchd start a job
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Trigger {
+    /// RunAtLoad
+    Load,
+    /// KeepAlive, with its conditions (empty: always)
+    KeepAlive(Vec<String>),
+    /// A connection to a socket, or a lookup of a Mach service
+    Endpoint(Endpoint),
+    /// LaunchEvents, e.g. com.apple.iokit.matching
+    Event {
+        stream: String,
+        name: String,
+    },
+    Timer(JobSchedule),
+    WatchPath(String),
+    QueueDirectory(String),
+    StartOnMount,
+}
+
+impl fmt::Display for Trigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Trigger::Load => write!(f, "load (RunAtLoad)"),
+            Trigger::KeepAlive(conditions) if conditions.is_empty() => {
+                write!(f, "keep-alive, always restarted")
+            }
+            Trigger::KeepAlive(conditions) => {
+                write!(f, "keep-alive while {}", conditions.join(", "))
+            }
+            Trigger::Endpoint(e) => {
+                let state = match e.active {
+                    Some(true) => " (active)",
+                    _ => "",
+                };
+
+                match e.kind {
+                    EndpointKind::Socket => write!(
+                        f,
+                        "socket {} {}{}",
+                        e.name,
+                        e.address.as_deref().unwrap_or("-"),
+                        state
+                    ),
+                    EndpointKind::MachService => write!(f, "Mach service {}{}", e.name, state),
+                }
+            }
+            Trigger::Event { stream, name } => write!(f, "event {} {}", stream, name),
+            Trigger::Timer(JobSchedule::Interval(seconds)) => {
+                write!(f, "timer, every {}s", seconds)
+            }
+            Trigger::Timer(JobSchedule::Calendar(intervals)) => {
+                write!(f, "timer, {} calendar interval(s)", intervals.len())
+            }
+            Trigger::WatchPath(path) => write!(f, "path changed {}", path),
+            Trigger::QueueDirectory(path) => write!(f, "queue directory not empty {}", path),
+            Trigger::StartOnMount => write!(f, "filesystem mounted"),
+        }
+    }
+}
+
+fn bool_key(dict: &Dictionary, key: &str) -> bool {
+    dict.get(key).and_then(Value::as_boolean).unwrap_or(false)
+}
+
+fn strings(dict: &Dictionary, key: &str) -> Vec<String> {
+    dict.get(key)
+        .and_then(Value::as_array)
+        .map(|a| {
+            a.iter()
+                .filter_map(Value::as_string)
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// KeepAlive's dictionary form, e.g. SuccessfulExit=false
+fn keep_alive_conditions(keep_alive: &Dictionary) -> Vec<String> {
+    let mut conditions = vec![];
+
+    for (key, value) in keep_alive {
+        match (key.as_str(), value) {
+            ("PathState", Value::Dictionary(paths)) => {
+                conditions.extend(paths.iter().map(|(path, exists)| {
+                    match exists.as_boolean().unwrap_or(true) {
+                        true => format!("{} exists", path),
+                        false => format!("{} is missing", path),
+                    }
+                }))
+            }
+            ("OtherJobEnabled", Value::Dictionary(jobs)) => {
+                conditions.extend(jobs.iter().map(|(label, enabled)| {
+                    match enabled.as_boolean().unwrap_or(true) {
+                        true => format!("{} is loaded", label),
+                        false => format!("{} is not loaded", label),
+                    }
+                }))
+            }
+            (key, Value::Boolean(b)) => conditions.push(format!("{}={}", key, b)),
+            _ => (),
+        }
+    }
+
+    conditions
+}
+
+/// What will start the job next: from the plist's RunAtLoad, KeepAlive,
+/// LaunchEvents, WatchPaths, QueueDirectories and StartOnMount keys, its
+/// sockets and Mach services, and its schedule
+pub fn launch_triggers(
+    plist: Option<&Value>,
+    endpoints: &[Endpoint],
+    schedule: Option<&JobSchedule>,
+) -> Vec<Trigger> {
+    let mut triggers = vec![];
+    let dict = match plist.and_then(Value::as_dictionary) {
+        Some(d) => d,
+        None => return endpoints.iter().cloned().map(Trigger::Endpoint).collect(),
+    };
+
+    if bool_key(dict, "RunAtLoad") {
+        triggers.push(Trigger::Load);
+    }
+
+    match dict.get("KeepAlive") {
+        Some(Value::Boolean(true)) => triggers.push(Trigger::KeepAlive(vec![])),
+        Some(Value::Dictionary(d)) => triggers.push(Trigger::KeepAlive(keep_alive_conditions(d))),
+        _ => (),
+    }
+
+    triggers.extend(endpoints.iter().cloned().map(Trigger::Endpoint));
+
+    let events = dict.get("LaunchEvents").and_then(Value::as_dictionary);
+    for (stream, descriptors) in events.into_iter().flatten() {
+        let names = descriptors
+            .as_dictionary()
+            .into_iter()
+            .flat_map(|d| d.keys());
+
+        triggers.extend(names.map(|name| Trigger::Event {
+            stream: stream.clone(),
+            name: name.clone(),
+        }));
+    }
+
+    if let Some(schedule) = schedule {
+        triggers.push(Trigger::Timer(schedule.clone()));
+    }
+
+    triggers.extend(
+        strings(dict, "WatchPaths")
+            .into_iter()
+            .map(Trigger::WatchPath),
+    );
+    triggers.extend(
+        strings(dict, "QueueDirectories")
+            .into_iter()
+            .map(Trigger::QueueDirectory),
+    );
+
+    if bool_key(dict, "StartOnMount") {
+        triggers.push(Trigger::StartOnMount);
+    }
+
+    triggers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{launch_triggers, Trigger};
+    use crate::launchd::schedule::JobSchedule;
+    use plist::{Dictionary, Value};
+
+    #[test]
+    fn triggers_from_plist() {
+        let mut paths = Dictionary::new();
+        paths.insert("/tmp/ready".to_string(), true.into());
+
+        let mut keep_alive = Dictionary::new();
+        keep_alive.insert("PathState".to_string(), Value::Dictionary(paths));
+        keep_alive.insert("SuccessfulExit".to_string(), false.into());
+
+        let mut matching = Dictionary::new();
+        matching.insert("usb-dock".to_string(), Value::Dictionary(Dictionary::new()));
+
+        let mut events = Dictionary::new();
+        events.insert(
+            "com.apple.iokit.matching".to_string(),
+            Value::Dictionary(matching),
+        );
+
+        let mut dict = Dictionary::new();
+        dict.insert("RunAtLoad".to_string(), true.into());
+        dict.insert("KeepAlive".to_string(), Value::Dictionary(keep_alive));
+        dict.insert("LaunchEvents".to_string(), Value::Dictionary(events));
+        dict.insert(
+            "WatchPaths".to_string(),
+            Value::Array(vec!["/etc/hosts".into()]),
+        );
+
+        let triggers = launch_triggers(
+            Some(&Value::Dictionary(dict)),
+            &[],
+            Some(&JobSchedule::Interval(300)),
+        );
+
+        assert_eq!(
+            triggers,
+            vec![
+                Trigger::Load,
+                Trigger::KeepAlive(vec![
+                    "/tmp/ready exists".to_string(),
+                    "SuccessfulExit=false".to_string()
+                ]),
+                Trigger::Event {
+                    stream: "com.apple.iokit.matching".to_string(),
+                    name: "usb-dock".to_string()
+                },
+                Trigger::Timer(JobSchedule::Interval(300)),
+                Trigger::WatchPath("/etc/hosts".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use plist::Value;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum WatchKind {
+    /// WatchPaths, started when the path is modified
+    Path,
+    /// QueueDirectories, kept running while the directory is not empty
+    Queue,
+}
+
+impl fmt::Display for WatchKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WatchKind::Path => write!(f, "watch"),
+            WatchKind::Queue => write!(f, "queue"),
+        }
+    }
+}
+
+/// A WatchPaths or QueueDirectories entry, and what is there right now
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct WatchedPath {
+    pub kind: WatchKind,
+    pub path: String,
+    pub exists: bool,
+    /// Seconds since the epoch
+    pub modified: Option<i64>,
+    /// Files in a queue directory
+    pub entries: Option<usize>,
+}
+
+impl WatchedPath {
+    fn read(kind: WatchKind, path: &str) -> Self {
+        let meta = fs::metadata(path).ok();
+
+        let modified = meta
+            .as_ref()
+            .and_then(|m| m.modified().ok())
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64);
+
+        let entries = match (kind, &meta) {
+            (WatchKind::Queue, Some(m)) if m.is_dir() => fs::read_dir(path)
+                .ok()
+                .map(|dir| dir.filter(|e| e.is_ok()).count()),
+            _ => None,
+        };
+
+        Self {
+            kind,
+            path: path.to_string(),
+            exists: meta.is_some(),
+            modified,
+            entries,
+        }
+    }
+
+    /// Why launchd might never start the job for this path
+    pub fn problem(&self) -> Option<&'static str> {
+        if self.path.starts_with('~') {
+            return Some("launchd does not expand ~");
+        }
+
+        if !Path::new(&self.path).is_absolute() {
+            return Some("not an absolute path");
+        }
+
+        match (self.kind, self.exists, self.entries) {
+            (_, false, _) => Some("missing"),
+            (WatchKind::Queue, true, None) => Some("not a directory"),
+            _ => None,
+        }
+    }
+}
+
+fn strings<'a>(dict: &'a plist::Dictionary, key: &str) -> impl Iterator<Item = &'a str> {
+    dict.get(key)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_string)
+}
+
+/// The plist's WatchPaths and QueueDirectories, checked on disk
+pub fn watched_paths(plist: Option<&Value>) -> Vec<WatchedPath> {
+    let dict = match plist.and_then(Value::as_dictionary) {
+        Some(d) => d,
+        None => return vec![],
+    };
+
+    strings(dict, "WatchPaths")
+        .map(|p| WatchedPath::read(WatchKind::Path, p))
+        .chain(strings(dict, "QueueDirectories").map(|p| WatchedPath::read(WatchKind::Queue, p)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use plist::{Dictionary, Value};
+
+    use super::{watched_paths, WatchKind};
+
+    #[test]
+    fn check_watched_paths() {
+        let queue = std::env::temp_dir().join("launchk_watch_paths_queue");
+        let _ = fs::remove_dir_all(&queue);
+        fs::create_dir_all(&queue).unwrap();
+        fs::write(queue.join("job"), "").unwrap();
+
+        let queue = queue.to_string_lossy().to_string();
+        let mut dict = Dictionary::new();
+        dict.insert(
+            "WatchPaths".to_string(),
+            Value::Array(vec!["~/Inbox".into(), "/nonexistent/launchk".into()]),
+        );
+        dict.insert(
+            "QueueDirectories".to_string(),
+            Value::Array(vec![queue.clone().into()]),
+        );
+
+        let paths = watched_paths(Some(&Value::Dictionary(dict)));
+        let problems: Vec<_> = paths.iter().map(|p| p.problem()).collect();
+
+        assert_eq!(
+            problems,
+            vec![Some("launchd does not expand ~"), Some("missing"), None]
+        );
+        assert_eq!(paths[2].kind, WatchKind::Queue);
+        assert_eq!(paths[2].entries, Some(1));
+        assert!(paths[2].modified.is_some());
+
+        fs::remove_dir_all(&queue).unwrap();
+    }
+}
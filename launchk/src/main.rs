@@ -11,18 +11,47 @@ extern crate plist;
 
 use clearscreen;
 use cursive::view::Resizable;
-use cursive::views::{NamedView, Panel};
+use cursive::views::{Dialog, NamedView, Panel};
 use git_version::git_version;
+use std::env;
 use std::process::exit;
 
 use crate::launchd::plist::{init_plist_map, PLIST_MAP_INIT};
-use crate::tui::root::RootLayout;
+use crate::tui::root::{load_theme, RootLayout};
+use crate::tui::tour;
+use crate::tui::ui_state;
 
+mod app_log;
+mod cli;
+mod config;
 mod launchd;
 mod tui;
 
 fn main() {
-    env_logger::init();
+    app_log::init();
+
+    // Any arguments means headless mode, as does piping, e.g. launchk | grep foo
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let no_state = args.iter().any(|a| a == "--no-state");
+    args.retain(|a| a != "--no-state");
+
+    let piped = unsafe { libc::isatty(libc::STDOUT_FILENO) } == 0;
+
+    if piped && args.iter().all(|a| a == "--json") {
+        args.insert(0, "list".to_string());
+    }
+
+    if !args.is_empty() {
+        let code = match cli::parse(&args) {
+            Ok(cli_args) => cli::run(cli_args),
+            Err(e) => {
+                eprintln!("launchk: {}", e);
+                2
+            }
+        };
+
+        exit(code);
+    }
 
     let runtime = tokio::runtime::Builder::new_multi_thread()
         .enable_all()
@@ -33,8 +62,8 @@ fn main() {
     PLIST_MAP_INIT.call_once(|| init_plist_map(runtime.handle()));
 
     let mut siv = cursive::default();
-    siv.load_toml(include_str!("tui/style.toml"))
-        .expect("Must load styles");
+    let config_result = config::reload().and_then(|_| load_theme(&mut siv));
+    ui_state::init(!no_state && config::config().restore_state());
 
     let root_layout = RootLayout::new(&mut siv, runtime.handle());
     let root_layout = NamedView::new("root_layout", root_layout);
@@ -45,8 +74,17 @@ fn main() {
         .full_height();
 
     siv.add_layer(panel);
+
+    if tour::first_run() {
+        tour::show_tour()(&mut siv);
+    }
+
+    if let Err(e) = config_result {
+        siv.add_layer(Dialog::info(e).title("Config"));
+    }
     siv.run();
     siv.quit();
+    ui_state::save();
 
     // Fix reset on exit
     // https://github.com/gyscos/cursive/issues/415
@@ -0,0 +1,130 @@
+use std::mem::MaybeUninit;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use cursive::theme::{Effect, Style};
+use cursive::traits::{Nameable, Resizable, Scrollable};
+use cursive::utils::markup::StyledString;
+use cursive::view::ScrollStrategy;
+use cursive::views::{Dialog, OnEventView, TextView};
+use cursive::Cursive;
+use log::Level;
+
+use crate::app_log::{records, AppLogRecord};
+use crate::tui::root::CbSinkMessage;
+use crate::tui::theme::roles;
+
+const APP_LOGS_TEXT: &str = "app_logs_text";
+
+/// (key, level) to show at least
+static LEVEL_KEYS: [(char, Level); 4] = [
+    ('e', Level::Error),
+    ('w', Level::Warn),
+    ('i', Level::Info),
+    ('d', Level::Debug),
+];
+
+/// Local HH:MM:SS
+pub fn clock(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as libc::time_t)
+        .unwrap_or(0);
+
+    let mut tm = MaybeUninit::<libc::tm>::uninit();
+    let tm = unsafe {
+        libc::localtime_r(&seconds, tm.as_mut_ptr());
+        tm.assume_init()
+    };
+
+    format!("{:02}:{:02}:{:02}", tm.tm_hour, tm.tm_min, tm.tm_sec)
+}
+
+fn level_style(level: Level) -> Style {
+    match level {
+        Level::Error => Style::from(roles().error),
+        Level::Warn => Style::from(roles().warning),
+        Level::Info => Style::none(),
+        Level::Debug | Level::Trace => Style::from(Effect::Dim),
+    }
+}
+
+fn render(level: Level) -> StyledString {
+    let records = records(level);
+    let mut styled = StyledString::new();
+
+    if records.is_empty() {
+        styled.append_plain(format!("Nothing logged at {} or above", level));
+    }
+
+    for AppLogRecord {
+        time,
+        level,
+        target,
+        message,
+        count,
+    } in records
+    {
+        let repeats = if count > 1 {
+            format!(" (x{})", count)
+        } else {
+            String::new()
+        };
+
+        styled.append_styled(
+            format!(
+                "{} {:5} {} {}{}\n",
+                clock(time),
+                level,
+                target,
+                message,
+                repeats
+            ),
+            level_style(level),
+        );
+    }
+
+    styled
+}
+
+fn title(level: Level) -> String {
+    format!("launchk log: {} and above (e/w/i/d)", level)
+}
+
+fn set_level(siv: &mut Cursive, level: Level) {
+    siv.call_on_name(APP_LOGS_TEXT, |v: &mut TextView| {
+        v.set_content(render(level))
+    });
+
+    if let Some(mut dialog) = siv.find_name::<Dialog>("app_logs") {
+        dialog.set_title(title(level));
+    }
+}
+
+/// launchk's own log, filtered by level with e/w/i/d
+pub fn show_app_logs(level: Level) -> CbSinkMessage {
+    Box::new(move |siv: &mut Cursive| {
+        let dialog = Dialog::around(
+            TextView::new(render(level))
+                .with_name(APP_LOGS_TEXT)
+                .scrollable()
+                .scroll_strategy(ScrollStrategy::StickToBottom),
+        )
+        .title(title(level))
+        .button("Close", |s| {
+            s.pop_layer();
+        })
+        .with_name("app_logs")
+        .full_screen();
+
+        let mut view = OnEventView::new(dialog).on_event('q', |s| {
+            s.pop_layer();
+        });
+
+        for (key, level) in LEVEL_KEYS.iter() {
+            let level = *level;
+            view = view.on_event(*key, move |s| set_level(s, level));
+        }
+
+        siv.add_layer(view);
+    })
+}
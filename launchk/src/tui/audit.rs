@@ -0,0 +1,108 @@
+use std::path::PathBuf;
+use std::sync::mpsc::Sender;
+
+use cursive::theme::{Effect, Style};
+use cursive::traits::{Resizable, Scrollable};
+use cursive::utils::markup::StyledString;
+use cursive::views::{Dialog, TextView};
+
+use crate::config::state_dir;
+use crate::launchd::audit::{AuditFinding, ThirdPartyAgent};
+use crate::tui::omnibox::command::OmniboxCommand;
+use crate::tui::omnibox::view::OmniboxEvent;
+use crate::tui::root::CbSinkMessage;
+use crate::tui::theme::roles;
+
+/// Jobs worth a closer look, most suspicious first
+pub fn show_audit(findings: Vec<AuditFinding>) -> CbSinkMessage {
+    Box::new(move |siv| {
+        let mut text = StyledString::new();
+
+        for finding in &findings {
+            text.append_styled(finding.label.clone(), Effect::Bold);
+            text.append(format!("  {}\n", finding.plist_path));
+
+            for reason in &finding.reasons {
+                let style = if reason.is_high() {
+                    Style::from(roles().error)
+                } else {
+                    Style::from(roles().warning)
+                };
+
+                text.append_styled(format!("  ! {}\n", reason), style);
+            }
+
+            text.append("\n");
+        }
+
+        if findings.is_empty() {
+            text.append("Nothing suspicious outside /System");
+        }
+
+        siv.add_layer(
+            Dialog::around(TextView::new(text).scrollable())
+                .title(format!("Persistence audit: {} job(s)", findings.len()))
+                .dismiss_button("OK")
+                .max_height(40),
+        );
+    })
+}
+
+/// Where SetThirdPartyEnabled saves each agent's state before changing it
+pub fn manifest_dir() -> PathBuf {
+    state_dir().join("manifests")
+}
+
+/// Non-Apple agents and why, with buttons to disable or enable them all
+pub fn show_third_party(tx: Sender<OmniboxEvent>, agents: Vec<ThirdPartyAgent>) -> CbSinkMessage {
+    Box::new(move |siv| {
+        let mut text = StyledString::new();
+
+        for agent in &agents {
+            text.append_styled(agent.label.clone(), Effect::Bold);
+            text.append(format!("  {}\n", agent.plist_path));
+            text.append_styled(format!("  {}\n", agent.reason), roles().subtle);
+        }
+
+        let mut dialog = Dialog::new().title(format!("Third-party agents: {}", agents.len()));
+
+        if agents.is_empty() {
+            text.append("No agents outside /System look third-party");
+        } else {
+            let labels: Vec<String> = agents.iter().map(|a| a.label.clone()).collect();
+
+            for (button, verb, enabled) in [
+                ("Disable all", "Disable", false),
+                ("Enable all", "Enable", true),
+            ] {
+                let (tx, labels) = (tx.clone(), labels.clone());
+                let prompt = format!(
+                    "{} {} agent(s)? Their current state is saved to {} first, \
+                     `overrides apply` on that file undoes it.",
+                    verb,
+                    labels.len(),
+                    manifest_dir().join("third-party-<time>.json").display()
+                );
+
+                dialog.add_button(button, move |s| {
+                    s.pop_layer();
+                    tx.send(OmniboxEvent::Command(OmniboxCommand::Confirm(
+                        prompt.clone(),
+                        vec![OmniboxCommand::SetThirdPartyEnabled(
+                            labels.clone(),
+                            enabled,
+                        )],
+                    )))
+                    .expect("Must send command");
+                });
+            }
+        }
+
+        siv.add_layer(
+            dialog
+                .content(TextView::new(text).scrollable())
+                .dismiss_button("Close")
+                .max_height(40),
+        );
+    })
+}
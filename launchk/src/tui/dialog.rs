@@ -1,19 +1,37 @@
+use std::collections::{BTreeMap, HashMap};
 use std::sync::mpsc::Sender;
 
+use cursive::traits::{Nameable, Resizable, Scrollable};
 use cursive::Cursive;
 use cursive::{
     theme::Effect,
     view::Margins,
-    views::{Dialog, DummyView, LinearLayout, RadioGroup, TextView},
+    views::{
+        Checkbox, Dialog, DummyView, EditView, LinearLayout, OnEventView, RadioGroup, SelectView,
+        TextView,
+    },
 };
+use git_version::git_version;
 
+use crate::config::bindings;
+use crate::config::columns::{ColumnLayout, ServiceColumn, ALL_COLUMNS};
+use crate::launchd::domain_target::DomainTarget;
 use crate::launchd::entry_status::{get_entry_status, LaunchdEntryStatus};
+use crate::launchd::limits::{Limit, UNLIMITED};
+use crate::launchd::new_job::{split_args, NewJob, Schedule};
+use crate::launchd::plist::{LaunchdEntryLocation, LaunchdEntryType};
+use crate::launchd::query::{launchd_manager, DebugOptions};
+use crate::launchd::schedule::LocalTime;
+use crate::launchd::session::LaunchSession;
+use crate::launchd::templates::{Template, TEMPLATES};
+use crate::tui::keys::event_name;
 use crate::tui::omnibox::command::OmniboxCommand;
 use crate::tui::omnibox::command::OMNIBOX_COMMANDS;
 use crate::tui::omnibox::view::OmniboxEvent;
 use crate::tui::root::CbSinkMessage;
-use xpc_sys::csr::{csr_check, CsrConfig};
+use xpc_sys::csr::{csr_check, sip_enabled, CsrConfig};
 use xpc_sys::enums::{DomainType, SessionType};
+use xpc_sys::rs_boottime;
 
 /// XPC "error" key can be present with no failure..."notice"?
 pub fn show_error(err: String) -> CbSinkMessage {
@@ -55,8 +73,157 @@ pub fn show_prompt(
     Box::new(cl)
 }
 
+/// Like show_prompt, but commands only run once expected is typed
+pub fn typed_confirm_prompt(
+    tx: Sender<OmniboxEvent>,
+    prompt: String,
+    expected: String,
+    commands: Vec<OmniboxCommand>,
+) -> CbSinkMessage {
+    let cl = move |siv: &mut Cursive| {
+        let tx = tx.clone();
+        let expected = expected.clone();
+        let commands = commands.clone();
+
+        let submit = move |s: &mut Cursive| {
+            let typed = s
+                .call_on_name("typed_confirm", |v: &mut EditView| v.get_content())
+                .unwrap_or_default();
+
+            if *typed != expected {
+                return show_error(format!("Type {} to continue", expected))(s);
+            }
+
+            s.pop_layer();
+            commands
+                .iter()
+                .try_for_each(|c| tx.send(OmniboxEvent::Command(c.clone())))
+                .expect("Must send commands");
+        };
+
+        let layout = LinearLayout::vertical()
+            .child(TextView::new(prompt.clone()))
+            .child(DummyView)
+            .child(EditView::new().with_name("typed_confirm").fixed_width(60));
+
+        let ask = Dialog::around(layout)
+            .button("Continue", submit)
+            .dismiss_button("Cancel")
+            .title("Protected job");
+
+        siv.add_layer(ask);
+    };
+
+    Box::new(cl)
+}
+
+type ColumnPickerRow = (ColumnLayout, bool);
+
+fn column_picker_label((column, width): &ColumnLayout, shown: bool) -> String {
+    let width = width.map(|w| w.to_string()).unwrap_or("*".to_string());
+    format!(
+        "[{}] {:<10} {}",
+        if shown { "x" } else { " " },
+        column.key(),
+        width
+    )
+}
+
+fn toggle_column(v: &mut SelectView<ColumnPickerRow>) {
+    let item = v.selected_id().and_then(|i| v.get_item_mut(i));
+
+    if let Some((label, (layout, shown))) = item {
+        *shown = !*shown;
+        *label = column_picker_label(layout, *shown).into();
+    }
+}
+
+fn move_column(v: &mut SelectView<ColumnPickerRow>, up: bool) {
+    let i = match v.selected_id() {
+        Some(i) => i,
+        None => return,
+    };
+
+    let j = if up { i.checked_sub(1) } else { Some(i + 1) };
+    let j = match j.filter(|j| *j < v.len()) {
+        Some(j) => j,
+        None => return,
+    };
+
+    if let Some((label, row)) = v.get_item(i).map(|(l, r)| (l.to_string(), *r)) {
+        v.remove_item(i);
+        v.insert_item(j, label, row);
+        v.set_selection(j);
+    }
+}
+
+/// Shown columns in order, then the hidden ones. Space or enter toggles,
+/// + and - move.
+pub fn column_picker(tx: Sender<OmniboxEvent>, columns: Vec<ColumnLayout>) -> CbSinkMessage {
+    let cl = move |siv: &mut Cursive| {
+        let tx = tx.clone();
+        let hidden = ALL_COLUMNS
+            .iter()
+            .filter(|c| !columns.iter().any(|(shown, _)| shown == *c))
+            .map(|c| ((*c, c.default_width()), false));
+
+        let mut select = SelectView::<ColumnPickerRow>::new().on_submit(|s, _| {
+            s.call_on_name("column_picker", toggle_column);
+        });
+
+        for (layout, shown) in columns.iter().map(|l| (*l, true)).chain(hidden) {
+            select.add_item(column_picker_label(&layout, shown), (layout, shown));
+        }
+
+        let select = OnEventView::new(select.with_name("column_picker"))
+            .on_event(' ', |s| {
+                s.call_on_name("column_picker", toggle_column);
+            })
+            .on_event('-', |s| {
+                s.call_on_name("column_picker", |v| move_column(v, true));
+            })
+            .on_event('+', |s| {
+                s.call_on_name("column_picker", |v| move_column(v, false));
+            });
+
+        let apply = move |s: &mut Cursive| {
+            let columns: Vec<ColumnLayout> = s
+                .call_on_name("column_picker", |v: &mut SelectView<ColumnPickerRow>| {
+                    v.iter()
+                        .filter(|(_, (_, shown))| *shown)
+                        .map(|(_, (layout, _))| *layout)
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            if !columns.iter().any(|(c, _)| *c == ServiceColumn::Label) {
+                return show_error("The label column must be shown".to_string())(s);
+            }
+
+            s.pop_layer();
+            tx.send(OmniboxEvent::Command(OmniboxCommand::SetColumns(columns)))
+                .expect("Must send columns");
+        };
+
+        let layout = LinearLayout::vertical()
+            .child(TextView::new("space: show/hide  -/+: move up/down"))
+            .child(DummyView)
+            .child(select);
+
+        let ask = Dialog::around(layout)
+            .button("Apply", apply)
+            .dismiss_button("Cancel")
+            .title("Columns");
+
+        siv.add_layer(ask);
+    };
+
+    Box::new(cl)
+}
+
 /// Don't know how to get this info when job is not running,
-/// so we can ask user and suggest a default (domain 7, aqua)
+/// so we can ask user and suggest a default for this session (gui and
+/// Aqua in a login session, user and Background over SSH)
 pub fn domain_session_prompt<S: Into<String>>(
     label: S,
     domain_only: bool,
@@ -69,6 +236,17 @@ pub fn domain_session_prompt<S: Into<String>>(
         ..
     } = get_entry_status(label);
 
+    // Not loaded, suggest where this session would put it
+    let session = LaunchSession::current();
+    let domain = match domain {
+        DomainType::Unknown => session.requestor_domain(),
+        d => d,
+    };
+    let limit_load_to_session_type = match limit_load_to_session_type {
+        SessionType::Unknown => session.default_session_type(),
+        st => st,
+    };
+
     let cl = move |siv: &mut Cursive| {
         let mut domain_group: RadioGroup<DomainType> = RadioGroup::new();
         let mut st_group: RadioGroup<SessionType> = RadioGroup::new();
@@ -78,7 +256,7 @@ pub fn domain_session_prompt<S: Into<String>>(
             .child(TextView::new("Domain Type").style(Effect::Bold))
             .child(DummyView);
 
-        for d in DomainType::System as u64..DomainType::Unknown as u64 {
+        for d in DomainType::System as u64..=DomainType::RequestorDomain as u64 {
             let as_domain: DomainType = d.into();
             let mut button =
                 domain_group.button(as_domain.clone(), format!("{}: {}", d, &as_domain));
@@ -138,6 +316,656 @@ pub fn domain_session_prompt<S: Into<String>>(
     Box::new(cl)
 }
 
+/// Signals offered by the kill prompt, anything else can be entered by number
+static SIGNALS: [(&str, i32); 6] = [
+    ("SIGTERM", libc::SIGTERM),
+    ("SIGKILL", libc::SIGKILL),
+    ("SIGHUP", libc::SIGHUP),
+    ("SIGINT", libc::SIGINT),
+    ("SIGUSR1", libc::SIGUSR1),
+    ("SIGUSR2", libc::SIGUSR2),
+];
+
+/// Pick a signal to send to a job, None in the radio group means custom
+pub fn signal_prompt(
+    tx: Sender<OmniboxEvent>,
+    domain: DomainType,
+    f: fn(DomainType, i32) -> OmniboxCommand,
+) -> CbSinkMessage {
+    let cl = move |siv: &mut Cursive| {
+        let mut signal_group: RadioGroup<Option<i32>> = RadioGroup::new();
+
+        let mut layout = LinearLayout::vertical()
+            .child(TextView::new("Signal").style(Effect::Bold))
+            .child(DummyView);
+
+        for (name, signal) in SIGNALS.iter() {
+            layout =
+                layout.child(signal_group.button(Some(*signal), format!("{}: {}", signal, name)));
+        }
+
+        layout = layout.child(
+            LinearLayout::horizontal()
+                .child(signal_group.button(None, "Custom: "))
+                .child(EditView::new().with_name("custom_signal").fixed_width(6)),
+        );
+
+        let tx = tx.clone();
+        let domain = domain.clone();
+
+        let ask = Dialog::new()
+            .title("Send signal")
+            .content(layout)
+            .button("OK", move |s| {
+                let signal = signal_group.selection().or_else(|| {
+                    s.call_on_name("custom_signal", |v: &mut EditView| v.get_content())
+                        .and_then(|c| c.parse::<i32>().ok())
+                });
+
+                s.pop_layer();
+
+                match signal {
+                    Some(signal) => tx
+                        .send(OmniboxEvent::Command(f(domain.clone(), signal)))
+                        .expect("Must send command"),
+                    None => show_error("Invalid signal number".to_string())(s),
+                }
+            })
+            .dismiss_button("Cancel")
+            .padding(Margins::trbl(5, 5, 5, 5));
+
+        siv.add_layer(ask);
+    };
+
+    Box::new(cl)
+}
+
+/// Pick a uid to scope the service list to
+pub fn user_scope_prompt(tx: Sender<OmniboxEvent>, users: BTreeMap<u32, String>) -> CbSinkMessage {
+    let cl = move |siv: &mut Cursive| {
+        let mut select = SelectView::new().item("All users", None);
+
+        for (uid, name) in &users {
+            select.add_item(format!("{} ({})", name, uid), Some(*uid));
+        }
+
+        let tx = tx.clone();
+        select.set_on_submit(move |s, uid: &Option<u32>| {
+            s.pop_layer();
+            tx.send(OmniboxEvent::Command(OmniboxCommand::UserScope(*uid)))
+                .expect("Must send command");
+        });
+
+        siv.add_layer(
+            Dialog::around(select)
+                .title("Logged in users")
+                .dismiss_button("Cancel"),
+        );
+    };
+
+    Box::new(cl)
+}
+
+/// Pick the domain target for load, unload, enable and disable. Other
+/// users' domains are offered to root.
+pub fn domain_target_prompt(
+    tx: Sender<OmniboxEvent>,
+    current: Option<DomainTarget>,
+    users: BTreeMap<u32, String>,
+) -> CbSinkMessage {
+    let cl = move |siv: &mut Cursive| {
+        let mut targets = vec![
+            ("Ask each time".to_string(), None),
+            (
+                "system".to_string(),
+                Some(DomainTarget::new(DomainType::System, 0)),
+            ),
+        ];
+
+        for (uid, name) in &users {
+            for dt in [DomainType::Gui, DomainType::User] {
+                let target = DomainTarget::new(dt, *uid as u64);
+                targets.push((format!("{} ({})", target, name), Some(target)));
+            }
+        }
+
+        let mut select = SelectView::new();
+        for (i, (text, target)) in targets.into_iter().enumerate() {
+            select.add_item(text, target.clone());
+            if target == current {
+                select.set_selection(i);
+            }
+        }
+
+        let tx = tx.clone();
+        select.set_on_submit(move |s, target: &Option<DomainTarget>| {
+            s.pop_layer();
+            tx.send(OmniboxEvent::Command(OmniboxCommand::SetDomainTarget(
+                target.clone(),
+            )))
+            .expect("Must send command");
+        });
+
+        siv.add_layer(
+            Dialog::around(select)
+                .title("Domain target")
+                .dismiss_button("Cancel"),
+        );
+    };
+
+    Box::new(cl)
+}
+
+/// Labelled text field for the new job wizard
+fn new_job_field(label: &str, name: &str) -> LinearLayout {
+    LinearLayout::vertical()
+        .child(TextView::new(label).style(Effect::Bold))
+        .child(EditView::new().with_name(name).fixed_width(60))
+}
+
+fn new_job_answer(siv: &mut Cursive, name: &str) -> String {
+    siv.call_on_name(name, |v: &mut EditView| v.get_content().trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Prompt for a new plist, sends OmniboxCommand::CreateJob
+pub fn new_job_prompt(tx: Sender<OmniboxEvent>) -> CbSinkMessage {
+    let cl = move |siv: &mut Cursive| {
+        let mut kind_group: RadioGroup<(LaunchdEntryLocation, LaunchdEntryType)> =
+            RadioGroup::new();
+        let kinds = kind_buttons(&mut kind_group, &LaunchdEntryType::Agent);
+
+        let layout = LinearLayout::vertical()
+            .child(new_job_field("Label", "new_job_label"))
+            .child(new_job_field(
+                "Program arguments (quote to group)",
+                "new_job_args",
+            ))
+            .child(new_job_field(
+                "Schedule: seconds, or e.g. Hour=3 Minute=30 (empty for none)",
+                "new_job_schedule",
+            ))
+            .child(new_job_field("Standard out path", "new_job_stdout"))
+            .child(new_job_field("Standard error path", "new_job_stderr"))
+            .child(DummyView)
+            .child(kinds)
+            .child(DummyView)
+            .child(
+                LinearLayout::horizontal()
+                    .child(Checkbox::new().with_name("new_job_run_at_load"))
+                    .child(TextView::new(" RunAtLoad  "))
+                    .child(Checkbox::new().with_name("new_job_load_now"))
+                    .child(TextView::new(" Load now")),
+            );
+
+        let tx = tx.clone();
+        let template_tx = tx.clone();
+
+        let ask = Dialog::new()
+            .title("New job")
+            .content(layout)
+            .button("Create", move |s| {
+                let optional = |v: String| Some(v).filter(|v| !v.is_empty());
+                let checked = |s: &mut Cursive, name: &str| {
+                    s.call_on_name(name, |v: &mut Checkbox| v.is_checked())
+                        .unwrap_or(false)
+                };
+
+                let schedule = match Schedule::parse(&new_job_answer(s, "new_job_schedule")) {
+                    Ok(schedule) => schedule,
+                    Err(e) => return show_error(e)(s),
+                };
+
+                let (location, entry_type) = (*kind_group.selection()).clone();
+                let job = NewJob {
+                    label: new_job_answer(s, "new_job_label"),
+                    program_arguments: split_args(&new_job_answer(s, "new_job_args")),
+                    run_at_load: checked(s, "new_job_run_at_load"),
+                    schedule,
+                    stdout_path: optional(new_job_answer(s, "new_job_stdout")),
+                    stderr_path: optional(new_job_answer(s, "new_job_stderr")),
+                    location,
+                    entry_type,
+                    template: None,
+                };
+                let load_now = checked(s, "new_job_load_now");
+
+                s.pop_layer();
+                tx.send(OmniboxEvent::Command(OmniboxCommand::CreateJob(
+                    Box::new(job),
+                    load_now,
+                )))
+                .expect("Must send command");
+            })
+            .button("Templates...", move |s| {
+                s.pop_layer();
+                template_picker(template_tx.clone())(s);
+            })
+            .dismiss_button("Cancel");
+
+        siv.add_layer(ask);
+    };
+
+    Box::new(cl)
+}
+
+/// Type radio buttons for the new job wizards, first of entry_type selected
+fn kind_buttons(
+    group: &mut RadioGroup<(LaunchdEntryLocation, LaunchdEntryType)>,
+    entry_type: &LaunchdEntryType,
+) -> LinearLayout {
+    let kinds = [
+        (
+            LaunchdEntryLocation::User,
+            LaunchdEntryType::Agent,
+            "User agent (~/Library/LaunchAgents)",
+        ),
+        (
+            LaunchdEntryLocation::Global,
+            LaunchdEntryType::Agent,
+            "Global agent (/Library/LaunchAgents)",
+        ),
+        (
+            LaunchdEntryLocation::Global,
+            LaunchdEntryType::Daemon,
+            "Global daemon (/Library/LaunchDaemons)",
+        ),
+    ];
+
+    let mut layout = LinearLayout::vertical().child(TextView::new("Type").style(Effect::Bold));
+    let selected = kinds.iter().position(|(_, t, _)| t == entry_type);
+
+    for (i, (location, kind, label)) in kinds.iter().enumerate() {
+        let button = group.button((location.clone(), kind.clone()), *label);
+
+        layout.add_child(if Some(i) == selected {
+            button.selected()
+        } else {
+            button
+        });
+    }
+
+    layout
+}
+
+/// Pick one of TEMPLATES for template_job_prompt
+fn template_picker(tx: Sender<OmniboxEvent>) -> CbSinkMessage {
+    Box::new(move |siv| {
+        let mut select = SelectView::new();
+
+        for (i, template) in TEMPLATES.iter().enumerate() {
+            select.add_item(format!("{:<14} {}", template.name, template.description), i);
+        }
+
+        let tx = tx.clone();
+        select.set_on_submit(move |s, i: &usize| {
+            s.pop_layer();
+            template_job_prompt(tx.clone(), &TEMPLATES[*i])(s);
+        });
+
+        siv.add_layer(
+            Dialog::around(select)
+                .title("New job from template")
+                .dismiss_button("Cancel"),
+        );
+    })
+}
+
+/// Label and template variables, previewing the plist before
+/// sending OmniboxCommand::CreateJob
+fn template_job_prompt(tx: Sender<OmniboxEvent>, template: &'static Template) -> CbSinkMessage {
+    Box::new(move |siv| {
+        let mut kind_group: RadioGroup<(LaunchdEntryLocation, LaunchdEntryType)> =
+            RadioGroup::new();
+
+        let mut layout = LinearLayout::vertical().child(new_job_field("Label", "new_job_label"));
+
+        for (name, prompt, default) in template.variables {
+            layout.add_child(
+                LinearLayout::vertical()
+                    .child(TextView::new(*prompt).style(Effect::Bold))
+                    .child(
+                        EditView::new()
+                            .content(*default)
+                            .with_name(format!("template_{}", name))
+                            .fixed_width(60),
+                    ),
+            );
+        }
+
+        layout.add_child(DummyView);
+        layout.add_child(kind_buttons(&mut kind_group, &template.entry_type));
+        layout.add_child(DummyView);
+        layout.add_child(
+            LinearLayout::horizontal()
+                .child(Checkbox::new().with_name("new_job_load_now"))
+                .child(TextView::new(" Load now")),
+        );
+
+        let tx = tx.clone();
+
+        let ask = Dialog::new()
+            .title(format!("New job: {}", template.name))
+            .content(layout.scrollable())
+            .button("Preview", move |s| {
+                let label = new_job_answer(s, "new_job_label");
+                let mut values: HashMap<String, String> = template
+                    .variables
+                    .iter()
+                    .map(|(name, _, _)| {
+                        let value = new_job_answer(s, &format!("template_{}", name));
+                        (name.to_string(), value)
+                    })
+                    .collect();
+                values.insert("label".to_string(), label.clone());
+
+                let (location, entry_type) = (*kind_group.selection()).clone();
+                let job = match template.render(&values) {
+                    Ok(xml) => NewJob {
+                        label,
+                        program_arguments: vec![],
+                        run_at_load: false,
+                        schedule: Schedule::None,
+                        stdout_path: None,
+                        stderr_path: None,
+                        location,
+                        entry_type,
+                        template: Some(xml),
+                    },
+                    Err(e) => return show_error(e)(s),
+                };
+
+                let preview = match (job.path(), job.to_xml()) {
+                    (Ok(path), Ok(xml)) => format!("{}\n\n{}", path.display(), xml),
+                    (Err(e), _) | (_, Err(e)) => return show_error(e)(s),
+                };
+
+                let load_now = s
+                    .call_on_name("new_job_load_now", |v: &mut Checkbox| v.is_checked())
+                    .unwrap_or(false);
+
+                let tx = tx.clone();
+
+                s.add_layer(
+                    Dialog::around(TextView::new(preview).scrollable())
+                        .title("Preview")
+                        .button("Create", move |s| {
+                            s.pop_layer();
+                            s.pop_layer();
+                            tx.send(OmniboxEvent::Command(OmniboxCommand::CreateJob(
+                                Box::new(job.clone()),
+                                load_now,
+                            )))
+                            .expect("Must send command");
+                        })
+                        .dismiss_button("Back")
+                        .max_height(40),
+                );
+            })
+            .dismiss_button("Cancel");
+
+        siv.add_layer(ask);
+    })
+}
+
+/// KEY=value pairs, quote values with spaces
+fn parse_environment(env: &str) -> Result<Vec<(String, String)>, String> {
+    split_args(env)
+        .into_iter()
+        .map(|pair| {
+            pair.split_once('=')
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .ok_or_else(|| format!("Expected KEY=value, got {}", pair))
+        })
+        .collect()
+}
+
+/// Options for launchctl debug, sends OmniboxCommand::Debug
+pub fn debug_prompt(tx: Sender<OmniboxEvent>, domain_type: DomainType) -> CbSinkMessage {
+    let cl = move |siv: &mut Cursive| {
+        let layout = LinearLayout::vertical()
+            .child(new_job_field(
+                "Program path (empty for the job's own)",
+                "debug_program",
+            ))
+            .child(new_job_field("Standard in path", "debug_stdin"))
+            .child(new_job_field("Standard out path", "debug_stdout"))
+            .child(new_job_field("Standard error path", "debug_stderr"))
+            .child(new_job_field("Environment: KEY=value ...", "debug_env"))
+            .child(DummyView)
+            .child(
+                LinearLayout::horizontal()
+                    .child(Checkbox::new().with_name("debug_wait"))
+                    .child(TextView::new(" Wait for debugger")),
+            );
+
+        let tx = tx.clone();
+        let domain_type = domain_type.clone();
+
+        let ask = Dialog::new()
+            .title(format!("Debug next launch ({})", domain_type))
+            .content(layout)
+            .button("Apply", move |s| {
+                let optional = |v: String| Some(v).filter(|v| !v.is_empty());
+
+                let environment = match parse_environment(&new_job_answer(s, "debug_env")) {
+                    Ok(env) => env,
+                    Err(e) => return show_error(e)(s),
+                };
+
+                let options = DebugOptions {
+                    program_path: optional(new_job_answer(s, "debug_program")),
+                    wait_for_debugger: s
+                        .call_on_name("debug_wait", |v: &mut Checkbox| v.is_checked())
+                        .unwrap_or(false),
+                    stdin_path: optional(new_job_answer(s, "debug_stdin")),
+                    stdout_path: optional(new_job_answer(s, "debug_stdout")),
+                    stderr_path: optional(new_job_answer(s, "debug_stderr")),
+                    environment,
+                };
+
+                s.pop_layer();
+                tx.send(OmniboxEvent::Command(OmniboxCommand::Debug(
+                    domain_type.clone(),
+                    Box::new(options),
+                )))
+                .expect("Must send command");
+            })
+            .dismiss_button("Cancel");
+
+        siv.add_layer(ask);
+    };
+
+    Box::new(cl)
+}
+
+/// Set or unset a variable in a domain, key is editable when adding
+pub fn env_prompt(
+    tx: Sender<OmniboxEvent>,
+    target: DomainTarget,
+    key: Option<String>,
+    value: Option<String>,
+) -> CbSinkMessage {
+    let cl = move |siv: &mut Cursive| {
+        let key_view = match &key {
+            Some(k) => EditView::new().content(k.clone()).disabled(),
+            None => EditView::new(),
+        };
+
+        let layout = LinearLayout::vertical()
+            .child(TextView::new("Key").style(Effect::Bold))
+            .child(key_view.with_name("env_key").fixed_width(40))
+            .child(DummyView)
+            .child(TextView::new("Value").style(Effect::Bold))
+            .child(
+                EditView::new()
+                    .content(value.clone().unwrap_or_default())
+                    .with_name("env_value")
+                    .fixed_width(40),
+            );
+
+        let read = |s: &mut Cursive, name: &str| {
+            s.call_on_name(name, |v: &mut EditView| v.get_content().to_string())
+                .unwrap_or_default()
+        };
+
+        let set_tx = tx.clone();
+        let set_target = target.clone();
+        let unset_tx = tx.clone();
+        let unset_target = target.clone();
+
+        let mut ask = Dialog::new()
+            .title(format!("Environment ({})", target))
+            .content(layout)
+            .button("Set", move |s| {
+                let args = vec![read(s, "env_key"), read(s, "env_value")];
+                s.pop_layer();
+
+                set_tx
+                    .send(OmniboxEvent::Command(OmniboxCommand::SetEnv(
+                        set_target.clone(),
+                        args,
+                    )))
+                    .expect("Must send command");
+            });
+
+        if key.is_some() {
+            ask.add_button("Unset", move |s| {
+                let args = vec![read(s, "env_key")];
+                s.pop_layer();
+
+                unset_tx
+                    .send(OmniboxEvent::Command(OmniboxCommand::UnsetEnv(
+                        unset_target.clone(),
+                        args,
+                    )))
+                    .expect("Must send command");
+            });
+        }
+
+        siv.add_layer(ask.dismiss_button("Cancel"));
+    };
+
+    Box::new(cl)
+}
+
+fn limit_field(value: Option<u64>) -> String {
+    match value {
+        Some(v) if v >= UNLIMITED => "unlimited".to_string(),
+        Some(v) => v.to_string(),
+        None => String::new(),
+    }
+}
+
+/// maxfiles and maxproc soft/hard, like launchctl limit, sends a
+/// OmniboxCommand::Limit for each
+pub fn limit_prompt(tx: Sender<OmniboxEvent>, current: BTreeMap<String, Limit>) -> CbSinkMessage {
+    const NAMES: [&str; 2] = ["maxfiles", "maxproc"];
+
+    let cl = move |siv: &mut Cursive| {
+        let mut layout = LinearLayout::vertical();
+
+        for name in NAMES {
+            let limit = current.get(name).copied().unwrap_or_default();
+
+            layout.add_child(TextView::new(name).style(Effect::Bold));
+            layout.add_child(
+                LinearLayout::horizontal()
+                    .child(TextView::new("soft "))
+                    .child(
+                        EditView::new()
+                            .content(limit_field(limit.soft))
+                            .with_name(format!("{}_soft", name))
+                            .fixed_width(14),
+                    )
+                    .child(TextView::new("  hard "))
+                    .child(
+                        EditView::new()
+                            .content(limit_field(limit.hard))
+                            .with_name(format!("{}_hard", name))
+                            .fixed_width(14),
+                    ),
+            );
+            layout.add_child(DummyView);
+        }
+
+        let tx = tx.clone();
+        let ask = Dialog::new()
+            .title("launchd resource limits")
+            .content(layout)
+            .button("Set", move |s| {
+                let commands: Vec<OmniboxCommand> = NAMES
+                    .iter()
+                    .map(|name| {
+                        OmniboxCommand::Limit(vec![
+                            name.to_string(),
+                            new_job_answer(s, &format!("{}_soft", name)),
+                            new_job_answer(s, &format!("{}_hard", name)),
+                        ])
+                    })
+                    .collect();
+                s.pop_layer();
+
+                for c in commands {
+                    tx.send(OmniboxEvent::Command(c))
+                        .expect("Must send command");
+                }
+            })
+            .dismiss_button("Cancel");
+
+        siv.add_layer(ask);
+    };
+
+    Box::new(cl)
+}
+
+/// Which launchd we are talking to, and since when
+pub fn show_about() -> CbSinkMessage {
+    let manager = launchd_manager();
+    let now = LocalTime::now();
+    let booted = rs_boottime()
+        .map(|t| {
+            let booted = LocalTime::from_epoch(t);
+            format!("{} (up {})", booted, booted.until(&now))
+        })
+        .unwrap_or_else(|e| e);
+
+    let about = [
+        (
+            "launchd",
+            manager.version.clone().unwrap_or("-".to_string()),
+        ),
+        ("Manager", manager.to_string()),
+        (
+            "Variant",
+            manager.variant.clone().unwrap_or("-".to_string()),
+        ),
+        (
+            "SIP",
+            if sip_enabled() {
+                "on, /System jobs cannot be unloaded, disabled or enabled".to_string()
+            } else {
+                "off".to_string()
+            },
+        ),
+        ("Booted", booted),
+        ("launchk", git_version!().to_string()),
+    ]
+    .iter()
+    .map(|(k, v)| format!("{:<8} {}", k, v))
+    .collect::<Vec<String>>();
+
+    Box::new(move |siv| {
+        siv.add_layer(
+            Dialog::new()
+                .title("About")
+                .content(TextView::new(about.join("\n")))
+                .dismiss_button("OK")
+                .padding(Margins::lrtb(2, 2, 1, 1)),
+        )
+    })
+}
+
 pub fn show_csr_info() -> CbSinkMessage {
     let csr_flags = (0..11)
         .map(|s| {
@@ -157,23 +985,58 @@ pub fn show_csr_info() -> CbSinkMessage {
     })
 }
 
+/// Keys that can't be rebound, for help
+static FIXED_KEYS: [(&str, &str); 6] = [
+    (":", "Command mode"),
+    ("/", "Filter mode, // for a regex"),
+    (
+        "sguadlexnqbworh",
+        "Toggle job type and session type filters",
+    ),
+    ("Space", "Mark highlighted job for batch commands"),
+    ("Ctrl-U", "Clear omnibox"),
+    ("Ctrl-R", "Search command history"),
+];
+
 pub fn show_help() -> CbSinkMessage {
+    let bindings = bindings();
+    let keys_for = |name: &str| {
+        bindings
+            .iter()
+            .filter(|(_, n)| n == name)
+            .map(|(e, _)| event_name(e))
+            .collect::<Vec<String>>()
+            .join(" ")
+    };
+
+    let keys = FIXED_KEYS
+        .iter()
+        .map(|(key, desc)| format!("{:<15}: {}", key, desc))
+        .collect::<Vec<String>>();
+
     let commands = OMNIBOX_COMMANDS
         .iter()
         .map(|(cmd, desc, _)| {
             format!(
-                "{:<15}: {}",
+                "{:<15} {:<8}: {}",
                 cmd,
+                keys_for(cmd),
                 desc.chars().filter(|c| c.is_ascii()).collect::<String>()
             )
         })
         .collect::<Vec<String>>();
 
+    let text = format!(
+        "Keys\n\n{}\n\nCommands\n\n{}",
+        keys.join("\n"),
+        commands.join("\n")
+    );
+
     Box::new(move |siv| {
         siv.add_layer(
             Dialog::new()
                 .title("Help")
-                .content(TextView::new(commands.join("\n")))
+                .content(TextView::new(text).scrollable())
                 .dismiss_button("OK")
                 .padding(Margins::trbl(4, 4, 4, 4)),
         )
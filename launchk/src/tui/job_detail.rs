@@ -0,0 +1,628 @@
+use std::sync::mpsc::Sender;
+use std::time::{Duration, UNIX_EPOCH};
+
+use cursive::traits::Nameable;
+use cursive::view::ViewWrapper;
+use cursive::views::{Button, DummyView, HideableView, LinearLayout, Panel, ScrollView, TextView};
+use cursive::Cursive;
+use xpc_sys::code_signing::{CodeSignature, SignatureKind};
+
+use crate::launchd::backup::Backup;
+use crate::launchd::crash_reports::CrashReport;
+use crate::launchd::domain_target::DomainTarget;
+use crate::launchd::endpoints::{Endpoint, EndpointKind};
+use crate::launchd::events::{EventLine, JobEvent};
+use crate::launchd::job_detail::JobDetail;
+use crate::launchd::procinfo::ProcInfo;
+use crate::launchd::schedule::{JobSchedule, LocalTime};
+use crate::launchd::watch_paths::{WatchKind, WatchedPath};
+use crate::tui::app_logs::clock;
+use crate::tui::omnibox::command::OmniboxCommand;
+use crate::tui::omnibox::view::OmniboxEvent;
+
+/// Fire times shown for calendar schedules
+const SCHEDULE_PREVIEW_COUNT: usize = 5;
+
+/// (title, lines)
+pub type DetailSection = (String, Vec<String>);
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum DetailTab {
+    Job,
+    Environment,
+    Endpoints,
+    Backups,
+}
+
+/// Split pane under the service list with collapsible sections
+/// for the highlighted job, hidden until requested
+pub struct JobDetailView {
+    inner: HideableView<Panel<LinearLayout>>,
+    omnibox_tx: Sender<OmniboxEvent>,
+    tab: DetailTab,
+    sections: Vec<DetailSection>,
+    /// Domain of the job and its launchctl setenv variables
+    environment: Option<(DomainTarget, Vec<(String, String)>)>,
+    endpoints: Vec<Endpoint>,
+    backups: Vec<Backup>,
+    crash_reports: Vec<CrashReport>,
+}
+
+impl JobDetailView {
+    pub fn new(omnibox_tx: Sender<OmniboxEvent>) -> Self {
+        let inner = HideableView::new(Panel::new(LinearLayout::vertical())).hidden();
+
+        Self {
+            inner,
+            omnibox_tx,
+            tab: DetailTab::Job,
+            sections: vec![],
+            environment: None,
+            endpoints: vec![],
+            backups: vec![],
+            crash_reports: vec![],
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.inner.is_visible()
+    }
+
+    pub fn hide(&mut self) {
+        self.inner.hide();
+    }
+
+    /// Replace contents and show
+    pub fn show_detail(&mut self, detail: &JobDetail) {
+        self.inner.get_inner_mut().set_title(detail.target.clone());
+        self.sections = detail_sections(detail);
+        self.environment = detail
+            .domain_target
+            .clone()
+            .map(|t| (t, detail.domain_environment.clone()));
+        self.endpoints = detail.endpoints.clone();
+        self.backups = detail.backups.clone();
+        self.crash_reports = detail.crash_reports.clone();
+
+        self.render();
+        self.inner.unhide();
+    }
+
+    pub fn select_tab(&mut self, tab: DetailTab) {
+        self.tab = tab;
+        self.render();
+    }
+
+    fn render(&mut self) {
+        let body = match self.tab {
+            DetailTab::Job => self.render_sections(),
+            DetailTab::Environment => self.render_environment(),
+            DetailTab::Endpoints => self.render_endpoints(),
+            DetailTab::Backups => self.render_backups(),
+        };
+
+        let layout = self.inner.get_inner_mut().get_inner_mut();
+        layout.clear();
+        layout.add_child(tab_bar(self.tab));
+        layout.add_child(ScrollView::new(body));
+    }
+
+    fn render_sections(&self) -> LinearLayout {
+        let mut layout = LinearLayout::vertical();
+
+        for (i, (name, lines)) in self.sections.iter().enumerate() {
+            let header_name = format!("job_detail_header_{}", i);
+            let body_name = format!("job_detail_body_{}", i);
+            let header_label = format!("{} ({})", name, lines.len());
+
+            let header = Button::new_raw(format!("▾ {}", header_label), {
+                let header_name = header_name.clone();
+                let body_name = body_name.clone();
+
+                move |s: &mut Cursive| {
+                    let visible = s
+                        .call_on_name(&body_name, |v: &mut HideableView<TextView>| {
+                            v.set_visible(!v.is_visible());
+                            v.is_visible()
+                        })
+                        .unwrap_or(false);
+
+                    let arrow = if visible { "▾" } else { "▸" };
+                    s.call_on_name(&header_name, |b: &mut Button| {
+                        b.set_label_raw(format!("{} {}", arrow, header_label))
+                    });
+                }
+            });
+
+            layout.add_child(header.with_name(header_name));
+            layout
+                .add_child(HideableView::new(TextView::new(lines.join("\n"))).with_name(body_name));
+        }
+
+        // Crashed last time, offer the newest report
+        if let Some(report) = self.crash_reports.first() {
+            let tx = self.omnibox_tx.clone();
+            let cmd = OmniboxCommand::CrashReport(report.path.clone());
+            let modified = report
+                .modified
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| LocalTime::from_epoch(d.as_secs() as libc::time_t).to_string())
+                .unwrap_or_default();
+
+            layout.add_child(DummyView);
+            layout.add_child(Button::new_raw(
+                format!(
+                    "⚡ Open crash report ({} recent): {}  {}",
+                    self.crash_reports.len(),
+                    modified,
+                    report.path
+                ),
+                move |_| {
+                    tx.send(OmniboxEvent::Command(cmd.clone()))
+                        .expect("Must send command")
+                },
+            ));
+        }
+
+        layout
+    }
+
+    /// Variables as buttons opening an edit prompt
+    fn render_environment(&self) -> LinearLayout {
+        let mut layout = LinearLayout::vertical();
+
+        let (target, vars) = match &self.environment {
+            Some(env) => env,
+            None => return layout.child(TextView::new("Unknown domain")),
+        };
+
+        layout.add_child(TextView::new(format!("Domain: {}", target)));
+        layout.add_child(DummyView);
+
+        let prompt_button = |label: String, cmd: OmniboxCommand| {
+            let tx = self.omnibox_tx.clone();
+
+            Button::new_raw(label, move |_| {
+                tx.send(OmniboxEvent::Command(cmd.clone()))
+                    .expect("Must send command")
+            })
+        };
+
+        for (key, value) in vars {
+            layout.add_child(prompt_button(
+                format!("  {} = {}", key, value),
+                OmniboxCommand::EnvPrompt(target.clone(), Some(key.clone())),
+            ));
+        }
+
+        layout.add_child(DummyView);
+        layout.add_child(prompt_button(
+            "  + Add variable".to_string(),
+            OmniboxCommand::EnvPrompt(target.clone(), None),
+        ));
+
+        layout
+    }
+
+    /// Sockets, then Mach services, with launchd's active state and
+    /// whether launchk can look the service up
+    fn render_endpoints(&self) -> LinearLayout {
+        let mut layout = LinearLayout::vertical();
+
+        for (kind, title, none) in [
+            (EndpointKind::Socket, "Sockets", "No sockets"),
+            (
+                EndpointKind::MachService,
+                "Mach Services",
+                "No Mach services",
+            ),
+        ]
+        .iter()
+        {
+            let lines: Vec<String> = self
+                .endpoints
+                .iter()
+                .filter(|e| e.kind == *kind)
+                .map(|e| format!("  {}", e))
+                .collect();
+
+            layout.add_child(TextView::new(format!("{} ({})", title, lines.len())));
+            layout.add_child(TextView::new(if lines.is_empty() {
+                format!("  {}", none)
+            } else {
+                lines.join("\n")
+            }));
+            layout.add_child(DummyView);
+        }
+
+        layout
+    }
+
+    /// Plist backups as buttons asking to restore
+    fn render_backups(&self) -> LinearLayout {
+        let mut layout = LinearLayout::vertical();
+
+        if self.backups.is_empty() {
+            return layout.child(TextView::new(
+                "No backups, the plist is copied before edit or disable",
+            ));
+        }
+
+        for backup in self.backups.iter() {
+            let tx = self.omnibox_tx.clone();
+            let cmd = OmniboxCommand::RestoreBackupRequest(backup.path.clone());
+
+            layout.add_child(Button::new_raw(
+                format!(
+                    "  {}  {}",
+                    LocalTime::from_epoch(backup.taken as libc::time_t),
+                    backup.path
+                ),
+                move |_| {
+                    tx.send(OmniboxEvent::Command(cmd.clone()))
+                        .expect("Must send command")
+                },
+            ));
+        }
+
+        layout
+    }
+}
+
+impl ViewWrapper for JobDetailView {
+    wrap_impl!(self.inner: HideableView<Panel<LinearLayout>>);
+}
+
+fn tab_bar(selected: DetailTab) -> LinearLayout {
+    let tab_button = |tab: DetailTab, name: &str| {
+        let label = if tab == selected {
+            format!("[{}]", name)
+        } else {
+            format!(" {} ", name)
+        };
+
+        Button::new_raw(label, move |s| {
+            s.call_on_name("job_detail", |v: &mut JobDetailView| v.select_tab(tab));
+        })
+    };
+
+    LinearLayout::horizontal()
+        .child(tab_button(DetailTab::Job, "Job"))
+        .child(DummyView)
+        .child(tab_button(DetailTab::Environment, "Environment"))
+        .child(DummyView)
+        .child(tab_button(DetailTab::Endpoints, "Endpoints"))
+        .child(DummyView)
+        .child(tab_button(DetailTab::Backups, "Backups"))
+}
+
+fn key_values(values: &[(String, String)]) -> Vec<String> {
+    values
+        .iter()
+        .map(|(k, v)| format!("  {}: {}", k, v))
+        .collect()
+}
+
+/// Code signing and CPU types first, then everything procinfo printed
+fn process_lines(process: &ProcInfo) -> Vec<String> {
+    let mut code_signing = process.code_signing.clone().unwrap_or("-".to_string());
+    if !process.code_signing_flags.is_empty() {
+        code_signing = format!(
+            "{} ({})",
+            code_signing,
+            process.code_signing_flags.join(", ")
+        );
+    }
+
+    let cputypes = if process.cputypes.is_empty() {
+        "-".to_string()
+    } else {
+        process.cputypes.join(", ")
+    };
+
+    let mut lines = vec![
+        format!("  Code signing: {}", code_signing),
+        format!("  CPU types: {}", cputypes),
+    ];
+
+    lines.extend(key_values(&process.values));
+    lines
+}
+
+fn yes_no(b: bool) -> &'static str {
+    if b {
+        "yes"
+    } else {
+        "no"
+    }
+}
+
+/// Like codesign -dv, with entitlement keys
+fn signature_lines(
+    executable: Option<&String>,
+    signature: &Result<CodeSignature, String>,
+) -> Vec<String> {
+    let mut lines = vec![format!(
+        "  Executable: {}",
+        executable.map(String::as_str).unwrap_or("-")
+    )];
+
+    let signature = match signature {
+        Ok(s) => s,
+        Err(e) => {
+            lines.push(format!("  {}", e));
+            return lines;
+        }
+    };
+
+    let kind = match signature.kind {
+        SignatureKind::Unsigned => "unsigned",
+        SignatureKind::AdHoc => "ad-hoc",
+        SignatureKind::Signed => "signed",
+    };
+
+    let optional = |v: &Option<String>| v.clone().unwrap_or("-".to_string());
+
+    lines.extend(vec![
+        format!(
+            "  Signature: {} ({})",
+            kind,
+            if signature.valid { "valid" } else { "invalid" }
+        ),
+        format!("  Identifier: {}", optional(&signature.identifier)),
+        format!("  Team ID: {}", optional(&signature.team_id)),
+        format!(
+            "  Signing identity: {}",
+            optional(&signature.signing_identity)
+        ),
+        format!("  Notarized: {}", yes_no(signature.notarized)),
+        format!("  Entitlements: {}", signature.entitlements.len()),
+    ]);
+
+    lines.extend(signature.entitlements.iter().map(|e| format!("    {}", e)));
+    lines
+}
+
+/// The schedule, then the next fire times like systemctl list-timers
+fn schedule_lines(schedule: &JobSchedule) -> Vec<String> {
+    let intervals = match schedule {
+        JobSchedule::Interval(seconds) => return vec![format!("  Every {}s after load", seconds)],
+        JobSchedule::Calendar(intervals) => intervals,
+    };
+
+    let now = LocalTime::now();
+    let mut lines: Vec<String> = intervals.iter().map(|i| format!("  {}", i)).collect();
+
+    lines.push(format!("  {:<22}LEFT", "NEXT"));
+    lines.extend(
+        schedule
+            .next_fire_times(&now, SCHEDULE_PREVIEW_COUNT)
+            .iter()
+            .map(|t| format!("  {:<22}{}", t.to_string(), now.until(t))),
+    );
+
+    lines
+}
+
+/// Why it last started, then what will start it next
+fn trigger_lines(detail: &JobDetail) -> Vec<String> {
+    let mut lines = vec![format!(
+        "  Last launched by: {}",
+        detail.blame.as_deref().unwrap_or("-")
+    )];
+
+    if detail.triggers.is_empty() {
+        lines.push("  Nothing declared, only started on request (e.g. kickstart)".to_string());
+    }
+
+    lines.extend(detail.triggers.iter().map(|t| format!("  {}", t)));
+    lines
+}
+
+/// Each path, whether it is there and when it last changed, e.g.
+/// "watch  Tue 2024-01-02 10:00  3h 5m ago  /etc/hosts"
+fn watched_path_lines(paths: &[WatchedPath]) -> Vec<String> {
+    let now = LocalTime::now();
+
+    paths
+        .iter()
+        .map(|p| {
+            let modified = match p.modified.map(LocalTime::from_epoch) {
+                Some(t) => format!(
+                    "{:<22}{:<10}",
+                    t.to_string(),
+                    format!("{} ago", t.until(&now))
+                ),
+                None => format!("{:<32}", "-"),
+            };
+
+            let note = match (p.problem(), p.kind, p.entries) {
+                (Some(problem), _, _) => format!(" ({})", problem),
+                (None, WatchKind::Queue, Some(0)) => " (empty)".to_string(),
+                (None, WatchKind::Queue, Some(n)) => format!(" ({} queued, kept running)", n),
+                _ => String::new(),
+            };
+
+            format!("  {:<7}{}{}{}", p.kind.to_string(), modified, p.path, note)
+        })
+        .collect()
+}
+
+/// The plist's limits, and launchd's for maxfiles/maxproc and anything
+/// the plist sets
+fn limit_lines(detail: &JobDetail) -> Vec<String> {
+    let mut names: Vec<&str> = vec!["maxfiles", "maxproc"];
+    names.extend(
+        detail
+            .limits
+            .iter()
+            .map(|(name, _)| *name)
+            .filter(|name| *name != "maxfiles" && *name != "maxproc"),
+    );
+
+    let mut lines = vec![format!("  {:<10}{:<24}{}", "NAME", "JOB", "LAUNCHD")];
+    lines.extend(names.iter().map(|name| {
+        let job = detail
+            .limits
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, l)| l.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        let launchd = detail
+            .launchd_limits
+            .get(*name)
+            .map(|l| l.to_string())
+            .unwrap_or_else(|| "-".to_string());
+
+        format!("  {:<10}{:<24}{}", name, job, launchd)
+    }));
+
+    lines
+}
+
+/// Newest first, e.g. "12:01:05  exited 1 (pid 123)"
+fn history_lines(history: &[EventLine]) -> Vec<String> {
+    history
+        .iter()
+        .rev()
+        .map(|EventLine { time, event }| {
+            let what = match event {
+                JobEvent::Loaded { domain, .. } => format!("loaded in {}", domain),
+                JobEvent::Unloaded { domain, .. } => format!("unloaded from {}", domain),
+                JobEvent::Started { pid, .. } => format!("started (pid {})", pid),
+                JobEvent::Exited {
+                    pid,
+                    status: Some(status),
+                    ..
+                } => format!("exited {} (pid {})", status, pid),
+                JobEvent::Exited { pid, .. } => format!("exited (pid {})", pid),
+                JobEvent::PidChanged { old_pid, pid, .. } => {
+                    format!("restarted (pid {} -> {})", old_pid, pid)
+                }
+            };
+
+            format!(
+                "  {}  {}",
+                clock(UNIX_EPOCH + Duration::from_secs(*time)),
+                what
+            )
+        })
+        .collect()
+}
+
+/// Present a JobDetail as sections
+fn detail_sections(detail: &JobDetail) -> Vec<DetailSection> {
+    let overview = [
+        ("Target", Some(&detail.target)),
+        ("State", detail.state.as_ref()),
+        ("PID", detail.pid.as_ref()),
+        ("Last exit", detail.last_exit_code.as_ref()),
+        ("Program", detail.program.as_ref()),
+        ("Plist", detail.plist_path.as_ref()),
+    ]
+    .iter()
+    .map(|(k, v)| format!("  {}: {}", k, v.map(|s| s.as_str()).unwrap_or("-")))
+    .collect();
+
+    let mut sections = vec![
+        ("Overview".to_string(), overview),
+        (
+            "Arguments".to_string(),
+            detail
+                .arguments
+                .iter()
+                .map(|a| format!("  {}", a))
+                .collect(),
+        ),
+        ("Environment".to_string(), key_values(&detail.environment)),
+        (
+            "Mach Services".to_string(),
+            detail
+                .mach_services
+                .iter()
+                .map(|m| format!("  {}", m))
+                .collect(),
+        ),
+    ];
+
+    sections.push(("Launch Triggers".to_string(), trigger_lines(detail)));
+
+    if let Some(schedule) = &detail.schedule {
+        sections.push(("Schedule".to_string(), schedule_lines(schedule)));
+    }
+
+    if !detail.watched_paths.is_empty() {
+        sections.push((
+            "Watched Paths".to_string(),
+            watched_path_lines(&detail.watched_paths),
+        ));
+    }
+
+    sections.push(("Resource Limits".to_string(), limit_lines(detail)));
+
+    if !detail.history.is_empty() {
+        sections.push(("History".to_string(), history_lines(&detail.history)));
+    }
+
+    if let Some(process) = &detail.process {
+        sections.push(("Process".to_string(), process_lines(process)));
+    }
+
+    if let Some(signature) = &detail.signature {
+        sections.push((
+            "Code Signature".to_string(),
+            signature_lines(detail.executable.as_ref(), signature),
+        ));
+    }
+
+    sections.push(("Properties".to_string(), key_values(&detail.section.values)));
+    sections
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use xpc_sys::objects::xpc_dictionary::XPCDictionary;
+    use xpc_sys::traits::query_builder::QueryBuilder;
+
+    use super::limit_lines;
+    use crate::launchd::job_detail::JobDetail;
+    use crate::launchd::limits::{Limit, UNLIMITED};
+    use crate::launchd::pipe::mock::MockPipe;
+    use crate::launchd::pipe::with_pipe;
+    use crate::launchd::print_parser::StateSection;
+    use crate::launchd::query::limits;
+
+    #[test]
+    fn limits_from_mock_pipe() {
+        let maxfiles = XPCDictionary::new()
+            .entry("soft", 256_u64)
+            .entry("hard", UNLIMITED);
+        let pipe = Rc::new(MockPipe::default().reply(
+            818,
+            XPCDictionary::new().entry("limits", XPCDictionary::new().entry("maxfiles", &maxfiles)),
+        ));
+
+        let mut detail = JobDetail::from(StateSection::default());
+        detail.launchd_limits = with_pipe(pipe, limits).unwrap();
+        detail.limits = vec![(
+            "maxproc",
+            Limit {
+                soft: Some(100),
+                hard: Some(200),
+            },
+        )];
+
+        let row =
+            |name: &str, job: &str, launchd: &str| format!("  {:<10}{:<24}{}", name, job, launchd);
+        assert_eq!(
+            limit_lines(&detail),
+            vec![
+                row("NAME", "JOB", "LAUNCHD"),
+                row("maxfiles", "-", "256/unlimited"),
+                row("maxproc", "100/200", "-"),
+            ]
+        );
+    }
+}
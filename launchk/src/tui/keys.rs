@@ -0,0 +1,53 @@
+use cursive::event::{Event, Key};
+
+use crate::config::bound_command;
+use crate::tui::omnibox::command::{OmniboxCommand, OMNIBOX_COMMANDS};
+
+fn key_name(key: Key) -> String {
+    match key {
+        Key::Ins => "Ins".to_string(),
+        Key::Del => "Del".to_string(),
+        k => format!("{:?}", k),
+    }
+}
+
+/// Inverse of parse_key, for help
+pub fn event_name(event: &Event) -> String {
+    match event {
+        Event::Char(c) => c.to_string(),
+        Event::CtrlChar(c) => format!("Ctrl-{}", c),
+        Event::AltChar(c) => format!("Alt-{}", c),
+        Event::Key(k) => key_name(*k),
+        Event::Ctrl(k) => format!("Ctrl-{}", key_name(*k)),
+        Event::Alt(k) => format!("Alt-{}", key_name(*k)),
+        Event::Shift(k) => format!("Shift-{}", key_name(*k)),
+        Event::CtrlAlt(k) => format!("Ctrl-Alt-{}", key_name(*k)),
+        Event::CtrlShift(k) => format!("Ctrl-Shift-{}", key_name(*k)),
+        Event::AltShift(k) => format!("Alt-Shift-{}", key_name(*k)),
+        e => format!("{:?}", e),
+    }
+}
+
+/// Omnibox command bound to a key
+pub fn hotkey_command(event: &Event) -> Option<OmniboxCommand> {
+    let name = bound_command(event)?;
+
+    OMNIBOX_COMMANDS
+        .iter()
+        .find(|(n, _, _)| *n == name)
+        .map(|(_, _, cmd)| cmd.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::event_name;
+    use cursive::event::{Event, Key};
+
+    #[test]
+    fn event_names() {
+        assert_eq!(event_name(&Event::Char('K')), "K");
+        assert_eq!(event_name(&Event::CtrlChar('k')), "Ctrl-k");
+        assert_eq!(event_name(&Event::Key(Key::Del)), "Del");
+        assert_eq!(event_name(&Event::CtrlShift(Key::F12)), "Ctrl-Shift-F12");
+    }
+}
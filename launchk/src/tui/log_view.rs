@@ -0,0 +1,602 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::os::unix::io::{AsRawFd, FromRawFd};
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::ptr;
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use cursive::event::{Event, EventResult, Key};
+use cursive::theme::{Effect, Style};
+use cursive::traits::{Nameable, Resizable};
+use cursive::utils::markup::StyledString;
+use cursive::view::{ScrollStrategy, ViewWrapper};
+use cursive::views::{Dialog, EditView, LinearLayout, Panel, ScrollView, TextContent, TextView};
+use cursive::{Cursive, View};
+
+use super::root::CbSinkMessage;
+use super::theme::roles;
+use crate::launchd::dry_run::DryRun;
+
+pub const LOG_VIEW: &str = "log_view";
+
+/// Lines kept in memory, older lines are dropped
+const MAX_LINES: usize = 5000;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum LogLevel {
+    Default,
+    Info,
+    Debug,
+    Error,
+    Fault,
+    /// Headers and anything else not in the compact format
+    Other,
+}
+
+impl LogLevel {
+    /// "Ty" column of `log stream --style compact`
+    fn from_compact(ty: &str) -> Self {
+        match ty {
+            "Df" => LogLevel::Default,
+            "I" => LogLevel::Info,
+            "Db" => LogLevel::Debug,
+            "E" => LogLevel::Error,
+            "F" => LogLevel::Fault,
+            _ => LogLevel::Other,
+        }
+    }
+
+    fn style(&self) -> Style {
+        match self {
+            LogLevel::Error => Style::from(roles().error),
+            LogLevel::Fault => Style::from(roles().error).combine(Effect::Bold),
+            LogLevel::Info => Style::from(roles().info),
+            LogLevel::Debug | LogLevel::Other => Style::from(Effect::Dim),
+            LogLevel::Default => Style::none(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct LogLine {
+    pub level: LogLevel,
+    pub text: String,
+}
+
+impl LogLine {
+    /// e.g. `2022-05-01 12:00:00.000 E  syslogd[113:1c2] message`
+    pub fn parse(text: String) -> Self {
+        let level = text
+            .split_whitespace()
+            .nth(2)
+            .map(LogLevel::from_compact)
+            .unwrap_or(LogLevel::Other);
+
+        Self { level, text }
+    }
+
+    /// A line from a job's own output, styled by which stream it is
+    pub fn output(text: String, stderr: bool) -> Self {
+        let level = if stderr {
+            LogLevel::Error
+        } else {
+            LogLevel::Default
+        };
+
+        Self { level, text }
+    }
+
+    fn styled(&self) -> StyledString {
+        StyledString::styled(format!("{}\n", self.text), self.level.style())
+    }
+}
+
+/// Match a running job by PID, otherwise by subsystem or program name
+/// so the stream picks up the job once it starts
+pub fn job_predicate(label: &str, pid: i64, program: Option<&str>) -> String {
+    if pid > 0 {
+        return format!("processIdentifier == {}", pid);
+    }
+
+    let quote = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+    let mut predicate = format!("subsystem == \"{}\"", quote(label));
+
+    if let Some(program) = program.and_then(|p| Path::new(p).file_name()) {
+        predicate.push_str(&format!(
+            " OR process == \"{}\"",
+            quote(&program.to_string_lossy())
+        ));
+    }
+
+    predicate
+}
+
+/// Program (or first of ProgramArguments) from a launchd plist
+pub fn plist_program<P: AsRef<Path>>(path: P) -> Option<String> {
+    let plist = plist::Value::from_file(path).ok()?;
+    let dict = plist.as_dictionary()?;
+
+    dict.get("Program")
+        .and_then(|p| p.as_string())
+        .or_else(|| {
+            dict.get("ProgramArguments")
+                .and_then(|a| a.as_array())
+                .and_then(|a| a.first())
+                .and_then(|p| p.as_string())
+        })
+        .map(|p| p.to_string())
+}
+
+/// StandardOutPath and StandardErrorPath from a launchd plist
+pub fn plist_output_paths<P: AsRef<Path>>(path: P) -> (Option<String>, Option<String>) {
+    let plist = match plist::Value::from_file(path) {
+        Ok(p) => p,
+        Err(_) => return (None, None),
+    };
+
+    let key = |k: &str| {
+        plist
+            .as_dictionary()
+            .and_then(|d| d.get(k))
+            .and_then(|p| p.as_string())
+            .map(|p| p.to_string())
+    };
+
+    (key("StandardOutPath"), key("StandardErrorPath"))
+}
+
+/// `log stream`, `tail` or dry run child, killed when the view is closed
+struct LogStream(Arc<Mutex<Child>>);
+
+impl Drop for LogStream {
+    fn drop(&mut self) {
+        if let Ok(mut child) = self.0.lock() {
+            child.kill().unwrap_or(());
+            child.wait().map(|_| ()).unwrap_or(());
+        }
+    }
+}
+
+/// Start `log stream` and show its output in a full screen layer
+pub fn show_log_stream(
+    cb_sink: &Sender<CbSinkMessage>,
+    title: String,
+    predicate: String,
+) -> Result<(), String> {
+    let mut command = Command::new("log");
+    command
+        .args(["stream", "--style", "compact", "--level", "debug"])
+        .arg("--predicate")
+        .arg(&predicate);
+
+    show_stream(
+        cb_sink,
+        "log stream",
+        command,
+        title,
+        predicate,
+        LogLine::parse,
+    )
+}
+
+/// Follow a job's StandardOutPath or StandardErrorPath with `tail -F`,
+/// which waits for the file to appear and follows it across rotation
+pub fn show_file_tail(
+    cb_sink: &Sender<CbSinkMessage>,
+    title: String,
+    path: String,
+    stderr: bool,
+) -> Result<(), String> {
+    let mut command = Command::new("tail");
+    command.args(["-F", "-n", "1000"]).arg(&path);
+
+    show_stream(cb_sink, "tail", command, title, path, move |line| {
+        LogLine::output(line, stderr)
+    })
+}
+
+/// (master, slave) of a new pseudo terminal
+fn open_pty() -> Result<(File, File), String> {
+    let (mut master, mut slave) = (0, 0);
+
+    let res = unsafe {
+        libc::openpty(
+            &mut master,
+            &mut slave,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+        )
+    };
+
+    if res != 0 {
+        return Err(format!("openpty: {}", io::Error::last_os_error()));
+    }
+
+    let (master, slave) = unsafe { (File::from_raw_fd(master), File::from_raw_fd(slave)) };
+
+    // Otherwise every child spawned meanwhile keeps the pty open, and
+    // the reader never sees EOF
+    for fd in [master.as_raw_fd(), slave.as_raw_fd()] {
+        if unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) } != 0 {
+            return Err(format!("fcntl: {}", io::Error::last_os_error()));
+        }
+    }
+
+    Ok((master, slave))
+}
+
+/// Run a job's program once, outside launchd, on a pseudo terminal so it
+/// sees a tty the way it would when run by hand
+pub fn show_dry_run(
+    cb_sink: &Sender<CbSinkMessage>,
+    title: String,
+    run: &DryRun,
+) -> Result<(), String> {
+    let (master, slave) = open_pty()?;
+    let stdio = |f: &File| f.try_clone().map(Stdio::from).map_err(|e| e.to_string());
+
+    let mut command = run.command();
+    command
+        .stdin(stdio(&slave)?)
+        .stdout(stdio(&slave)?)
+        .stderr(Stdio::from(slave));
+
+    // Own session with the pty as its controlling terminal
+    unsafe {
+        command.pre_exec(|| {
+            if libc::setsid() < 0 || libc::ioctl(0, libc::TIOCSCTTY as _, 0) < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+
+    let child = command
+        .spawn()
+        .map_err(|e| format!("Cannot start {}: {}", run.program, e))?;
+    // Drop our copies of the slave, so reads end when the job exits
+    drop(command);
+
+    let mut source = run.command_line();
+    if let Some(user) = &run.user {
+        source.push_str(&format!(" (as you, not {})", user));
+    }
+
+    let child = open_view(cb_sink, title, source, child);
+    follow(
+        cb_sink,
+        "dry run",
+        child,
+        master,
+        |line| LogLine::output(line.trim_end_matches('\r').to_string(), false),
+        |child| {
+            // Polled, closing the view kills the job while we wait
+            let status = loop {
+                match child.lock().map(|mut c| c.try_wait()) {
+                    Ok(Ok(Some(status))) => break Some(status),
+                    Ok(Ok(None)) => thread::sleep(Duration::from_millis(100)),
+                    _ => break None,
+                }
+            };
+
+            match status.map(|s| (s.code(), s.signal())) {
+                Some((Some(code), _)) => format!("exited with status {}", code),
+                Some((_, Some(signal))) => format!("killed by signal {}", signal),
+                _ => String::new(),
+            }
+        },
+    );
+
+    Ok(())
+}
+
+/// Run command, showing each line of its stdout as parsed
+fn show_stream<F>(
+    cb_sink: &Sender<CbSinkMessage>,
+    name: &'static str,
+    mut command: Command,
+    title: String,
+    source: String,
+    parse: F,
+) -> Result<(), String>
+where
+    F: Fn(String) -> LogLine + Send + Copy + 'static,
+{
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Cannot start {}: {}", name, e))?;
+
+    let stdout = child.stdout.take().expect("Must get log stdout");
+    let mut stderr = child.stderr.take().expect("Must get log stderr");
+
+    let child = open_view(cb_sink, title, source, child);
+
+    // Either closed by us, or log refused the predicate
+    follow(cb_sink, name, child, stdout, parse, move |_| {
+        let mut err = String::new();
+        stderr.read_to_string(&mut err).unwrap_or(0);
+        err.trim().to_string()
+    });
+
+    Ok(())
+}
+
+/// Full screen log view owning child, which is killed when it closes
+fn open_view(
+    cb_sink: &Sender<CbSinkMessage>,
+    title: String,
+    source: String,
+    child: Child,
+) -> Arc<Mutex<Child>> {
+    let child = Arc::new(Mutex::new(child));
+    let stream = LogStream(child.clone());
+
+    cb_sink
+        .send(Box::new(move |siv: &mut Cursive| {
+            siv.add_layer(
+                LogView::new(title, source, stream)
+                    .with_name(LOG_VIEW)
+                    .full_screen(),
+            );
+        }))
+        .expect("Must show log view");
+
+    child
+}
+
+/// Push each line of output to the log view, then why it ended
+fn follow<R, F, E>(
+    cb_sink: &Sender<CbSinkMessage>,
+    name: &'static str,
+    child: Arc<Mutex<Child>>,
+    output: R,
+    parse: F,
+    ended: E,
+) where
+    R: Read + Send + 'static,
+    F: Fn(String) -> LogLine + Send + Copy + 'static,
+    E: FnOnce(&Mutex<Child>) -> String + Send + 'static,
+{
+    let tx = cb_sink.clone();
+    thread::spawn(move || {
+        // A pty read fails with EIO once the job has exited
+        for line in BufReader::new(output).lines().map_while(Result::ok) {
+            let sent = tx.send(Box::new(move |siv: &mut Cursive| {
+                siv.call_on_name(LOG_VIEW, |v: &mut LogView| v.push(parse(line)));
+            }));
+
+            if sent.is_err() {
+                return;
+            }
+        }
+
+        let ended = format!("-- {} ended {}", name, ended(&child));
+
+        tx.send(Box::new(move |siv: &mut Cursive| {
+            siv.call_on_name(LOG_VIEW, |v: &mut LogView| {
+                v.push(LogLine {
+                    level: LogLevel::Error,
+                    text: ended,
+                })
+            });
+        }))
+        .unwrap_or(());
+    });
+}
+
+/// Scrolling tail of `log stream` or a file with pause and search
+pub struct LogView {
+    inner: Panel<LinearLayout>,
+    lines: VecDeque<LogLine>,
+    content: TextContent,
+    status: TextContent,
+    /// Predicate or file path, shown in the status line
+    source: String,
+    paused: bool,
+    /// log (or tail of an empty file) can take a while to print anything,
+    /// until then q aborts
+    started: bool,
+    /// Lines received while paused
+    pending: usize,
+    search: Option<String>,
+    _stream: LogStream,
+}
+
+impl LogView {
+    fn new(title: String, source: String, stream: LogStream) -> Self {
+        let content = TextContent::new("");
+        let status = TextContent::new("");
+
+        let layout = LinearLayout::vertical()
+            .child(
+                ScrollView::new(TextView::new_with_content(content.clone()))
+                    .scroll_strategy(ScrollStrategy::StickToBottom)
+                    .full_height(),
+            )
+            .child(TextView::new_with_content(status.clone()));
+
+        let mut view = Self {
+            inner: Panel::new(layout).title(title),
+            lines: VecDeque::new(),
+            content,
+            status,
+            source,
+            paused: false,
+            started: false,
+            pending: 0,
+            search: None,
+            _stream: stream,
+        };
+
+        view.update_status();
+        view
+    }
+
+    fn matches(&self, line: &LogLine) -> bool {
+        self.search
+            .as_ref()
+            .map(|s| line.text.to_lowercase().contains(s))
+            .unwrap_or(true)
+    }
+
+    pub fn push(&mut self, line: LogLine) {
+        self.lines.push_back(line);
+
+        if !self.started {
+            self.started = true;
+            self.update_status();
+        }
+
+        // Trim in batches to avoid re-rendering for every line
+        if self.lines.len() > MAX_LINES + MAX_LINES / 10 {
+            self.lines.drain(..self.lines.len() - MAX_LINES);
+
+            if !self.paused {
+                self.render();
+                return;
+            }
+        }
+
+        if self.paused {
+            self.pending += 1;
+            self.update_status();
+            return;
+        }
+
+        let line = self.lines.back().expect("Must have line");
+        if self.matches(line) {
+            self.content.append(line.styled());
+        }
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        self.pending = 0;
+
+        if !self.paused {
+            self.render();
+        }
+
+        self.update_status();
+    }
+
+    pub fn set_search(&mut self, search: &str) {
+        self.search = Some(search.trim().to_lowercase()).filter(|s| !s.is_empty());
+        self.render();
+        self.update_status();
+    }
+
+    fn render(&mut self) {
+        let mut styled = StyledString::new();
+
+        for line in self.lines.iter().filter(|l| self.matches(l)) {
+            styled.append(line.styled());
+        }
+
+        self.content.set_content(styled);
+    }
+
+    fn update_status(&mut self) {
+        if !self.started {
+            self.status.set_content(format!(
+                "Waiting for output...  [{}]  q: abort",
+                self.source
+            ));
+            return;
+        }
+
+        let state = if self.paused {
+            format!("PAUSED ({} new)", self.pending)
+        } else {
+            "LIVE".to_string()
+        };
+
+        let search = self
+            .search
+            .as_ref()
+            .map(|s| format!("  /{}", s))
+            .unwrap_or_default();
+
+        self.status.set_content(format!(
+            "{}{}  [{}]  p: pause  /: search  q: close",
+            state, search, self.source
+        ));
+    }
+}
+
+fn search_prompt(siv: &mut Cursive) {
+    let current = siv
+        .call_on_name(LOG_VIEW, |v: &mut LogView| v.search.clone())
+        .flatten()
+        .unwrap_or_default();
+
+    let submit = |s: &mut Cursive, text: &str| {
+        s.pop_layer();
+        s.call_on_name(LOG_VIEW, |v: &mut LogView| v.set_search(text));
+    };
+
+    let dialog = Dialog::around(
+        EditView::new()
+            .content(current)
+            .on_submit(submit)
+            .min_width(40),
+    )
+    .title("Search (empty to clear)")
+    .dismiss_button("Cancel");
+
+    siv.add_layer(dialog);
+}
+
+impl ViewWrapper for LogView {
+    wrap_impl!(self.inner: Panel<LinearLayout>);
+
+    fn wrap_on_event(&mut self, event: Event) -> EventResult {
+        match event {
+            Event::Char('p') | Event::Char(' ') => {
+                self.toggle_pause();
+                EventResult::Consumed(None)
+            }
+            Event::Char('/') => EventResult::with_cb(search_prompt),
+            Event::Char('q') | Event::Key(Key::Esc) => EventResult::with_cb(|s| {
+                s.pop_layer();
+            }),
+            _ => self.inner.on_event(event),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{job_predicate, LogLevel, LogLine};
+
+    #[test]
+    fn parse_compact_level() {
+        let line = LogLine::parse("2022-05-01 12:00:00.000 E  syslogd[113:1c2] oops".to_string());
+        assert_eq!(line.level, LogLevel::Error);
+
+        let header = LogLine::parse("Filtering the log data using \"x\"".to_string());
+        assert_eq!(header.level, LogLevel::Other);
+    }
+
+    #[test]
+    fn predicate_for_job() {
+        assert_eq!(
+            job_predicate("com.example", 42, None),
+            "processIdentifier == 42"
+        );
+        assert_eq!(
+            job_predicate("com.example", 0, Some("/usr/libexec/exampled")),
+            "subsystem == \"com.example\" OR process == \"exampled\""
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use std::sync::mpsc::Sender;
+
+use cursive::traits::{Resizable, Scrollable};
+use cursive::views::{Dialog, SelectView};
+
+use crate::launchd::login_items::LoginItem;
+use crate::launchd::plist::for_label;
+use crate::launchd::query::print_disabled;
+use crate::tui::dialog::show_error;
+use crate::tui::omnibox::command::OmniboxCommand;
+use crate::tui::omnibox::view::OmniboxEvent;
+use crate::tui::root::CbSinkMessage;
+
+fn row(item: &LoginItem, items: &[LoginItem]) -> String {
+    let state = match (item.enabled, item.allowed) {
+        (true, true) => "✔",
+        (_, false) => "⊘ not allowed",
+        (false, true) => "⊘",
+    };
+
+    // Embedded plists aren't in the directories launchk watches
+    let source = item
+        .label()
+        .and_then(for_label)
+        .map(|p| p.plist_path)
+        .or_else(|| item.source_app(items))
+        .or_else(|| item.url.clone())
+        .unwrap_or_default();
+
+    format!(
+        "{} {} ({})  {}  {}",
+        state,
+        item.name,
+        item.kind,
+        item.developer.as_deref().unwrap_or("-"),
+        source
+    )
+}
+
+/// BTM records, Enter enables or disables agents and daemons (launchctl
+/// enable/disable). Apps and login items can only be changed in System
+/// Settings
+pub fn show_login_items(tx: Sender<OmniboxEvent>, items: Vec<LoginItem>) -> CbSinkMessage {
+    Box::new(move |siv| {
+        let mut select = SelectView::new();
+
+        for item in &items {
+            select.add_item(row(item, &items), item.clone());
+        }
+
+        let count = items.len();
+        select.set_on_submit(move |s, item: &LoginItem| {
+            let (label, target) = match (item.label(), item.domain_target()) {
+                (Some(label), Some(target)) => (label.to_string(), target),
+                _ => {
+                    show_error(format!(
+                        "{} is a {}, change it in System Settings > General > Login Items",
+                        item.name, item.kind
+                    ))(s);
+                    return;
+                }
+            };
+
+            // launchctl enable/disable flip launchd's override, which BTM
+            // may not agree with
+            let disabled = print_disabled(&target)
+                .ok()
+                .and_then(|o| o.get(&label).copied())
+                .unwrap_or(!item.enabled);
+
+            s.pop_layer();
+            tx.send(OmniboxEvent::Command(OmniboxCommand::SetLoginItemEnabled(
+                label, target, disabled,
+            )))
+            .expect("Must send command");
+        });
+
+        siv.add_layer(
+            Dialog::around(select.scrollable())
+                .title(format!("Login items: {}", count))
+                .dismiss_button("Close")
+                .max_height(40),
+        );
+    })
+}
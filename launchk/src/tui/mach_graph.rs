@@ -0,0 +1,90 @@
+use cursive::theme::Effect;
+use cursive::traits::{Resizable, Scrollable};
+use cursive::views::{Dialog, DummyView, LinearLayout, TextView};
+
+use crate::launchd::mach_graph::{MachServiceLinks, ServiceOwner};
+use crate::tui::root::CbSinkMessage;
+
+/// Service names with the jobs on the other end, indented below
+fn service_lines(links: &[(String, Vec<String>)], none: &str) -> String {
+    if links.is_empty() {
+        return format!("  {}", none);
+    }
+
+    links
+        .iter()
+        .flat_map(|(service, jobs)| {
+            let mut lines = vec![format!("  {}", service)];
+
+            if jobs.is_empty() {
+                lines.push("      (none found)".to_string());
+            }
+
+            lines.extend(jobs.iter().map(|j| format!("      {}", j)));
+            lines
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Provides/consumes MachServices for a job
+pub fn show_mach_graph(label: String, links: MachServiceLinks) -> CbSinkMessage {
+    Box::new(move |siv| {
+        let layout = LinearLayout::vertical()
+            .child(TextView::new("Provides (consumed by)").style(Effect::Bold))
+            .child(TextView::new(service_lines(
+                &links.provides,
+                "No MachServices",
+            )))
+            .child(DummyView)
+            .child(TextView::new("Consumes (provided by)").style(Effect::Bold))
+            .child(TextView::new(service_lines(
+                &links.consumes,
+                "No endpoints to other jobs",
+            )));
+
+        siv.add_layer(
+            Dialog::around(layout.scrollable())
+                .title(format!("Mach services: {}", label))
+                .dismiss_button("OK")
+                .max_height(40),
+        );
+    })
+}
+
+/// Jobs hosting a Mach service, with program and code signing if running
+pub fn show_whohas(service: String, owners: Vec<ServiceOwner>) -> CbSinkMessage {
+    Box::new(move |siv| {
+        let mut layout = LinearLayout::vertical();
+
+        for owner in &owners {
+            let mut lines = vec![if owner.pid > 0 {
+                format!("  PID: {}", owner.pid)
+            } else {
+                "  Not running".to_string()
+            }];
+
+            if let Some(process) = &owner.process {
+                lines.push(format!(
+                    "  Program: {}",
+                    process.program_path.as_deref().unwrap_or("-")
+                ));
+                lines.push(format!(
+                    "  Code signing: {}",
+                    process.code_signing.as_deref().unwrap_or("-")
+                ));
+            }
+
+            layout.add_child(TextView::new(owner.label.clone()).style(Effect::Bold));
+            layout.add_child(TextView::new(lines.join("\n")));
+            layout.add_child(DummyView);
+        }
+
+        siv.add_layer(
+            Dialog::around(layout.scrollable())
+                .title(format!("Who has {}", service))
+                .dismiss_button("OK")
+                .max_height(40),
+        );
+    })
+}
@@ -1,7 +1,24 @@
+mod app_logs;
+mod audit;
 mod dialog;
+mod job_detail;
+mod keys;
+mod log_view;
+mod login_items;
+mod mach_graph;
 mod omnibox;
 mod pager;
+mod pid_domain;
+mod plist_diff;
+mod plist_view;
+mod process_tree;
 pub mod root;
 mod service_list;
+mod snapshot;
+mod state_pager;
 mod sysinfo;
 mod table;
+mod theme;
+pub mod tour;
+pub mod ui_state;
+mod worker;
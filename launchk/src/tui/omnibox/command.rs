@@ -1,24 +1,101 @@
 use std::fmt;
 use xpc_sys::enums::{DomainType, SessionType};
 
+use crate::config::columns::ColumnLayout;
+use crate::launchd::domain_target::DomainTarget;
+use crate::launchd::new_job::NewJob;
+use crate::launchd::query::DebugOptions;
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum OmniboxCommand {
     Chain(Vec<OmniboxCommand>),
     // (message, on ok)
     Confirm(String, Vec<OmniboxCommand>),
+    // (message, text to type, on match)
+    TypedConfirm(String, String, Vec<OmniboxCommand>),
+    // Allow the next unload, disable or kill of a protected label
+    Unprotect(String),
     // Try to see if we have session type & domain in entry_status,
     // to avoid having to prompt the user
     LoadRequest,
     UnloadRequest,
     EnableRequest,
     DisableRequest,
+    KickstartRequest,
+    KillRequest,
+    StartRequest,
+    // Load a not loaded job into its usual domain, no prompt
+    QuickLoad,
+    StopRequest,
     Load(SessionType, DomainType, Option<u64>),
     Unload(DomainType, Option<u64>),
+    // Domain target typed after load/unload/enable/disable, e.g. gui/501
+    LoadTarget(String),
+    UnloadTarget(String),
+    EnableTarget(String),
+    DisableTarget(String),
     // Reuses domain, handle, limit load to session type from existing
     Reload,
-    Enable(DomainType),
-    Disable(DomainType),
+    // (domain, handle)
+    Enable(DomainType, Option<u64>),
+    Disable(DomainType, Option<u64>),
+    // (domain, kill running instance first?)
+    Kickstart(DomainType, bool),
+    // (domain, signal)
+    Kill(DomainType, i32),
+    Start(DomainType),
+    Stop(DomainType),
+    // launchctl remove highlighted job
+    RemoveRequest,
+    Remove,
+    // ([label, program, args...]) launchctl submit
+    Submit(Vec<String>),
+    // (domain, command to send with chosen signal)
+    SignalPrompt(DomainType, fn(DomainType, i32) -> OmniboxCommand),
+    // One-shot debug launch: ask for domain, then options, then apply
+    DebugRequest,
+    DebugPrompt(DomainType),
+    Debug(DomainType, Box<DebugOptions>),
+    // Tail unified log for highlighted job
+    LogStream,
+    // Follow the highlighted job's StandardOutPath, or StandardErrorPath
+    // if true
+    TailOutput(bool),
+    // MachServices the highlighted job provides and consumes
+    MachGraph,
+    // Loaded config of highlighted job against its plist on disk
+    PlistDiff,
+    // ([Mach service name]) job hosting it
+    WhoHas(Vec<String>),
+    // Show or hide detail pane
+    ToggleJobDetail,
+    JobDetailRequest,
+    // Read-only, highlighted plist
+    ViewPlist,
+    // Copy highlighted job's label, plist path or procinfo to the clipboard
+    CopyLabel,
+    CopyPath,
+    CopyProcInfo,
+    // launchctl command line(s) for the last thing launchk changed
+    CopyLaunchctl,
+    // Wizard for a new agent or daemon plist
+    NewJobPrompt,
+    // (answers, bootstrap after writing?)
+    CreateJob(Box<NewJob>, bool),
     Edit,
+    // (backup path) unload and load the edited job, putting the backup
+    // back and loading that if the new plist won't load
+    ApplyEdit(String),
+    // (backup path) put it back over the highlighted job's plist
+    RestoreBackupRequest(String),
+    RestoreBackup(String),
+    // Summarize action for marked jobs and ask before running it
+    BatchConfirm(BatchAction),
+    Batch(BatchAction),
+    // Clear marked jobs
+    Unmark,
+    // Reverse the last load, unload, enable or disable
+    Undo,
     // (unit label, prompt for domain only?, action gen fn)
     DomainSessionPrompt(
         String,
@@ -26,68 +103,500 @@ pub enum OmniboxCommand {
         fn(DomainType, Option<SessionType>) -> Vec<OmniboxCommand>,
     ),
     FocusServiceList,
+    // Arguments typed after the command, e.g. ["json", "jobs.json"]
+    Export(Vec<String>),
+    // ([path]), otherwise the highlighted job's plist
+    Lint(Vec<String>),
+    // ([path]) run the program once outside launchd, otherwise the
+    // highlighted job's
+    DryRun(Vec<String>),
+    // ([path])
+    Snapshot(Vec<String>),
+    // ([old path, new path?])
+    SnapshotDiff(Vec<String>),
+    // ([save|apply, path]) print-disabled of each domain
+    Overrides(Vec<String>),
+    // (path) enable/disable to match it, after confirming
+    ApplyOverrides(String),
+    // (domain, [key, value])
+    SetEnv(DomainTarget, Vec<String>),
+    // (domain, [key])
+    UnsetEnv(DomainTarget, Vec<String>),
+    GetEnv(DomainTarget, Vec<String>),
+    // Edit or add (no key) a domain variable
+    EnvPrompt(DomainTarget, Option<String>),
+    // Re-read config.toml
+    ReloadConfig,
+    // ([color scheme])
+    Theme(Vec<String>),
+    // Pick service list columns, starting from the current ones
+    ColumnsRequest,
+    ColumnPicker(Vec<ColumnLayout>),
+    SetColumns(Vec<ColumnLayout>),
+    // (name) filter, sort and columns from a [[profiles]] entry
+    Profile(Vec<String>),
+    // Pick a logged in user to scope the list to (root only)
+    UserScopeRequest,
+    // None for all users
+    UserScope(Option<u32>),
+    // ([domain target|ask]) where load, unload, enable and disable go,
+    // no args picks from a list
+    Target(Vec<String>),
+    // None asks for a domain each time
+    SetDomainTarget(Option<DomainTarget>),
     CSRInfo,
+    // launchd version, manager and boot time
+    About,
+    // Domains the job list could not include, and why
+    DomainErrors,
+    // (path) .ips or .crash file, in the pager
+    CrashReport(String),
+    // Flag suspicious persistence outside /System
+    Audit,
+    // Non-Apple agents, to disable or enable all at once
+    ThirdParty,
+    // (labels, enable?) in the session's domain, saving a manifest to undo
+    SetThirdPartyEnabled(Vec<String>, bool),
+    // ([name, soft, hard?]) launchctl limit, no args prompts for
+    // maxfiles and maxproc
+    Limit(Vec<String>),
+    // Background Task Management login items and embedded agents
+    LoginItems,
+    // (label, domain, enable?) for a login item's agent or daemon
+    SetLoginItemEnabled(String, DomainTarget, bool),
+    // Running jobs and the processes they spawned
+    ProcessTree,
+    // (pid, signal) for a job's child process
+    KillPid(i32, i32),
     DumpState,
+    // Re-read every plist and dumpstate, with progress
+    Refresh,
+    // Words of a dictionary to send to launchd as is
+    Xpc(Vec<String>),
+    // ([level]) launchk's own log
+    Logs(Vec<String>),
+    // (path) write dumpstate there instead of paging it
+    DumpStateToFile(String),
+    // ([domain or service target])
+    PrintDomain(Vec<String>),
+    // ([pid]) XPC services in pid/<pid>, the highlighted job's by default
+    PidDomain(Vec<String>),
+    PrintService(DomainTarget, String),
+    // Run lldb against a pid until it exits
+    AttachDebugger(i64),
     DumpJetsamPropertiesCategory,
+    // ([pid]) launchctl print-cache, launchk's own by default
+    PrintCache(Vec<String>),
+    // (service name, [pid]) launchctl uncache
+    Uncache(Vec<String>),
     ProcInfo,
     Sudo,
     Help,
+    // First-run walkthrough, again
+    Tour,
     Quit,
 }
 
+/// Action applied to every marked job. Where a job's domain is known it is
+/// used for Unload and Kill, the domain here is only a fallback.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum BatchAction {
+    Load(SessionType, DomainType),
+    Unload(DomainType),
+    Enable(DomainType),
+    Disable(DomainType),
+    Kill(DomainType, i32),
+}
+
+impl fmt::Display for BatchAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BatchAction::Load(_, _) => write!(f, "Load"),
+            BatchAction::Unload(_) => write!(f, "Unload"),
+            BatchAction::Enable(_) => write!(f, "Enable"),
+            BatchAction::Disable(_) => write!(f, "Disable"),
+            BatchAction::Kill(_, signal) => write!(f, "Send signal {} to", signal),
+        }
+    }
+}
+
+impl OmniboxCommand {
+    /// Attach arguments typed after the command name
+    pub fn with_args(self, args: &[&str]) -> OmniboxCommand {
+        match self {
+            OmniboxCommand::LoadRequest if !args.is_empty() => {
+                OmniboxCommand::LoadTarget(args.join(" "))
+            }
+            OmniboxCommand::UnloadRequest if !args.is_empty() => {
+                OmniboxCommand::UnloadTarget(args.join(" "))
+            }
+            OmniboxCommand::EnableRequest if !args.is_empty() => {
+                OmniboxCommand::EnableTarget(args.join(" "))
+            }
+            OmniboxCommand::DisableRequest if !args.is_empty() => {
+                OmniboxCommand::DisableTarget(args.join(" "))
+            }
+            OmniboxCommand::DumpState if !args.is_empty() => {
+                OmniboxCommand::DumpStateToFile(args.join(" "))
+            }
+            OmniboxCommand::Export(_) => OmniboxCommand::Export(owned(args)),
+            OmniboxCommand::Lint(_) => OmniboxCommand::Lint(owned(args)),
+            OmniboxCommand::DryRun(_) => OmniboxCommand::DryRun(owned(args)),
+            OmniboxCommand::Xpc(_) => OmniboxCommand::Xpc(owned(args)),
+            OmniboxCommand::Logs(_) => OmniboxCommand::Logs(owned(args)),
+            OmniboxCommand::Target(_) => OmniboxCommand::Target(owned(args)),
+            OmniboxCommand::Profile(_) => OmniboxCommand::Profile(owned(args)),
+            OmniboxCommand::PrintCache(_) => OmniboxCommand::PrintCache(owned(args)),
+            OmniboxCommand::Uncache(_) => OmniboxCommand::Uncache(owned(args)),
+            OmniboxCommand::WhoHas(_) => OmniboxCommand::WhoHas(owned(args)),
+            OmniboxCommand::Submit(_) => OmniboxCommand::Submit(owned(args)),
+            OmniboxCommand::PrintDomain(_) => OmniboxCommand::PrintDomain(owned(args)),
+            OmniboxCommand::PidDomain(_) => OmniboxCommand::PidDomain(owned(args)),
+            OmniboxCommand::Theme(_) => OmniboxCommand::Theme(owned(args)),
+            OmniboxCommand::Limit(_) => OmniboxCommand::Limit(owned(args)),
+            OmniboxCommand::Snapshot(_) => OmniboxCommand::Snapshot(owned(args)),
+            OmniboxCommand::SnapshotDiff(_) => OmniboxCommand::SnapshotDiff(owned(args)),
+            OmniboxCommand::Overrides(_) => OmniboxCommand::Overrides(owned(args)),
+            OmniboxCommand::SetEnv(t, _) => OmniboxCommand::SetEnv(t, owned(args)),
+            OmniboxCommand::UnsetEnv(t, _) => OmniboxCommand::UnsetEnv(t, owned(args)),
+            OmniboxCommand::GetEnv(t, _) => OmniboxCommand::GetEnv(t, owned(args)),
+            cmd => cmd,
+        }
+    }
+}
+
+fn owned(args: &[&str]) -> Vec<String> {
+    args.iter().map(|a| a.to_string()).collect()
+}
+
+/// launchctl setenv etc. default to the requestor's domain
+const REQUESTOR_DOMAIN: DomainTarget = DomainTarget {
+    domain_type: DomainType::RequestorDomain,
+    handle: 0,
+};
+
 impl fmt::Display for OmniboxCommand {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", format!("{:?}", self).to_ascii_lowercase())
     }
 }
 
-pub static OMNIBOX_COMMANDS: [(&str, &str, OmniboxCommand); 12] = [
+pub static OMNIBOX_COMMANDS: [(&str, &str, OmniboxCommand); 65] = [
     (
         "load",
-        "▶️  Load highlighted job",
+        "▶️  Load highlighted job: load [gui/<uid>|user/<uid>|pid/<pid>|session/<asid>] [session type]",
         OmniboxCommand::LoadRequest,
     ),
     (
         "unload",
-        "⏏️  Unload highlighted job",
+        "⏏️  Unload highlighted job: unload [domain target]",
         OmniboxCommand::UnloadRequest,
     ),
     (
         "enable",
-        "▶️  Enable highlighted job (enables load)",
+        "▶️  Enable highlighted job (enables load): enable [domain target]",
         OmniboxCommand::EnableRequest,
     ),
     (
         "disable",
-        "⏏️  Disable highlighted job (prevents load)",
+        "⏏️  Disable highlighted job (prevents load): disable [domain target]",
         OmniboxCommand::DisableRequest,
     ),
+    (
+        "view",
+        "👀  View plist of highlighted job",
+        OmniboxCommand::ViewPlist,
+    ),
+    (
+        "copylabel",
+        "📋  Copy label of highlighted job",
+        OmniboxCommand::CopyLabel,
+    ),
+    (
+        "copypath",
+        "📋  Copy plist path of highlighted job",
+        OmniboxCommand::CopyPath,
+    ),
+    (
+        "copycmd",
+        "📋  Copy launchctl command for the last load, unload, enable, kickstart...",
+        OmniboxCommand::CopyLaunchctl,
+    ),
+    (
+        "new",
+        "✨  Create a new agent or daemon plist",
+        OmniboxCommand::NewJobPrompt,
+    ),
     (
         "edit",
-        "✍️  Edit plist with $EDITOR then reload job",
+        "✍️  Edit plist with $EDITOR then apply to the loaded job",
         OmniboxCommand::Edit,
     ),
     (
         "reload",
-        "🔄  Reload highlighted job",
+        "🔄  Reload highlighted job from disk",
         OmniboxCommand::Reload,
     ),
+    (
+        "kickstart",
+        "🔄  Kill and restart highlighted job",
+        OmniboxCommand::KickstartRequest,
+    ),
+    (
+        "quickload",
+        "▶️  Load highlighted orphaned job into gui/<uid>, or system for daemons",
+        OmniboxCommand::QuickLoad,
+    ),
+    (
+        "start",
+        "▶️  Start highlighted job",
+        OmniboxCommand::StartRequest,
+    ),
+    (
+        "stop",
+        "⏹️  Stop highlighted job, leaving it loaded",
+        OmniboxCommand::StopRequest,
+    ),
+    (
+        "remove",
+        "🗑️  launchctl remove highlighted job, e.g. one made with submit",
+        OmniboxCommand::RemoveRequest,
+    ),
+    (
+        "submit",
+        "🚀  Run a command as a job without a plist: submit <label> <program> [args]",
+        OmniboxCommand::Submit(Vec::new()),
+    ),
+    (
+        "kill",
+        "☠️  Send signal to highlighted job",
+        OmniboxCommand::KillRequest,
+    ),
+    (
+        "debug",
+        "🐛  Next launch of highlighted job with another program, stdio or env",
+        OmniboxCommand::DebugRequest,
+    ),
+    (
+        "log",
+        "📜  Stream unified log for highlighted job",
+        OmniboxCommand::LogStream,
+    ),
+    (
+        "stdout",
+        "📜  Follow highlighted job's StandardOutPath",
+        OmniboxCommand::TailOutput(false),
+    ),
+    (
+        "stderr",
+        "📜  Follow highlighted job's StandardErrorPath",
+        OmniboxCommand::TailOutput(true),
+    ),
+    (
+        "graph",
+        "🕸️  MachServices provided and consumed by highlighted job",
+        OmniboxCommand::MachGraph,
+    ),
+    (
+        "plistdiff",
+        "🆚  Compare loaded config of highlighted job with its plist",
+        OmniboxCommand::PlistDiff,
+    ),
+    (
+        "whohas",
+        "🔎  Job hosting a Mach service: whohas <service name>",
+        OmniboxCommand::WhoHas(Vec::new()),
+    ),
+    (
+        "info",
+        "ℹ️  Toggle detail pane for highlighted job",
+        OmniboxCommand::ToggleJobDetail,
+    ),
+    (
+        "export",
+        "💾  Export filtered jobs: export json <path>",
+        OmniboxCommand::Export(Vec::new()),
+    ),
+    (
+        "lint",
+        "🧹  Check a plist for problems: lint [path], or the highlighted job",
+        OmniboxCommand::Lint(Vec::new()),
+    ),
+    (
+        "dryrun",
+        "🧪  Run a plist's program once outside launchd, with its environment: dryrun [path]",
+        OmniboxCommand::DryRun(Vec::new()),
+    ),
+    (
+        "snapshot",
+        "📸  Save all jobs and their state: snapshot <path>",
+        OmniboxCommand::Snapshot(Vec::new()),
+    ),
+    (
+        "diff",
+        "🔍  Compare snapshots, or a snapshot with now: diff <old> [new]",
+        OmniboxCommand::SnapshotDiff(Vec::new()),
+    ),
+    (
+        "overrides",
+        "🚦  Save or re-apply disabled jobs per domain: overrides <save|apply> <path>",
+        OmniboxCommand::Overrides(Vec::new()),
+    ),
+    (
+        "setenv",
+        "🌱  Set variable in your domain: setenv <key> <value>",
+        OmniboxCommand::SetEnv(REQUESTOR_DOMAIN, Vec::new()),
+    ),
+    (
+        "unsetenv",
+        "🍂  Remove variable from your domain: unsetenv <key>",
+        OmniboxCommand::UnsetEnv(REQUESTOR_DOMAIN, Vec::new()),
+    ),
+    (
+        "getenv",
+        "ℹ️  Show variable in your domain: getenv <key>",
+        OmniboxCommand::GetEnv(REQUESTOR_DOMAIN, Vec::new()),
+    ),
+    (
+        "unmark",
+        "🧹  Clear marked jobs (space marks)",
+        OmniboxCommand::Unmark,
+    ),
+    (
+        "undo",
+        "↩️  Undo last load, unload, enable or disable",
+        OmniboxCommand::Undo,
+    ),
+    (
+        "user",
+        "👤  Show one logged in user's agents (root)",
+        OmniboxCommand::UserScopeRequest,
+    ),
+    (
+        "target",
+        "🎯  Domain for load, unload, enable and disable: target [system|gui/501|ask]",
+        OmniboxCommand::Target(Vec::new()),
+    ),
+    (
+        "profile",
+        "🗂️  Switch view profile from config (or 1-9): profile <name>",
+        OmniboxCommand::Profile(Vec::new()),
+    ),
+    (
+        "config",
+        "🔧  Reload ~/.config/launchk/config.toml",
+        OmniboxCommand::ReloadConfig,
+    ),
+    (
+        "theme",
+        "🎨  Switch color scheme: theme dark|light|solarized",
+        OmniboxCommand::Theme(Vec::new()),
+    ),
+    (
+        "columns",
+        "🏛️  Choose and order service list columns",
+        OmniboxCommand::ColumnsRequest,
+    ),
     ("csrinfo", "ℹ️  See all CSR flags", OmniboxCommand::CSRInfo),
+    (
+        "audit",
+        "🕵️  Flag suspicious persistence: writable, unsigned, Apple-like or hidden jobs",
+        OmniboxCommand::Audit,
+    ),
+    (
+        "thirdparty",
+        "🧩  Disable or enable every non-Apple agent at once, saving a manifest to undo",
+        OmniboxCommand::ThirdParty,
+    ),
+    (
+        "limit",
+        "📏  launchd resource limits: limit [maxfiles|maxproc|... <soft> [hard]]",
+        OmniboxCommand::Limit(vec![]),
+    ),
+    (
+        "loginitems",
+        "🔑  Login items and app-embedded agents (macOS 13+, root)",
+        OmniboxCommand::LoginItems,
+    ),
+    (
+        "tree",
+        "🌳  Running jobs and the helper processes they spawned",
+        OmniboxCommand::ProcessTree,
+    ),
+    (
+        "domainerrors",
+        "⚠️  Domains missing from the job list, and why",
+        OmniboxCommand::DomainErrors,
+    ),
+    (
+        "about",
+        "ℹ️  launchd version, manager and boot time",
+        OmniboxCommand::About,
+    ),
     (
         "dumpstate",
-        "ℹ️  launchctl dumpstate",
+        "ℹ️  launchctl dumpstate, or save it: dumpstate [path]",
         OmniboxCommand::DumpState,
     ),
+    (
+        "refresh",
+        "🔄  Re-read every plist and job state from launchd",
+        OmniboxCommand::Refresh,
+    ),
+    (
+        "xpc",
+        "🧪  Send launchd a raw request: xpc {subsystem: 3, routine: 815, ...}",
+        OmniboxCommand::Xpc(Vec::new()),
+    ),
+    (
+        "logs",
+        "📜  launchk's own log: logs [error|warn|info|debug]",
+        OmniboxCommand::Logs(Vec::new()),
+    ),
+    (
+        "print",
+        "ℹ️  launchctl print for a domain or service: print <domain target>[/<label>]",
+        OmniboxCommand::PrintDomain(Vec::new()),
+    ),
+    (
+        "piddomain",
+        "🧩  XPC services in a process's domain: piddomain [pid]",
+        OmniboxCommand::PidDomain(Vec::new()),
+    ),
     (
         "dumpjpcategory",
         "ℹ️  launchctl dumpjpcategory",
         OmniboxCommand::DumpJetsamPropertiesCategory,
     ),
+    (
+        "printcache",
+        "ℹ️  launchctl print-cache, XPC service cache: printcache [pid]",
+        OmniboxCommand::PrintCache(Vec::new()),
+    ),
+    (
+        "uncache",
+        "🧹  Bypass the XPC service cache: uncache <service name> [pid]",
+        OmniboxCommand::Uncache(Vec::new()),
+    ),
     (
         "procinfo",
         "ℹ️  launchctl procinfo for highlighted process",
         OmniboxCommand::ProcInfo,
     ),
-    ("help", "🤔  Show all commands", OmniboxCommand::Help),
+    (
+        "copyprocinfo",
+        "📋  Copy procinfo for highlighted process",
+        OmniboxCommand::CopyProcInfo,
+    ),
+    (
+        "help",
+        "🤔  Show all keys and commands",
+        OmniboxCommand::Help,
+    ),
+    (
+        "tour",
+        "🧭  Walk through filters, the omnibox and common commands",
+        OmniboxCommand::Tour,
+    ),
     ("exit", "🚪  see ya!", OmniboxCommand::Quit),
 ];
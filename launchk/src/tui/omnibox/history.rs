@@ -0,0 +1,174 @@
+use std::fs;
+use std::path::PathBuf;
+
+use crate::config::config_path;
+
+/// Oldest entries are dropped past this
+const MAX_ENTRIES: usize = 500;
+
+/// How the text typed before browsing picks entries
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Browse {
+    /// Up and down arrows
+    Prefix,
+    /// Ctrl-R
+    Contains,
+}
+
+/// Submitted omnibox commands, oldest first, saved next to config.toml
+pub struct History {
+    entries: Vec<String>,
+    path: Option<PathBuf>,
+    /// (index of recalled entry, text typed before browsing, how to match)
+    browsing: Option<(usize, String, Browse)>,
+}
+
+impl History {
+    pub fn new(entries: Vec<String>, path: Option<PathBuf>) -> Self {
+        Self {
+            entries,
+            path,
+            browsing: None,
+        }
+    }
+
+    /// Read from the config dir, missing or unreadable means empty
+    pub fn load() -> Self {
+        let path = config_path().with_file_name("history");
+        let entries = fs::read_to_string(&path)
+            .map(|h| h.lines().map(String::from).collect())
+            .unwrap_or_default();
+
+        let mut history = Self::new(entries, Some(path));
+        history.trim();
+        history
+    }
+
+    fn trim(&mut self) {
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.drain(..self.entries.len() - MAX_ENTRIES);
+        }
+    }
+
+    fn save(&self) {
+        let path = match &self.path {
+            Some(p) => p,
+            None => return,
+        };
+
+        let mut text = self.entries.join("\n");
+        text.push('\n');
+
+        if let Err(e) = path
+            .parent()
+            .map(fs::create_dir_all)
+            .unwrap_or(Ok(()))
+            .and_then(|_| fs::write(path, text))
+        {
+            log::error!("[history/save]: {:?} {}", path, e);
+        }
+    }
+
+    /// Record a submitted command, skipping a repeat of the last one
+    pub fn push(&mut self, entry: &str) {
+        self.browsing = None;
+        let entry = entry.trim();
+
+        if entry.is_empty() || self.entries.last().map(String::as_str) == Some(entry) {
+            return;
+        }
+
+        self.entries.push(entry.to_string());
+        self.trim();
+        self.save();
+    }
+
+    /// Typing something else ends browsing
+    pub fn reset(&mut self) {
+        self.browsing = None;
+    }
+
+    fn matches(entry: &str, typed: &str, browse: Browse) -> bool {
+        match browse {
+            Browse::Prefix => entry.starts_with(typed),
+            Browse::Contains => entry.contains(typed),
+        }
+    }
+
+    fn step_back(&mut self, typed: &str, browse: Browse) -> Option<String> {
+        let (from, typed) = match &self.browsing {
+            Some((i, typed, _)) => (*i, typed.clone()),
+            None => (self.entries.len(), typed.to_string()),
+        };
+
+        let found = self.entries[..from]
+            .iter()
+            .rposition(|e| Self::matches(e, &typed, browse))?;
+
+        let entry = self.entries[found].clone();
+        self.browsing = Some((found, typed, browse));
+        Some(entry)
+    }
+
+    /// Up: older entry starting with what was typed
+    pub fn older(&mut self, typed: &str) -> Option<String> {
+        self.step_back(typed, Browse::Prefix)
+    }
+
+    /// Ctrl-R: older entry containing what was typed
+    pub fn search(&mut self, typed: &str) -> Option<String> {
+        self.step_back(typed, Browse::Contains)
+    }
+
+    /// Down: newer matching entry, then back to what was typed
+    pub fn newer(&mut self) -> Option<String> {
+        let (from, typed, browse) = self.browsing.take()?;
+
+        match self.entries[from + 1..]
+            .iter()
+            .position(|e| Self::matches(e, &typed, browse))
+        {
+            Some(offset) => {
+                let found = from + 1 + offset;
+                let entry = self.entries[found].clone();
+                self.browsing = Some((found, typed, browse));
+                Some(entry)
+            }
+            None => Some(typed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::History;
+
+    #[test]
+    fn browse_history() {
+        let entries = [
+            "load",
+            "export json a.json",
+            "print system",
+            "export json b.json",
+        ];
+        let mut history = History::new(entries.iter().map(|e| e.to_string()).collect(), None);
+
+        assert_eq!(history.older("").as_deref(), Some("export json b.json"));
+        assert_eq!(history.older("").as_deref(), Some("print system"));
+        assert_eq!(history.newer().as_deref(), Some("export json b.json"));
+        assert_eq!(history.newer().as_deref(), Some(""));
+        assert_eq!(history.newer(), None);
+
+        assert_eq!(history.older("ex").as_deref(), Some("export json b.json"));
+        assert_eq!(history.older("ex").as_deref(), Some("export json a.json"));
+        assert_eq!(history.older("ex"), None);
+        history.reset();
+
+        assert_eq!(history.search("system").as_deref(), Some("print system"));
+
+        history.push("print system");
+        history.push("print system");
+        assert_eq!(history.older("").as_deref(), Some("print system"));
+        assert_eq!(history.older("").as_deref(), Some("export json b.json"));
+    }
+}
@@ -1,4 +1,5 @@
 pub mod command;
+pub mod history;
 pub mod state;
 pub mod subscribed_view;
 pub mod view;
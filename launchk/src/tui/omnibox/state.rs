@@ -1,5 +1,6 @@
 use std::time::SystemTime;
 
+use crate::config::config;
 use crate::launchd::job_type_filter::JobTypeFilter;
 use crate::tui::omnibox::command::{OmniboxCommand, OMNIBOX_COMMANDS};
 use crate::tui::omnibox::view::OmniboxMode;
@@ -31,6 +32,19 @@ impl OmniboxState {
         }
     }
 
+    /// Command name and any arguments typed after it
+    pub fn command_parts(&self) -> (&str, Vec<&str>) {
+        let mut parts = self.command_filter.split(' ');
+        let name = parts.next().unwrap_or("");
+
+        (name, parts.filter(|a| !a.is_empty()).collect())
+    }
+
+    /// Typing arguments after a command name
+    pub fn has_command_args(&self) -> bool {
+        self.mode == OmniboxMode::CommandFilter && self.command_filter.contains(' ')
+    }
+
     /// Suggest a command based on name filter
     pub fn suggest_command(&self) -> Option<(&str, &str, OmniboxCommand)> {
         let OmniboxState {
@@ -43,9 +57,18 @@ impl OmniboxState {
             return None;
         }
 
+        let (name, _) = self.command_parts();
+        let has_args = self.has_command_args();
+
         OMNIBOX_COMMANDS
             .iter()
-            .filter(|(c, _, _)| c.to_string().starts_with(command_filter))
+            .filter(|(c, _, _)| {
+                if has_args {
+                    *c == name
+                } else {
+                    c.starts_with(name)
+                }
+            })
             .next()
             .map(|s| s.clone())
     }
@@ -58,7 +81,7 @@ impl Default for OmniboxState {
             tick: SystemTime::now(),
             label_filter: "".to_string(),
             command_filter: "".to_string(),
-            job_type_filter: JobTypeFilter::launchk_default(),
+            job_type_filter: config().job_type_filter(),
         }
     }
 }
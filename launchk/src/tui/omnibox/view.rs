@@ -7,14 +7,17 @@ use tokio::runtime::Handle;
 use tokio::time::interval;
 
 use cursive::direction::Direction;
-use cursive::event::{Event, EventResult, Key};
-use cursive::theme::{BaseColor, Color, Effect, Style};
+use cursive::event::{Event, EventResult, Key, MouseButton, MouseEvent};
+use cursive::theme::{Effect, Style};
 use cursive::view::CannotFocus;
 use cursive::{Printer, Vec2, View, XY};
 
-use crate::launchd::job_type_filter::JobTypeFilter;
+use crate::launchd::job_type_filter::{JobTypeFilter, JOB_TYPE_FILTER_KEYS};
 use crate::tui::omnibox::command::OmniboxCommand;
+use crate::tui::omnibox::history::History;
 use crate::tui::omnibox::state::OmniboxState;
+use crate::tui::theme::roles;
+use crate::tui::tour::{highlighted, TourTarget};
 
 /// Consumers impl OmniboxSubscriber receive these events
 /// via a channel in a wrapped view
@@ -91,6 +94,7 @@ pub struct OmniboxView {
     state: Arc<RwLock<OmniboxState>>,
     tx: Sender<OmniboxEvent>,
     last_size: RefCell<XY<usize>>,
+    history: History,
 }
 
 impl OmniboxView {
@@ -109,12 +113,30 @@ impl OmniboxView {
                 state,
                 tx: tx.clone(),
                 last_size: RefCell::new(XY::new(0, 0)),
+                history: History::load(),
             },
             tx,
             rx,
         )
     }
 
+    /// Replace both filters at once, e.g. from a profile
+    pub fn set_filters(&mut self, label_filter: String, job_type_filter: JobTypeFilter) {
+        let mut write = self.state.write().expect("Must write state");
+        let new_state = write.with_new(
+            Some(OmniboxMode::Idle),
+            Some(label_filter),
+            Some("".to_string()),
+            Some(job_type_filter),
+        );
+
+        self.tx
+            .send(OmniboxEvent::StateUpdate(new_state.clone()))
+            .expect("Must send state");
+
+        *write = new_state;
+    }
+
     /// Commands
     fn handle_active(event: &Event, state: &OmniboxState) -> Option<OmniboxState> {
         let OmniboxState {
@@ -125,11 +147,13 @@ impl OmniboxView {
         } = &state;
 
         let suggested_command = state.suggest_command();
+        let (command_name, command_args) = state.command_parts();
+        let has_args = state.has_command_args();
 
         let matched_command = suggested_command
             .as_ref()
-            .filter(|(cmd, _, _)| *cmd == *command_filter)
-            .map(|(_, _, oc)| oc.clone());
+            .filter(|(cmd, _, _)| *cmd == command_name)
+            .map(|(_, _, oc)| oc.clone().with_args(&command_args));
 
         // Avoid extra clauses below, use same options for string filters
         let (lf_char_update, cf_char_update) = match (event, mode) {
@@ -166,14 +190,16 @@ impl OmniboxView {
                 let (name, _, cmd) = suggested_command.unwrap();
 
                 Some(state.with_new(
-                    Some(OmniboxMode::CommandConfirm(cmd)),
+                    Some(OmniboxMode::CommandConfirm(cmd.with_args(&command_args))),
                     None,
-                    Some(name.to_string()),
+                    Some(name.to_string()).filter(|_| !has_args),
                     None,
                 ))
             }
             // Complete suggestion
-            (Event::Key(Key::Tab), OmniboxMode::CommandFilter) if suggested_command.is_some() => {
+            (Event::Key(Key::Tab), OmniboxMode::CommandFilter)
+                if suggested_command.is_some() && !has_args =>
+            {
                 let (cmd, _, _) = suggested_command.unwrap();
 
                 // Can submit from here, but catching a glimpse of the whole command
@@ -193,21 +219,45 @@ impl OmniboxView {
         }
     }
 
+    /// Recall history while typing a command
+    fn handle_history(
+        history: &mut History,
+        event: &Event,
+        state: &OmniboxState,
+    ) -> Option<OmniboxState> {
+        let recalled = match event {
+            Event::Key(Key::Up) => history.older(&state.command_filter),
+            Event::Key(Key::Down) => history.newer(),
+            Event::CtrlChar('r') => history.search(&state.command_filter),
+            _ => {
+                history.reset();
+                return None;
+            }
+        };
+
+        // Consume arrows even without a match, or focus moves
+        Some(state.with_new(
+            None,
+            None,
+            recalled.or(Some(state.command_filter.clone())),
+            None,
+        ))
+    }
+
     /// Toggle bitmask on key
     fn handle_job_type_filter(event: &Event, state: &OmniboxState) -> Option<OmniboxState> {
-        let mut jtf = state.job_type_filter.clone();
+        let (mask, _, _) = JOB_TYPE_FILTER_KEYS
+            .iter()
+            .find(|(_, key, _)| *event == Event::Char(*key))?;
 
-        match event {
-            Event::Char('s') => jtf.toggle(JobTypeFilter::SYSTEM),
-            Event::Char('g') => jtf.toggle(JobTypeFilter::GLOBAL),
-            Event::Char('u') => jtf.toggle(JobTypeFilter::USER),
-            Event::Char('a') => jtf.toggle(JobTypeFilter::AGENT),
-            Event::Char('d') => jtf.toggle(JobTypeFilter::DAEMON),
-            Event::Char('l') => jtf.toggle(JobTypeFilter::LOADED),
-            _ => return None,
-        };
+        Some(Self::toggle_job_type_filter(*mask, state))
+    }
+
+    fn toggle_job_type_filter(mask: JobTypeFilter, state: &OmniboxState) -> OmniboxState {
+        let mut jtf = state.job_type_filter.clone();
+        jtf.toggle(mask);
 
-        Some(state.with_new(Some(OmniboxMode::JobTypeFilter), None, None, Some(jtf)))
+        state.with_new(Some(OmniboxMode::JobTypeFilter), None, None, Some(jtf))
     }
 
     fn draw_command_header(&self, printer: &Printer<'_, '_>) {
@@ -227,8 +277,8 @@ impl OmniboxView {
             _ => "",
         };
 
-        let subtle = Style::from(Color::Light(BaseColor::Black));
-        let purple = Style::from(Color::Light(BaseColor::Blue));
+        let subtle = Style::from(roles().subtle);
+        let purple = Style::from(roles().accent);
 
         let modal_hilight = if let OmniboxMode::Idle = mode {
             subtle
@@ -242,6 +292,16 @@ impl OmniboxView {
             label_filter
         };
 
+        // Point at the omnibox during the tour
+        if highlighted() == TourTarget::Omnibox
+            && *mode == OmniboxMode::Idle
+            && visible_filter.is_empty()
+        {
+            let pointed = purple.combine(Effect::Reverse);
+            printer.with_style(pointed, |p| p.print(XY::new(0, 0), "/ filter  : command"));
+            return;
+        }
+
         // Print command header
         printer.with_style(modal_hilight, |p| p.print(XY::new(0, 0), cmd_header));
 
@@ -263,9 +323,13 @@ impl OmniboxView {
             return;
         }
         let (cmd, desc, ..) = suggestion.unwrap();
-        let cmd_string = cmd.to_string().replacen(&state.command_filter, "", 1);
+        let cmd_string = if state.has_command_args() {
+            "".to_string()
+        } else {
+            cmd.to_string().replacen(&state.command_filter, "", 1)
+        };
 
-        printer.with_style(Style::from(Color::Light(BaseColor::Black)), |p| {
+        printer.with_style(Style::from(roles().subtle), |p| {
             p.print(XY::new(0, 0), cmd_string.as_str())
         });
 
@@ -273,63 +337,110 @@ impl OmniboxView {
         printer.print(XY::new(start, 0), format!("-- {}", desc).as_str());
     }
 
-    fn draw_job_type_filter(&self, printer: &Printer<'_, '_>) {
-        let read = self.state.read().expect("Must read state");
-        let OmniboxState {
-            job_type_filter,
-            mode,
-            ..
-        } = &*read;
-
-        let mut jtf_ofs = if *mode != OmniboxMode::JobTypeFilter {
-            "[sguadl]".len()
+    /// x of the opening [, then each filter bit's x and text, in
+    /// [sguadlexnqbworh] or [system global ... ghost]
+    fn job_type_filter_badges(
+        &self,
+        expanded: bool,
+    ) -> (usize, Vec<(JobTypeFilter, usize, String)>) {
+        let mut jtf_ofs = if expanded {
+            JOB_TYPE_FILTER_KEYS
+                .iter()
+                .map(|(_, _, name)| name.len() + 1)
+                .sum::<usize>()
+                + 1
         } else {
-            "[system global user agent daemon loaded]".len()
+            JOB_TYPE_FILTER_KEYS.len() + 2
         };
 
         if jtf_ofs < self.last_size.borrow().x {
             jtf_ofs = self.last_size.borrow().x - jtf_ofs;
         }
 
-        printer.print(XY::new(jtf_ofs, 0), "[");
+        let start = jtf_ofs;
         jtf_ofs += 1;
 
-        let inactive = Style::from(Color::Light(BaseColor::Black));
-        let active = Style::from(Color::Light(BaseColor::Blue)).combine(Effect::Bold);
+        let badges = JOB_TYPE_FILTER_KEYS
+            .iter()
+            .enumerate()
+            .map(|(i, (mask, key, name))| {
+                // No space at end if expanded
+                let mask_string = if !expanded {
+                    key.to_string()
+                } else if i == JOB_TYPE_FILTER_KEYS.len() - 1 {
+                    name.to_string()
+                } else {
+                    format!("{} ", name)
+                };
+
+                let badge = (*mask, jtf_ofs, mask_string);
+                jtf_ofs += badge.2.len();
+                badge
+            })
+            .collect();
+
+        (start, badges)
+    }
+
+    /// Filter bit under a left click
+    fn clicked_job_type_filter(
+        &self,
+        event: &Event,
+        state: &OmniboxState,
+    ) -> Option<JobTypeFilter> {
+        let position = match event {
+            Event::Mouse {
+                offset,
+                position,
+                event: MouseEvent::Press(MouseButton::Left),
+            } => position.checked_sub(*offset)?,
+            _ => return None,
+        };
 
-        for mask in [
-            JobTypeFilter::SYSTEM,
-            JobTypeFilter::GLOBAL,
-            JobTypeFilter::USER,
-            JobTypeFilter::AGENT,
-            JobTypeFilter::DAEMON,
-            JobTypeFilter::LOADED,
-        ]
-        .iter()
-        {
-            let mut mask_string = format!("{:?} ", mask).to_ascii_lowercase();
-            if *mode != OmniboxMode::JobTypeFilter {
-                mask_string.truncate(1);
-            }
+        let (_, badges) = self.job_type_filter_badges(state.mode == OmniboxMode::JobTypeFilter);
 
-            // No space at end if expanded
-            if *mask == JobTypeFilter::LOADED && *mode == OmniboxMode::JobTypeFilter {
-                mask_string.truncate(mask_string.len() - 1);
-            }
+        badges
+            .into_iter()
+            .find(|(_, x, text)| position.x >= *x && position.x < x + text.len())
+            .map(|(mask, _, _)| mask)
+    }
+
+    fn draw_job_type_filter(&self, printer: &Printer<'_, '_>) {
+        let read = self.state.read().expect("Must read state");
+        let OmniboxState {
+            job_type_filter,
+            mode,
+            ..
+        } = &*read;
+
+        let (start, badges) = self.job_type_filter_badges(*mode == OmniboxMode::JobTypeFilter);
 
-            let style = if job_type_filter.contains(*mask) {
+        // Reversed while the tour points at them
+        let pointed = if highlighted() == TourTarget::FilterBadges {
+            Style::from(Effect::Reverse)
+        } else {
+            Style::none()
+        };
+        printer.with_style(pointed, |p| p.print(XY::new(start, 0), "["));
+
+        let inactive = Style::from(roles().subtle).combine(pointed);
+        let active = Style::from(roles().accent)
+            .combine(Effect::Bold)
+            .combine(pointed);
+        let mut end = start + 1;
+
+        for (mask, x, mask_string) in badges {
+            let style = if job_type_filter.contains(mask) {
                 active
             } else {
                 inactive
             };
 
-            printer.with_style(style, |p| {
-                p.print(XY::new(jtf_ofs, 0), mask_string.as_str())
-            });
-            jtf_ofs += mask_string.len();
+            printer.with_style(style, |p| p.print(XY::new(x, 0), mask_string.as_str()));
+            end = x + mask_string.len();
         }
 
-        printer.print(XY::new(jtf_ofs, 0), "]");
+        printer.with_style(pointed, |p| p.print(XY::new(end, 0), "]"));
     }
 }
 
@@ -347,7 +458,23 @@ impl View for OmniboxView {
         let state = self.state.read().expect("Must read state");
         let mode = &state.mode;
 
+        // Paths etc. in command arguments
+        let has_args = state.has_command_args();
+
+        let clicked = self.clicked_job_type_filter(&event, &state);
+        let typing_command = *mode == OmniboxMode::CommandFilter;
+
+        let recalled = if typing_command {
+            Self::handle_history(&mut self.history, &event, &state)
+        } else {
+            None
+        };
+
         let new_state = match (event, mode) {
+            _ if recalled.is_some() => recalled,
+            (Event::Mouse { .. }, _) => {
+                clicked.map(|mask| Self::toggle_job_type_filter(mask, &state))
+            }
             (Event::CtrlChar('u'), _) => {
                 self.tx
                     .send(OmniboxEvent::Command(OmniboxCommand::FocusServiceList))
@@ -360,15 +487,18 @@ impl View for OmniboxView {
                     .expect("Must focus");
                 Some(state.with_new(Some(OmniboxMode::Idle), None, Some("".to_string()), None))
             }
-            (Event::Char(':'), _) => {
+            (Event::Char(':'), _) if !has_args => {
                 Some(state.with_new(Some(OmniboxMode::CommandFilter), None, None, None))
             }
-            (Event::Char('/'), _) => Some(state.with_new(
-                Some(OmniboxMode::LabelFilter),
-                None,
-                Some("".to_string()),
-                None,
-            )),
+            // Typed into label filter for regex mode
+            (Event::Char('/'), mode) if !has_args && *mode != OmniboxMode::LabelFilter => {
+                Some(state.with_new(
+                    Some(OmniboxMode::LabelFilter),
+                    None,
+                    Some("".to_string()),
+                    None,
+                ))
+            }
             (e, OmniboxMode::Idle) => Self::handle_job_type_filter(&e, &*state),
             (e, _) => Self::handle_active(&e, &*state),
         };
@@ -379,6 +509,12 @@ impl View for OmniboxView {
 
         let new_state = new_state.unwrap();
 
+        if typing_command {
+            if let OmniboxMode::CommandConfirm(_) = new_state.mode {
+                self.history.push(&new_state.command_filter);
+            }
+        }
+
         self.tx
             .send(OmniboxEvent::StateUpdate(new_state.clone()))
             .expect("Must send state");
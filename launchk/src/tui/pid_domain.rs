@@ -0,0 +1,88 @@
+use std::process::Command;
+use std::sync::mpsc::Sender;
+
+use cursive::traits::{Resizable, Scrollable};
+use cursive::views::{Dialog, SelectView};
+use cursive::Cursive;
+use xpc_sys::enums::DomainType;
+
+use crate::launchd::domain_target::DomainTarget;
+use crate::launchd::query::ListedService;
+use crate::tui::omnibox::command::OmniboxCommand;
+use crate::tui::omnibox::view::OmniboxEvent;
+use crate::tui::root::CbSinkMessage;
+
+/// XPC services in pid/<pid>. Enter prints one, or attaches lldb to it
+/// if running
+pub fn show_pid_domain(
+    tx: Sender<OmniboxEvent>,
+    pid: u64,
+    services: Vec<(String, ListedService)>,
+) -> CbSinkMessage {
+    Box::new(move |siv| {
+        let mut select = SelectView::new();
+
+        for (label, svc) in &services {
+            let pid = match svc.pid {
+                0 => "-".to_string(),
+                p => p.to_string(),
+            };
+
+            select.add_item(format!("{:<8} {}", pid, label), (label.clone(), svc.pid));
+        }
+
+        let target = DomainTarget::new(DomainType::PID, pid);
+        select.set_on_submit(move |s, (label, svc_pid): &(String, i64)| {
+            let print_tx = tx.clone();
+            let print = OmniboxCommand::PrintService(target.clone(), label.clone());
+
+            let mut dialog = Dialog::text(format!("{}/{}", target, label))
+                .title("XPC service")
+                .button("Print", move |s| {
+                    s.pop_layer();
+                    print_tx
+                        .send(OmniboxEvent::Command(print.clone()))
+                        .expect("Must send command");
+                });
+
+            if *svc_pid > 0 {
+                let attach_tx = tx.clone();
+                let attach = OmniboxCommand::AttachDebugger(*svc_pid);
+
+                dialog = dialog.button("Attach", move |s| {
+                    s.pop_layer();
+                    attach_tx
+                        .send(OmniboxEvent::Command(attach.clone()))
+                        .expect("Must send command");
+                });
+            }
+
+            s.add_layer(dialog.dismiss_button("Cancel"));
+        });
+
+        siv.add_layer(
+            Dialog::around(select.scrollable())
+                .title(format!("pid/{}: {} services", pid, services.len()))
+                .dismiss_button("Close")
+                .max_height(40),
+        );
+    })
+}
+
+/// lldb -p <pid> (launchctl attach), clearing Cursive after it exits
+pub fn attach_debugger(siv: &mut Cursive, pid: i64) -> Result<(), String> {
+    clearscreen::clear().expect("Must clear screen");
+
+    let res = Command::new("lldb")
+        .arg("-p")
+        .arg(pid.to_string())
+        .status()
+        .map_err(|e| format!("lldb: {}", e));
+
+    siv.clear();
+
+    match res? {
+        s if s.success() => Ok(()),
+        s => Err(format!("lldb exited {:?}", s)),
+    }
+}
@@ -0,0 +1,74 @@
+use cursive::theme::{Effect, Style};
+use cursive::traits::{Resizable, Scrollable};
+use cursive::utils::markup::StyledString;
+use cursive::views::{Dialog, LinearLayout, Panel, TextView};
+
+use crate::launchd::plist_diff::KeyDiff;
+use crate::tui::root::CbSinkMessage;
+use crate::tui::theme::roles;
+
+const SIDE_WIDTH: usize = 48;
+
+/// One line per key so both sides stay aligned, differing keys stand out
+fn side(diffs: &[KeyDiff], value: fn(&KeyDiff) -> Option<&String>) -> StyledString {
+    let mut text = StyledString::new();
+
+    for diff in diffs {
+        let line = format!("{}\n", value(diff).map(String::as_str).unwrap_or("-"));
+
+        if diff.differs() {
+            text.append_styled(line, Style::from(roles().warning));
+        } else {
+            text.append(line);
+        }
+    }
+
+    text
+}
+
+/// Loaded config and on-disk plist side by side, e.g. edited but not reloaded
+pub fn show_plist_diff(path: String, diffs: Vec<KeyDiff>) -> CbSinkMessage {
+    Box::new(move |siv| {
+        let mut keys = StyledString::new();
+
+        for diff in &diffs {
+            let style = if diff.differs() {
+                Style::from(roles().warning).combine(Effect::Bold)
+            } else {
+                Style::from(Effect::Bold)
+            };
+            keys.append_styled(format!("{}\n", diff.key), style);
+        }
+
+        let differing = diffs.iter().filter(|d| d.differs()).count();
+        let summary = if differing == 0 {
+            "Loaded job matches its plist".to_string()
+        } else {
+            format!("{} key(s) differ, reload to apply the plist", differing)
+        };
+
+        let columns = LinearLayout::horizontal()
+            .child(Panel::new(TextView::new(keys).no_wrap()).title("Key"))
+            .child(
+                Panel::new(TextView::new(side(&diffs, |d| d.loaded.as_ref())).no_wrap())
+                    .title("Loaded")
+                    .fixed_width(SIDE_WIDTH),
+            )
+            .child(
+                Panel::new(TextView::new(side(&diffs, |d| d.on_disk.as_ref())).no_wrap())
+                    .title("On disk")
+                    .fixed_width(SIDE_WIDTH),
+            );
+
+        let layout = LinearLayout::vertical()
+            .child(TextView::new(summary))
+            .child(columns.scrollable());
+
+        siv.add_layer(
+            Dialog::around(layout)
+                .title(format!("Plist diff: {}", path))
+                .dismiss_button("OK")
+                .max_height(40),
+        );
+    })
+}
@@ -0,0 +1,260 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use cursive::event::{Event, EventResult, Key};
+use cursive::theme::{Effect, Style};
+use cursive::traits::{Resizable, Scrollable};
+use cursive::utils::markup::StyledString;
+use cursive::view::ViewWrapper;
+use cursive::views::{LinearLayout, Panel, ResizedView, ScrollView, SelectView, TextView};
+use cursive::View;
+
+use crate::launchd::plist::{is_binary_plist, plist_xml};
+use crate::tui::root::CbSinkMessage;
+use crate::tui::theme::roles;
+
+type PlistLines = Panel<ResizedView<ScrollView<SelectView<usize>>>>;
+
+/// Style for text between tags, depending on the opening tag
+fn text_style(tag: &str) -> Style {
+    match tag {
+        "<key>" => Style::from(roles().accent).combine(Effect::Bold),
+        "<string>" => Style::from(roles().success),
+        "<integer>" | "<real>" | "<date>" | "<data>" => Style::from(roles().special),
+        _ => Style::none(),
+    }
+}
+
+fn tag_style(tag: &str) -> Style {
+    match tag {
+        "<true/>" | "<false/>" => Style::from(roles().warning),
+        _ => Style::from(Effect::Dim),
+    }
+}
+
+/// One line of plist XML, tags dimmed and values coloured by type
+fn highlight(line: &str) -> StyledString {
+    let mut styled = StyledString::new();
+    let mut rest = line;
+    let mut open_tag = "";
+
+    while !rest.is_empty() {
+        if rest.starts_with('<') {
+            let end = rest.find('>').map(|i| i + 1).unwrap_or(rest.len());
+            let tag = &rest[..end];
+
+            styled.append_styled(tag, tag_style(tag));
+            open_tag = tag;
+            rest = &rest[end..];
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+
+            styled.append_styled(&rest[..end], text_style(open_tag));
+            rest = &rest[end..];
+        }
+    }
+
+    styled
+}
+
+/// Line index of every non-empty <dict> or <array> to its closing line
+fn fold_ranges(lines: &[String]) -> BTreeMap<usize, usize> {
+    let mut ranges = BTreeMap::new();
+    let mut open = vec![];
+
+    for (i, line) in lines.iter().enumerate() {
+        match line.trim() {
+            "<dict>" | "<array>" => open.push(i),
+            "</dict>" | "</array>" => {
+                if let Some(start) = open.pop() {
+                    ranges.insert(start, i);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    ranges
+}
+
+/// Read-only plist, Enter folds the dict or array under a key
+pub struct PlistView {
+    lines: Vec<String>,
+    folds: BTreeMap<usize, usize>,
+    folded: BTreeSet<usize>,
+    inner: LinearLayout,
+}
+
+impl PlistView {
+    pub fn new(title: String, xml: &str) -> Self {
+        let lines: Vec<String> = xml.lines().map(|l| l.replace('\t', "  ")).collect();
+        let folds = fold_ranges(&lines);
+
+        let inner = LinearLayout::vertical()
+            .child(Panel::new(SelectView::<usize>::new().scrollable().full_height()).title(title))
+            .child(TextView::new(
+                "Enter: fold/unfold  c: fold all  o: unfold all  q: close",
+            ));
+
+        let mut view = Self {
+            lines,
+            folds,
+            folded: BTreeSet::new(),
+            inner,
+        };
+
+        view.render();
+        view
+    }
+
+    fn select_view(&mut self) -> &mut SelectView<usize> {
+        self.inner
+            .get_child_mut(0)
+            .and_then(|c| c.downcast_mut::<PlistLines>())
+            .map(|p| p.get_inner_mut().get_inner_mut().get_inner_mut())
+            .expect("Must have select view")
+    }
+
+    /// Foldable line for a selection, a key folds the value below it
+    fn fold_for(&self, line: usize) -> Option<usize> {
+        if self.folds.contains_key(&line) {
+            return Some(line);
+        }
+
+        let is_key = self.lines[line].trim_start().starts_with("<key>");
+        Some(line + 1).filter(|next| is_key && self.folds.contains_key(next))
+    }
+
+    fn render(&mut self) {
+        let mut items = vec![];
+        let mut i = 0;
+
+        while i < self.lines.len() {
+            let mut styled = highlight(&self.lines[i]);
+
+            match self.folds.get(&i) {
+                Some(close) if self.folded.contains(&i) => {
+                    styled.append_styled(
+                        format!(" … {} lines", close - i - 1),
+                        Style::from(Effect::Dim),
+                    );
+                    styled.append(highlight(self.lines[*close].trim()));
+                    items.push((styled, i));
+                    i = close + 1;
+                }
+                _ => {
+                    items.push((styled, i));
+                    i += 1;
+                }
+            }
+        }
+
+        let select = self.select_view();
+        let selected = select.selection().map(|s| *s);
+
+        select.clear();
+        select.add_all(items);
+
+        if let Some(line) = selected {
+            // Last visible line at or above, in case it was folded away
+            let position = select.iter().filter(|(_, l)| **l <= line).count();
+            select.set_selection(position.saturating_sub(1));
+        }
+    }
+
+    fn toggle_fold(&mut self) {
+        let selected = self.select_view().selection();
+        let fold = selected.and_then(|line| self.fold_for(*line));
+
+        if let Some(line) = fold {
+            if !self.folded.remove(&line) {
+                self.folded.insert(line);
+            }
+
+            self.render();
+        }
+    }
+
+    fn fold_all(&mut self, fold: bool) {
+        self.folded = if fold {
+            // Keep the root dict open
+            self.folds.keys().skip(1).copied().collect()
+        } else {
+            BTreeSet::new()
+        };
+
+        self.render();
+    }
+}
+
+impl ViewWrapper for PlistView {
+    wrap_impl!(self.inner: LinearLayout);
+
+    fn wrap_on_event(&mut self, event: Event) -> EventResult {
+        match event {
+            Event::Key(Key::Enter) => {
+                self.toggle_fold();
+                EventResult::Consumed(None)
+            }
+            Event::Char('c') => {
+                self.fold_all(true);
+                EventResult::Consumed(None)
+            }
+            Event::Char('o') => {
+                self.fold_all(false);
+                EventResult::Consumed(None)
+            }
+            Event::Char('q') | Event::Key(Key::Esc) => EventResult::with_cb(|s| {
+                s.pop_layer();
+            }),
+            _ => self.inner.on_event(event),
+        }
+    }
+}
+
+/// Read a plist (converting binary) and show it full screen
+pub fn show_plist(label: &str, plist_path: &str) -> Result<CbSinkMessage, String> {
+    let xml = plist_xml(plist_path)?;
+
+    let title = if is_binary_plist(plist_path)? {
+        format!("{}: {} (binary)", label, plist_path)
+    } else {
+        format!("{}: {}", label, plist_path)
+    };
+
+    Ok(Box::new(move |siv| {
+        siv.add_layer(PlistView::new(title, &xml).full_screen());
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fold_ranges, highlight};
+
+    #[test]
+    fn fold_nested() {
+        let lines: Vec<String> = [
+            "<dict>",
+            "  <key>ProgramArguments</key>",
+            "  <array>",
+            "    <string>/bin/true</string>",
+            "  </array>",
+            "  <key>Empty</key>",
+            "  <array/>",
+            "</dict>",
+        ]
+        .iter()
+        .map(|l| l.to_string())
+        .collect();
+
+        let ranges = fold_ranges(&lines);
+        assert_eq!(ranges.get(&0), Some(&7));
+        assert_eq!(ranges.get(&2), Some(&4));
+        assert_eq!(ranges.len(), 2);
+    }
+
+    #[test]
+    fn highlight_keeps_text() {
+        let line = "  <key>Label</key>";
+        assert_eq!(highlight(line).source(), line);
+    }
+}
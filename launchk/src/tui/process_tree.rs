@@ -0,0 +1,74 @@
+use std::sync::mpsc::Sender;
+
+use cursive::traits::{Resizable, Scrollable};
+use cursive::views::{Dialog, SelectView};
+
+use crate::launchd::process_tree::ProcessNode;
+use crate::tui::omnibox::command::OmniboxCommand;
+use crate::tui::omnibox::view::OmniboxEvent;
+use crate::tui::root::CbSinkMessage;
+
+/// Running jobs that spawned processes, each followed by its descendants.
+/// Enter on a child offers SIGTERM or SIGKILL, job PIDs are left to kill
+pub fn show_process_tree(
+    tx: Sender<OmniboxEvent>,
+    trees: Vec<(String, ProcessNode)>,
+) -> CbSinkMessage {
+    Box::new(move |siv| {
+        let mut select: SelectView<Option<(i32, String)>> = SelectView::new();
+
+        for (label, tree) in &trees {
+            select.add_item(format!("{} ({})", label, tree.descendants()), None);
+
+            for (i, (pid, line)) in tree.lines().into_iter().enumerate() {
+                let value = if i == 0 {
+                    None
+                } else {
+                    Some((pid, line.clone()))
+                };
+                select.add_item(format!("  {}", line), value);
+            }
+        }
+
+        select.set_on_submit(move |s, child: &Option<(i32, String)>| {
+            let (pid, line) = match child {
+                Some(c) => c.clone(),
+                None => return,
+            };
+            let name = line.trim_start_matches(|c: char| !c.is_ascii_digit());
+
+            let term_tx = tx.clone();
+            let kill_tx = tx.clone();
+            s.add_layer(
+                Dialog::text(format!("Send a signal to {}?", name))
+                    .title("Kill child process")
+                    .button("SIGTERM", move |s| {
+                        s.pop_layer();
+                        term_tx
+                            .send(OmniboxEvent::Command(OmniboxCommand::KillPid(
+                                pid,
+                                libc::SIGTERM,
+                            )))
+                            .expect("Must send command");
+                    })
+                    .button("SIGKILL", move |s| {
+                        s.pop_layer();
+                        kill_tx
+                            .send(OmniboxEvent::Command(OmniboxCommand::KillPid(
+                                pid,
+                                libc::SIGKILL,
+                            )))
+                            .expect("Must send command");
+                    })
+                    .dismiss_button("Cancel"),
+            );
+        });
+
+        siv.add_layer(
+            Dialog::around(select.scrollable())
+                .title(format!("Process tree: {} jobs with children", trees.len()))
+                .dismiss_button("Close")
+                .max_height(40),
+        );
+    })
+}
@@ -1,32 +1,106 @@
 use std::collections::VecDeque;
+use std::fs;
 
-use std::ptr::slice_from_raw_parts;
+use log::Level;
 use std::sync::mpsc::{channel, Receiver, Sender};
 
 use cursive::event::{Event, EventResult, Key};
-use cursive::traits::{Resizable, Scrollable};
-use cursive::view::ViewWrapper;
-use cursive::views::{LinearLayout, NamedView, Panel};
+use cursive::traits::{Nameable, Resizable, Scrollable};
+use cursive::view::{Finder, ViewWrapper};
+use cursive::views::{LinearLayout, NamedView, Panel, TextView};
 use cursive::{Cursive, Vec2, View};
 
 use tokio::runtime::Handle;
+use xpc_sys::objects::xpc_error::XPCError;
+use xpc_sys::objects::xpc_shmem::XPCShmem;
+use xpc_sys::rs_geteuid;
 
-use crate::tui::omnibox::command::OmniboxCommand;
+use crate::config::{self, config, parse_job_type_filter, Config};
+use crate::launchd::audit::{audit, third_party_agents};
+use crate::launchd::domain_target::{parse_service_target, resolve_domain_target, DomainTarget};
+use crate::launchd::entry_status::{
+    domain_target, refresh_dumpstate_jobs, set_domain_target, set_user_scope, ENTRY_STATUS_CACHE,
+};
+use crate::launchd::limits::{parse_limit_value, resource_name, Limit};
+use crate::launchd::login_items::login_items;
+use crate::launchd::mach_graph::whohas;
+use crate::launchd::overrides::Overrides;
+use crate::launchd::plist::rescan_plists;
+use crate::launchd::preflight::Preflight;
+use crate::launchd::process_tree::job_trees;
+use crate::launchd::query::{
+    getenv, limits, list_errors, list_pid_domain, logged_in_users, print_cache, print_domain,
+    print_service, set_enabled_in, set_limit, setenv, submit, uncache, unsetenv,
+};
+use crate::launchd::raw_xpc;
+use crate::launchd::session::LaunchSession;
+use crate::launchd::snapshot::Snapshot;
+use crate::tui::app_logs::show_app_logs;
+use crate::tui::audit::{manifest_dir, show_audit, show_third_party};
+use crate::tui::job_detail::JobDetailView;
+use crate::tui::keys::hotkey_command;
+use crate::tui::login_items::show_login_items;
+use crate::tui::mach_graph::show_whohas;
+use crate::tui::omnibox::command::{OmniboxCommand, OMNIBOX_COMMANDS};
 use crate::tui::omnibox::subscribed_view::{
     OmniboxResult, OmniboxSubscribedView, OmniboxSubscriber, Subscribable,
 };
 use crate::tui::omnibox::view::{OmniboxError, OmniboxEvent, OmniboxView};
-use crate::tui::pager::show_pager;
+use crate::tui::pid_domain::{attach_debugger, show_pid_domain};
+use crate::tui::process_tree::show_process_tree;
+use crate::tui::service_list::launchctl;
 use crate::tui::service_list::view::ServiceListView;
+use crate::tui::snapshot::show_snapshot_diff;
+use crate::tui::state_pager::{show_state_pager, show_text_pager};
+use crate::tui::sysinfo::domain_target_badge;
+use crate::tui::theme;
+use crate::tui::tour::show_tour;
+use crate::tui::ui_state;
+use crate::tui::worker::{run_with_progress, run_with_spinner};
 use crate::{
     launchd::query::dumpjpcategory,
     tui::dialog::{show_csr_info, show_help},
 };
-use crate::{launchd::query::dumpstate, tui::dialog};
+use crate::{
+    launchd::query::{dumpstate, dumpstate_to_file},
+    tui::dialog,
+};
 use std::thread;
 
 pub type CbSinkMessage = Box<dyn FnOnce(&mut Cursive) + Send>;
 
+/// Built-in theme with config overrides, built-in only if invalid
+pub fn load_theme(siv: &mut Cursive) -> Result<(), String> {
+    let fallback = |siv: &mut Cursive, e: String| {
+        let (builtin, roles) = theme::resolve(&Config::default()).expect("Must load styles");
+        siv.load_toml(&builtin).expect("Must load styles");
+        theme::set_roles(roles);
+        format!("Invalid theme: {}", e)
+    };
+
+    let (toml, roles) = match theme::resolve(&config()) {
+        Ok(resolved) => resolved,
+        Err(e) => return Err(fallback(siv, e)),
+    };
+
+    match siv.load_toml(&toml) {
+        Ok(_) => {
+            theme::set_roles(roles);
+            Ok(())
+        }
+        Err(e) => Err(fallback(siv, format!("{:?}", e))),
+    }
+}
+
+/// Optional pid argument, e.g. printcache 123
+fn parse_pid(arg: Option<&String>) -> Result<Option<i64>, OmniboxError> {
+    arg.map(|a| {
+        a.parse()
+            .map_err(|_| OmniboxError::CommandError(format!("Invalid PID {}", a)))
+    })
+    .transpose()
+}
+
 pub struct RootLayout {
     layout: LinearLayout,
     omnibox_tx: Sender<OmniboxEvent>,
@@ -43,6 +117,29 @@ enum RootLayoutChildren {
     ServiceList,
 }
 
+/// print of a domain or one service
+type PrintQuery = Box<dyn FnOnce() -> Result<(usize, XPCShmem), XPCError> + Send>;
+
+/// 1-9 to that [[profiles]] entry's name, if there is one
+fn profile_key(event: &Event) -> Option<String> {
+    match event {
+        Event::Char(c @ '1'..='9') => {
+            let index = c.to_digit(10)? as usize - 1;
+            config().profiles.get(index).map(|p| p.name.clone())
+        }
+        _ => None,
+    }
+}
+
+fn profile_usage(config: &Config) -> OmniboxError {
+    if config.profiles.is_empty() {
+        return OmniboxError::CommandError("No [[profiles]] in config".to_string());
+    }
+
+    let names: Vec<&str> = config.profiles.iter().map(|p| p.name.as_str()).collect();
+    OmniboxError::CommandError(format!("Usage: profile <{}>", names.join("|")))
+}
+
 async fn poll_omnibox(cb_sink: Sender<CbSinkMessage>, rx: Receiver<OmniboxEvent>) {
     loop {
         let recv = rx.recv().expect("Must receive event");
@@ -75,9 +172,25 @@ impl RootLayout {
         };
 
         new.setup(omnibox);
+        new.restore_filters();
         new
     }
 
+    /// Filters from the last run, the service list restores the rest
+    fn restore_filters(&mut self) {
+        let restored = ui_state::current();
+        let jtf = match restored.job_type_filter.as_deref() {
+            Some(letters) => parse_job_type_filter(letters).ok(),
+            None => None,
+        };
+
+        if let Some(jtf) = jtf {
+            let filter = restored.label_filter;
+            self.layout
+                .call_on_name("omnibox", |v: &mut OmniboxView| v.set_filters(filter, jtf));
+        }
+    }
+
     fn setup(&mut self, omnibox: OmniboxView) {
         let sysinfo = Panel::new(crate::tui::sysinfo::make_layout());
 
@@ -91,10 +204,16 @@ impl RootLayout {
             .scrollable()
             .subscribable();
 
+        let job_detail = JobDetailView::new(self.omnibox_tx.clone())
+            .with_name("job_detail")
+            .full_width()
+            .max_height(20);
+
         self.with_view_mut(|v| {
             v.add_child(sysinfo);
             v.add_child(omnibox);
             v.add_child(service_list);
+            v.add_child(job_detail);
         });
 
         self.layout
@@ -151,6 +270,18 @@ impl RootLayout {
         }
     }
 
+    /// Reload the detail pane if it is showing
+    fn refresh_job_detail(&mut self) -> Option<OmniboxCommand> {
+        self.layout
+            .call_on_name("job_detail", |v: &mut JobDetailView| v.is_visible())
+            .filter(|visible| *visible)
+            .map(|_| OmniboxCommand::JobDetailRequest)
+    }
+
+    fn service_list_focused(&self) -> bool {
+        self.layout.get_focus_index() == RootLayoutChildren::ServiceList as usize
+    }
+
     fn ring_to_arrows(&mut self) -> Option<Event> {
         if self.key_ring.len() < 3 {
             None
@@ -199,6 +330,9 @@ impl ViewWrapper for RootLayout {
             | Event::Char('a')
             | Event::Char('d')
             | Event::Char('l')
+            | Event::Char('e')
+            | Event::Char('x')
+            | Event::Char('n')
             | Event::Key(Key::Backspace) => {
                 self.focus_and_forward(RootLayoutChildren::Omnibox, event)
             }
@@ -215,6 +349,33 @@ impl ViewWrapper for RootLayout {
                 let event = self.ring_to_arrows().unwrap_or(event);
                 self.layout.on_event(event)
             }
+            // Clicks (e.g. on filter badges) must not take focus from the
+            // service list, or hotkeys stop working
+            Event::Mouse { .. } => {
+                let was_focused = self.service_list_focused();
+                let result = self.layout.on_event(event);
+
+                if was_focused {
+                    self.layout
+                        .set_focus_index(RootLayoutChildren::ServiceList as usize)
+                        .expect("Must focus service list");
+                }
+
+                result
+            }
+            // Hotkeys only when not typing into the omnibox
+            _ if self.service_list_focused() => match profile_key(&event)
+                .map(|name| OmniboxCommand::Profile(vec![name]))
+                .or_else(|| hotkey_command(&event))
+            {
+                Some(cmd) => {
+                    self.omnibox_tx
+                        .send(OmniboxEvent::Command(cmd))
+                        .expect("Must send command");
+                    EventResult::Consumed(None)
+                }
+                None => self.layout.on_event(event),
+            },
             _ => self.layout.on_event(event),
         };
 
@@ -269,6 +430,17 @@ impl OmniboxSubscriber for RootLayout {
                     .expect("Must show prompt");
                 Ok(None)
             }
+            OmniboxEvent::Command(OmniboxCommand::TypedConfirm(p, expected, c)) => {
+                self.cbsink_channel
+                    .send(dialog::typed_confirm_prompt(
+                        self.omnibox_tx.clone(),
+                        p,
+                        expected,
+                        c,
+                    ))
+                    .expect("Must show prompt");
+                Ok(None)
+            }
             OmniboxEvent::Command(OmniboxCommand::DomainSessionPrompt(label, domain_only, f)) => {
                 self.cbsink_channel
                     .send(dialog::domain_session_prompt(
@@ -280,6 +452,572 @@ impl OmniboxSubscriber for RootLayout {
                     .expect("Must show prompt");
                 Ok(None)
             }
+            OmniboxEvent::Command(OmniboxCommand::SignalPrompt(dt, f)) => {
+                self.cbsink_channel
+                    .send(dialog::signal_prompt(self.omnibox_tx.clone(), dt, f))
+                    .expect("Must show prompt");
+                Ok(None)
+            }
+            OmniboxEvent::Command(OmniboxCommand::ReloadConfig) => {
+                config::reload().map_err(OmniboxError::CommandError)?;
+
+                let config = config();
+                let unknown: Vec<String> = config
+                    .key_bindings()
+                    .into_iter()
+                    .filter(|(_, name)| !OMNIBOX_COMMANDS.iter().any(|(n, _, _)| n == name))
+                    .map(|(key, name)| format!("{} = {}", key, name))
+                    .collect();
+
+                self.cbsink_channel
+                    .send(Box::new(|siv| {
+                        if let Err(e) = load_theme(siv) {
+                            dialog::show_error(e)(siv);
+                        }
+                    }))
+                    .expect("Must load theme");
+
+                if unknown.is_empty() {
+                    Ok(None)
+                } else {
+                    Err(OmniboxError::CommandError(format!(
+                        "Unknown commands in [keys] or [bindings]: {}",
+                        unknown.join(", ")
+                    )))
+                }
+            }
+            OmniboxEvent::Command(OmniboxCommand::Theme(args)) => {
+                let name = match args.as_slice() {
+                    [name] => name,
+                    _ => {
+                        return Err(OmniboxError::CommandError(format!(
+                            "Usage: theme {}",
+                            theme::scheme_names().join("|")
+                        )))
+                    }
+                };
+
+                theme::set_scheme(name).map_err(OmniboxError::CommandError)?;
+
+                self.cbsink_channel
+                    .send(Box::new(|siv| {
+                        if let Err(e) = load_theme(siv) {
+                            dialog::show_error(e)(siv);
+                        }
+                    }))
+                    .expect("Must load theme");
+
+                Ok(None)
+            }
+            OmniboxEvent::Command(OmniboxCommand::ToggleJobDetail) => {
+                let visible = self
+                    .layout
+                    .call_on_name("job_detail", |v: &mut JobDetailView| {
+                        let visible = v.is_visible();
+                        v.hide();
+                        visible
+                    })
+                    .unwrap_or(false);
+
+                // Hidden, ask ServiceList to fill it for the highlighted job
+                if visible {
+                    Ok(None)
+                } else {
+                    Ok(Some(OmniboxCommand::JobDetailRequest))
+                }
+            }
+            OmniboxEvent::Command(OmniboxCommand::EnvPrompt(target, key)) => {
+                let value = key.as_ref().and_then(|k| getenv(k.clone(), &target).ok());
+
+                self.cbsink_channel
+                    .send(dialog::env_prompt(
+                        self.omnibox_tx.clone(),
+                        target,
+                        key,
+                        value,
+                    ))
+                    .expect("Must show prompt");
+                Ok(None)
+            }
+            OmniboxEvent::Command(OmniboxCommand::SetEnv(target, args)) => match args.as_slice() {
+                [key, value] => {
+                    setenv(key.clone(), value.clone(), &target)
+                        .map_err(|e| OmniboxError::CommandError(e.to_string()))?;
+                    launchctl::record(&OmniboxCommand::SetEnv(target, args.clone()), None);
+                    Ok(self.refresh_job_detail())
+                }
+                _ => Err(OmniboxError::CommandError(
+                    "Usage: setenv <key> <value>".to_string(),
+                )),
+            },
+            OmniboxEvent::Command(OmniboxCommand::UnsetEnv(target, args)) => {
+                match args.as_slice() {
+                    [key] => {
+                        unsetenv(key.clone(), &target)
+                            .map_err(|e| OmniboxError::CommandError(e.to_string()))?;
+                        launchctl::record(&OmniboxCommand::UnsetEnv(target, args.clone()), None);
+                        Ok(self.refresh_job_detail())
+                    }
+                    _ => Err(OmniboxError::CommandError(
+                        "Usage: unsetenv <key>".to_string(),
+                    )),
+                }
+            }
+            OmniboxEvent::Command(OmniboxCommand::Submit(args)) => match args.as_slice() {
+                [label, program, rest @ ..] => {
+                    submit(label.clone(), program.clone(), rest.to_vec())
+                        .map_err(|e| OmniboxError::CommandError(e.to_string()))?;
+                    launchctl::record(&OmniboxCommand::Submit(args.clone()), None);
+
+                    self.cbsink_channel
+                        .send(dialog::show_error(format!("Submitted {}", label)))
+                        .expect("Must show submitted");
+                    Ok(None)
+                }
+                _ => Err(OmniboxError::CommandError(
+                    "Usage: submit <label> <program> [args]".to_string(),
+                )),
+            },
+            OmniboxEvent::Command(OmniboxCommand::GetEnv(target, args)) => match args.as_slice() {
+                [key] => {
+                    let value = getenv(key.clone(), &target)
+                        .map_err(|e| OmniboxError::CommandError(e.to_string()))?;
+
+                    self.cbsink_channel
+                        .send(dialog::show_error(format!("{}={}", key, value)))
+                        .expect("Must show value");
+                    Ok(None)
+                }
+                _ => Err(OmniboxError::CommandError(
+                    "Usage: getenv <key>".to_string(),
+                )),
+            },
+            OmniboxEvent::Command(OmniboxCommand::Snapshot(args)) => match args.as_slice() {
+                [path] => {
+                    Snapshot::capture()
+                        .save(path)
+                        .map_err(OmniboxError::CommandError)?;
+                    Ok(None)
+                }
+                _ => Err(OmniboxError::CommandError(
+                    "Usage: snapshot <path>".to_string(),
+                )),
+            },
+            OmniboxEvent::Command(OmniboxCommand::SnapshotDiff(args)) => {
+                let (old, new, title) = match args.as_slice() {
+                    [old] => (old, None, format!("{} -> now", old)),
+                    [old, new] => (old, Some(new), format!("{} -> {}", old, new)),
+                    _ => {
+                        return Err(OmniboxError::CommandError(
+                            "Usage: diff <old> [new]".to_string(),
+                        ))
+                    }
+                };
+
+                let old = Snapshot::read(old).map_err(OmniboxError::CommandError)?;
+                let new = match new {
+                    Some(path) => Snapshot::read(path).map_err(OmniboxError::CommandError)?,
+                    None => Snapshot::capture(),
+                };
+
+                self.cbsink_channel
+                    .send(show_snapshot_diff(title, old.diff(&new)))
+                    .expect("Must show diff");
+
+                Ok(None)
+            }
+            OmniboxEvent::Command(OmniboxCommand::Overrides(args)) => match args.as_slice() {
+                [action, path] if action == "save" => {
+                    Overrides::capture()
+                        .save(path)
+                        .map_err(OmniboxError::CommandError)?;
+                    Ok(None)
+                }
+                [action, path] if action == "apply" => {
+                    let (changes, blocked) = Overrides::read(path)
+                        .map_err(OmniboxError::CommandError)?
+                        .checked_changes_from(&Overrides::capture(), &Preflight::current());
+
+                    if changes.is_empty() && blocked.is_empty() {
+                        return Err(OmniboxError::CommandError(format!(
+                            "Nothing to change, already matches {}",
+                            path
+                        )));
+                    }
+
+                    if changes.is_empty() {
+                        return Err(OmniboxError::CommandError(format!(
+                            "Every change is blocked:\n\n{}",
+                            blocked.join("\n")
+                        )));
+                    }
+
+                    let lines: Vec<String> = changes.iter().map(|c| c.to_string()).collect();
+                    let mut summary =
+                        format!("{} change(s):\n\n{}", changes.len(), lines.join("\n"));
+                    if !blocked.is_empty() {
+                        summary
+                            .push_str(&format!("\n\nSkipped, blocked:\n\n{}", blocked.join("\n")));
+                    }
+
+                    // Disabling protected jobs is typed, as from the service list
+                    let protected = changes.iter().filter(|c| c.disables_protected()).count();
+                    if protected > 0 {
+                        return Ok(Some(OmniboxCommand::TypedConfirm(
+                            format!(
+                                "{}\n\n{} protected system job(s) would be disabled, which can break your session.\n\nType {} to continue.",
+                                summary, protected, protected
+                            ),
+                            protected.to_string(),
+                            vec![OmniboxCommand::ApplyOverrides(path.clone())],
+                        )));
+                    }
+
+                    Ok(Some(OmniboxCommand::Confirm(
+                        summary,
+                        vec![OmniboxCommand::ApplyOverrides(path.clone())],
+                    )))
+                }
+                _ => Err(OmniboxError::CommandError(
+                    "Usage: overrides <save|apply> <path>".to_string(),
+                )),
+            },
+            OmniboxEvent::Command(OmniboxCommand::ApplyOverrides(path)) => {
+                let (changes, mut errors) = Overrides::read(&path)
+                    .map_err(OmniboxError::CommandError)?
+                    .checked_changes_from(&Overrides::capture(), &Preflight::current());
+
+                for change in &changes {
+                    match (change.apply(), change.target.parse::<DomainTarget>()) {
+                        (Err(e), _) => errors.push(e),
+                        (Ok(()), Ok(target)) => {
+                            launchctl::record_enabled(&change.label, &target, !change.disabled)
+                        }
+                        (Ok(()), Err(_)) => (),
+                    }
+                }
+
+                if errors.is_empty() {
+                    Ok(None)
+                } else {
+                    Err(OmniboxError::CommandError(errors.join("\n")))
+                }
+            }
+            OmniboxEvent::Command(OmniboxCommand::UserScopeRequest) => {
+                if rs_geteuid() != 0 {
+                    return Ok(Some(OmniboxCommand::Confirm(
+                        "This requires root privileges. Sudo and restart?".to_string(),
+                        vec![OmniboxCommand::Quit, OmniboxCommand::Sudo],
+                    )));
+                }
+
+                self.cbsink_channel
+                    .send(dialog::user_scope_prompt(
+                        self.omnibox_tx.clone(),
+                        logged_in_users(),
+                    ))
+                    .expect("Must show prompt");
+                Ok(None)
+            }
+            OmniboxEvent::Command(OmniboxCommand::UserScope(uid)) => {
+                set_user_scope(uid);
+                Ok(None)
+            }
+            OmniboxEvent::Command(OmniboxCommand::Target(args)) => match args.first() {
+                Some(a) if a == "ask" => Ok(Some(OmniboxCommand::SetDomainTarget(None))),
+                Some(a) => a
+                    .parse()
+                    .map(|t| Some(OmniboxCommand::SetDomainTarget(Some(t))))
+                    .map_err(OmniboxError::CommandError),
+                None => {
+                    // Only root can act on other users' domains
+                    let uid = rs_geteuid();
+                    let mut users = logged_in_users();
+                    if uid != 0 {
+                        users.retain(|u, _| *u == uid);
+                        users
+                            .entry(uid)
+                            .or_insert_with(|| std::env::var("USER").unwrap_or_default());
+                    }
+
+                    self.cbsink_channel
+                        .send(dialog::domain_target_prompt(
+                            self.omnibox_tx.clone(),
+                            domain_target(),
+                            users,
+                        ))
+                        .expect("Must show prompt");
+                    Ok(None)
+                }
+            },
+            OmniboxEvent::Command(OmniboxCommand::Profile(args)) => {
+                let config = config();
+                let profile = args
+                    .first()
+                    .and_then(|name| config.profile(name))
+                    .ok_or_else(|| profile_usage(&config))?;
+
+                let (filter, jtf) = (profile.filter.clone(), profile.job_type_filter());
+                self.layout
+                    .call_on_name("omnibox", |v: &mut OmniboxView| v.set_filters(filter, jtf));
+                Ok(None)
+            }
+            OmniboxEvent::Command(OmniboxCommand::SetDomainTarget(target)) => {
+                set_domain_target(target);
+                ENTRY_STATUS_CACHE.invalidate_all();
+
+                self.cbsink_channel
+                    .send(Box::new(|siv| {
+                        siv.call_on_name("domain_target", |v: &mut TextView| {
+                            v.set_content(domain_target_badge())
+                        });
+                    }))
+                    .expect("Must update target");
+                Ok(None)
+            }
+            OmniboxEvent::Command(OmniboxCommand::NewJobPrompt) => {
+                self.cbsink_channel
+                    .send(dialog::new_job_prompt(self.omnibox_tx.clone()))
+                    .expect("Must show prompt");
+                Ok(None)
+            }
+            OmniboxEvent::Command(OmniboxCommand::ColumnPicker(columns)) => {
+                self.cbsink_channel
+                    .send(dialog::column_picker(self.omnibox_tx.clone(), columns))
+                    .expect("Must show prompt");
+                Ok(None)
+            }
+            OmniboxEvent::Command(OmniboxCommand::DebugPrompt(dt)) => {
+                self.cbsink_channel
+                    .send(dialog::debug_prompt(self.omnibox_tx.clone(), dt))
+                    .expect("Must show prompt");
+                Ok(None)
+            }
+            OmniboxEvent::Command(OmniboxCommand::About) => {
+                self.cbsink_channel
+                    .send(dialog::show_about())
+                    .expect("Must show about");
+                Ok(None)
+            }
+            OmniboxEvent::Command(OmniboxCommand::Audit) => {
+                run_with_spinner(
+                    &self.runtime_handle,
+                    self.cbsink_channel.clone(),
+                    "audit".to_string(),
+                    || Ok(audit()),
+                    |siv, findings| show_audit(findings)(siv),
+                );
+
+                Ok(None)
+            }
+            OmniboxEvent::Command(OmniboxCommand::ThirdParty) => {
+                let tx = self.omnibox_tx.clone();
+                run_with_spinner(
+                    &self.runtime_handle,
+                    self.cbsink_channel.clone(),
+                    "thirdparty".to_string(),
+                    || Ok(third_party_agents()),
+                    move |siv, agents| show_third_party(tx, agents)(siv),
+                );
+
+                Ok(None)
+            }
+            OmniboxEvent::Command(OmniboxCommand::SetThirdPartyEnabled(labels, enabled)) => {
+                let (domain_type, handle) = LaunchSession::current().default_domain();
+                let target = DomainTarget::new(domain_type, handle.unwrap_or(0));
+                let count = labels.len();
+
+                let work = {
+                    let target = target.clone();
+                    move || {
+                        // What they were before, `overrides apply` puts it back
+                        let manifest = Overrides::capture_labels(&target, &labels)?;
+                        let dir = manifest_dir();
+                        let path = dir.join(format!("third-party-{}.json", manifest.taken_at));
+                        let path = path.to_string_lossy().to_string();
+
+                        fs::create_dir_all(&dir)
+                            .map_err(|e| e.to_string())
+                            .and_then(|_| manifest.save(&path))?;
+
+                        let errors: Vec<String> = labels
+                            .iter()
+                            .filter_map(|l| match set_enabled_in(l, &target, enabled) {
+                                Ok(_) => {
+                                    launchctl::record_enabled(l, &target, enabled);
+                                    None
+                                }
+                                Err(e) => Some(format!("{}: {}", l, e)),
+                            })
+                            .collect();
+
+                        Ok((path, errors))
+                    }
+                };
+
+                run_with_spinner(
+                    &self.runtime_handle,
+                    self.cbsink_channel.clone(),
+                    "thirdparty".to_string(),
+                    work,
+                    move |siv, (path, errors): (String, Vec<String>)| {
+                        let verb = if enabled { "Enabled" } else { "Disabled" };
+                        let mut notice = format!(
+                            "{} {} agent(s) in {}\n\nUndo with: overrides apply {}",
+                            verb,
+                            count - errors.len(),
+                            target,
+                            path
+                        );
+
+                        if !errors.is_empty() {
+                            notice.push_str(&format!("\n\nFailed:\n{}", errors.join("\n")));
+                        }
+
+                        dialog::show_error(notice)(siv)
+                    },
+                );
+
+                Ok(None)
+            }
+            OmniboxEvent::Command(OmniboxCommand::Limit(args)) => {
+                let current = limits().map_err(|e| OmniboxError::CommandError(e.to_string()))?;
+
+                let (name, soft, hard) = match args.as_slice() {
+                    [] => {
+                        self.cbsink_channel
+                            .send(dialog::limit_prompt(self.omnibox_tx.clone(), current))
+                            .expect("Must show prompt");
+                        return Ok(None);
+                    }
+                    [name] => (name, None, None),
+                    [name, soft] => (name, Some(soft), None),
+                    [name, soft, hard] => (name, Some(soft), Some(hard)),
+                    _ => {
+                        return Err(OmniboxError::CommandError(
+                            "Usage: limit [<name> [<soft> [<hard>]]]".to_string(),
+                        ))
+                    }
+                };
+
+                let name = resource_name(name).ok_or_else(|| {
+                    OmniboxError::CommandError(format!("Unknown resource {}", name))
+                })?;
+                let limit = current.get(name).copied().unwrap_or_default();
+
+                let soft = match soft {
+                    Some(soft) => soft,
+                    None => {
+                        self.cbsink_channel
+                            .send(dialog::show_error(format!("{}: {}", name, limit)))
+                            .expect("Must show limit");
+                        return Ok(None);
+                    }
+                };
+
+                if rs_geteuid() != 0 {
+                    return Ok(Some(OmniboxCommand::Confirm(
+                        "This requires root privileges. Sudo and restart?".to_string(),
+                        vec![OmniboxCommand::Quit, OmniboxCommand::Sudo],
+                    )));
+                }
+
+                // Like launchctl limit, the hard limit follows a lone soft one
+                let soft = parse_limit_value(soft).map_err(OmniboxError::CommandError)?;
+                let hard = hard
+                    .map(|h| parse_limit_value(h))
+                    .transpose()
+                    .map_err(OmniboxError::CommandError)?
+                    .unwrap_or(soft);
+
+                let new = Limit {
+                    soft: Some(soft),
+                    hard: Some(hard),
+                };
+                if new == limit {
+                    return Ok(None);
+                }
+
+                set_limit(name, new).map_err(|e| OmniboxError::CommandError(e.to_string()))?;
+                launchctl::record(&OmniboxCommand::Limit(args.clone()), None);
+                Ok(self.refresh_job_detail())
+            }
+            OmniboxEvent::Command(OmniboxCommand::LoginItems) => {
+                if rs_geteuid() != 0 {
+                    return Ok(Some(OmniboxCommand::Confirm(
+                        "This requires root privileges. Sudo and restart?".to_string(),
+                        vec![OmniboxCommand::Quit, OmniboxCommand::Sudo],
+                    )));
+                }
+
+                let tx = self.omnibox_tx.clone();
+                run_with_spinner(
+                    &self.runtime_handle,
+                    self.cbsink_channel.clone(),
+                    "sfltool dumpbtm".to_string(),
+                    login_items,
+                    move |siv, items| show_login_items(tx, items)(siv),
+                );
+
+                Ok(None)
+            }
+            OmniboxEvent::Command(OmniboxCommand::SetLoginItemEnabled(label, target, enabled)) => {
+                set_enabled_in(&label, &target, enabled)
+                    .map_err(|e| OmniboxError::CommandError(e.to_string()))?;
+                launchctl::record_enabled(&label, &target, enabled);
+
+                let verb = if enabled { "Enabled" } else { "Disabled" };
+                self.cbsink_channel
+                    .send(dialog::show_error(format!(
+                        "{} {} in {}",
+                        verb, label, target
+                    )))
+                    .expect("Must show notice");
+
+                // Listed again over the notice
+                Ok(Some(OmniboxCommand::LoginItems))
+            }
+            OmniboxEvent::Command(OmniboxCommand::DomainErrors) => {
+                let errors = list_errors();
+                let text = if errors.is_empty() {
+                    "Every domain was listed".to_string()
+                } else {
+                    errors
+                        .iter()
+                        .map(|(domain, e)| format!("{}: {}", domain, e))
+                        .collect::<Vec<String>>()
+                        .join("\n")
+                };
+
+                self.cbsink_channel
+                    .send(dialog::show_error(text))
+                    .expect("Must show errors");
+
+                Ok(None)
+            }
+            OmniboxEvent::Command(OmniboxCommand::ProcessTree) => {
+                let tx = self.omnibox_tx.clone();
+                run_with_spinner(
+                    &self.runtime_handle,
+                    self.cbsink_channel.clone(),
+                    "Reading process tree".to_string(),
+                    job_trees,
+                    move |siv, trees| show_process_tree(tx, trees)(siv),
+                );
+
+                Ok(None)
+            }
+            OmniboxEvent::Command(OmniboxCommand::KillPid(pid, signal)) => {
+                if unsafe { libc::kill(pid, signal) } != 0 {
+                    return Err(OmniboxError::CommandError(format!(
+                        "kill {}: {}",
+                        pid,
+                        std::io::Error::last_os_error()
+                    )));
+                }
+
+                Ok(None)
+            }
             OmniboxEvent::Command(OmniboxCommand::CSRInfo) => {
                 self.cbsink_channel
                     .send(show_csr_info())
@@ -287,27 +1025,268 @@ impl OmniboxSubscriber for RootLayout {
 
                 Ok(None)
             }
+            OmniboxEvent::Command(OmniboxCommand::PrintDomain(args)) => {
+                let target = match args.as_slice() {
+                    [target] => target.clone(),
+                    _ => {
+                        return Err(OmniboxError::CommandError(
+                            "Usage: print <domain target>[/<label>], e.g. system or gui/501"
+                                .to_string(),
+                        ))
+                    }
+                };
+
+                let print: PrintQuery = match resolve_domain_target(&target) {
+                    Ok((domain_type, handle)) => {
+                        Box::new(move || print_domain(domain_type, handle))
+                    }
+                    // One service, e.g. pid/123/com.example.xpcservice
+                    Err(e) => {
+                        let (service_target, label) = parse_service_target(&target)
+                            .map_err(|_| OmniboxError::CommandError(e))?;
+                        Box::new(move || print_service(&service_target, &label))
+                    }
+                };
+                let title = format!("print {}", target);
+
+                run_with_spinner(
+                    &self.runtime_handle,
+                    self.cbsink_channel.clone(),
+                    title.clone(),
+                    move || print().map_err(|e| e.to_string()),
+                    |siv, (size, shmem)| show_state_pager(siv, title, shmem, size),
+                );
+
+                Ok(None)
+            }
+            OmniboxEvent::Command(OmniboxCommand::PrintService(target, label)) => {
+                let title = format!("print {}/{}", target, label);
+
+                run_with_spinner(
+                    &self.runtime_handle,
+                    self.cbsink_channel.clone(),
+                    title.clone(),
+                    move || print_service(&target, &label).map_err(|e| e.to_string()),
+                    |siv, (size, shmem)| show_state_pager(siv, title, shmem, size),
+                );
+
+                Ok(None)
+            }
+            // No pid is for the service list, with the highlighted job's
+            OmniboxEvent::Command(OmniboxCommand::PidDomain(args)) if !args.is_empty() => {
+                let pid: u64 = match args.as_slice() {
+                    [pid] => pid.parse().ok().filter(|p| *p > 0),
+                    _ => None,
+                }
+                .ok_or_else(|| OmniboxError::CommandError("Usage: piddomain [pid]".to_string()))?;
+
+                let tx = self.omnibox_tx.clone();
+
+                run_with_spinner(
+                    &self.runtime_handle,
+                    self.cbsink_channel.clone(),
+                    format!("pid/{}", pid),
+                    move || list_pid_domain(pid).map_err(|e| format!("pid/{}: {}", pid, e)),
+                    move |siv, services| show_pid_domain(tx, pid, services)(siv),
+                );
+
+                Ok(None)
+            }
+            OmniboxEvent::Command(OmniboxCommand::AttachDebugger(pid)) => {
+                self.cbsink_channel
+                    .send(Box::new(move |siv| {
+                        if let Err(e) = attach_debugger(siv, pid) {
+                            dialog::show_error(e)(siv);
+                        }
+                    }))
+                    .expect("Must attach");
+
+                Ok(None)
+            }
+            OmniboxEvent::Command(OmniboxCommand::WhoHas(args)) => {
+                let service = match args.as_slice() {
+                    [service] => service.clone(),
+                    _ => {
+                        return Err(OmniboxError::CommandError(
+                            "Usage: whohas <service name>".to_string(),
+                        ))
+                    }
+                };
+
+                run_with_spinner(
+                    &self.runtime_handle,
+                    self.cbsink_channel.clone(),
+                    format!("whohas {}", service),
+                    {
+                        let service = service.clone();
+                        move || whohas(&service)
+                    },
+                    |siv, owners| show_whohas(service, owners)(siv),
+                );
+
+                Ok(None)
+            }
             OmniboxEvent::Command(OmniboxCommand::DumpState) => {
-                let (size, shmem) =
-                    dumpstate().map_err(|e| OmniboxError::CommandError(e.to_string()))?;
+                run_with_spinner(
+                    &self.runtime_handle,
+                    self.cbsink_channel.clone(),
+                    "dumpstate".to_string(),
+                    || {
+                        let (size, shmem) = dumpstate().map_err(|e| e.to_string())?;
+
+                        log::info!("shmem response sz {}", size);
+
+                        Ok((size, shmem))
+                    },
+                    |siv, (size, shmem)| {
+                        show_state_pager(siv, "dumpstate".to_string(), shmem, size)
+                    },
+                );
+
+                Ok(None)
+            }
+            OmniboxEvent::Command(OmniboxCommand::Logs(args)) => {
+                let level = match args.first() {
+                    Some(level) => level.parse().map_err(|_| {
+                        OmniboxError::CommandError(format!("Unknown log level {}", level))
+                    })?,
+                    None => Level::Info,
+                };
 
-                log::info!("shmem response sz {}", size);
+                self.cbsink_channel
+                    .send(show_app_logs(level))
+                    .expect("Must show logs");
+
+                Ok(None)
+            }
+            OmniboxEvent::Command(OmniboxCommand::Xpc(args)) => {
+                let input = args.join(" ");
+                if input.is_empty() {
+                    return Err(OmniboxError::CommandError(
+                        "Usage: xpc {subsystem: 3, routine: 815, type: 1, handle: 0}".to_string(),
+                    ));
+                }
+
+                // Catch syntax errors before the spinner
+                raw_xpc::parse(&input).map_err(OmniboxError::CommandError)?;
+
+                run_with_spinner(
+                    &self.runtime_handle,
+                    self.cbsink_channel.clone(),
+                    "xpc".to_string(),
+                    {
+                        let input = input.clone();
+                        move || raw_xpc::send(&input)
+                    },
+                    move |siv, reply| {
+                        show_text_pager(
+                            siv,
+                            format!("xpc {}", input),
+                            format!("{}\n\n{}", input, reply).into_bytes(),
+                        )
+                    },
+                );
+
+                Ok(None)
+            }
+            OmniboxEvent::Command(OmniboxCommand::Refresh) => {
+                run_with_progress(
+                    &self.runtime_handle,
+                    self.cbsink_channel.clone(),
+                    "Reading plists".to_string(),
+                    |progress| {
+                        let count = rescan_plists(|done, total| {
+                            progress.set(done, total);
+                            !progress.is_cancelled()
+                        })?;
+
+                        refresh_dumpstate_jobs();
+                        ENTRY_STATUS_CACHE.invalidate_all();
+
+                        Ok(count)
+                    },
+                    |siv, count| dialog::show_error(format!("Read {} plists", count))(siv),
+                );
 
-                show_pager(&self.cbsink_channel, unsafe {
-                    &*slice_from_raw_parts(shmem.region as *mut u8, size)
-                })
-                .map_err(|e| OmniboxError::CommandError(e))?;
+                Ok(None)
+            }
+            OmniboxEvent::Command(OmniboxCommand::DumpStateToFile(path)) => {
+                run_with_progress(
+                    &self.runtime_handle,
+                    self.cbsink_channel.clone(),
+                    format!("dumpstate > {}", path),
+                    {
+                        let path = path.clone();
+                        move |progress| {
+                            dumpstate_to_file(&path, |done, total| {
+                                progress.set(done, total);
+                                !progress.is_cancelled()
+                            })
+                        }
+                    },
+                    move |siv, size| {
+                        dialog::show_error(format!("Wrote {} bytes of dumpstate to {}", size, path))(
+                            siv,
+                        )
+                    },
+                );
 
                 Ok(None)
             }
             OmniboxEvent::Command(OmniboxCommand::DumpJetsamPropertiesCategory) => {
-                let (size, shmem) =
-                    dumpjpcategory().map_err(|e| OmniboxError::CommandError(e.to_string()))?;
+                run_with_spinner(
+                    &self.runtime_handle,
+                    self.cbsink_channel.clone(),
+                    "dumpjpcategory".to_string(),
+                    || dumpjpcategory().map_err(|e| e.to_string()),
+                    |siv, bytes| show_text_pager(siv, "dumpjpcategory".to_string(), bytes),
+                );
+
+                Ok(None)
+            }
+            OmniboxEvent::Command(OmniboxCommand::PrintCache(args)) => {
+                let pid = parse_pid(args.first())?;
+                let title = match pid {
+                    Some(pid) => format!("print-cache {}", pid),
+                    None => "print-cache".to_string(),
+                };
+
+                run_with_spinner(
+                    &self.runtime_handle,
+                    self.cbsink_channel.clone(),
+                    title.clone(),
+                    move || print_cache(pid).map_err(|e| e.to_string()),
+                    move |siv, text| show_text_pager(siv, title, text.into_bytes()),
+                );
+
+                Ok(None)
+            }
+            OmniboxEvent::Command(OmniboxCommand::Uncache(args)) => {
+                let service = args.first().cloned().ok_or_else(|| {
+                    OmniboxError::CommandError("Usage: uncache <service name> [pid]".to_string())
+                })?;
+                let pid = parse_pid(args.get(1))?;
+
+                uncache(service.clone(), pid)
+                    .map_err(|e| OmniboxError::CommandError(e.to_string()))?;
+                launchctl::record(&OmniboxCommand::Uncache(args.clone()), None);
+
+                self.cbsink_channel
+                    .send(dialog::show_error(format!(
+                        "Next lookup of {} bypasses the XPC service cache",
+                        service
+                    )))
+                    .expect("Must show notice");
 
-                show_pager(&self.cbsink_channel, unsafe {
-                    &*slice_from_raw_parts(shmem.region as *mut u8, size)
-                })
-                .map_err(|e| OmniboxError::CommandError(e))?;
+                Ok(None)
+            }
+            OmniboxEvent::Command(OmniboxCommand::CrashReport(path)) => {
+                let bytes = std::fs::read(&path)
+                    .map_err(|e| OmniboxError::CommandError(format!("{}: {}", path, e)))?;
+
+                self.cbsink_channel
+                    .send(Box::new(move |siv| show_text_pager(siv, path, bytes)))
+                    .expect("Must show report");
 
                 Ok(None)
             }
@@ -318,6 +1297,13 @@ impl OmniboxSubscriber for RootLayout {
 
                 Ok(None)
             }
+            OmniboxEvent::Command(OmniboxCommand::Tour) => {
+                self.cbsink_channel
+                    .send(show_tour())
+                    .expect("Must show tour");
+
+                Ok(None)
+            }
             _ => Ok(None),
         }
     }
@@ -0,0 +1,217 @@
+use regex::{Regex, RegexBuilder};
+
+use crate::launchd::entry_status::JobState;
+use crate::launchd::job_type_filter::JobTypeFilter;
+use crate::launchd::plist::plist_program;
+use crate::launchd::signing::{running_executable, running_team_id};
+use crate::tui::service_list::list_item::ServiceListItem;
+
+const MATCH_SCORE: i64 = 16;
+const CONSECUTIVE_BONUS: i64 = 24;
+const BOUNDARY_BONUS: i64 = 32;
+const SUBSTRING_BONUS: i64 = 256;
+const GAP_PENALTY: i64 = 1;
+
+/// Label filter from the omnibox: fuzzy by default, or regex with a `/` prefix
+#[derive(Debug, Clone)]
+pub enum LabelFilter {
+    None,
+    Fuzzy(String),
+    Regex(Regex),
+}
+
+impl LabelFilter {
+    pub fn new(filter: &str) -> Self {
+        match filter.strip_prefix('/') {
+            _ if filter.is_empty() => LabelFilter::None,
+            // Fall back to fuzzy while the pattern is incomplete
+            Some(pattern) => RegexBuilder::new(pattern)
+                .case_insensitive(true)
+                .build()
+                .map(LabelFilter::Regex)
+                .unwrap_or_else(|_| LabelFilter::Fuzzy(pattern.to_string())),
+            None => LabelFilter::Fuzzy(filter.to_string()),
+        }
+    }
+
+    /// Score label against the filter, higher is better. None if no match.
+    pub fn score(&self, label: &str) -> Option<i64> {
+        match self {
+            LabelFilter::None => Some(0),
+            LabelFilter::Fuzzy(pattern) => fuzzy_score(pattern, label),
+            LabelFilter::Regex(re) => re
+                .find(label)
+                .map(|m| -(m.start() as i64) - (m.as_str().len() as i64)),
+        }
+    }
+}
+
+/// A `name:value` term in the filter
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum FieldFilter {
+    /// Executable contains this, or the plist's program if not running
+    Path(String),
+    /// Team ID of a running job's executable starts with this
+    Team(String),
+    Pid(i64),
+    Status(JobState),
+}
+
+impl FieldFilter {
+    /// None if not a field, or the value is not valid (yet)
+    pub fn parse(term: &str) -> Option<Self> {
+        let (name, value) = term.split_once(':')?;
+
+        match name {
+            "path" if !value.is_empty() => Some(FieldFilter::Path(value.to_string())),
+            "team" if !value.is_empty() => Some(FieldFilter::Team(value.to_string())),
+            "pid" => value.parse().ok().map(FieldFilter::Pid),
+            "status" => JobState::from_name(value).map(FieldFilter::Status),
+            _ => None,
+        }
+    }
+
+    pub fn matches(&self, item: &ServiceListItem) -> bool {
+        let pid = Some(item.status.pid)
+            .filter(|p| *p > 0 && item.job_type_filter.intersects(JobTypeFilter::LOADED));
+
+        match self {
+            FieldFilter::Path(path) => pid
+                .and_then(running_executable)
+                .or_else(|| item.plist_path.as_deref().and_then(plist_program))
+                .map(|p| p.contains(path.as_str()))
+                .unwrap_or(false),
+            FieldFilter::Team(team) => pid
+                .and_then(running_team_id)
+                .map(|t| {
+                    t.to_ascii_uppercase()
+                        .starts_with(&team.to_ascii_uppercase())
+                })
+                .unwrap_or(false),
+            FieldFilter::Pid(p) => pid == Some(*p),
+            FieldFilter::Status(state) => item.state == *state,
+        }
+    }
+}
+
+/// Everything typed in filter mode: `path:`, `team:`, `pid:` and `status:`
+/// terms, with whatever is left over matched against the label
+#[derive(Debug, Clone)]
+pub struct ServiceFilter {
+    pub label: LabelFilter,
+    pub fields: Vec<FieldFilter>,
+}
+
+impl ServiceFilter {
+    pub fn new(filter: &str) -> Self {
+        let mut fields = vec![];
+        let mut rest = vec![];
+
+        for term in filter.split(' ') {
+            match FieldFilter::parse(term) {
+                Some(field) => fields.push(field),
+                None => rest.push(term),
+            }
+        }
+
+        ServiceFilter {
+            label: LabelFilter::new(rest.join(" ").trim()),
+            fields,
+        }
+    }
+
+    /// Every field term matches
+    pub fn matches(&self, item: &ServiceListItem) -> bool {
+        self.fields.iter().all(|f| f.matches(item))
+    }
+}
+
+impl Default for ServiceFilter {
+    fn default() -> Self {
+        ServiceFilter {
+            label: LabelFilter::None,
+            fields: vec![],
+        }
+    }
+}
+
+fn is_boundary(c: char) -> bool {
+    matches!(c, '.' | '-' | '_' | '/' | ' ')
+}
+
+/// Case insensitive subsequence match, rewarding runs of consecutive characters,
+/// matches at the start of a label component, and plain substrings
+pub fn fuzzy_score(pattern: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.chars().collect();
+    let mut score = 0;
+    let mut next = 0;
+    let mut last_match: Option<usize> = None;
+
+    for pc in pattern.chars().map(|c| c.to_ascii_lowercase()) {
+        let pos = (next..text.len()).find(|i| text[*i].to_ascii_lowercase() == pc)?;
+
+        score += MATCH_SCORE;
+
+        if pos == 0 || is_boundary(text[pos - 1]) {
+            score += BOUNDARY_BONUS;
+        }
+
+        match last_match {
+            Some(last) if last + 1 == pos => score += CONSECUTIVE_BONUS,
+            Some(last) => score -= GAP_PENALTY * (pos - last - 1) as i64,
+            None => {}
+        }
+
+        last_match = Some(pos);
+        next = pos + 1;
+    }
+
+    let lower: String = text.iter().collect::<String>().to_ascii_lowercase();
+    if lower.contains(&pattern.to_ascii_lowercase()) {
+        score += SUBSTRING_BONUS;
+    }
+
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fuzzy_score, FieldFilter, LabelFilter, ServiceFilter};
+    use crate::launchd::entry_status::JobState;
+
+    #[test]
+    fn fuzzy_prefers_substring_and_boundaries() {
+        let substring = fuzzy_score("spot", "com.apple.Spotlight").unwrap();
+        let scattered = fuzzy_score("spot", "com.apple.sharepoint").unwrap();
+
+        assert!(substring > scattered);
+        assert_eq!(fuzzy_score("xyz", "com.apple.Spotlight"), None);
+    }
+
+    #[test]
+    fn regex_prefix() {
+        let filter = LabelFilter::new("/^com\\.apple\\.(finder|dock)$");
+
+        assert!(filter.score("com.apple.Dock").is_some());
+        assert!(filter.score("com.apple.dock.extra").is_none());
+    }
+
+    #[test]
+    fn field_terms() {
+        let filter = ServiceFilter::new("spot path:/usr/libexec status:crashed pid:12x");
+
+        assert_eq!(
+            filter.fields,
+            vec![
+                FieldFilter::Path("/usr/libexec".to_string()),
+                FieldFilter::Status(JobState::Crashed)
+            ]
+        );
+        // Incomplete terms are matched against the label
+        assert!(filter.label.score("com.apple.Spotlight").is_none());
+        assert!(ServiceFilter::new("team:ABC")
+            .label
+            .score("anything")
+            .is_some());
+    }
+}
@@ -0,0 +1,237 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use xpc_sys::enums::DomainType;
+use xpc_sys::rs_geteuid;
+
+use crate::launchd::domain_target::DomainTarget;
+use crate::launchd::plist::for_label;
+use crate::tui::omnibox::command::{BatchAction, OmniboxCommand};
+
+/// Lines recorded this close together are one change for :copycmd
+const GROUP: Duration = Duration::from_secs(1);
+
+lazy_static! {
+    /// launchctl lines for the last change, and when it was made
+    static ref LAST_CHANGE: Mutex<Option<(Instant, Vec<String>)>> = Mutex::new(None);
+}
+
+/// Quote for sh if needed, e.g. 'a b'
+fn quote(arg: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "_-./:=@%+,".contains(c);
+
+    if !arg.is_empty() && arg.chars().all(plain) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// What launchd picks for no handle: the caller's uid in user domains
+fn target(domain_type: &DomainType, handle: Option<u64>) -> DomainTarget {
+    let handle = match domain_type {
+        DomainType::User | DomainType::UserLogin | DomainType::Gui => {
+            handle.unwrap_or_else(|| rs_geteuid() as u64)
+        }
+        _ => handle.unwrap_or(0),
+    };
+
+    DomainTarget::new(domain_type.clone(), handle)
+}
+
+fn plist_path(label: &str) -> String {
+    for_label(label)
+        .map(|p| quote(&p.plist_path))
+        .unwrap_or_else(|| "<plist>".to_string())
+}
+
+/// A batch action on one job, with the domain it resolved to
+pub fn batch_command(action: &BatchAction, dt: DomainType, handle: Option<u64>) -> OmniboxCommand {
+    match action {
+        BatchAction::Load(st, _) => OmniboxCommand::Load(st.clone(), dt, handle),
+        BatchAction::Unload(_) => OmniboxCommand::Unload(dt, handle),
+        BatchAction::Enable(_) => OmniboxCommand::Enable(dt, handle),
+        BatchAction::Disable(_) => OmniboxCommand::Disable(dt, handle),
+        BatchAction::Kill(_, signal) => OmniboxCommand::Kill(dt, *signal),
+    }
+}
+
+/// launchctl command lines doing what cmd does to label. Empty when
+/// cmd changes nothing or launchctl has no equivalent.
+pub fn launchctl_lines(cmd: &OmniboxCommand, label: Option<&str>) -> Vec<String> {
+    let service = |dt: &DomainType, handle: Option<u64>, label: &str| {
+        format!("{}/{}", target(dt, handle), quote(label))
+    };
+
+    let line = match (cmd, label) {
+        (OmniboxCommand::Chain(cmds), _) => {
+            return cmds
+                .iter()
+                .flat_map(|c| launchctl_lines(c, label))
+                .collect()
+        }
+        (OmniboxCommand::Load(_, dt, handle), Some(label)) => format!(
+            "launchctl bootstrap {} {}",
+            target(dt, *handle),
+            plist_path(label)
+        ),
+        (OmniboxCommand::Unload(dt, handle), Some(label)) => {
+            format!("launchctl bootout {}", service(dt, *handle, label))
+        }
+        (OmniboxCommand::Enable(dt, handle), Some(label)) => {
+            format!("launchctl enable {}", service(dt, *handle, label))
+        }
+        (OmniboxCommand::Disable(dt, handle), Some(label)) => {
+            format!("launchctl disable {}", service(dt, *handle, label))
+        }
+        (OmniboxCommand::Kickstart(dt, kill_first), Some(label)) => format!(
+            "launchctl kickstart {}{}",
+            if *kill_first { "-k " } else { "" },
+            service(dt, None, label)
+        ),
+        (OmniboxCommand::Kill(dt, signal), Some(label)) => {
+            format!("launchctl kill {} {}", signal, service(dt, None, label))
+        }
+        // Legacy subcommands, no domain target
+        (OmniboxCommand::Start(_), Some(label)) => format!("launchctl start {}", quote(label)),
+        (OmniboxCommand::Stop(_), Some(label)) => format!("launchctl stop {}", quote(label)),
+        (OmniboxCommand::Remove, Some(label)) => format!("launchctl remove {}", quote(label)),
+        // Not launchctl, but what changed until the reload
+        (OmniboxCommand::RestoreBackup(backup), Some(label)) => {
+            format!("cp {} {}", quote(backup), plist_path(label))
+        }
+        (OmniboxCommand::Submit(args), _) if args.len() >= 2 => {
+            let program: Vec<String> = args[1..].iter().map(|a| quote(a)).collect();
+            format!(
+                "launchctl submit -l {} -- {}",
+                quote(&args[0]),
+                program.join(" ")
+            )
+        }
+        (OmniboxCommand::SetEnv(_, args), _) | (OmniboxCommand::UnsetEnv(_, args), _) => {
+            let verb = match cmd {
+                OmniboxCommand::SetEnv(_, _) => "setenv",
+                _ => "unsetenv",
+            };
+            let args: Vec<String> = args.iter().map(|a| quote(a)).collect();
+            format!("launchctl {} {}", verb, args.join(" "))
+        }
+        (OmniboxCommand::Limit(args), _) if args.len() >= 2 => {
+            let args: Vec<String> = args.iter().map(|a| quote(a)).collect();
+            format!("launchctl limit {}", args.join(" "))
+        }
+        (OmniboxCommand::Uncache(args), _) if !args.is_empty() => {
+            let args: Vec<String> = args.iter().map(|a| quote(a)).collect();
+            format!("launchctl uncache {}", args.join(" "))
+        }
+        _ => return vec![],
+    };
+
+    vec![line]
+}
+
+/// Keep what cmd did to label for :copycmd and the app log, once it has
+/// succeeded. Commands sent together, e.g. unload then load, or a batch,
+/// make up one change.
+pub fn record(cmd: &OmniboxCommand, label: Option<&str>) {
+    let lines = launchctl_lines(cmd, label);
+    if lines.is_empty() {
+        return;
+    }
+
+    for line in &lines {
+        log::info!("[launchctl]: {}", line);
+    }
+
+    let mut last = LAST_CHANGE.lock().expect("Must lock launchctl");
+    match &mut *last {
+        Some((at, change)) if at.elapsed() <= GROUP => {
+            *at = Instant::now();
+            change.extend(lines);
+        }
+        _ => *last = Some((Instant::now(), lines)),
+    }
+}
+
+/// launchctl enable or disable of label in target
+pub fn record_enabled(label: &str, target: &DomainTarget, enabled: bool) {
+    let (dt, handle) = (target.domain_type.clone(), Some(target.handle));
+    let cmd = match enabled {
+        true => OmniboxCommand::Enable(dt, handle),
+        false => OmniboxCommand::Disable(dt, handle),
+    };
+
+    record(&cmd, Some(label));
+}
+
+/// launchctl lines for the last change, empty if nothing changed yet
+pub fn last_change() -> Vec<String> {
+    LAST_CHANGE
+        .lock()
+        .expect("Must lock launchctl")
+        .as_ref()
+        .map(|(_, change)| change.clone())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use xpc_sys::enums::{DomainType, SessionType};
+
+    use super::{batch_command, launchctl_lines};
+    use crate::tui::omnibox::command::{BatchAction, OmniboxCommand};
+
+    #[test]
+    fn launchctl_equivalents() {
+        let label = Some("com.example.job");
+
+        assert_eq!(
+            launchctl_lines(
+                &OmniboxCommand::Chain(vec![
+                    OmniboxCommand::Unload(DomainType::Gui, Some(501)),
+                    OmniboxCommand::Load(SessionType::Aqua, DomainType::System, None),
+                ]),
+                label
+            ),
+            vec![
+                "launchctl bootout gui/501/com.example.job",
+                "launchctl bootstrap system <plist>"
+            ]
+        );
+        assert_eq!(
+            launchctl_lines(&OmniboxCommand::Kickstart(DomainType::System, true), label),
+            vec!["launchctl kickstart -k system/com.example.job"]
+        );
+        assert_eq!(
+            launchctl_lines(
+                &batch_command(
+                    &BatchAction::Kill(DomainType::Gui, 9),
+                    DomainType::System,
+                    None
+                ),
+                label
+            ),
+            vec!["launchctl kill 9 system/com.example.job"]
+        );
+        assert_eq!(
+            launchctl_lines(
+                &OmniboxCommand::Submit(vec![
+                    "com.example.once".to_string(),
+                    "/bin/sh".to_string(),
+                    "-c".to_string(),
+                    "echo it's".to_string()
+                ]),
+                None
+            ),
+            vec!["launchctl submit -l com.example.once -- /bin/sh -c 'echo it'\\''s'"]
+        );
+        assert_eq!(
+            launchctl_lines(
+                &OmniboxCommand::RestoreBackup("/tmp/my backup.plist".to_string()),
+                label
+            ),
+            vec!["cp '/tmp/my backup.plist' <plist>"]
+        );
+        assert!(launchctl_lines(&OmniboxCommand::ViewPlist, label).is_empty());
+    }
+}
@@ -1,46 +1,194 @@
 use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::time::Duration;
 
-use crate::launchd::entry_status::LaunchdEntryStatus;
+use xpc_sys::enums::{DomainType, SessionType};
+
+use cursive::theme::Style;
+
+use crate::config::columns::ServiceColumn;
+use crate::launchd::entry_status::{JobState, LaunchdEntryStatus, ProcUsage};
 use crate::launchd::job_type_filter::JobTypeFilter;
 use crate::tui::table::table_list_view::TableListItem;
+use crate::tui::theme::roles;
+
+/// Cell text for a field launchd sent in a shape we cannot read
+const UNPARSEABLE: &str = "unparseable";
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub struct ServiceListItem {
     pub name: String,
     pub status: LaunchdEntryStatus,
     pub job_type_filter: JobTypeFilter,
+    /// Marked for a batch operation
+    pub marked: bool,
+    /// CPU and memory if running
+    pub usage: Option<ProcUsage>,
+    /// Plist on disk, from the standard directories or dumpstate
+    pub plist_path: Option<String>,
+    /// Last exit status, if loaded
+    pub last_exit: Option<i64>,
+    /// Restarts and sparkline of starts since launchk started watching
+    pub restarts: Option<(u64, String)>,
+    pub state: JobState,
+    /// Until launchd may respawn it, if throttled
+    pub throttle: Option<Duration>,
+    /// Loaded, and the plist has been written to since
+    pub plist_changed: bool,
+}
+
+/// Bytes as e.g. 12.3M
+fn human_bytes(bytes: u64) -> String {
+    let units = ["B", "K", "M", "G", "T"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+
+    while value >= 1024.0 && unit < units.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{}{}", bytes, units[unit])
+    } else {
+        format!("{:.1}{}", value, units[unit])
+    }
+}
+
+impl ServiceListItem {
+    /// launchd sent any of keys in a shape we cannot read
+    fn is_unparseable(&self, keys: &[&str]) -> bool {
+        self.status.unparseable.iter().any(|k| keys.contains(k))
+    }
+
+    /// Numeric columns, None if there is nothing to show
+    fn sort_value(&self, column: &ServiceColumn) -> Option<i64> {
+        match column {
+            ServiceColumn::Pid => Some(self.status.pid)
+                .filter(|p| *p > 0 && self.job_type_filter.intersects(JobTypeFilter::LOADED)),
+            ServiceColumn::Cpu => self.usage.map(|u| u.cpu_permille as i64),
+            ServiceColumn::Memory => self.usage.map(|u| u.rss as i64),
+            ServiceColumn::ExitCode => self.last_exit,
+            ServiceColumn::Restarts => self.restarts.as_ref().map(|(n, _)| *n as i64),
+            _ => None,
+        }
+    }
+
+    /// By a column's value, numbers compare as numbers and missing
+    /// values go last
+    pub fn compare(&self, other: &ServiceListItem, column: &ServiceColumn) -> Ordering {
+        match (self.sort_value(column), other.sort_value(column)) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) if *column == ServiceColumn::Label => self.name.cmp(&other.name),
+            (None, None) => self.cell(column).cmp(&other.cell(column)),
+        }
+    }
 }
 
 impl TableListItem for ServiceListItem {
-    fn as_row(&self) -> Vec<String> {
-        let session_type = self.status.limit_load_to_session_type.to_string();
-
-        let entry_type = self
-            .status
-            .plist
-            .borrow()
-            .as_ref()
-            .map(|ec| format!("{}/{}", ec.entry_location, ec.entry_type))
-            .unwrap_or("-".to_string());
-
-        let pid = if self.status.pid > 0 && self.job_type_filter.intersects(JobTypeFilter::LOADED) {
-            format!("{}", self.status.pid)
-        } else {
-            "-".to_string()
-        };
+    type Column = ServiceColumn;
+
+    fn cell(&self, column: &ServiceColumn) -> String {
+        let is_loaded = self.job_type_filter.intersects(JobTypeFilter::LOADED);
+
+        match column {
+            ServiceColumn::Label if self.marked => format!("● {}", self.name),
+            ServiceColumn::Label => self.name.clone(),
+            ServiceColumn::Domain => match self.status.domain {
+                DomainType::Unknown => "-".to_string(),
+                ref domain => domain.to_string(),
+            },
+            ServiceColumn::Pid if self.is_unparseable(&["PID", "service"]) => {
+                UNPARSEABLE.to_string()
+            }
+            ServiceColumn::Session
+                if self.is_unparseable(&["LimitLoadToSessionType", "service"]) =>
+            {
+                UNPARSEABLE.to_string()
+            }
+            // Loaded session type, or what the plist limits it to
+            ServiceColumn::Session => {
+                match (&self.status.limit_load_to_session_type, &self.status.plist) {
+                    (SessionType::Unknown, Some(plist)) if !plist.session_types.is_empty() => plist
+                        .session_types
+                        .iter()
+                        .map(|st| st.to_string())
+                        .collect::<Vec<_>>()
+                        .join(","),
+                    (SessionType::Unknown, _) => "-".to_string(),
+                    (st, _) => st.to_string(),
+                }
+            }
+            ServiceColumn::JobType => self
+                .status
+                .plist
+                .borrow()
+                .as_ref()
+                .map(|ec| format!("{}/{}", ec.entry_location, ec.entry_type))
+                .unwrap_or("-".to_string()),
+            ServiceColumn::Pid if self.status.pid > 0 && is_loaded => {
+                format!("{}", self.status.pid)
+            }
+            ServiceColumn::Pid => "-".to_string(),
+            ServiceColumn::Cpu => self
+                .usage
+                .map(|u| format!("{}.{}", u.cpu_permille / 10, u.cpu_permille % 10))
+                .unwrap_or("-".to_string()),
+            ServiceColumn::Memory => self
+                .usage
+                .map(|u| human_bytes(u.rss))
+                .unwrap_or("-".to_string()),
+            ServiceColumn::Status => {
+                let mut status = self.state.glyph().to_string();
+
+                match self.throttle {
+                    Some(t) if self.state == JobState::Throttled && t.as_secs() > 0 => {
+                        status.push_str(&format!(" {}s", t.as_secs()))
+                    }
+                    _ => (),
+                }
+
+                // Reload to pick up what is on disk
+                if self.plist_changed {
+                    status.push_str(" ↻");
+                }
+
+                status
+            }
+            // Disabled override, launchd will refuse to load it
+            ServiceColumn::Enabled if self.job_type_filter.intersects(JobTypeFilter::DISABLED) => {
+                "⊘ no".to_string()
+            }
+            ServiceColumn::Enabled => "yes".to_string(),
+            ServiceColumn::ExitCode => self
+                .last_exit
+                .map(|s| s.to_string())
+                .unwrap_or("-".to_string()),
+            ServiceColumn::Restarts => self
+                .restarts
+                .as_ref()
+                .map(|(n, spark)| format!("{:<4} {}", n, spark))
+                .unwrap_or("-".to_string()),
+            ServiceColumn::Path => self.plist_path.clone().unwrap_or("-".to_string()),
+        }
+    }
+
+    fn cell_style(&self, column: &ServiceColumn) -> Option<Style> {
+        if *column != ServiceColumn::Status {
+            return None;
+        }
 
-        let loaded = if self.job_type_filter.intersects(JobTypeFilter::LOADED) {
-            "✔"
-        } else {
-            "✘"
+        let roles = roles();
+        let color = match self.state {
+            JobState::Running => roles.success,
+            JobState::ExitedOk => roles.info,
+            JobState::ExitedError | JobState::Throttled => roles.warning,
+            JobState::Crashed => roles.error,
+            JobState::Disabled | JobState::NotLoaded => roles.subtle,
         };
 
-        vec![
-            self.name.clone(),
-            session_type,
-            entry_type,
-            pid,
-            loaded.to_string(),
-        ]
+        Some(Style::from(color))
     }
 }
@@ -1,2 +1,5 @@
+mod label_filter;
+pub mod launchctl;
 mod list_item;
+mod undo;
 pub mod view;
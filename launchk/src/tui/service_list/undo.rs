@@ -0,0 +1,58 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+use xpc_sys::enums::{DomainType, SessionType};
+
+use crate::launchd::plist::LaunchdPlist;
+
+/// Undo history is dropped oldest first after this many entries
+const MAX_UNDO: usize = 20;
+
+/// What is needed to load or unload a job again
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct JobContext {
+    pub label: String,
+    pub plist: LaunchdPlist,
+    pub session: SessionType,
+    pub domain: DomainType,
+    pub handle: Option<u64>,
+}
+
+/// A completed operation, undone by doing the opposite
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum UndoEntry {
+    Load(JobContext),
+    Unload(JobContext),
+    // (label, domain, handle)
+    Enable(String, DomainType, Option<u64>),
+    Disable(String, DomainType, Option<u64>),
+}
+
+impl fmt::Display for UndoEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UndoEntry::Load(job) => write!(f, "load {} ({})", job.label, job.domain),
+            UndoEntry::Unload(job) => write!(f, "unload {} ({})", job.label, job.domain),
+            UndoEntry::Enable(label, domain, _) => write!(f, "enable {} ({})", label, domain),
+            UndoEntry::Disable(label, domain, _) => write!(f, "disable {} ({})", label, domain),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct UndoStack(VecDeque<UndoEntry>);
+
+impl UndoStack {
+    pub fn push(&mut self, entry: UndoEntry) {
+        if self.0.len() == MAX_UNDO {
+            self.0.pop_front();
+        }
+
+        self.0.push_back(entry);
+    }
+
+    /// Most recent entry
+    pub fn pop(&mut self) -> Option<UndoEntry> {
+        self.0.pop_back()
+    }
+}
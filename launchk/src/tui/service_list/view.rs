@@ -1,6 +1,9 @@
 use std::cell::RefCell;
 use std::cmp::Ordering;
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Stdio};
 use std::ptr::slice_from_raw_parts;
 use std::rc::Rc;
 use std::sync::mpsc::Sender;
@@ -8,39 +11,111 @@ use std::sync::{Arc, RwLock};
 use std::time::Duration;
 
 use cursive::direction::Direction;
-use cursive::event::EventResult;
+use cursive::event::{Event, EventResult, Key};
 use cursive::view::CannotFocus;
 use cursive::view::ViewWrapper;
+use cursive::views::TextView;
 use cursive::{Cursive, View, XY};
-use sudo::RunningAs;
 
 use tokio::runtime::Handle;
 use tokio::time::interval;
+use xpc_sys::csr::sip_enabled;
 use xpc_sys::enums::{DomainType, SessionType};
+use xpc_sys::rs_geteuid;
 
+use crate::config::{config, poll_interval};
+use crate::launchd::backup::{backup_plist, restore_backup};
+use crate::launchd::domain_target::{resolve_domain_target, DomainTarget};
+use crate::launchd::dry_run::DryRun;
+use crate::launchd::job_detail::job_detail;
+use crate::launchd::job_stats::{record_stats, restart_summary};
 use crate::launchd::job_type_filter::JobTypeFilter;
-use crate::launchd::plist::{edit_and_replace, LaunchdEntryLocation, LABEL_TO_ENTRY_CONFIG};
+use crate::launchd::mach_graph::mach_service_graph;
+use crate::launchd::new_job::NewJob;
+use crate::launchd::plist::{
+    changed_since_load, clear_changed, edit_and_replace, for_label, insert_plists, retain_changed,
+    LaunchdEntryType, LABEL_TO_ENTRY_CONFIG,
+};
+use crate::launchd::plist_diff::plist_diff;
+use crate::launchd::plist_lint::{lint_file, PlistLint};
+use crate::launchd::preflight::{Blocker, Operation, Preflight};
+use crate::launchd::protected::{is_protected, is_sip_protected};
 use crate::launchd::query::procinfo;
-use crate::launchd::query::{disable, enable, list_all, load, unload};
+use crate::launchd::query::{
+    bootout, bootstrap, debug, disable, enable, has_bootstrap, kickstart, kill_signal,
+    list_all_detailed, load, remove, start, stop, unload, ServiceEntry,
+};
+use crate::launchd::session::LaunchSession;
+use crate::launchd::signing::{is_untrusted, refresh_signatures};
 use crate::launchd::{
-    entry_status::get_entry_status, entry_status::LaunchdEntryStatus, plist::LaunchdPlist,
+    entry_status::domain_target, entry_status::domain_target_labels,
+    entry_status::dumpstate_plist_path, entry_status::get_entry_status,
+    entry_status::get_exit_info, entry_status::get_last_exit, entry_status::get_proc_usage,
+    entry_status::is_disabled, entry_status::record_last_exit, entry_status::refresh_domain_target,
+    entry_status::refresh_dumpstate_jobs, entry_status::refresh_overrides,
+    entry_status::refresh_user_scope, entry_status::sample_proc_usage,
+    entry_status::throttle_remaining, entry_status::track_spawns, entry_status::user_scope,
+    entry_status::JobState, entry_status::LaunchdEntryStatus, entry_status::ENTRY_STATUS_CACHE,
+    plist::LaunchdPlist,
 };
-use crate::tui::omnibox::command::OmniboxCommand;
+use crate::tui::job_detail::JobDetailView;
+use crate::tui::log_view::{
+    job_predicate, plist_output_paths, plist_program, show_dry_run, show_file_tail, show_log_stream,
+};
+use crate::tui::mach_graph::show_mach_graph;
+use crate::tui::omnibox::command::{BatchAction, OmniboxCommand};
 
+use crate::config::columns::{
+    columns_spec, default_columns, parse_columns, parse_sort, sort_spec, ColumnLayout,
+    ServiceColumn,
+};
+use crate::tui::dialog::show_error;
 use crate::tui::omnibox::state::OmniboxState;
 use crate::tui::omnibox::subscribed_view::{OmniboxResult, OmniboxSubscriber};
 use crate::tui::omnibox::view::{OmniboxError, OmniboxEvent, OmniboxMode};
 use crate::tui::pager::show_pager;
+use crate::tui::plist_diff::show_plist_diff;
+use crate::tui::plist_view::show_plist;
 use crate::tui::root::CbSinkMessage;
+use crate::tui::service_list::label_filter::ServiceFilter;
+use crate::tui::service_list::launchctl;
+use crate::tui::service_list::launchctl::batch_command;
 use crate::tui::service_list::list_item::ServiceListItem;
-use crate::tui::table::table_list_view::TableListView;
+use crate::tui::service_list::undo::{JobContext, UndoEntry, UndoStack};
+use crate::tui::sysinfo::list_errors_badge;
+use crate::tui::table::table_list_view::{TableColumn, TableListView};
+use crate::tui::ui_state;
+use crate::tui::worker::run_with_spinner;
 
-/// Polls XPC for job list
+const DUMPSTATE_JOBS_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Polls XPC for job list and process usage, redrawing each time
 async fn poll_running_jobs(svcs: Arc<RwLock<HashSet<String>>>, cb_sink: Sender<CbSinkMessage>) {
-    let mut interval = interval(Duration::from_secs(1));
+    let mut interval = interval(poll_interval());
 
     loop {
         interval.tick().await;
+
+        // Config may have been reloaded
+        let period = poll_interval();
+        if interval.period() != period {
+            interval = tokio::time::interval(period);
+        }
+
+        let detailed = list_all_detailed();
+        let pids: Vec<i64> = detailed.iter().map(|e| e.pid).collect();
+        sample_proc_usage(&pids);
+        refresh_signatures(&pids);
+        record_last_exit(&detailed);
+        track_spawns(&detailed);
+        record_stats(&detailed);
+        refresh_overrides();
+        refresh_user_scope();
+        refresh_domain_target();
+
+        let latest: HashSet<String> = detailed.into_iter().map(|e| e.label).collect();
+        retain_changed(&latest);
+
         let write = svcs.try_write();
 
         if write.is_err() {
@@ -48,38 +123,290 @@ async fn poll_running_jobs(svcs: Arc<RwLock<HashSet<String>>>, cb_sink: Sender<C
         }
 
         let mut write = write.unwrap();
-        *write = list_all();
+        let changed: Vec<String> = write.symmetric_difference(&latest).cloned().collect();
+
+        *write = latest;
+        drop(write);
+
+        if !changed.is_empty() {
+            log::debug!("[poll_running_jobs]: {} changed", changed.len());
+        }
+
+        for label in changed {
+            ENTRY_STATUS_CACHE.invalidate(&label);
+        }
+
+        // Always sent, so CPU and memory columns redraw
+        cb_sink
+            .send(Box::new(move |siv| {
+                siv.call_on_name("list_errors", |v: &mut TextView| {
+                    v.set_content(list_errors_badge())
+                });
+            }))
+            .expect("Must send");
+    }
+}
+
+/// dumpstate is slow, so plist paths for jobs outside the standard
+/// directories are refreshed separately from the job list
+async fn poll_dumpstate_paths() {
+    let mut interval = interval(DUMPSTATE_JOBS_INTERVAL);
+
+    loop {
+        interval.tick().await;
+        refresh_dumpstate_jobs();
+    }
+}
+
+/// Put text on the pasteboard with pbcopy
+fn copy_to_clipboard(text: &str) -> Result<(), String> {
+    let mut pbcopy = Command::new("pbcopy")
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Cannot run pbcopy: {}", e))?;
+
+    pbcopy
+        .stdin
+        .take()
+        .ok_or_else(|| "Cannot write to pbcopy".to_string())?
+        .write_all(text.as_bytes())
+        .map_err(|e| e.to_string())?;
+
+    let status = pbcopy.wait().map_err(|e| e.to_string())?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err("pbcopy failed".to_string())
+    }
+}
+
+/// Session type to load into a domain when neither the job nor the
+/// command says, Background over SSH
+fn target_session(dt: &DomainType) -> SessionType {
+    match dt {
+        DomainType::System => SessionType::System,
+        _ => LaunchSession::current().default_session_type(),
+    }
+}
+
+/// Raw launchctl procinfo output for pid
+fn procinfo_bytes(pid: i64) -> Result<Vec<u8>, String> {
+    let (size, shmem) = procinfo(pid).map_err(|e| e.to_string())?;
+    Ok(unsafe { &*slice_from_raw_parts(shmem.region as *mut u8, size) }.to_vec())
+}
 
-        cb_sink.send(Box::new(Cursive::noop)).expect("Must send");
+/// Offer sudo when it would help, otherwise just say why
+fn blocked(preflight: &Preflight, lines: Vec<String>, blocker: &Blocker) -> OmniboxResult {
+    let message = lines.join("\n");
+
+    if blocker.sudo_helps() && preflight.euid != 0 {
+        Ok(Some(OmniboxCommand::Confirm(
+            format!("{}\n\nSudo and restart?", message),
+            vec![OmniboxCommand::Quit, OmniboxCommand::Sudo],
+        )))
+    } else {
+        Err(OmniboxError::CommandError(message))
     }
 }
 
+/// Lint then bootstrap (or legacy load) a job from its plist
+fn load_job(
+    name: String,
+    plist: &LaunchdPlist,
+    st: SessionType,
+    dt: DomainType,
+    handle: Option<u64>,
+) -> Result<(), String> {
+    let PlistLint { errors, .. } = lint_file(&plist.plist_path)?;
+
+    if !errors.is_empty() {
+        return Err(format!("Refusing to load {}: {}", name, errors.join(", ")));
+    }
+
+    let result = if has_bootstrap() {
+        bootstrap(
+            name,
+            plist.plist_path.clone(),
+            &DomainTarget::new(dt, handle.unwrap_or(0)),
+            Some(st),
+        )
+    } else {
+        load(name, plist.plist_path.clone(), Some(dt), Some(st), handle)
+    };
+
+    result.map(|_| ()).map_err(|e| e.to_string())?;
+    clear_changed(&plist.plist_path);
+
+    Ok(())
+}
+
+/// bootout (or legacy unload) a job
+fn unload_job(
+    name: String,
+    plist: &LaunchdPlist,
+    st: SessionType,
+    dt: DomainType,
+    handle: Option<u64>,
+) -> Result<(), String> {
+    let result = if has_bootstrap() {
+        bootout(name, &DomainTarget::new(dt, handle.unwrap_or(0)))
+    } else {
+        unload(name, plist.plist_path.clone(), Some(dt), Some(st), handle)
+    };
+
+    result.map(|_| ()).map_err(|e| e.to_string())
+}
+
+fn table_columns(
+    columns: &[ColumnLayout],
+    sort: Option<(ServiceColumn, bool)>,
+) -> Vec<TableColumn<ServiceColumn>> {
+    columns
+        .iter()
+        .map(|(c, width)| {
+            let header = match sort {
+                Some((sorted, false)) if sorted == *c => format!("{} ▲", c.header()),
+                Some((sorted, true)) if sorted == *c => format!("{} ▼", c.header()),
+                _ => c.header().to_string(),
+            };
+
+            (*c, header, *width)
+        })
+        .collect()
+}
+
 pub struct ServiceListView {
     cb_sink: Sender<CbSinkMessage>,
+    runtime_handle: Handle,
     running_jobs: Arc<RwLock<HashSet<String>>>,
     table_list_view: TableListView<ServiceListItem>,
-    label_filter: RefCell<String>,
+    label_filter: RefCell<ServiceFilter>,
     job_type_filter: RefCell<JobTypeFilter>,
+    /// Labels marked for batch operations
+    marked: RefCell<BTreeSet<String>>,
+    undo: RefCell<UndoStack>,
+    /// Protected label confirmed for its next unload, disable or kill
+    unprotected: RefCell<Option<String>>,
+    columns: Vec<ColumnLayout>,
+    /// (column, descending?) from clicking a header
+    sort: Option<(ServiceColumn, bool)>,
+    /// Label from the last run, highlighted once it is listed
+    restore_selection: RefCell<Option<String>>,
 }
 
 impl ServiceListView {
     pub fn new(runtime_handle: &Handle, cb_sink: Sender<CbSinkMessage>) -> Self {
         let arc_svc = Arc::new(RwLock::new(HashSet::new()));
         runtime_handle.spawn(poll_running_jobs(arc_svc.clone(), cb_sink.clone()));
+        runtime_handle.spawn(poll_dumpstate_paths());
+
+        let restored = ui_state::current();
+
+        let columns = match restored.columns.as_slice() {
+            [] => parse_columns(&config().columns),
+            columns => parse_columns(columns),
+        }
+        .unwrap_or_else(|e| {
+            log::error!("Using default columns: {}", e);
+            default_columns()
+        });
+        let sort = restored.sort.and_then(|sort| parse_sort(&sort).ok());
 
         Self {
             cb_sink,
+            runtime_handle: runtime_handle.clone(),
             running_jobs: arc_svc.clone(),
-            label_filter: RefCell::new("".into()),
-            job_type_filter: RefCell::new(JobTypeFilter::launchk_default()),
-            table_list_view: TableListView::new(vec![
-                ("Name", None),
-                ("Session", Some(12)),
-                ("Job Type", Some(14)),
-                ("PID", Some(6)),
-                ("Loaded", Some(6)),
-            ]),
+            label_filter: RefCell::new(ServiceFilter::default()),
+            job_type_filter: RefCell::new(config().job_type_filter()),
+            marked: RefCell::new(BTreeSet::new()),
+            undo: RefCell::new(UndoStack::default()),
+            unprotected: RefCell::new(None),
+            table_list_view: TableListView::new(table_columns(&columns, sort)),
+            columns,
+            sort,
+            restore_selection: RefCell::new(restored.selected),
+        }
+    }
+
+    fn set_columns(&mut self, columns: Vec<ColumnLayout>) {
+        self.table_list_view
+            .set_columns(table_columns(&columns, self.sort));
+
+        let from_config = parse_columns(&config().columns).ok() == Some(columns.clone());
+        ui_state::update(|state| {
+            state.sort = self.sort.map(sort_spec);
+            state.columns = match from_config {
+                true => vec![],
+                false => columns_spec(&columns),
+            };
+        });
+
+        self.columns = columns;
+    }
+
+    fn copy_launchctl(&self) -> OmniboxResult {
+        let lines = launchctl::last_change().join("\n");
+
+        if lines.is_empty() {
+            return Err(OmniboxError::CommandError(
+                "Nothing changed yet, or no launchctl equivalent".to_string(),
+            ));
+        }
+
+        copy_to_clipboard(&lines).map_err(OmniboxError::CommandError)?;
+        self.cb_sink
+            .send(show_error(format!("Copied\n\n{}", lines)))
+            .expect("Must show notice");
+
+        Ok(None)
+    }
+
+    /// Ascending, descending, then back to the default order
+    fn toggle_sort(&mut self, column: ServiceColumn) -> EventResult {
+        self.sort = match self.sort {
+            Some((c, false)) if c == column => Some((column, true)),
+            Some((c, true)) if c == column => None,
+            _ => Some((column, false)),
+        };
+
+        self.set_columns(self.columns.clone());
+        EventResult::Consumed(None)
+    }
+
+    /// Columns from config, e.g. after a reload
+    fn reload_columns(&mut self) -> OmniboxResult {
+        let columns = parse_columns(&config().columns).map_err(OmniboxError::CommandError)?;
+
+        if columns != self.columns {
+            self.set_columns(columns);
         }
+
+        Ok(None)
+    }
+
+    /// Sort and columns, the omnibox takes the filters. Unknown names
+    /// are reported by the root view.
+    fn apply_profile(&mut self, args: &[String]) -> OmniboxResult {
+        let config = config();
+        let profile = match args.first().and_then(|name| config.profile(name)) {
+            Some(profile) => profile,
+            None => return Ok(None),
+        };
+
+        self.sort = match &profile.sort {
+            Some(sort) => Some(parse_sort(sort).map_err(OmniboxError::CommandError)?),
+            None => None,
+        };
+
+        let columns = if profile.columns.is_empty() {
+            self.columns.clone()
+        } else {
+            parse_columns(&profile.columns).map_err(OmniboxError::CommandError)?
+        };
+
+        self.set_columns(columns);
+        Ok(None)
     }
 
     fn present_services(&self) -> Option<Vec<ServiceListItem>> {
@@ -88,49 +415,145 @@ impl ServiceListView {
 
         let name_filter = self.label_filter.borrow();
         let job_type_filter = self.job_type_filter.borrow();
+        let marked = self.marked.borrow();
+        let scope = user_scope();
+        let target = domain_target_labels();
 
         let running_no_plist = running.iter().filter(|r| !plists.contains_key(*r));
+        // Loaded in another user's domain, so not in running
+        let scoped_only = scope
+            .iter()
+            .chain(target.iter())
+            .flatten()
+            .filter(|l| !plists.contains_key(*l) && !running.contains(*l))
+            .collect::<HashSet<_>>();
 
-        let mut items: Vec<ServiceListItem> = plists
+        let mut items: Vec<(i64, ServiceListItem)> = plists
             .keys()
             .into_iter()
             .chain(running_no_plist)
+            .chain(scoped_only)
             .filter_map(|label| {
-                if !name_filter.is_empty()
-                    && !label
-                        .to_ascii_lowercase()
-                        .contains(name_filter.to_ascii_lowercase().as_str())
-                {
+                let in_scope = scope.as_ref().map(|s| s.contains(label));
+                if in_scope == Some(false) {
                     return None;
                 }
 
+                // With a domain target, jobs loaded elsewhere are hidden and
+                // plists that are not loaded stay, to load them into it
+                let in_target = target.as_ref().map(|t| t.contains(label));
+                if in_target == Some(false) && running.contains(label) {
+                    return None;
+                }
+
+                let score = name_filter.label.score(label)?;
+
                 let status = get_entry_status(label);
-                let is_loaded = running.contains(label);
+                let is_loaded =
+                    running.contains(label) || in_scope == Some(true) || in_target == Some(true);
+                let is_disabled = !status.enabled;
 
-                let entry_job_type_filter = status
+                let mut entry_job_type_filter = status
                     .plist
                     .as_ref()
-                    .map(|ec| ec.job_type_filter(is_loaded))
-                    .unwrap_or(if is_loaded {
-                        JobTypeFilter::LOADED
-                    } else {
-                        JobTypeFilter::default()
+                    .map(|ec| ec.job_type_filter(is_loaded, is_disabled))
+                    .unwrap_or_else(|| {
+                        let mut jtf = JobTypeFilter::default();
+                        jtf.set(JobTypeFilter::LOADED, is_loaded);
+                        jtf.set(JobTypeFilter::DISABLED, is_disabled);
+                        jtf.set(JobTypeFilter::ENABLED, !is_disabled);
+                        jtf.insert(JobTypeFilter::for_session_type(
+                            &status.limit_load_to_session_type,
+                        ));
+                        jtf
                     });
 
+                entry_job_type_filter.set(
+                    JobTypeFilter::UNSIGNED,
+                    is_loaded && is_untrusted(status.pid),
+                );
+
+                let plist_path = status
+                    .plist
+                    .as_ref()
+                    .map(|p| p.plist_path.clone())
+                    .or_else(|| dumpstate_plist_path(label));
+
+                entry_job_type_filter.set(
+                    JobTypeFilter::ORPHANED,
+                    !is_loaded && status.plist.is_some(),
+                );
+                entry_job_type_filter.set(JobTypeFilter::GHOST, is_loaded && plist_path.is_none());
+
                 if !job_type_filter.is_empty() && !entry_job_type_filter.contains(*job_type_filter)
                 {
                     return None;
                 }
 
-                Some(ServiceListItem {
+                let usage = if is_loaded {
+                    get_proc_usage(status.pid)
+                } else {
+                    None
+                };
+
+                // Written since launchd read it
+                let plist_changed = is_loaded
+                    && plist_path
+                        .as_deref()
+                        .map(changed_since_load)
+                        .unwrap_or(false);
+
+                let last_exit = get_last_exit(label);
+                let mut exit_info = get_exit_info(label);
+                let throttle = throttle_remaining(label, exit_info.as_ref());
+
+                // Churning between polls even if dumpstate has not caught up
+                if throttle.is_some() {
+                    exit_info.get_or_insert_with(Default::default).throttled = true;
+                }
+
+                let state = JobState::new(
+                    is_loaded,
+                    !is_disabled,
+                    status.pid,
+                    last_exit,
+                    exit_info.as_ref(),
+                );
+
+                let item = ServiceListItem {
+                    plist_path,
                     status,
                     name: label.clone(),
                     job_type_filter: entry_job_type_filter,
-                })
+                    marked: marked.contains(label),
+                    last_exit,
+                    restarts: restart_summary(label),
+                    state,
+                    throttle,
+                    plist_changed,
+                    usage,
+                };
+
+                Some((score, item)).filter(|(_, item)| name_filter.matches(item))
             })
             .collect();
 
-        items.sort_by(|a, b| {
+        // Best matches first when filtering, then the clicked column, then
+        // unloaded before loaded
+        items.sort_by(|(score_a, a), (score_b, b)| {
+            if score_a != score_b {
+                return score_b.cmp(score_a);
+            }
+
+            if let Some((column, descending)) = &self.sort {
+                let ord = a.compare(b, column);
+                let ord = if *descending { ord.reverse() } else { ord };
+
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+            }
+
             let loaded_a = a.job_type_filter.intersects(JobTypeFilter::LOADED);
             let loaded_b = b.job_type_filter.intersects(JobTypeFilter::LOADED);
             let name_cmp = a.name.cmp(&b.name);
@@ -144,7 +567,7 @@ impl ServiceListView {
             }
         });
 
-        Some(items)
+        Some(items.into_iter().map(|(_, item)| item).collect())
     }
 
     fn handle_state_update(&mut self, state: OmniboxState) -> OmniboxResult {
@@ -157,18 +580,23 @@ impl ServiceListView {
 
         match mode {
             OmniboxMode::LabelFilter => {
-                self.label_filter.replace(label_filter);
+                self.label_filter.replace(ServiceFilter::new(&label_filter));
             }
             OmniboxMode::JobTypeFilter => {
                 self.job_type_filter.replace(job_type_filter);
             }
             OmniboxMode::Idle => {
-                self.label_filter.replace(label_filter);
+                self.label_filter.replace(ServiceFilter::new(&label_filter));
                 self.job_type_filter.replace(job_type_filter);
             }
-            _ => {}
+            _ => return Ok(None),
         };
 
+        ui_state::update(|state| {
+            state.label_filter = label_filter;
+            state.job_type_filter = Some(job_type_filter.to_string());
+        });
+
         Ok(None)
     }
 
@@ -187,6 +615,100 @@ impl ServiceListView {
         Ok((item.clone(), plist))
     }
 
+    /// Write the current filtered view to a file
+    fn export(&self, args: &[String]) -> OmniboxResult {
+        let path = match args {
+            [format, path] if format == "json" => path,
+            _ => {
+                return Err(OmniboxError::CommandError(
+                    "Usage: export json <path>".to_string(),
+                ))
+            }
+        };
+
+        let visible = self.present_services().unwrap_or_default();
+        let detailed = list_all_detailed();
+
+        let entries: Vec<ServiceEntry> = visible
+            .into_iter()
+            .map(
+                |ServiceListItem {
+                     name,
+                     status,
+                     plist_path,
+                     ..
+                 }| {
+                    detailed
+                        .iter()
+                        .find(|e| e.label == name)
+                        .cloned()
+                        .unwrap_or(ServiceEntry {
+                            domain: status.domain,
+                            pid: status.pid,
+                            status: None,
+                            plist_path,
+                            unparseable: status.unparseable,
+                            label: name,
+                        })
+                },
+            )
+            .collect();
+
+        let json = serde_json::to_string_pretty(&entries)
+            .map_err(|e| OmniboxError::CommandError(e.to_string()))?;
+
+        fs::write(path, json)
+            .map_err(|e| OmniboxError::CommandError(format!("Cannot write {}: {}", path, e)))?;
+
+        Ok(None)
+    }
+
+    /// Lint a plist at a path, or the highlighted job's
+    fn lint(&self, args: &[String]) -> OmniboxResult {
+        let path = if args.is_empty() {
+            let (ServiceListItem { name, .. }, plist) = self.with_active_item_plist()?;
+            plist
+                .map(|p| p.plist_path)
+                .ok_or_else(|| OmniboxError::CommandError(format!("No plist path for {}", name)))?
+        } else {
+            args.join(" ")
+        };
+
+        let lines = lint_file(&path)
+            .map_err(OmniboxError::CommandError)?
+            .lines();
+        let message = if lines.is_empty() {
+            format!("{}: no problems found", path)
+        } else {
+            format!("{}:\n{}", path, lines.join("\n"))
+        };
+
+        self.cb_sink
+            .send(show_error(message))
+            .expect("Must show lint");
+
+        Ok(None)
+    }
+
+    /// Run a plist's program once, the highlighted job's without a path
+    fn dry_run(&self, args: &[String]) -> OmniboxResult {
+        let (name, path) = if args.is_empty() {
+            let (ServiceListItem { name, .. }, plist) = self.with_active_item_plist()?;
+            let path = plist
+                .map(|p| p.plist_path)
+                .ok_or_else(|| OmniboxError::CommandError(format!("No plist path for {}", name)))?;
+            (name, path)
+        } else {
+            (args.join(" "), args.join(" "))
+        };
+
+        let run = DryRun::from_file(&path).map_err(OmniboxError::CommandError)?;
+        show_dry_run(&self.cb_sink, format!("dry run: {}", name), &run)
+            .map_err(OmniboxError::CommandError)?;
+
+        Ok(None)
+    }
+
     fn handle_plist_command(&self, cmd: OmniboxCommand) -> OmniboxResult {
         let (ServiceListItem { name, status, .. }, plist) = self.with_active_item_plist()?;
 
@@ -194,67 +716,608 @@ impl ServiceListView {
             plist.ok_or_else(|| OmniboxError::CommandError("Cannot find plist".to_string()))?;
 
         match cmd {
+            OmniboxCommand::ViewPlist => {
+                self.cb_sink
+                    .send(show_plist(&name, &plist.plist_path).map_err(OmniboxError::CommandError)?)
+                    .expect("Must show plist");
+
+                Ok(None)
+            }
+            OmniboxCommand::RestoreBackupRequest(backup) => Ok(Some(OmniboxCommand::Confirm(
+                format!("Replace {} with backup {}?", plist.plist_path, backup),
+                vec![OmniboxCommand::RestoreBackup(backup)],
+            ))),
+            OmniboxCommand::RestoreBackup(backup) => {
+                if plist.readonly {
+                    return Err(OmniboxError::CommandError(
+                        "plist is read-only!".to_string(),
+                    ));
+                }
+
+                restore_backup(&name, &backup, &plist.plist_path)
+                    .map_err(OmniboxError::CommandError)?;
+                launchctl::record(&OmniboxCommand::RestoreBackup(backup), Some(&name));
+
+                Ok(Some(OmniboxCommand::Confirm(
+                    format!("Restored. Reload {}?", name),
+                    vec![OmniboxCommand::Reload],
+                )))
+            }
             OmniboxCommand::Edit => {
-                edit_and_replace(&plist).map_err(OmniboxError::CommandError)?;
+                let (backup, warnings) =
+                    edit_and_replace(&name, &plist).map_err(OmniboxError::CommandError)?;
 
                 // Clear term
                 self.cb_sink
                     .send(Box::new(Cursive::clear))
                     .expect("Must clear");
 
-                Ok(Some(OmniboxCommand::Confirm(
-                    format!("Reload {}?", name),
-                    vec![OmniboxCommand::Reload],
+                // Not loaded, nothing to roll back to
+                let (prompt, next) = match status.domain {
+                    DomainType::Unknown => (format!("Reload {}?", name), OmniboxCommand::Reload),
+                    _ => (
+                        format!(
+                            "Apply changes to {}?\n\nIt is unloaded and loaded again, the previous plist is put back if it fails to load.",
+                            name
+                        ),
+                        OmniboxCommand::ApplyEdit(backup.path),
+                    ),
+                };
+
+                let prompt = if warnings.is_empty() {
+                    prompt
+                } else {
+                    format!("{}\n\nWarnings:\n{}", prompt, warnings.join("\n"))
+                };
+
+                Ok(Some(OmniboxCommand::Confirm(prompt, vec![next])))
+            }
+            OmniboxCommand::ApplyEdit(backup) => {
+                // Same fallback as the XPC query
+                let session = match status.limit_load_to_session_type {
+                    SessionType::Unknown => SessionType::Aqua,
+                    st => st,
+                };
+
+                let job = JobContext {
+                    label: name.clone(),
+                    plist: plist.clone(),
+                    session,
+                    domain: status.domain,
+                    handle: None,
+                };
+
+                // Still running the old plist if this fails
+                self.unload(job.clone())
+                    .map_err(OmniboxError::CommandError)?;
+
+                let e = match self.load(job.clone()) {
+                    Ok(()) => return Ok(None),
+                    Err(e) => e,
+                };
+
+                restore_backup(&name, &backup, &plist.plist_path)
+                    .map(|_| launchctl::record(&OmniboxCommand::RestoreBackup(backup), Some(&name)))
+                    .and_then(|_| self.load(job))
+                    .map_err(|rollback| {
+                        OmniboxError::CommandError(format!(
+                            "{} failed to load: {}\n\nRolling back also failed, {} is unloaded: {}",
+                            name, e, name, rollback
+                        ))
+                    })?;
+
+                Err(OmniboxError::CommandError(format!(
+                    "{} failed to load: {}\n\nThe previous plist was put back and loaded.",
+                    name, e
                 )))
             }
-            OmniboxCommand::Load(st, dt, _handle) => {
-                load(name, plist.plist_path, Some(dt), Some(st), None)
-                    .map(|_| None)
-                    .map_err(|e| OmniboxError::CommandError(e.to_string()))
+            OmniboxCommand::Load(st, dt, handle) => self
+                .load(JobContext {
+                    label: name,
+                    plist,
+                    session: st,
+                    domain: dt,
+                    handle,
+                })
+                .map(|_| None)
+                .map_err(OmniboxError::CommandError),
+            OmniboxCommand::Unload(dt, handle) => self
+                .unload(JobContext {
+                    label: name,
+                    plist,
+                    session: status.limit_load_to_session_type,
+                    domain: dt,
+                    handle,
+                })
+                .map(|_| None)
+                .map_err(OmniboxError::CommandError),
+            _ => Ok(None),
+        }
+    }
+
+    /// Mark or unmark the highlighted job, then move down
+    fn toggle_mark(&mut self) -> EventResult {
+        if let Ok(item) = self.get_active_list_item() {
+            let mut marked = self.marked.borrow_mut();
+
+            if !marked.remove(&item.name) {
+                marked.insert(item.name.clone());
             }
-            OmniboxCommand::Unload(dt, _handle) => {
-                let LaunchdEntryStatus {
-                    limit_load_to_session_type,
-                    ..
-                } = status;
+        }
 
-                unload(
-                    name,
-                    plist.plist_path,
-                    Some(dt),
-                    Some(limit_load_to_session_type),
-                    None,
-                )
-                .map(|_| None)
-                .map_err(|e| OmniboxError::CommandError(e.to_string()))
+        self.table_list_view.on_event(Event::Key(Key::Down))
+    }
+
+    /// Ask for a domain etc. once, then confirm for all marked jobs
+    fn handle_batch_request(&self, cmd: OmniboxCommand) -> OmniboxResult {
+        let marked = self.marked.borrow();
+        let first = marked.iter().next().cloned().unwrap_or_default();
+
+        let statuses: Vec<LaunchdEntryStatus> = marked.iter().map(get_entry_status).collect();
+
+        // SIP is flagged per job in the summary instead
+        let preflight = Preflight {
+            sip: false,
+            ..Preflight::current()
+        };
+        let blockers: Vec<(String, Blocker)> = marked
+            .iter()
+            .zip(statuses.iter())
+            .filter_map(|(label, s)| {
+                let location = s.plist.as_ref().map(|p| &p.entry_location);
+                let blocker = preflight.check(Operation::Control, location, None).err()?;
+                Some((format!("{}: {}", label, blocker), blocker))
+            })
+            .collect();
+
+        if let Some((_, blocker)) = blockers.first() {
+            let lines = blockers.iter().map(|(line, _)| line.clone()).collect();
+            return blocked(&preflight, lines, blocker);
+        }
+
+        // The status bar's domain target replaces the prompts
+        if let Some(target) = domain_target() {
+            let dt = target.domain_type;
+            let action = match cmd {
+                OmniboxCommand::LoadRequest => Some(BatchAction::Load(target_session(&dt), dt)),
+                OmniboxCommand::UnloadRequest => Some(BatchAction::Unload(dt)),
+                OmniboxCommand::EnableRequest => Some(BatchAction::Enable(dt)),
+                OmniboxCommand::DisableRequest => Some(BatchAction::Disable(dt)),
+                _ => None,
+            };
+
+            if let Some(action) = action {
+                return Ok(Some(OmniboxCommand::BatchConfirm(action)));
             }
-            _ => Ok(None),
         }
+
+        // Unload and kill use each job's domain, only prompt if one is unknown
+        let all_known = statuses.iter().all(|s| s.domain != DomainType::Unknown);
+
+        let next = match cmd {
+            OmniboxCommand::LoadRequest => {
+                OmniboxCommand::DomainSessionPrompt(first, false, |dt, st| {
+                    vec![OmniboxCommand::BatchConfirm(BatchAction::Load(
+                        st.expect("Must be provided"),
+                        dt,
+                    ))]
+                })
+            }
+            OmniboxCommand::UnloadRequest if all_known => {
+                OmniboxCommand::BatchConfirm(BatchAction::Unload(DomainType::Unknown))
+            }
+            OmniboxCommand::UnloadRequest => {
+                OmniboxCommand::DomainSessionPrompt(first, true, |dt, _| {
+                    vec![OmniboxCommand::BatchConfirm(BatchAction::Unload(dt))]
+                })
+            }
+            OmniboxCommand::EnableRequest => {
+                OmniboxCommand::DomainSessionPrompt(first, true, |dt, _| {
+                    vec![OmniboxCommand::BatchConfirm(BatchAction::Enable(dt))]
+                })
+            }
+            OmniboxCommand::DisableRequest => {
+                OmniboxCommand::DomainSessionPrompt(first, true, |dt, _| {
+                    vec![OmniboxCommand::BatchConfirm(BatchAction::Disable(dt))]
+                })
+            }
+            OmniboxCommand::KillRequest if all_known => {
+                OmniboxCommand::SignalPrompt(DomainType::Unknown, |dt, signal| {
+                    OmniboxCommand::BatchConfirm(BatchAction::Kill(dt, signal))
+                })
+            }
+            OmniboxCommand::KillRequest => {
+                OmniboxCommand::DomainSessionPrompt(first, true, |dt, _| {
+                    vec![OmniboxCommand::SignalPrompt(dt, |dt, signal| {
+                        OmniboxCommand::BatchConfirm(BatchAction::Kill(dt, signal))
+                    })]
+                })
+            }
+            _ => return Ok(None),
+        };
+
+        Ok(Some(next))
     }
 
-    fn handle_command(&self, cmd: OmniboxCommand) -> OmniboxResult {
-        let (ServiceListItem { name, status, .. }, plist) = self.with_active_item_plist()?;
+    /// Domain a batch action will use for a job
+    fn batch_domain(action: &BatchAction, status: &LaunchdEntryStatus) -> DomainType {
+        match action {
+            BatchAction::Unload(dt) | BatchAction::Kill(dt, _)
+                if status.domain == DomainType::Unknown =>
+            {
+                dt.clone()
+            }
+            BatchAction::Unload(_) | BatchAction::Kill(_, _) => status.domain.clone(),
+            BatchAction::Load(_, dt) | BatchAction::Enable(dt) | BatchAction::Disable(dt) => {
+                dt.clone()
+            }
+        }
+    }
 
-        let need_escalate = plist
-            .map(|LaunchdPlist { entry_location, .. }| {
-                entry_location == LaunchdEntryLocation::System
-                    || entry_location == LaunchdEntryLocation::Global
+    /// One line per marked job
+    fn batch_summary(&self, action: BatchAction) -> OmniboxResult {
+        let mut protected = 0;
+        let lines: Vec<String> = self
+            .marked
+            .borrow()
+            .iter()
+            .map(|label| {
+                let status = get_entry_status(label);
+                let domain = Self::batch_domain(&action, &status);
+                let warning = match action {
+                    BatchAction::Unload(_) | BatchAction::Disable(_) | BatchAction::Kill(_, _)
+                        if is_protected(label, &status.domain) =>
+                    {
+                        protected += 1;
+                        " ⚠ protected"
+                    }
+                    BatchAction::Unload(_) | BatchAction::Disable(_) | BatchAction::Enable(_)
+                        if sip_enabled() && is_sip_protected(status.plist.as_ref()) =>
+                    {
+                        " ⚠ SIP, will fail"
+                    }
+                    _ => "",
+                };
+
+                format!("{} {} ({}){}", action, label, domain, warning)
             })
-            .unwrap_or(true);
+            .collect();
 
+        // Like a single protected job, typed rather than Enter
+        if protected > 0 {
+            return Ok(Some(OmniboxCommand::TypedConfirm(
+                format!(
+                    "{}\n\n{} protected system job(s), stopping them can break your session.\n\nType {} to continue.",
+                    lines.join("\n"),
+                    protected,
+                    protected
+                ),
+                protected.to_string(),
+                vec![OmniboxCommand::Batch(action)],
+            )));
+        }
+
+        Ok(Some(OmniboxCommand::Confirm(
+            format!("{}\n\nContinue?", lines.join("\n")),
+            vec![OmniboxCommand::Batch(action)],
+        )))
+    }
+
+    fn load(&self, job: JobContext) -> Result<(), String> {
+        load_job(
+            job.label.clone(),
+            &job.plist,
+            job.session.clone(),
+            job.domain.clone(),
+            job.handle,
+        )?;
+
+        launchctl::record(
+            &OmniboxCommand::Load(job.session.clone(), job.domain.clone(), job.handle),
+            Some(&job.label),
+        );
+        self.undo.borrow_mut().push(UndoEntry::Load(job));
+        Ok(())
+    }
+
+    fn unload(&self, job: JobContext) -> Result<(), String> {
+        unload_job(
+            job.label.clone(),
+            &job.plist,
+            job.session.clone(),
+            job.domain.clone(),
+            job.handle,
+        )?;
+
+        launchctl::record(
+            &OmniboxCommand::Unload(job.domain.clone(), job.handle),
+            Some(&job.label),
+        );
+        self.undo.borrow_mut().push(UndoEntry::Unload(job));
+        Ok(())
+    }
+
+    /// Only remembered for undo if the override actually changed
+    fn set_enabled(
+        &self,
+        label: String,
+        dt: DomainType,
+        handle: Option<u64>,
+        enabled: bool,
+    ) -> Result<(), String> {
+        let was_disabled = is_disabled(&label);
+
+        // Like unload -w, worth having the plist to go back to
+        if let (false, Some(plist)) = (enabled, for_label(label.clone())) {
+            if let Err(e) = backup_plist(&label, &plist.plist_path) {
+                log::error!("[set_enabled]: {}", e);
+            }
+        }
+
+        let entry = if enabled {
+            enable(label.clone(), dt.clone(), handle).map_err(|e| e.to_string())?;
+            launchctl::record(&OmniboxCommand::Enable(dt.clone(), handle), Some(&label));
+            UndoEntry::Enable(label, dt, handle)
+        } else {
+            disable(label.clone(), dt.clone(), handle).map_err(|e| e.to_string())?;
+            launchctl::record(&OmniboxCommand::Disable(dt.clone(), handle), Some(&label));
+            UndoEntry::Disable(label, dt, handle)
+        };
+
+        if was_disabled == enabled {
+            self.undo.borrow_mut().push(entry);
+        }
+
+        Ok(())
+    }
+
+    /// Run the opposite of the last recorded action, keeping it on failure
+    fn undo(&self) -> OmniboxResult {
+        let entry = self
+            .undo
+            .borrow_mut()
+            .pop()
+            .ok_or_else(|| OmniboxError::CommandError("Nothing to undo".to_string()))?;
+
+        let result = match &entry {
+            UndoEntry::Load(job) => unload_job(
+                job.label.clone(),
+                &job.plist,
+                job.session.clone(),
+                job.domain.clone(),
+                job.handle,
+            ),
+            UndoEntry::Unload(job) => load_job(
+                job.label.clone(),
+                &job.plist,
+                job.session.clone(),
+                job.domain.clone(),
+                job.handle,
+            ),
+            UndoEntry::Enable(label, dt, handle) => disable(label.clone(), dt.clone(), *handle)
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+            UndoEntry::Disable(label, dt, handle) => enable(label.clone(), dt.clone(), *handle)
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+        };
+
+        if let Err(e) = result {
+            let message = format!("Cannot undo {}: {}", entry, e);
+            self.undo.borrow_mut().push(entry);
+            return Err(OmniboxError::CommandError(message));
+        }
+
+        let (cmd, label) = match entry {
+            UndoEntry::Load(job) => (OmniboxCommand::Unload(job.domain, job.handle), job.label),
+            UndoEntry::Unload(job) => (
+                OmniboxCommand::Load(job.session, job.domain, job.handle),
+                job.label,
+            ),
+            UndoEntry::Enable(label, dt, handle) => (OmniboxCommand::Disable(dt, handle), label),
+            UndoEntry::Disable(label, dt, handle) => (OmniboxCommand::Enable(dt, handle), label),
+        };
+        launchctl::record(&cmd, Some(&label));
+
+        Ok(None)
+    }
+
+    /// Write the wizard's plist, then optionally bootstrap it
+    fn create_job(&self, job: NewJob, load_now: bool) -> OmniboxResult {
+        let preflight = Preflight::current();
+        if let Err(blocker) = preflight.check(Operation::Control, Some(&job.location), None) {
+            return blocked(
+                &preflight,
+                vec![format!("{}: {}", job.label, blocker)],
+                &blocker,
+            );
+        }
+
+        let path = job.write().map_err(OmniboxError::CommandError)?;
+        insert_plists(std::iter::once(path));
+
+        if !load_now {
+            return Ok(None);
+        }
+
+        let plist = for_label(&job.label)
+            .ok_or_else(|| OmniboxError::CommandError("Cannot find new plist".to_string()))?;
+
+        let (domain, handle) = match job.entry_type {
+            LaunchdEntryType::Daemon => (DomainType::System, None),
+            LaunchdEntryType::Agent => LaunchSession::current().default_domain(),
+        };
+
+        self.load(JobContext {
+            label: job.label,
+            plist,
+            session: target_session(&domain),
+            domain,
+            handle,
+        })
+        .map(|_| None)
+        .map_err(OmniboxError::CommandError)
+    }
+
+    fn run_batch_action(&self, label: &str, action: &BatchAction) -> Result<(), String> {
+        let status = get_entry_status(label);
+        let dt = Self::batch_domain(action, &status);
+        let name = label.to_string();
+        // Only the domain target knows a handle (uid) for its domain type
+        let handle = domain_target()
+            .filter(|t| t.domain_type == dt && dt != DomainType::System)
+            .map(|t| t.handle);
+        let plist = || {
+            status
+                .plist
+                .clone()
+                .ok_or_else(|| "Cannot find plist".to_string())
+        };
+
+        match action {
+            BatchAction::Load(st, _) => self.load(JobContext {
+                label: name,
+                plist: plist()?,
+                session: st.clone(),
+                domain: dt,
+                handle,
+            }),
+            BatchAction::Unload(_) => self.unload(JobContext {
+                label: name,
+                plist: plist()?,
+                session: status.limit_load_to_session_type.clone(),
+                domain: dt,
+                handle,
+            }),
+            BatchAction::Enable(_) => self.set_enabled(name, dt, handle, true),
+            BatchAction::Disable(_) => self.set_enabled(name, dt, handle, false),
+            BatchAction::Kill(_, signal) => {
+                kill_signal(name, dt.clone(), *signal).map_err(|e| e.to_string())?;
+                launchctl::record(&batch_command(action, dt, handle), Some(label));
+                Ok(())
+            }
+        }
+    }
+
+    /// Run for every marked job, reporting failures per label
+    fn run_batch(&self, action: BatchAction) -> OmniboxResult {
+        let marked = self.marked.borrow();
+
+        let failed: Vec<String> = marked
+            .iter()
+            .filter_map(|label| {
+                self.run_batch_action(label, &action)
+                    .err()
+                    .map(|e| format!("{}: {}", label, e))
+            })
+            .collect();
+
+        if failed.is_empty() {
+            return Ok(None);
+        }
+
+        Err(OmniboxError::CommandError(format!(
+            "{} of {} failed:\n\n{}",
+            failed.len(),
+            marked.len(),
+            failed.join("\n")
+        )))
+    }
+
+    fn handle_command(&self, cmd: OmniboxCommand) -> OmniboxResult {
         match cmd {
             OmniboxCommand::LoadRequest
             | OmniboxCommand::UnloadRequest
+            | OmniboxCommand::EnableRequest
+            | OmniboxCommand::DisableRequest
+            | OmniboxCommand::KillRequest
+                if !self.marked.borrow().is_empty() =>
+            {
+                return self.handle_batch_request(cmd)
+            }
+            OmniboxCommand::BatchConfirm(action) => return self.batch_summary(action),
+            OmniboxCommand::Batch(action) => return self.run_batch(action),
+            OmniboxCommand::Unmark => {
+                self.marked.borrow_mut().clear();
+                return Ok(None);
+            }
+            OmniboxCommand::Undo => return self.undo(),
+            OmniboxCommand::Unprotect(label) => {
+                self.unprotected.replace(Some(label));
+                return Ok(None);
+            }
+            OmniboxCommand::CreateJob(job, load_now) => return self.create_job(*job, load_now),
+            _ => (),
+        };
+
+        let (
+            ServiceListItem {
+                name,
+                status,
+                plist_path,
+                job_type_filter,
+                ..
+            },
+            plist,
+        ) = self.with_active_item_plist()?;
+
+        // Say so up front rather than surface launchd's error
+        let op = match cmd {
+            OmniboxCommand::UnloadRequest
+            | OmniboxCommand::UnloadTarget(_)
             | OmniboxCommand::DisableRequest
             | OmniboxCommand::EnableRequest
+            | OmniboxCommand::DisableTarget(_)
+            | OmniboxCommand::EnableTarget(_)
+            | OmniboxCommand::RemoveRequest => Some(Operation::Change),
+            OmniboxCommand::LoadRequest
+            | OmniboxCommand::LoadTarget(_)
+            | OmniboxCommand::QuickLoad
+            | OmniboxCommand::KickstartRequest
+            | OmniboxCommand::DebugRequest
+            | OmniboxCommand::KillRequest
+            | OmniboxCommand::StartRequest
+            | OmniboxCommand::StopRequest
             | OmniboxCommand::ProcInfo
-            | OmniboxCommand::Edit => {
-                if (sudo::check() != RunningAs::Root) && need_escalate {
-                    return Ok(Some(OmniboxCommand::Confirm(
-                        "This requires root privileges. Sudo and restart?".to_string(),
-                        vec![OmniboxCommand::Quit, OmniboxCommand::Sudo],
-                    )));
-                }
+            | OmniboxCommand::CopyProcInfo
+            | OmniboxCommand::JobDetailRequest
+            | OmniboxCommand::MachGraph
+            | OmniboxCommand::PlistDiff
+            | OmniboxCommand::ApplyEdit(_) => Some(Operation::Control),
+            OmniboxCommand::RestoreBackup(_) | OmniboxCommand::Edit => Some(Operation::Write),
+            _ => None,
+        };
+
+        let preflight = Preflight::current();
+        let blocker = op
+            .and_then(|op| preflight.check_job(op, plist.as_ref()).err())
+            // launchd lets us remove our own submitted jobs, which have no plist
+            .filter(|b| {
+                !(cmd == OmniboxCommand::RemoveRequest && plist.is_none() && b.sudo_helps())
+            });
+
+        if let Some(blocker) = blocker {
+            return blocked(&preflight, vec![format!("{}: {}", name, blocker)], &blocker);
+        }
+
+        match cmd {
+            OmniboxCommand::UnloadRequest
+            | OmniboxCommand::UnloadTarget(_)
+            | OmniboxCommand::DisableRequest
+            | OmniboxCommand::DisableTarget(_)
+            | OmniboxCommand::KillRequest
+            | OmniboxCommand::RemoveRequest
+                if is_protected(&name, &status.domain)
+                    && self.unprotected.take().as_ref() != Some(&name) =>
+            {
+                return Ok(Some(OmniboxCommand::TypedConfirm(
+                    format!(
+                        "{} is a protected system job, stopping it can break your session.\n\nType its label to continue.",
+                        name
+                    ),
+                    name.clone(),
+                    vec![OmniboxCommand::Unprotect(name.clone()), cmd.clone()],
+                )));
             }
             _ => (),
         };
@@ -282,6 +1345,53 @@ impl ServiceListView {
                     ]))),
                 }
             }
+            OmniboxCommand::QuickLoad => {
+                let plist = status
+                    .plist
+                    .as_ref()
+                    .filter(|_| job_type_filter.contains(JobTypeFilter::ORPHANED))
+                    .ok_or_else(|| {
+                        OmniboxError::CommandError(format!(
+                            "{} is already loaded, or has no plist",
+                            name
+                        ))
+                    })?;
+
+                // Agents where this session puts them, in the plist's session type
+                let (st, dt, handle) = match plist.entry_type {
+                    LaunchdEntryType::Daemon => (SessionType::System, DomainType::System, None),
+                    LaunchdEntryType::Agent => {
+                        let session = LaunchSession::current();
+                        let (dt, handle) = session.default_domain();
+                        let st = plist
+                            .session_types
+                            .first()
+                            .cloned()
+                            .unwrap_or_else(|| session.default_session_type());
+
+                        (st, dt, handle)
+                    }
+                };
+
+                Ok(Some(OmniboxCommand::Load(st, dt, handle)))
+            }
+            // The status bar's domain target replaces the prompts, as if
+            // it had been typed after the command
+            OmniboxCommand::LoadRequest
+            | OmniboxCommand::UnloadRequest
+            | OmniboxCommand::EnableRequest
+            | OmniboxCommand::DisableRequest
+                if domain_target().is_some() =>
+            {
+                let target = domain_target().expect("Must have target").to_string();
+
+                Ok(Some(match cmd {
+                    OmniboxCommand::LoadRequest => OmniboxCommand::LoadTarget(target),
+                    OmniboxCommand::UnloadRequest => OmniboxCommand::UnloadTarget(target),
+                    OmniboxCommand::EnableRequest => OmniboxCommand::EnableTarget(target),
+                    _ => OmniboxCommand::DisableTarget(target),
+                }))
+            }
             OmniboxCommand::LoadRequest => Ok(Some(OmniboxCommand::DomainSessionPrompt(
                 name.clone(),
                 false,
@@ -305,11 +1415,62 @@ impl ServiceListView {
                     _ => Ok(Some(OmniboxCommand::Unload(domain, None))),
                 }
             }
+            OmniboxCommand::LoadTarget(target) => {
+                let mut args = target.split_whitespace();
+                let (dt, handle) = resolve_domain_target(args.next().unwrap_or(""))
+                    .map_err(OmniboxError::CommandError)?;
+
+                // Typed session type, or the same fallback as the XPC query
+                let st = match (args.next(), status.limit_load_to_session_type) {
+                    (Some(session), _) => session
+                        .parse()
+                        .map_err(|e| OmniboxError::CommandError(format!("{}", e)))?,
+                    (None, SessionType::Unknown) => target_session(&dt),
+                    (None, st) => st,
+                };
+
+                Ok(Some(OmniboxCommand::Load(st, dt, handle)))
+            }
+            OmniboxCommand::UnloadTarget(target) => {
+                let (dt, handle) =
+                    resolve_domain_target(&target).map_err(OmniboxError::CommandError)?;
+
+                Ok(Some(OmniboxCommand::Unload(dt, handle)))
+            }
             OmniboxCommand::EnableRequest => Ok(Some(OmniboxCommand::DomainSessionPrompt(
                 name.clone(),
                 true,
-                |dt, _| vec![OmniboxCommand::Enable(dt)],
+                |dt, _| vec![OmniboxCommand::Enable(dt, None)],
             ))),
+            OmniboxCommand::EnableTarget(ref target)
+            | OmniboxCommand::DisableTarget(ref target) => {
+                let (dt, handle) =
+                    resolve_domain_target(target).map_err(OmniboxError::CommandError)?;
+
+                // launchd only lets root change another user's overrides
+                let uid = rs_geteuid() as u64;
+                let other_user = match dt {
+                    DomainType::User | DomainType::UserLogin | DomainType::Gui => {
+                        handle.is_some_and(|h| h != uid)
+                    }
+                    _ => false,
+                };
+
+                if other_user && uid != 0 {
+                    return Ok(Some(OmniboxCommand::Confirm(
+                        format!(
+                            "Changing {} requires root privileges. Sudo and restart?",
+                            target
+                        ),
+                        vec![OmniboxCommand::Quit, OmniboxCommand::Sudo],
+                    )));
+                }
+
+                match cmd {
+                    OmniboxCommand::EnableTarget(_) => Ok(Some(OmniboxCommand::Enable(dt, handle))),
+                    _ => Ok(Some(OmniboxCommand::Disable(dt, handle))),
+                }
+            }
             OmniboxCommand::DisableRequest => {
                 let LaunchdEntryStatus { domain, .. } = status;
 
@@ -317,36 +1478,277 @@ impl ServiceListView {
                     DomainType::Unknown => Ok(Some(OmniboxCommand::DomainSessionPrompt(
                         name.clone(),
                         true,
-                        |dt, _| vec![OmniboxCommand::Disable(dt)],
+                        |dt, _| vec![OmniboxCommand::Disable(dt, None)],
                     ))),
                     _ => Ok(Some(OmniboxCommand::Chain(vec![OmniboxCommand::Disable(
-                        domain,
+                        domain, None,
                     )]))),
                 }
             }
-            OmniboxCommand::Enable(dt) => enable(name, dt)
+            OmniboxCommand::KickstartRequest => {
+                let LaunchdEntryStatus { domain, .. } = status;
+
+                match domain {
+                    DomainType::Unknown => Ok(Some(OmniboxCommand::DomainSessionPrompt(
+                        name.clone(),
+                        true,
+                        |dt, _| vec![OmniboxCommand::Kickstart(dt, true)],
+                    ))),
+                    _ => Ok(Some(OmniboxCommand::Confirm(
+                        format!("Kickstart {}?", name),
+                        vec![OmniboxCommand::Kickstart(domain, true)],
+                    ))),
+                }
+            }
+            OmniboxCommand::DebugRequest => {
+                let LaunchdEntryStatus { domain, .. } = status;
+
+                match domain {
+                    DomainType::Unknown => Ok(Some(OmniboxCommand::DomainSessionPrompt(
+                        name.clone(),
+                        true,
+                        |dt, _| vec![OmniboxCommand::DebugPrompt(dt)],
+                    ))),
+                    _ => Ok(Some(OmniboxCommand::DebugPrompt(domain))),
+                }
+            }
+            OmniboxCommand::StartRequest => {
+                let LaunchdEntryStatus { domain, .. } = status;
+
+                match domain {
+                    DomainType::Unknown => Ok(Some(OmniboxCommand::DomainSessionPrompt(
+                        name.clone(),
+                        true,
+                        |dt, _| vec![OmniboxCommand::Start(dt)],
+                    ))),
+                    _ => Ok(Some(OmniboxCommand::Start(domain))),
+                }
+            }
+            OmniboxCommand::StopRequest => {
+                let LaunchdEntryStatus { domain, .. } = status;
+
+                match domain {
+                    DomainType::Unknown => Ok(Some(OmniboxCommand::DomainSessionPrompt(
+                        name.clone(),
+                        true,
+                        |dt, _| vec![OmniboxCommand::Stop(dt)],
+                    ))),
+                    _ => Ok(Some(OmniboxCommand::Confirm(
+                        format!("Stop {}?", name),
+                        vec![OmniboxCommand::Stop(domain)],
+                    ))),
+                }
+            }
+            OmniboxCommand::RemoveRequest => Ok(Some(OmniboxCommand::Confirm(
+                format!("Remove {}?", name),
+                vec![OmniboxCommand::Remove],
+            ))),
+            OmniboxCommand::Remove => {
+                remove(name.clone()).map_err(|e| OmniboxError::CommandError(e.to_string()))?;
+                launchctl::record(&cmd, Some(&name));
+                Ok(None)
+            }
+            OmniboxCommand::KillRequest => {
+                let LaunchdEntryStatus { domain, .. } = status;
+
+                match domain {
+                    DomainType::Unknown => Ok(Some(OmniboxCommand::DomainSessionPrompt(
+                        name.clone(),
+                        true,
+                        |dt, _| vec![OmniboxCommand::SignalPrompt(dt, OmniboxCommand::Kill)],
+                    ))),
+                    _ => Ok(Some(OmniboxCommand::SignalPrompt(
+                        domain,
+                        OmniboxCommand::Kill,
+                    ))),
+                }
+            }
+            OmniboxCommand::Kill(ref dt, signal) => {
+                kill_signal(name.clone(), dt.clone(), signal)
+                    .map_err(|e| OmniboxError::CommandError(e.to_string()))?;
+                launchctl::record(&cmd, Some(&name));
+                Ok(None)
+            }
+            OmniboxCommand::Kickstart(ref dt, kill_first) => {
+                kickstart(name.clone(), dt.clone(), kill_first)
+                    .map_err(|e| OmniboxError::CommandError(e.to_string()))?;
+                launchctl::record(&cmd, Some(&name));
+                Ok(None)
+            }
+            OmniboxCommand::Debug(dt, options) => debug(name, dt, &options)
                 .map(|_| None)
                 .map_err(|e| OmniboxError::CommandError(e.to_string())),
-            OmniboxCommand::Disable(dt) => disable(name, dt)
+            OmniboxCommand::Start(ref dt) => {
+                start(name.clone(), dt.clone())
+                    .map_err(|e| OmniboxError::CommandError(e.to_string()))?;
+                launchctl::record(&cmd, Some(&name));
+                Ok(None)
+            }
+            OmniboxCommand::Stop(ref dt) => {
+                stop(name.clone(), dt.clone())
+                    .map_err(|e| OmniboxError::CommandError(e.to_string()))?;
+                launchctl::record(&cmd, Some(&name));
+                Ok(None)
+            }
+            OmniboxCommand::Enable(dt, handle) => self
+                .set_enabled(name, dt, handle, true)
                 .map(|_| None)
-                .map_err(|e| OmniboxError::CommandError(e.to_string())),
+                .map_err(OmniboxError::CommandError),
+            OmniboxCommand::Disable(dt, handle) => self
+                .set_enabled(name, dt, handle, false)
+                .map(|_| None)
+                .map_err(OmniboxError::CommandError),
+            OmniboxCommand::PidDomain(args) if args.is_empty() => {
+                if status.pid == 0 {
+                    return Err(OmniboxError::CommandError(format!("No PID for {}", name)));
+                }
+
+                Ok(Some(OmniboxCommand::PidDomain(vec![status
+                    .pid
+                    .to_string()])))
+            }
             OmniboxCommand::ProcInfo => {
                 if status.pid == 0 {
                     return Err(OmniboxError::CommandError(format!("No PID for {}", name)));
                 }
-                let (size, shmem) =
-                    procinfo(status.pid).map_err(|e| OmniboxError::CommandError(e.to_string()))?;
+                let cb_sink = self.cb_sink.clone();
+                let pid = status.pid;
 
-                show_pager(&self.cb_sink, unsafe {
-                    &*slice_from_raw_parts(shmem.region as *mut u8, size)
-                })
-                .map_err(|e| OmniboxError::CommandError(e))?;
+                run_with_spinner(
+                    &self.runtime_handle,
+                    self.cb_sink.clone(),
+                    format!("procinfo {}", pid),
+                    move || procinfo_bytes(pid),
+                    move |siv, bytes: Vec<u8>| {
+                        if let Err(e) = show_pager(&cb_sink, &bytes) {
+                            show_error(e)(siv);
+                        }
+                    },
+                );
 
                 Ok(None)
             }
-            OmniboxCommand::Edit | OmniboxCommand::Load(_, _, _) | OmniboxCommand::Unload(_, _) => {
-                self.handle_plist_command(cmd)
+            OmniboxCommand::CopyProcInfo => {
+                if status.pid == 0 {
+                    return Err(OmniboxError::CommandError(format!("No PID for {}", name)));
+                }
+                let pid = status.pid;
+
+                run_with_spinner(
+                    &self.runtime_handle,
+                    self.cb_sink.clone(),
+                    format!("procinfo {}", pid),
+                    move || {
+                        let bytes = procinfo_bytes(pid)?;
+                        copy_to_clipboard(&String::from_utf8_lossy(&bytes))
+                    },
+                    move |siv, _| show_error(format!("Copied procinfo for PID {}", pid))(siv),
+                );
+
+                Ok(None)
+            }
+            OmniboxCommand::JobDetailRequest => {
+                let pid = status.pid;
+
+                run_with_spinner(
+                    &self.runtime_handle,
+                    self.cb_sink.clone(),
+                    format!("dumpstate: {}", name),
+                    move || job_detail(&name, pid),
+                    |siv, detail| {
+                        siv.call_on_name("job_detail", |v: &mut JobDetailView| {
+                            v.show_detail(&detail)
+                        });
+                    },
+                );
+
+                Ok(None)
+            }
+            OmniboxCommand::MachGraph => {
+                let label = name.clone();
+
+                run_with_spinner(
+                    &self.runtime_handle,
+                    self.cb_sink.clone(),
+                    format!("dumpstate: {}", name),
+                    move || mach_service_graph().map(|g| g.links(&label)),
+                    |siv, links| show_mach_graph(name, links)(siv),
+                );
+
+                Ok(None)
             }
+            OmniboxCommand::PlistDiff => {
+                let path = plist_path.clone();
+
+                run_with_spinner(
+                    &self.runtime_handle,
+                    self.cb_sink.clone(),
+                    format!("dumpstate: {}", name),
+                    move || plist_diff(&name, path.as_deref()),
+                    |siv, (path, diffs)| show_plist_diff(path, diffs)(siv),
+                );
+
+                Ok(None)
+            }
+            OmniboxCommand::LogStream => {
+                let program = status
+                    .plist
+                    .as_ref()
+                    .and_then(|p| plist_program(&p.plist_path));
+                let predicate = job_predicate(&name, status.pid, program.as_deref());
+
+                show_log_stream(&self.cb_sink, format!("log stream: {}", name), predicate)
+                    .map_err(OmniboxError::CommandError)?;
+
+                Ok(None)
+            }
+            OmniboxCommand::TailOutput(stderr) => {
+                let key = if stderr {
+                    "StandardErrorPath"
+                } else {
+                    "StandardOutPath"
+                };
+                let (stdout_path, stderr_path) = status
+                    .plist
+                    .as_ref()
+                    .map(|p| plist_output_paths(&p.plist_path))
+                    .unwrap_or_default();
+                let path = if stderr { stderr_path } else { stdout_path }.ok_or_else(|| {
+                    OmniboxError::CommandError(format!("No {} for {}", key, name))
+                })?;
+
+                show_file_tail(&self.cb_sink, format!("{}: {}", key, name), path, stderr)
+                    .map_err(OmniboxError::CommandError)?;
+
+                Ok(None)
+            }
+            OmniboxCommand::CopyLabel => {
+                copy_to_clipboard(&name).map_err(OmniboxError::CommandError)?;
+                self.cb_sink
+                    .send(show_error(format!("Copied {}", name)))
+                    .expect("Must show notice");
+
+                Ok(None)
+            }
+            OmniboxCommand::CopyPath => {
+                let path = plist_path.ok_or_else(|| {
+                    OmniboxError::CommandError(format!("No plist path for {}", name))
+                })?;
+
+                copy_to_clipboard(&path).map_err(OmniboxError::CommandError)?;
+                self.cb_sink
+                    .send(show_error(format!("Copied {}", path)))
+                    .expect("Must show notice");
+
+                Ok(None)
+            }
+            OmniboxCommand::ViewPlist
+            | OmniboxCommand::Edit
+            | OmniboxCommand::ApplyEdit(_)
+            | OmniboxCommand::RestoreBackupRequest(_)
+            | OmniboxCommand::RestoreBackup(_)
+            | OmniboxCommand::Load(_, _, _)
+            | OmniboxCommand::Unload(_, _) => self.handle_plist_command(cmd),
             _ => Ok(None),
         }
     }
@@ -361,6 +1763,35 @@ impl ViewWrapper for ServiceListView {
         if let Some(sorted) = self.present_services() {
             self.with_view_mut(|v| v.replace_and_preserve_selection(sorted));
         }
+
+        let restore = self.restore_selection.borrow().clone();
+        match restore {
+            Some(label) => {
+                if self.table_list_view.select_where(|item| item.name == label) {
+                    self.restore_selection.replace(None);
+                }
+            }
+            None => {
+                let selected = self.table_list_view.get_highlighted_row();
+                ui_state::update(|state| state.selected = selected.map(|item| item.name.clone()));
+            }
+        }
+    }
+
+    fn wrap_on_event(&mut self, event: Event) -> EventResult {
+        // Moving before the job is listed keeps the user's choice
+        if event != Event::Refresh {
+            self.restore_selection.replace(None);
+        }
+
+        if let Some(column) = self.table_list_view.clicked_column(&event) {
+            return self.toggle_sort(column);
+        }
+
+        match event {
+            Event::Char(' ') => self.toggle_mark(),
+            _ => self.table_list_view.on_event(event),
+        }
     }
 
     fn wrap_take_focus(&mut self, _: Direction) -> Result<EventResult, CannotFocus> {
@@ -372,6 +1803,19 @@ impl OmniboxSubscriber for ServiceListView {
     fn on_omnibox(&mut self, event: OmniboxEvent) -> OmniboxResult {
         match event {
             OmniboxEvent::StateUpdate(state) => self.handle_state_update(state),
+            OmniboxEvent::Command(OmniboxCommand::Export(args)) => self.export(&args),
+            OmniboxEvent::Command(OmniboxCommand::Lint(args)) => self.lint(&args),
+            OmniboxEvent::Command(OmniboxCommand::DryRun(args)) => self.dry_run(&args),
+            OmniboxEvent::Command(OmniboxCommand::ReloadConfig) => self.reload_columns(),
+            OmniboxEvent::Command(OmniboxCommand::ColumnsRequest) => {
+                Ok(Some(OmniboxCommand::ColumnPicker(self.columns.clone())))
+            }
+            OmniboxEvent::Command(OmniboxCommand::Profile(args)) => self.apply_profile(&args),
+            OmniboxEvent::Command(OmniboxCommand::SetColumns(columns)) => {
+                self.set_columns(columns);
+                Ok(None)
+            }
+            OmniboxEvent::Command(OmniboxCommand::CopyLaunchctl) => self.copy_launchctl(),
             OmniboxEvent::Command(cmd) => self.handle_command(cmd),
         }
     }
@@ -0,0 +1,40 @@
+use cursive::theme::Style;
+use cursive::traits::{Resizable, Scrollable};
+use cursive::utils::markup::StyledString;
+use cursive::views::{Dialog, TextView};
+
+use crate::launchd::snapshot::SnapshotChange;
+use crate::tui::root::CbSinkMessage;
+use crate::tui::theme::roles;
+
+fn change_style(change: &SnapshotChange) -> Style {
+    match change {
+        SnapshotChange::Added(_) => Style::from(roles().error),
+        SnapshotChange::Removed(_) => Style::from(roles().success),
+        SnapshotChange::PlistMoved(_, _, _) => Style::from(roles().special),
+        SnapshotChange::Loaded(_) | SnapshotChange::Enabled(_) => Style::from(roles().warning),
+        SnapshotChange::Unloaded(_) | SnapshotChange::Disabled(_) => Style::from(roles().info),
+    }
+}
+
+/// Changes between snapshots, new jobs stand out as possible persistence
+pub fn show_snapshot_diff(title: String, changes: Vec<SnapshotChange>) -> CbSinkMessage {
+    Box::new(move |siv| {
+        let mut text = StyledString::new();
+
+        for change in &changes {
+            text.append_styled(format!("{}\n", change), change_style(change));
+        }
+
+        if changes.is_empty() {
+            text.append("No changes");
+        }
+
+        siv.add_layer(
+            Dialog::around(TextView::new(text).scrollable())
+                .title(title)
+                .dismiss_button("OK")
+                .max_height(40),
+        );
+    })
+}
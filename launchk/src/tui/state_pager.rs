@@ -0,0 +1,387 @@
+use std::ptr::slice_from_raw_parts;
+
+use cursive::direction::Direction;
+use cursive::event::{Event, EventResult, Key, MouseEvent};
+use cursive::theme::{ColorStyle, Effect};
+use cursive::traits::{Nameable, Resizable};
+use cursive::view::CannotFocus;
+use cursive::views::{Dialog, EditView};
+use cursive::{Cursive, Printer, View, XY};
+use regex::{bytes, Regex, RegexBuilder};
+use xpc_sys::objects::xpc_shmem::XPCShmem;
+
+use crate::tui::dialog::show_error;
+
+const STATE_PAGER: &str = "state_pager";
+const WHEEL_LINES: isize = 3;
+
+/// Byte offset of every line
+fn line_starts(bytes: &[u8]) -> Vec<usize> {
+    let mut starts = vec![0];
+
+    starts.extend(
+        bytes
+            .iter()
+            .enumerate()
+            .filter(|(i, b)| **b == b'\n' && i + 1 < bytes.len())
+            .map(|(i, _)| i + 1),
+    );
+
+    starts
+}
+
+/// Line containing a byte offset
+fn line_of(starts: &[usize], offset: usize) -> usize {
+    match starts.binary_search(&offset) {
+        Ok(line) => line,
+        Err(line) => line.saturating_sub(1),
+    }
+}
+
+/// First matching line after from (wrapping), or the last one before it
+fn find_line(
+    bytes: &[u8],
+    starts: &[usize],
+    re: &bytes::Regex,
+    from: usize,
+    forward: bool,
+) -> Option<usize> {
+    let offset = starts.get(from).copied().unwrap_or(bytes.len());
+
+    let found = if forward {
+        re.find_at(bytes, offset)
+            .or_else(|| re.find(bytes))
+            .map(|m| m.start())
+    } else {
+        re.find_iter(&bytes[..offset])
+            .last()
+            .or_else(|| re.find_iter(bytes).last())
+            .map(|m| m.start())
+    };
+
+    found.map(|o| line_of(starts, o))
+}
+
+/// A service block header, e.g. gui/501/com.example.agent = {
+fn label_regex(label: &str) -> bytes::Regex {
+    bytes::Regex::new(&format!(r"(?m)^\S+/{} = \{{", regex::escape(label)))
+        .expect("Must build label regex")
+}
+
+/// Case insensitive (bytes for finding lines, str for highlighting)
+fn search_regexes(query: &str) -> Option<(bytes::Regex, Regex)> {
+    let pattern = regex::escape(query);
+
+    Some((
+        bytes::RegexBuilder::new(&pattern)
+            .case_insensitive(true)
+            .build()
+            .ok()?,
+        RegexBuilder::new(&pattern)
+            .case_insensitive(true)
+            .build()
+            .ok()?,
+    ))
+}
+
+/// A shmem response, of which size bytes were written
+struct ShmemBytes(XPCShmem, usize);
+
+impl AsRef<[u8]> for ShmemBytes {
+    fn as_ref(&self) -> &[u8] {
+        let Self(shmem, size) = self;
+        unsafe { &*slice_from_raw_parts(shmem.region as *const u8, (*size).min(shmem.size)) }
+    }
+}
+
+/// Pages through a response (e.g. dumpstate shmem) in place, lines are
+/// only decoded when drawn
+pub struct StatePager {
+    title: String,
+    buf: Box<dyn AsRef<[u8]>>,
+    size: usize,
+    starts: Vec<usize>,
+    top: usize,
+    height: usize,
+    query: String,
+    search: Option<(bytes::Regex, Regex)>,
+    /// Where incremental search started, restored on cancel
+    search_origin: usize,
+}
+
+impl StatePager {
+    fn new(title: String, buf: Box<dyn AsRef<[u8]>>) -> Self {
+        let size = buf.as_ref().as_ref().len();
+        let mut pager = Self {
+            title,
+            buf,
+            size,
+            starts: vec![],
+            top: 0,
+            height: 1,
+            query: String::new(),
+            search: None,
+            search_origin: 0,
+        };
+
+        pager.starts = line_starts(pager.bytes());
+        pager
+    }
+
+    fn bytes(&self) -> &[u8] {
+        self.buf.as_ref().as_ref()
+    }
+
+    fn line(&self, i: usize) -> String {
+        let start = self.starts[i];
+        let end = self.starts.get(i + 1).copied().unwrap_or(self.size);
+
+        String::from_utf8_lossy(&self.bytes()[start..end])
+            .trim_end_matches('\n')
+            .replace('\t', "    ")
+    }
+
+    /// Rows for text, the last one is the status line
+    fn page(&self) -> usize {
+        self.height.saturating_sub(1).max(1)
+    }
+
+    fn scroll_to(&mut self, line: usize) {
+        let last = self.starts.len().saturating_sub(self.page());
+        self.top = line.min(last);
+    }
+
+    fn scroll_by(&mut self, lines: isize) {
+        let line = if lines < 0 {
+            self.top.saturating_sub(lines.unsigned_abs())
+        } else {
+            self.top + lines as usize
+        };
+
+        self.scroll_to(line);
+    }
+
+    fn start_search(&mut self) {
+        self.search_origin = self.top;
+    }
+
+    /// Jump to the first match from where searching started
+    fn search_incremental(&mut self, query: &str) {
+        self.query = query.to_string();
+        self.search = Some(query)
+            .filter(|q| !q.is_empty())
+            .and_then(search_regexes);
+
+        let found = self.search.as_ref().and_then(|(re, _)| {
+            find_line(self.bytes(), &self.starts, re, self.search_origin, true)
+        });
+
+        self.scroll_to(found.unwrap_or(self.search_origin));
+    }
+
+    fn cancel_search(&mut self) {
+        self.search_incremental("");
+    }
+
+    fn search_next(&mut self, forward: bool) {
+        let from = if forward { self.top + 1 } else { self.top };
+        let found = self
+            .search
+            .as_ref()
+            .and_then(|(re, _)| find_line(self.bytes(), &self.starts, re, from, forward));
+
+        if let Some(line) = found {
+            self.scroll_to(line);
+        }
+    }
+
+    fn jump_to_label(&mut self, label: &str) -> Result<(), String> {
+        let line = find_line(self.bytes(), &self.starts, &label_regex(label), 0, true)
+            .ok_or_else(|| format!("{} not found in {}", label, self.title))?;
+
+        self.scroll_to(line);
+        Ok(())
+    }
+
+    fn status(&self) -> String {
+        let search = if self.query.is_empty() {
+            String::new()
+        } else {
+            format!("  /{}", self.query)
+        };
+
+        format!(
+            "{}: {}-{} of {}{}  /: search  n/N: next/prev  l: jump to label  q: close",
+            self.title,
+            self.top + 1,
+            (self.top + self.page()).min(self.starts.len()),
+            self.starts.len(),
+            search
+        )
+    }
+}
+
+fn search_prompt(siv: &mut Cursive) {
+    let query = siv
+        .call_on_name(STATE_PAGER, |v: &mut StatePager| {
+            v.start_search();
+            v.query.clone()
+        })
+        .unwrap_or_default();
+
+    let dialog = Dialog::around(
+        EditView::new()
+            .content(query)
+            .on_edit(|s, text, _| {
+                s.call_on_name(STATE_PAGER, |v: &mut StatePager| v.search_incremental(text));
+            })
+            .on_submit(|s, _| {
+                s.pop_layer();
+            })
+            .min_width(40),
+    )
+    .title("Search")
+    .button("Cancel", |s| {
+        s.pop_layer();
+        s.call_on_name(STATE_PAGER, StatePager::cancel_search);
+    });
+
+    siv.add_layer(dialog);
+}
+
+fn label_prompt(siv: &mut Cursive) {
+    let dialog = Dialog::around(
+        EditView::new()
+            .on_submit(|s, label| {
+                s.pop_layer();
+
+                let result = s
+                    .call_on_name(STATE_PAGER, |v: &mut StatePager| v.jump_to_label(label))
+                    .unwrap_or(Ok(()));
+
+                if let Err(e) = result {
+                    show_error(e)(s);
+                }
+            })
+            .min_width(40),
+    )
+    .title("Jump to label")
+    .dismiss_button("Cancel");
+
+    siv.add_layer(dialog);
+}
+
+impl View for StatePager {
+    fn draw(&self, printer: &Printer) {
+        let end = (self.top + self.page()).min(self.starts.len());
+
+        for (row, i) in (self.top..end).enumerate() {
+            let line = self.line(i);
+            printer.print((0, row), &line);
+
+            if let Some((_, re)) = &self.search {
+                for m in re.find_iter(&line) {
+                    let x = line[..m.start()].chars().count();
+                    printer.with_color(ColorStyle::highlight(), |p| p.print((x, row), m.as_str()));
+                }
+            }
+        }
+
+        printer.with_effect(Effect::Reverse, |p| {
+            p.print((0, self.page()), &self.status());
+        });
+    }
+
+    fn layout(&mut self, size: XY<usize>) {
+        self.height = size.y;
+        self.scroll_to(self.top);
+    }
+
+    fn required_size(&mut self, constraint: XY<usize>) -> XY<usize> {
+        constraint
+    }
+
+    fn take_focus(&mut self, _: Direction) -> Result<EventResult, CannotFocus> {
+        Ok(EventResult::Consumed(None))
+    }
+
+    fn on_event(&mut self, event: Event) -> EventResult {
+        let page = self.page() as isize;
+
+        match event {
+            Event::Key(Key::Down) | Event::Char('j') => self.scroll_by(1),
+            Event::Key(Key::Up) | Event::Char('k') => self.scroll_by(-1),
+            Event::Key(Key::PageDown) | Event::Char(' ') => self.scroll_by(page),
+            Event::Key(Key::PageUp) | Event::Char('b') => self.scroll_by(-page),
+            Event::Key(Key::Home) | Event::Char('g') => self.scroll_to(0),
+            Event::Key(Key::End) | Event::Char('G') => self.scroll_to(self.starts.len()),
+            Event::Mouse {
+                event: MouseEvent::WheelDown,
+                ..
+            } => self.scroll_by(WHEEL_LINES),
+            Event::Mouse {
+                event: MouseEvent::WheelUp,
+                ..
+            } => self.scroll_by(-WHEEL_LINES),
+            Event::Char('n') => self.search_next(true),
+            Event::Char('N') => self.search_next(false),
+            Event::Char('/') => return EventResult::with_cb(search_prompt),
+            Event::Char('l') => return EventResult::with_cb(label_prompt),
+            Event::Char('q') | Event::Key(Key::Esc) => {
+                return EventResult::with_cb(|s| {
+                    s.pop_layer();
+                })
+            }
+            _ => return EventResult::Ignored,
+        }
+
+        EventResult::Consumed(None)
+    }
+}
+
+/// Full screen pager over shmem, which is kept until the pager closes
+pub fn show_state_pager(siv: &mut Cursive, title: String, shmem: XPCShmem, size: usize) {
+    show_pager_layer(siv, title, Box::new(ShmemBytes(shmem, size)));
+}
+
+/// Same pager over output read into memory, e.g. from a pipe
+pub fn show_text_pager(siv: &mut Cursive, title: String, bytes: Vec<u8>) {
+    show_pager_layer(siv, title, Box::new(bytes));
+}
+
+fn show_pager_layer(siv: &mut Cursive, title: String, buf: Box<dyn AsRef<[u8]>>) {
+    siv.add_layer(
+        StatePager::new(title, buf)
+            .with_name(STATE_PAGER)
+            .full_screen(),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_line, label_regex, line_starts};
+    use regex::bytes::Regex;
+
+    static STATE: &[u8] = b"system = {\n\tservices = {\n\t}\n}\n\ngui/501/com.example.agent = {\n\tstate = running\n}\n";
+
+    #[test]
+    fn index_lines() {
+        let starts = line_starts(STATE);
+        assert_eq!(starts.len(), 8);
+        assert_eq!(starts[1], 11);
+    }
+
+    #[test]
+    fn find_forward_and_back() {
+        let starts = line_starts(STATE);
+        let re = Regex::new("=").unwrap();
+
+        assert_eq!(find_line(STATE, &starts, &re, 2, true), Some(5));
+        assert_eq!(find_line(STATE, &starts, &re, 5, false), Some(1));
+        // Wraps to the top
+        assert_eq!(find_line(STATE, &starts, &re, 7, true), Some(0));
+
+        let label = label_regex("com.example.agent");
+        assert_eq!(find_line(STATE, &starts, &label, 0, true), Some(5));
+    }
+}
@@ -1,14 +1,20 @@
-use cursive::theme::{BaseColor, Color, Effect, Style};
+use cursive::theme::{Effect, Style};
 use cursive::utils::markup::StyledString;
 
+use cursive::traits::Nameable;
 use cursive::views::{LinearLayout, ResizedView, TextView};
 
 use sudo::RunningAs;
-use xpc_sys::csr::{csr_check, CsrConfig};
+use xpc_sys::csr::sip_enabled;
 use xpc_sys::rs_sysctlbyname;
 
+use crate::launchd::entry_status::domain_target;
+use crate::launchd::query::{launchd_manager, list_errors};
+use crate::launchd::session::LaunchSession;
+use crate::tui::theme::roles;
+
 pub fn make_layout() -> LinearLayout {
-    let bold = Style::from(Color::Light(BaseColor::White)).combine(Effect::Bold);
+    let bold = Style::from(roles().emphasis).combine(Effect::Bold);
     let mut layout = LinearLayout::horizontal();
 
     let mut macos = StyledString::styled("macOS: ", bold);
@@ -18,22 +24,72 @@ pub fn make_layout() -> LinearLayout {
     macos.append_plain(format!("{} ({})", osproductversion, osversion));
 
     if sudo::check() == RunningAs::Root {
-        macos.append_styled(" (root)", bold.combine(Color::Light(BaseColor::Red)));
+        macos.append_styled(" (root)", bold.combine(roles().error));
     }
 
     layout.add_child(ResizedView::with_full_width(TextView::new(macos)));
 
-    // If granted CSR_ALLOW_UNTRUSTED_KEXTS, SIP is probably off
+    // /System jobs cannot be changed while on
     let mut sip = StyledString::styled("SIP: ", bold);
-    let sip_data = unsafe {
-        format!(
-            "{}",
-            csr_check(CsrConfig::ALLOW_UNTRUSTED_KEXTS.bits()) != 0
-        )
-    };
-    sip.append_plain(sip_data);
+    if sip_enabled() {
+        sip.append_plain("on");
+    } else {
+        sip.append_styled("off", roles().warning);
+    }
 
     layout.add_child(ResizedView::with_full_width(TextView::new(sip)));
 
+    let launchd = launchd_manager();
+    let mut manager = StyledString::styled("launchd: ", bold);
+    manager.append_plain(launchd.to_string());
+
+    // Only worth pointing out when not the usual build
+    match launchd.variant.as_deref() {
+        Some("RELEASE") | None => (),
+        Some(variant) => manager.append_styled(format!(" {}", variant), roles().warning),
+    }
+
+    // Agents default to the user domain and Background there
+    if LaunchSession::current() == LaunchSession::Ssh {
+        manager.append_styled(" via SSH", roles().info);
+    }
+
+    layout.add_child(ResizedView::with_full_width(TextView::new(manager)));
+    layout.add_child(ResizedView::with_full_width(
+        TextView::new(domain_target_badge()).with_name("domain_target"),
+    ));
+    layout.add_child(TextView::new(list_errors_badge()).with_name("list_errors"));
+
     layout
 }
+
+/// Where load, unload, enable and disable go, :target changes it
+pub fn domain_target_badge() -> StyledString {
+    let bold = Style::from(roles().emphasis).combine(Effect::Bold);
+    let mut badge = StyledString::styled("Target: ", bold);
+
+    match domain_target() {
+        Some(target) => badge.append_styled(target.to_string(), roles().info),
+        None => badge.append_plain("ask"),
+    }
+
+    badge
+}
+
+/// Warning for domains launchd wouldn't list, empty if none.
+/// domainerrors has the details
+pub fn list_errors_badge() -> StyledString {
+    let errors = list_errors();
+
+    match errors.as_slice() {
+        [] => StyledString::new(),
+        [(domain, _)] => StyledString::styled(
+            format!("⚠ {} not listed (:domainerrors)", domain),
+            roles().warning,
+        ),
+        _ => StyledString::styled(
+            format!("⚠ {} domains not listed (:domainerrors)", errors.len()),
+            roles().warning,
+        ),
+    }
+}
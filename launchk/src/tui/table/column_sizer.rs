@@ -9,7 +9,6 @@ pub struct ColumnSizer {
     pub padding: Cell<usize>,
     /// Column index -> width
     pub user_sizes: HashMap<usize, usize>,
-    pub num_columns: usize,
 
     num_dynamic_columns: usize,
     /// Sum of user size widths
@@ -36,7 +35,6 @@ impl ColumnSizer {
 
         let cs = Self {
             num_dynamic_columns,
-            num_columns,
             user_sizes,
             user_sizes_total,
             dynamic_column_size: Default::default(),
@@ -78,7 +76,8 @@ impl ColumnSizer {
             0
         };
 
-        let mut dcs = remaining / self.num_dynamic_columns;
+        // Every column may have a user width
+        let mut dcs = remaining / self.num_dynamic_columns.max(1);
         if dcs > 35 {
             dcs = 35;
         }
@@ -1,9 +1,10 @@
 use std::sync::Arc;
 
-use cursive::theme::{BaseColor, Color, Effect, Style};
+use cursive::theme::{Effect, Style};
 use cursive::{Printer, View, XY};
 
 use super::column_sizer::ColumnSizer;
+use crate::tui::theme::roles;
 
 /// Draw column headers from their names + a column sizer
 pub struct TableHeaders {
@@ -25,7 +26,7 @@ impl TableHeaders {
 
 impl View for TableHeaders {
     fn draw(&self, printer: &Printer<'_, '_>) {
-        let bold = Style::from(Color::Dark(BaseColor::Blue)).combine(Effect::Bold);
+        let bold = Style::from(roles().header).combine(Effect::Bold);
 
         let headers: String = self
             .columns
@@ -6,8 +6,10 @@ use std::rc::Rc;
 use std::collections::hash_map::DefaultHasher;
 use std::sync::Arc;
 
-use cursive::event::{Event, EventResult};
+use cursive::event::{Event, EventResult, MouseButton, MouseEvent};
+use cursive::theme::Style;
 use cursive::traits::{Resizable, Scrollable};
+use cursive::utils::markup::StyledString;
 use cursive::view::ViewWrapper;
 use cursive::views::{LinearLayout, ResizedView, ScrollView, SelectView};
 use cursive::{Vec2, View};
@@ -16,12 +18,23 @@ use crate::tui::table::table_headers::TableHeaders;
 
 use super::column_sizer::ColumnSizer;
 pub trait TableListItem {
-    fn as_row(&self) -> Vec<String>;
+    type Column;
+
+    fn cell(&self, column: &Self::Column) -> String;
+
+    /// Color for a cell, None for the view's default
+    fn cell_style(&self, _column: &Self::Column) -> Option<Style> {
+        None
+    }
 }
 
+/// Column, header and width (None to share remaining space)
+pub type TableColumn<C> = (C, String, Option<usize>);
+
 /// A "table" implemented on top of SelectView<T> where we
 /// divvy up x into columns
-pub struct TableListView<T> {
+pub struct TableListView<T: TableListItem> {
+    columns: Vec<T::Column>,
     column_sizer: Arc<ColumnSizer>,
     linear_layout: LinearLayout,
     // LinearLayout swallows T from , but we still need it
@@ -30,24 +43,12 @@ pub struct TableListView<T> {
 }
 
 impl<T: 'static + TableListItem> TableListView<T> {
-    pub fn new<I, K>(columns: I) -> TableListView<T>
-    where
-        I: IntoIterator<Item = (K, Option<usize>)> + Clone,
-        K: AsRef<str>,
-    {
-        let column_names = columns
-            .clone()
-            .into_iter()
-            .map(|(n, _)| n.as_ref().to_string());
-        let column_sizer = ColumnSizer::new(columns);
+    pub fn new(columns: Vec<TableColumn<T::Column>>) -> TableListView<T> {
+        let (columns, column_sizer, headers) = Self::build_columns(columns);
         let last_hash = RefCell::new(0u64);
 
         let mut linear_layout = LinearLayout::vertical();
-        linear_layout.add_child(
-            TableHeaders::new(column_names, column_sizer.clone())
-                .full_width()
-                .max_height(1),
-        );
+        linear_layout.add_child(headers);
         linear_layout.add_child(
             SelectView::<T>::new()
                 .full_width()
@@ -56,6 +57,7 @@ impl<T: 'static + TableListItem> TableListView<T> {
         );
 
         Self {
+            columns,
             linear_layout,
             column_sizer,
             inner: PhantomData::default(),
@@ -63,33 +65,64 @@ impl<T: 'static + TableListItem> TableListView<T> {
         }
     }
 
+    fn build_columns(
+        columns: Vec<TableColumn<T::Column>>,
+    ) -> (Vec<T::Column>, Arc<ColumnSizer>, impl View) {
+        let column_sizer = ColumnSizer::new(columns.iter().map(|(_, h, w)| (h, *w)));
+        let headers = TableHeaders::new(columns.iter().map(|(_, h, _)| h), column_sizer.clone())
+            .full_width()
+            .max_height(1);
+
+        (
+            columns.into_iter().map(|(c, _, _)| c).collect(),
+            column_sizer,
+            headers,
+        )
+    }
+
+    /// Replace the columns, rows are redrawn on the next replace
+    pub fn set_columns(&mut self, columns: Vec<TableColumn<T::Column>>) {
+        let (columns, column_sizer, headers) = Self::build_columns(columns);
+
+        self.columns = columns;
+        self.column_sizer = column_sizer;
+        self.linear_layout.remove_child(0);
+        self.linear_layout.insert_child(0, headers);
+        *self.last_hash.borrow_mut() = 0;
+    }
+
     pub fn replace_and_preserve_selection<I>(&mut self, items: I)
     where
         I: IntoIterator<Item = T>,
         T: Hash,
     {
-        let rows: Vec<(String, T)> = items
+        let rows: Vec<(StyledString, T)> = items
             .into_iter()
             .map(|item: T| {
-                let presented: Vec<String> = item
-                    .as_row()
-                    .iter()
-                    .take(self.column_sizer.num_columns)
-                    .enumerate()
-                    .map(|(i, field)| {
-                        let wfi = self.column_sizer.width_for_index(i);
-                        let mut truncated = field.clone();
-                        truncated.truncate(wfi - 1);
-                        format!("{:with_padding$}", truncated, with_padding = wfi)
-                    })
-                    .collect();
-
-                (presented.join(""), item)
+                let mut presented = StyledString::new();
+
+                for (i, column) in self.columns.iter().enumerate() {
+                    let wfi = self.column_sizer.width_for_index(i);
+                    // By chars, markers like ⊘ are multi-byte
+                    let truncated: String = item.cell(column).chars().take(wfi - 1).collect();
+                    let padded = format!("{:with_padding$}", truncated, with_padding = wfi);
+
+                    match item.cell_style(column) {
+                        Some(style) => presented.append_styled(padded, style),
+                        None => presented.append_plain(padded),
+                    }
+                }
+
+                (presented, item)
             })
             .collect();
 
+        // Styles follow from the item, the text and item are enough
         let mut row_hasher = DefaultHasher::new();
-        rows.hash(&mut row_hasher);
+        for (presented, item) in rows.iter() {
+            presented.source().hash(&mut row_hasher);
+            item.hash(&mut row_hasher);
+        }
         let hash = row_hasher.finish();
 
         if *self.last_hash.borrow() == hash {
@@ -106,6 +139,43 @@ impl<T: 'static + TableListItem> TableListView<T> {
         sv.set_selection(current_selection);
     }
 
+    /// Column whose header was clicked, if event is a click on the headers
+    pub fn clicked_column(&self, event: &Event) -> Option<T::Column>
+    where
+        T::Column: Clone,
+    {
+        let position = match event {
+            Event::Mouse {
+                offset,
+                position,
+                event: MouseEvent::Press(MouseButton::Left),
+            } => position.checked_sub(*offset)?,
+            _ => return None,
+        };
+
+        if position.y != 0 {
+            return None;
+        }
+
+        let mut x = 0;
+        self.columns.iter().enumerate().find_map(|(i, column)| {
+            x += self.column_sizer.width_for_index(i);
+            Some(column.clone()).filter(|_| position.x < x)
+        })
+    }
+
+    /// Highlight the first row matching f, false if none do
+    pub fn select_where<F: Fn(&T) -> bool>(&mut self, f: F) -> bool {
+        let sv = self.get_mut_selectview();
+        let index = match sv.iter().position(|(_, item)| f(item)) {
+            Some(i) => i,
+            None => return false,
+        };
+
+        sv.set_selection(index);
+        true
+    }
+
     pub fn get_highlighted_row(&self) -> Option<Rc<T>> {
         self.get_selectview().selection()
     }
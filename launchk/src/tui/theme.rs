@@ -0,0 +1,178 @@
+use std::sync::RwLock;
+
+use cursive::theme::Color;
+use toml::Value;
+
+use crate::config::Config;
+
+/// Built-in color schemes, the first is the default
+static SCHEMES: [(&str, &str); 3] = [
+    ("dark", include_str!("style.toml")),
+    ("light", include_str!("themes/light.toml")),
+    ("solarized", include_str!("themes/solarized.toml")),
+];
+
+lazy_static! {
+    static ref ROLES: RwLock<Roles> = RwLock::new(Roles::default());
+    /// Picked with the theme command, wins over config until restart
+    static ref SCHEME_OVERRIDE: RwLock<Option<String>> = RwLock::new(None);
+}
+
+/// Colors launchk draws with itself, Cursive's palette covers the rest
+/// (views, list highlights)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Roles {
+    /// Table headers
+    pub header: Color,
+    /// Active filter badges, omnibox mode
+    pub accent: Color,
+    /// Inactive filter badges, suggestions
+    pub subtle: Color,
+    /// Labels in the status bar
+    pub emphasis: Color,
+    pub error: Color,
+    pub warning: Color,
+    pub success: Color,
+    pub info: Color,
+    pub special: Color,
+}
+
+impl Default for Roles {
+    fn default() -> Self {
+        let (_, dark) = SCHEMES[0];
+        let mut theme: Value = dark.parse().expect("Must parse built-in theme");
+
+        take_roles(&mut theme, None).expect("Must read built-in roles")
+    }
+}
+
+pub fn scheme_names() -> Vec<&'static str> {
+    SCHEMES.iter().map(|(name, _)| *name).collect()
+}
+
+fn scheme(name: &str) -> Result<&'static str, String> {
+    SCHEMES
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, toml)| *toml)
+        .ok_or_else(|| {
+            format!(
+                "Unknown color scheme {}, expected one of {}",
+                name,
+                scheme_names().join(", ")
+            )
+        })
+}
+
+/// Remove [roles] (Cursive doesn't know it) and parse it, missing roles
+/// come from base
+fn take_roles(theme: &mut Value, base: Option<&Roles>) -> Result<Roles, String> {
+    let roles = theme
+        .as_table_mut()
+        .and_then(|t| t.remove("roles"))
+        .unwrap_or(Value::Table(Default::default()));
+
+    let roles = roles
+        .as_table()
+        .ok_or_else(|| "[roles] must be a table".to_string())?;
+
+    let color = |name: &str| -> Result<Option<Color>, String> {
+        match roles.get(name) {
+            None => Ok(None),
+            Some(v) => v
+                .as_str()
+                .and_then(Color::parse)
+                .map(Some)
+                .ok_or_else(|| format!("Invalid color for role {}", name)),
+        }
+    };
+
+    let fields = [
+        "header", "accent", "subtle", "emphasis", "error", "warning", "success", "info", "special",
+    ];
+
+    if let Some(unknown) = roles.keys().find(|k| !fields.contains(&k.as_str())) {
+        return Err(format!("Unknown theme role {}", unknown));
+    }
+
+    let pick = |name: &str, fallback: fn(&Roles) -> Color| -> Result<Color, String> {
+        match (color(name)?, base) {
+            (Some(c), _) => Ok(c),
+            (None, Some(base)) => Ok(fallback(base)),
+            (None, None) => Err(format!("Missing theme role {}", name)),
+        }
+    };
+
+    Ok(Roles {
+        header: pick("header", |r| r.header)?,
+        accent: pick("accent", |r| r.accent)?,
+        subtle: pick("subtle", |r| r.subtle)?,
+        emphasis: pick("emphasis", |r| r.emphasis)?,
+        error: pick("error", |r| r.error)?,
+        warning: pick("warning", |r| r.warning)?,
+        success: pick("success", |r| r.success)?,
+        info: pick("info", |r| r.info)?,
+        special: pick("special", |r| r.special)?,
+    })
+}
+
+/// Switch scheme without editing config
+pub fn set_scheme(name: &str) -> Result<(), String> {
+    scheme(name)?;
+    *SCHEME_OVERRIDE.write().expect("Must set scheme") = Some(name.to_string());
+    Ok(())
+}
+
+/// Scheme from the theme command or config, with [theme] merged over it.
+/// Returns the Cursive theme TOML, and launchk's colors.
+pub fn resolve(config: &Config) -> Result<(String, Roles), String> {
+    let name = SCHEME_OVERRIDE
+        .read()
+        .ok()
+        .and_then(|s| s.clone())
+        .or_else(|| config.color_scheme.clone())
+        .unwrap_or_else(|| SCHEMES[0].0.to_string());
+
+    let mut theme: Value = config
+        .theme_toml(scheme(&name)?)
+        .parse()
+        .map_err(|e| format!("{:?}", e))?;
+
+    let roles = take_roles(&mut theme, Some(&Roles::default()))?;
+    Ok((theme.to_string(), roles))
+}
+
+pub fn set_roles(roles: Roles) {
+    *ROLES.write().expect("Must set roles") = roles;
+}
+
+/// Current colors
+pub fn roles() -> Roles {
+    ROLES.read().map(|r| *r).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{resolve, scheme_names, Roles};
+    use crate::config::Config;
+    use cursive::theme::{BaseColor, Color};
+
+    #[test]
+    fn resolve_schemes() {
+        for name in scheme_names() {
+            let config = Config::parse(&format!("color_scheme = \"{}\"", name)).unwrap();
+            let (theme, _) = resolve(&config).unwrap();
+            assert!(!theme.contains("[roles]"));
+        }
+
+        assert_eq!(Roles::default().header, Color::Dark(BaseColor::Blue));
+
+        let config = Config::parse("[theme.roles]\nheader = \"#ff0000\"").unwrap();
+        let (_, roles) = resolve(&config).unwrap();
+        assert_eq!(roles.header, Color::Rgb(255, 0, 0));
+        assert_eq!(roles.accent, Roles::default().accent);
+
+        let config = Config::parse("[theme.roles]\nheadr = \"red\"").unwrap();
+        assert!(resolve(&config).is_err());
+    }
+}
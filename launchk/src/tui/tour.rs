@@ -0,0 +1,155 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use cursive::views::Dialog;
+use cursive::Cursive;
+
+use crate::config::{bindings, config_path};
+use crate::tui::keys::event_name;
+use crate::tui::root::CbSinkMessage;
+
+/// Part of the screen a tour step points at, drawn highlighted
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TourTarget {
+    None = 0,
+    FilterBadges = 1,
+    Omnibox = 2,
+}
+
+static HIGHLIGHT: AtomicU8 = AtomicU8::new(TourTarget::None as u8);
+
+pub fn highlighted() -> TourTarget {
+    match HIGHLIGHT.load(Ordering::Relaxed) {
+        1 => TourTarget::FilterBadges,
+        2 => TourTarget::Omnibox,
+        _ => TourTarget::None,
+    }
+}
+
+fn set_highlight(target: TourTarget) {
+    HIGHLIGHT.store(target as u8, Ordering::Relaxed);
+}
+
+/// Written once the tour has been shown
+fn marker_path() -> PathBuf {
+    config_path().with_file_name("tour_done")
+}
+
+pub fn first_run() -> bool {
+    !marker_path().exists()
+}
+
+fn mark_done() {
+    let path = marker_path();
+
+    if let Err(e) = path
+        .parent()
+        .map(fs::create_dir_all)
+        .unwrap_or(Ok(()))
+        .and_then(|_| fs::write(&path, ""))
+    {
+        log::error!("[tour/mark_done]: {:?} {}", path, e);
+    }
+}
+
+/// Keys bound to a command, e.g. "k or F5", or :name if none
+fn keys_for(name: &str) -> String {
+    let keys: Vec<String> = bindings()
+        .iter()
+        .filter(|(_, n)| n == name)
+        .map(|(e, _)| event_name(e))
+        .collect();
+
+    if keys.is_empty() {
+        format!(":{}", name)
+    } else {
+        keys.join(" or ")
+    }
+}
+
+fn steps() -> Vec<(&'static str, TourTarget, String)> {
+    vec![
+        (
+            "Welcome to launchk",
+            TourTarget::None,
+            "launchk lists every launchd job and keeps the list up to date.\n\n\
+             This tour takes a minute. Skip it any time, the tour command \
+             runs it again."
+                .to_string(),
+        ),
+        (
+            "Filter badges",
+            TourTarget::FilterBadges,
+            "The highlighted [sguadlexnqbworh] badges, top right, narrow the list:\n\n\
+             s system, g global, u user (where the plist lives)\n\
+             a agents, d daemons\n\
+             l loaded, e enabled, x disabled, n unsigned\n\
+             q Aqua, b Background, w LoginWindow, o StandardIO sessions\n\
+             r orphaned (plist, not loaded), h ghost (loaded, no plist)\n\n\
+             Press a letter or click a badge to toggle it."
+                .to_string(),
+        ),
+        (
+            "Omnibox",
+            TourTarget::Omnibox,
+            "/ filters jobs by label (fuzzy), // by regex. Add terms like \
+             path:/usr/libexec, team:ABCDE12345, pid:123 or status:crashed.\n\n\
+             : types a command, Tab completes it, Up and Down recall earlier \
+             ones and Ctrl-U clears."
+                .to_string(),
+        ),
+        (
+            "Common commands",
+            TourTarget::None,
+            format!(
+                "On the highlighted job:\n\n\
+                 {:<10} details\n\
+                 {:<10} kickstart\n\
+                 {:<10} log\n\
+                 {:<10} view plist\n\
+                 :load, :unload, :enable, :disable, :edit\n\n\
+                 {} lists every key and command.",
+                keys_for("info"),
+                keys_for("kickstart"),
+                keys_for("log"),
+                keys_for("view"),
+                keys_for("help")
+            ),
+        ),
+    ]
+}
+
+fn show_step(siv: &mut Cursive, steps: Vec<(&'static str, TourTarget, String)>, i: usize) {
+    let (title, target, text) = steps[i].clone();
+    let last = i + 1 == steps.len();
+    set_highlight(target);
+
+    let finish = |s: &mut Cursive| {
+        set_highlight(TourTarget::None);
+        s.pop_layer();
+    };
+
+    let mut dialog = Dialog::text(text).title(format!("{} ({}/{})", title, i + 1, steps.len()));
+
+    if last {
+        dialog = dialog.button("Done", finish);
+    } else {
+        dialog = dialog
+            .button("Next", move |s| {
+                s.pop_layer();
+                show_step(s, steps.clone(), i + 1);
+            })
+            .button("Skip", finish);
+    }
+
+    siv.add_layer(dialog);
+}
+
+/// Step through the filter badges, omnibox syntax and common commands
+pub fn show_tour() -> CbSinkMessage {
+    Box::new(|siv| {
+        mark_done();
+        show_step(siv, steps(), 0);
+    })
+}
@@ -0,0 +1,82 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::state_dir;
+
+/// What the service list looked like on exit, in state_dir()/state.json
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiState {
+    /// As typed after /
+    pub label_filter: String,
+    /// Job type filter letters, None for the config's
+    pub job_type_filter: Option<String>,
+    /// parse_sort spec, e.g. "pid:desc"
+    pub sort: Option<String>,
+    /// parse_columns spec, empty for the config's
+    pub columns: Vec<String>,
+    /// Highlighted label
+    pub selected: Option<String>,
+}
+
+lazy_static! {
+    /// None when restore_state is off or --no-state was given
+    static ref STATE: Mutex<Option<UiState>> = Mutex::new(None);
+}
+
+fn state_path() -> PathBuf {
+    state_dir().join("state.json")
+}
+
+/// Read the last state if enabled, otherwise nothing is saved on exit
+pub fn init(enabled: bool) {
+    if !enabled {
+        return;
+    }
+
+    let state = fs::read_to_string(state_path())
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default();
+
+    *STATE.lock().expect("Must init UI state") = Some(state);
+}
+
+/// State to restore, default if disabled
+pub fn current() -> UiState {
+    STATE
+        .lock()
+        .ok()
+        .and_then(|s| s.clone())
+        .unwrap_or_default()
+}
+
+pub fn update<F: FnOnce(&mut UiState)>(f: F) {
+    if let Some(state) = STATE.lock().expect("Must update UI state").as_mut() {
+        f(state);
+    }
+}
+
+/// Write state.json, creating Application Support/launchk if needed
+pub fn save() {
+    let state = match STATE.lock().ok().and_then(|s| s.clone()) {
+        Some(state) => state,
+        None => return,
+    };
+
+    let path = state_path();
+    let result = serde_json::to_string_pretty(&state)
+        .map_err(|e| e.to_string())
+        .and_then(|json| {
+            fs::create_dir_all(state_dir())
+                .and_then(|_| fs::write(&path, json))
+                .map_err(|e| e.to_string())
+        });
+
+    if let Err(e) = result {
+        log::error!("[ui_state/save]: {:?} {}", path, e);
+    }
+}
@@ -0,0 +1,176 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::time::Duration;
+
+use cursive::event::Key;
+use cursive::traits::Nameable;
+use cursive::views::{Dialog, OnEventView, TextView};
+use cursive::Cursive;
+use tokio::runtime::Handle;
+use tokio::time::interval;
+
+use crate::tui::dialog::show_error;
+use crate::tui::root::CbSinkMessage;
+
+static SPINNER_FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// Unique spinner names, queries can overlap
+static SPINNER_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Percent done reported from a worker, shown after the spinner title,
+/// and whether it was aborted
+#[derive(Clone, Default)]
+pub struct Progress {
+    percent: Arc<AtomicUsize>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Progress {
+    pub fn set(&self, done: usize, total: usize) {
+        // 0 is "not reported yet"
+        let percent = done.saturating_mul(100) / total.max(1);
+        self.percent.store(percent.min(100) + 1, Ordering::Relaxed);
+    }
+
+    /// The result will be dropped, workers should stop when they can
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    fn percent(&self) -> Option<usize> {
+        self.percent.load(Ordering::Relaxed).checked_sub(1)
+    }
+}
+
+fn remove_named_layer(siv: &mut Cursive, name: &str) {
+    let screen = siv.screen_mut();
+    if let Some(position) = screen.find_layer_from_name(name) {
+        screen.remove_layer(position);
+    }
+}
+
+fn spinner_text(title: &str, frame: usize, progress: &Progress) -> String {
+    let frame = SPINNER_FRAMES[frame % SPINNER_FRAMES.len()];
+
+    match progress.percent() {
+        Some(percent) => format!("{} {} {}%", frame, title, percent),
+        None => format!("{} {}", frame, title),
+    }
+}
+
+/// Run a slow XPC query on a blocking worker, showing a spinner until
+/// it finishes or is aborted with Esc. `done` gets the result back on
+/// the UI thread, errors are shown in a dialog.
+pub fn run_with_spinner<T, Q, D>(
+    runtime_handle: &Handle,
+    cb_sink: Sender<CbSinkMessage>,
+    title: String,
+    query: Q,
+    done: D,
+) where
+    T: Send + 'static,
+    Q: FnOnce() -> Result<T, String> + Send + 'static,
+    D: FnOnce(&mut Cursive, T) + Send + 'static,
+{
+    run_with_progress(runtime_handle, cb_sink, title, move |_| query(), done)
+}
+
+/// As run_with_spinner, for work that can report how far along it is and
+/// check whether it was aborted
+pub fn run_with_progress<T, Q, D>(
+    runtime_handle: &Handle,
+    cb_sink: Sender<CbSinkMessage>,
+    title: String,
+    query: Q,
+    done: D,
+) where
+    T: Send + 'static,
+    Q: FnOnce(&Progress) -> Result<T, String> + Send + 'static,
+    D: FnOnce(&mut Cursive, T) + Send + 'static,
+{
+    let progress = Progress::default();
+    let name = format!("spinner_{}", SPINNER_ID.fetch_add(1, Ordering::Relaxed));
+    let text_name = format!("{}_text", name);
+
+    {
+        let name = name.clone();
+        let text_name = text_name.clone();
+        let text = spinner_text(&title, 0, &progress);
+        let progress = progress.clone();
+
+        cb_sink
+            .send(Box::new(move |siv| {
+                let abort = {
+                    let name = name.clone();
+                    move |siv: &mut Cursive| {
+                        progress.cancel();
+                        remove_named_layer(siv, &name);
+                    }
+                };
+
+                let dialog = Dialog::around(TextView::new(text).with_name(text_name))
+                    .button("Abort (Esc)", abort.clone());
+
+                siv.add_layer(
+                    OnEventView::new(dialog)
+                        .on_event(Key::Esc, abort)
+                        .with_name(name),
+                );
+            }))
+            .expect("Must show spinner");
+    }
+
+    runtime_handle.spawn(async move {
+        let mut work = {
+            let progress = progress.clone();
+            tokio::task::spawn_blocking(move || query(&progress))
+        };
+        let mut ticker = interval(Duration::from_millis(100));
+        let mut frame = 0;
+
+        let result = loop {
+            tokio::select! {
+                result = &mut work => break result,
+                _ = ticker.tick() => {
+                    // Left to finish on its own, nothing waits for it
+                    if progress.is_cancelled() {
+                        return;
+                    }
+
+                    frame += 1;
+                    let text = spinner_text(&title, frame, &progress);
+                    let text_name = text_name.clone();
+
+                    cb_sink
+                        .send(Box::new(move |siv| {
+                            siv.call_on_name(&text_name, |v: &mut TextView| v.set_content(text));
+                        }))
+                        .expect("Must update spinner");
+                }
+            }
+        };
+
+        let result = result.map_err(|e| e.to_string()).and_then(|r| r);
+
+        cb_sink
+            .send(Box::new(move |siv| {
+                // Aborted after it finished but before this ran
+                if progress.is_cancelled() {
+                    return;
+                }
+
+                remove_named_layer(siv, &name);
+
+                match result {
+                    Ok(value) => done(siv, value),
+                    Err(e) => show_error(e)(siv),
+                }
+            }))
+            .expect("Must finish query");
+    });
+}
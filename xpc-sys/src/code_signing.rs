@@ -0,0 +1,314 @@
+use std::ffi::CString;
+use std::os::raw::{c_char, c_int, c_long, c_ulong, c_void};
+use std::ptr::{null, null_mut};
+
+// Just enough CoreFoundation and Security.framework to read a signature.
+// Linked in build.rs.
+type CFTypeRef = *const c_void;
+type CFStringRef = CFTypeRef;
+type CFDictionaryRef = CFTypeRef;
+type CFIndex = c_long;
+type CFTypeID = c_ulong;
+type OSStatus = i32;
+type SecCSFlags = u32;
+
+const kCFStringEncodingUTF8: u32 = 0x0800_0100;
+const kCFNumberSInt64Type: c_int = 4;
+
+const kSecCSDefaultFlags: SecCSFlags = 0;
+const kSecCSSigningInformation: SecCSFlags = 1 << 1;
+const kSecCSRequirementInformation: SecCSFlags = 1 << 2;
+const kSecCodeSignatureAdhoc: i64 = 0x0002;
+
+extern "C" {
+    fn CFRelease(cf: CFTypeRef);
+    fn CFGetTypeID(cf: CFTypeRef) -> CFTypeID;
+    fn CFStringGetTypeID() -> CFTypeID;
+    fn CFDictionaryGetTypeID() -> CFTypeID;
+    fn CFArrayGetTypeID() -> CFTypeID;
+    fn CFNumberGetTypeID() -> CFTypeID;
+
+    fn CFStringCreateWithCString(
+        alloc: CFTypeRef,
+        c_str: *const c_char,
+        encoding: u32,
+    ) -> CFStringRef;
+    fn CFStringGetLength(string: CFStringRef) -> CFIndex;
+    fn CFStringGetMaximumSizeForEncoding(length: CFIndex, encoding: u32) -> CFIndex;
+    fn CFStringGetCString(
+        string: CFStringRef,
+        buffer: *mut c_char,
+        size: CFIndex,
+        encoding: u32,
+    ) -> u8;
+
+    fn CFURLCreateFromFileSystemRepresentation(
+        alloc: CFTypeRef,
+        buffer: *const u8,
+        length: CFIndex,
+        is_directory: u8,
+    ) -> CFTypeRef;
+
+    fn CFDictionaryGetValue(dict: CFDictionaryRef, key: CFTypeRef) -> CFTypeRef;
+    fn CFDictionaryGetCount(dict: CFDictionaryRef) -> CFIndex;
+    fn CFDictionaryGetKeysAndValues(
+        dict: CFDictionaryRef,
+        keys: *mut CFTypeRef,
+        values: *mut CFTypeRef,
+    );
+    fn CFArrayGetCount(array: CFTypeRef) -> CFIndex;
+    fn CFArrayGetValueAtIndex(array: CFTypeRef, index: CFIndex) -> CFTypeRef;
+    fn CFNumberGetValue(number: CFTypeRef, number_type: c_int, value: *mut c_void) -> u8;
+
+    static kSecCodeInfoIdentifier: CFStringRef;
+    static kSecCodeInfoTeamIdentifier: CFStringRef;
+    static kSecCodeInfoFlags: CFStringRef;
+    static kSecCodeInfoCertificates: CFStringRef;
+    static kSecCodeInfoEntitlementsDict: CFStringRef;
+
+    fn SecStaticCodeCreateWithPath(
+        path: CFTypeRef,
+        flags: SecCSFlags,
+        static_code: *mut CFTypeRef,
+    ) -> OSStatus;
+    fn SecCodeCopySigningInformation(
+        code: CFTypeRef,
+        flags: SecCSFlags,
+        information: *mut CFDictionaryRef,
+    ) -> OSStatus;
+    fn SecStaticCodeCheckValidity(
+        code: CFTypeRef,
+        flags: SecCSFlags,
+        requirement: CFTypeRef,
+    ) -> OSStatus;
+    fn SecRequirementCreateWithString(
+        text: CFStringRef,
+        flags: SecCSFlags,
+        requirement: *mut CFTypeRef,
+    ) -> OSStatus;
+    fn SecCertificateCopySubjectSummary(certificate: CFTypeRef) -> CFStringRef;
+}
+
+/// Released on drop
+struct CFOwned(CFTypeRef);
+
+impl CFOwned {
+    fn new(cf: CFTypeRef) -> Option<Self> {
+        if cf.is_null() {
+            None
+        } else {
+            Some(Self(cf))
+        }
+    }
+}
+
+impl Drop for CFOwned {
+    fn drop(&mut self) {
+        unsafe { CFRelease(self.0) }
+    }
+}
+
+fn cf_string(string: &str) -> Option<CFOwned> {
+    let c_str = CString::new(string).ok()?;
+    CFOwned::new(unsafe {
+        CFStringCreateWithCString(null(), c_str.as_ptr(), kCFStringEncodingUTF8)
+    })
+}
+
+/// Some(String) if cf is a CFString
+unsafe fn rs_string(cf: CFTypeRef) -> Option<String> {
+    if cf.is_null() || CFGetTypeID(cf) != CFStringGetTypeID() {
+        return None;
+    }
+
+    let size = CFStringGetMaximumSizeForEncoding(CFStringGetLength(cf), kCFStringEncodingUTF8) + 1;
+    let mut buffer = vec![0u8; size as usize];
+
+    if CFStringGetCString(
+        cf,
+        buffer.as_mut_ptr() as *mut c_char,
+        size,
+        kCFStringEncodingUTF8,
+    ) == 0
+    {
+        return None;
+    }
+
+    let end = buffer.iter().position(|b| *b == 0).unwrap_or(buffer.len());
+    Some(String::from_utf8_lossy(&buffer[..end]).to_string())
+}
+
+unsafe fn rs_i64(cf: CFTypeRef) -> Option<i64> {
+    if cf.is_null() || CFGetTypeID(cf) != CFNumberGetTypeID() {
+        return None;
+    }
+
+    let mut value: i64 = 0;
+    if CFNumberGetValue(
+        cf,
+        kCFNumberSInt64Type,
+        &mut value as *mut i64 as *mut c_void,
+    ) == 0
+    {
+        return None;
+    }
+
+    Some(value)
+}
+
+unsafe fn dict_keys(dict: CFDictionaryRef) -> Vec<String> {
+    if dict.is_null() || CFGetTypeID(dict) != CFDictionaryGetTypeID() {
+        return vec![];
+    }
+
+    let count = CFDictionaryGetCount(dict) as usize;
+    let mut keys: Vec<CFTypeRef> = vec![null(); count];
+    CFDictionaryGetKeysAndValues(dict, keys.as_mut_ptr(), null_mut());
+
+    let mut keys: Vec<String> = keys.into_iter().filter_map(|k| rs_string(k)).collect();
+    keys.sort();
+    keys
+}
+
+/// Common name (subject summary) of the leaf certificate
+unsafe fn signing_identity(certificates: CFTypeRef) -> Option<String> {
+    if certificates.is_null()
+        || CFGetTypeID(certificates) != CFArrayGetTypeID()
+        || CFArrayGetCount(certificates) < 1
+    {
+        return None;
+    }
+
+    let summary = CFOwned::new(SecCertificateCopySubjectSummary(CFArrayGetValueAtIndex(
+        certificates,
+        0,
+    )))?;
+
+    rs_string(summary.0)
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum SignatureKind {
+    Unsigned,
+    AdHoc,
+    /// By a certificate (Apple, Developer ID, ...)
+    Signed,
+}
+
+/// Code signature of an executable on disk, like codesign -dv
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CodeSignature {
+    pub kind: SignatureKind,
+    /// Passes SecStaticCodeCheckValidity
+    pub valid: bool,
+    pub identifier: Option<String>,
+    pub team_id: Option<String>,
+    /// Leaf certificate, e.g. Developer ID Application: ...
+    pub signing_identity: Option<String>,
+    pub notarized: bool,
+    /// Entitlement keys
+    pub entitlements: Vec<String>,
+}
+
+fn static_code(path: &str) -> Result<CFOwned, String> {
+    let url = CFOwned::new(unsafe {
+        CFURLCreateFromFileSystemRepresentation(null(), path.as_ptr(), path.len() as CFIndex, 0)
+    })
+    .ok_or_else(|| format!("Invalid path {}", path))?;
+
+    let mut code: CFTypeRef = null();
+    let err = unsafe { SecStaticCodeCreateWithPath(url.0, kSecCSDefaultFlags, &mut code) };
+
+    match CFOwned::new(code) {
+        Some(code) if err == 0 => Ok(code),
+        _ => Err(format!("Cannot read code at {}: OSStatus {}", path, err)),
+    }
+}
+
+fn signing_information(code: &CFOwned, flags: SecCSFlags) -> Result<CFOwned, String> {
+    let mut info: CFDictionaryRef = null();
+    let err = unsafe { SecCodeCopySigningInformation(code.0, flags, &mut info) };
+
+    match CFOwned::new(info) {
+        Some(info) if err == 0 => Ok(info),
+        _ => Err(format!("Cannot read signature: OSStatus {}", err)),
+    }
+}
+
+unsafe fn kind(info: &CFOwned) -> SignatureKind {
+    if rs_string(CFDictionaryGetValue(info.0, kSecCodeInfoIdentifier)).is_none() {
+        return SignatureKind::Unsigned;
+    }
+
+    let flags = rs_i64(CFDictionaryGetValue(info.0, kSecCodeInfoFlags)).unwrap_or(0);
+
+    if flags & kSecCodeSignatureAdhoc != 0 {
+        SignatureKind::AdHoc
+    } else {
+        SignatureKind::Signed
+    }
+}
+
+/// What can be read without validating
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SignatureSummary {
+    pub kind: SignatureKind,
+    pub team_id: Option<String>,
+}
+
+/// Kind and Team ID, without validating (cheap enough to poll)
+pub fn signature_summary(path: &str) -> Result<SignatureSummary, String> {
+    let code = static_code(path)?;
+    let info = signing_information(&code, kSecCSSigningInformation)?;
+
+    unsafe {
+        Ok(SignatureSummary {
+            kind: kind(&info),
+            team_id: rs_string(CFDictionaryGetValue(info.0, kSecCodeInfoTeamIdentifier)),
+        })
+    }
+}
+
+/// Unsigned, ad-hoc or signed, without validating (cheap enough to poll)
+pub fn signature_kind(path: &str) -> Result<SignatureKind, String> {
+    signature_summary(path).map(|s| s.kind)
+}
+
+/// Full signature, validating the code and checking for notarization
+pub fn code_signature(path: &str) -> Result<CodeSignature, String> {
+    let code = static_code(path)?;
+    let info = signing_information(
+        &code,
+        kSecCSSigningInformation | kSecCSRequirementInformation,
+    )?;
+
+    let validity = unsafe { SecStaticCodeCheckValidity(code.0, kSecCSDefaultFlags, null()) };
+
+    let notarized = cf_string("notarized")
+        .and_then(|text| {
+            let mut requirement: CFTypeRef = null();
+            let err = unsafe {
+                SecRequirementCreateWithString(text.0, kSecCSDefaultFlags, &mut requirement)
+            };
+
+            CFOwned::new(requirement).filter(|_| err == 0)
+        })
+        .map(|requirement| unsafe {
+            SecStaticCodeCheckValidity(code.0, kSecCSDefaultFlags, requirement.0) == 0
+        })
+        .unwrap_or(false);
+
+    unsafe {
+        let value = |key: CFStringRef| CFDictionaryGetValue(info.0, key);
+
+        Ok(CodeSignature {
+            kind: kind(&info),
+            valid: validity == 0,
+            identifier: rs_string(value(kSecCodeInfoIdentifier)),
+            team_id: rs_string(value(kSecCodeInfoTeamIdentifier)),
+            signing_identity: signing_identity(value(kSecCodeInfoCertificates)),
+            notarized,
+            entitlements: dict_keys(value(kSecCodeInfoEntitlementsDict)),
+        })
+    }
+}
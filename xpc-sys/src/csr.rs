@@ -50,3 +50,9 @@ extern "C" {
     /// 0 if has mask
     pub fn csr_check(mask: csr_config_t) -> c_int;
 }
+
+/// SIP's filesystem protection is on, which launchd also honours: jobs
+/// from /System cannot be unloaded, disabled or enabled
+pub fn sip_enabled() -> bool {
+    unsafe { csr_check(CsrConfig::ALLOW_UNRESTRICTED_FS.bits()) != 0 }
+}
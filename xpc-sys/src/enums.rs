@@ -1,5 +1,6 @@
 use std::convert::TryFrom;
 use std::fmt;
+use std::str::FromStr;
 use std::sync::Arc;
 
 use crate::objects::xpc_error::XPCError;
@@ -39,26 +40,38 @@ impl From<u64> for SessionType {
     }
 }
 
-// TODO: This feels terrible
-impl From<String> for SessionType {
-    fn from(value: String) -> Self {
-        let aqua: String = SessionType::Aqua.to_string();
-        let standard_io: String = SessionType::StandardIO.to_string();
-        let background: String = SessionType::Background.to_string();
-        let login_window: String = SessionType::LoginWindow.to_string();
-        let system: String = SessionType::System.to_string();
+/// Names as launchctl prints them (Aqua, LoginWindow), any case, and
+/// numbers. StandardIO is also stdio
+impl FromStr for SessionType {
+    type Err = XPCError;
 
-        match value {
-            s if s == aqua => SessionType::Aqua,
-            s if s == standard_io => SessionType::StandardIO,
-            s if s == background => SessionType::Background,
-            s if s == login_window => SessionType::LoginWindow,
-            s if s == system => SessionType::System,
-            _ => SessionType::Unknown,
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(n) = s.parse::<u64>() {
+            return match n.into() {
+                SessionType::Unknown => {
+                    Err(XPCError::ValueError(format!("Invalid session type {}", s)))
+                }
+                session_type => Ok(session_type),
+            };
+        }
+
+        match s.to_ascii_lowercase().replace(['-', '_'], "").as_str() {
+            "aqua" => Ok(SessionType::Aqua),
+            "standardio" | "stdio" => Ok(SessionType::StandardIO),
+            "background" => Ok(SessionType::Background),
+            "loginwindow" => Ok(SessionType::LoginWindow),
+            "system" => Ok(SessionType::System),
+            _ => Err(XPCError::ValueError(format!("Invalid session type {}", s))),
         }
     }
 }
 
+impl From<String> for SessionType {
+    fn from(value: String) -> Self {
+        value.parse().unwrap_or(SessionType::Unknown)
+    }
+}
+
 impl TryFrom<Arc<XPCObject>> for SessionType {
     type Error = XPCError;
 
@@ -69,6 +82,13 @@ impl TryFrom<Arc<XPCObject>> for SessionType {
     }
 }
 
+impl TryXPCValue<SessionType> for XPCObject {
+    fn xpc_value(&self) -> Result<SessionType, XPCError> {
+        let string: String = self.xpc_value()?;
+        Ok(string.into())
+    }
+}
+
 // Huge thanks to: https://saelo.github.io/presentations/bits_of_launchd.pdf
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum DomainType {
@@ -79,6 +99,8 @@ pub enum DomainType {
     PID = 5,
     RequestorUserDomain = 6,
     RequestorDomain = 7,
+    /// gui/<uid>, needs a uid handle
+    Gui = 8,
     Unknown,
 }
 
@@ -92,6 +114,7 @@ impl From<u64> for DomainType {
             5 => DomainType::PID,
             6 => DomainType::RequestorUserDomain,
             7 => DomainType::RequestorDomain,
+            8 => DomainType::Gui,
             _ => DomainType::Unknown,
         }
     }
@@ -102,3 +125,77 @@ impl fmt::Display for DomainType {
         write!(f, "{:?}", self)
     }
 }
+
+/// Display names (Gui, RequestorDomain) in any case, launchctl domain
+/// target names (system, gui/501, login/501, the handle is ignored) and
+/// numbers
+impl FromStr for DomainType {
+    type Err = XPCError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || XPCError::ValueError(format!("Invalid domain type {}", s));
+
+        if let Ok(n) = s.parse::<u64>() {
+            return match n.into() {
+                DomainType::Unknown => Err(invalid()),
+                domain_type => Ok(domain_type),
+            };
+        }
+
+        let name = s.split('/').next().unwrap_or(s);
+
+        match name.to_ascii_lowercase().replace(['-', '_'], "").as_str() {
+            "system" => Ok(DomainType::System),
+            "user" => Ok(DomainType::User),
+            "login" | "userlogin" => Ok(DomainType::UserLogin),
+            "session" => Ok(DomainType::Session),
+            "pid" => Ok(DomainType::PID),
+            "requestoruserdomain" | "requestoruser" => Ok(DomainType::RequestorUserDomain),
+            "requestordomain" | "requestor" => Ok(DomainType::RequestorDomain),
+            "gui" => Ok(DomainType::Gui),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DomainType, SessionType};
+
+    #[test]
+    fn session_type_round_trip() {
+        for n in SessionType::Aqua as u64..SessionType::Unknown as u64 {
+            let session_type: SessionType = n.into();
+            assert_eq!(
+                session_type.to_string().parse::<SessionType>(),
+                Ok(session_type)
+            );
+        }
+
+        assert_eq!(
+            "loginwindow".parse::<SessionType>(),
+            Ok(SessionType::LoginWindow)
+        );
+        assert_eq!("stdio".parse::<SessionType>(), Ok(SessionType::StandardIO));
+        assert_eq!("2".parse::<SessionType>(), Ok(SessionType::Background));
+        assert!("Unknown".parse::<SessionType>().is_err());
+        assert_eq!(SessionType::from("Nope".to_string()), SessionType::Unknown);
+    }
+
+    #[test]
+    fn domain_type_round_trip() {
+        for n in DomainType::System as u64..=DomainType::Gui as u64 {
+            let domain_type: DomainType = n.into();
+            assert_eq!(
+                domain_type.to_string().parse::<DomainType>(),
+                Ok(domain_type)
+            );
+        }
+
+        assert_eq!("gui/501".parse::<DomainType>(), Ok(DomainType::Gui));
+        assert_eq!("system".parse::<DomainType>(), Ok(DomainType::System));
+        assert_eq!("7".parse::<DomainType>(), Ok(DomainType::RequestorDomain));
+        assert!("9".parse::<DomainType>().is_err());
+        assert!("launchd".parse::<DomainType>().is_err());
+    }
+}
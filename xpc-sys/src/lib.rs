@@ -15,6 +15,7 @@ use std::ptr::null_mut;
 include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
 
 //
+pub mod code_signing;
 pub mod csr;
 pub mod enums;
 pub mod objects;
@@ -173,10 +174,83 @@ pub unsafe fn rs_sysctlbyname(name: &str) -> Result<String, String> {
     }
 }
 
+/// kern.boottime in seconds since the epoch
+pub fn rs_boottime() -> Result<i64, String> {
+    let name = CString::new("kern.boottime").unwrap();
+    let mut tv = libc::timeval {
+        tv_sec: 0,
+        tv_usec: 0,
+    };
+    let mut size = std::mem::size_of::<libc::timeval>() as u64;
+
+    let err = unsafe {
+        sysctlbyname(
+            name.as_ptr(),
+            &mut tv as *mut libc::timeval as *mut _,
+            &mut size,
+            null_mut(),
+            0,
+        )
+    };
+
+    if err == 0 {
+        Ok(tv.tv_sec)
+    } else {
+        Err(rs_strerror(err))
+    }
+}
+
 pub fn rs_geteuid() -> uid_t {
     unsafe { geteuid() }
 }
 
+pub fn rs_getpid() -> pid_t {
+    unsafe { getpid() }
+}
+
+pub fn rs_bootstrap_strerror(kr: kern_return_t) -> String {
+    unsafe {
+        CStr::from_ptr(bootstrap_strerror(kr))
+            .to_string_lossy()
+            .to_string()
+    }
+}
+
+/// bootstrap_look_up, Err is the kern_return_t (e.g. BOOTSTRAP_UNKNOWN_SERVICE)
+fn bootstrap_look_up_kr(name: &str) -> Result<mach_port_t, kern_return_t> {
+    let c_name = CString::new(name).map_err(|_| BOOTSTRAP_UNKNOWN_SERVICE as kern_return_t)?;
+    let mut port: mach_port_t = MACH_PORT_NULL;
+
+    let kr = unsafe { bootstrap_look_up(get_bootstrap_port(), c_name.as_ptr(), &mut port) };
+
+    if kr == KERN_SUCCESS as i32 {
+        Ok(port)
+    } else {
+        Err(kr)
+    }
+}
+
+/// Send right for a Mach service registered with launchd, the caller
+/// owns it and should mach_port_deallocate
+pub fn rs_bootstrap_look_up(name: &str) -> Result<mach_port_t, String> {
+    bootstrap_look_up_kr(name).map_err(|kr| {
+        format!(
+            "bootstrap_look_up {} failed: {}",
+            name,
+            rs_bootstrap_strerror(kr)
+        )
+    })
+}
+
+/// Whether name can be looked up from our bootstrap namespace, without
+/// keeping the send right. Looking up does not launch the job.
+pub fn rs_bootstrap_check(name: &str) -> Result<(), kern_return_t> {
+    let port = bootstrap_look_up_kr(name)?;
+    unsafe { mach_port_deallocate(mach_task_self_, port) };
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
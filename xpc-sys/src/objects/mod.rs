@@ -4,6 +4,19 @@ pub mod xpc_object;
 /// xpc_object_t XPC_TYPE_DICTIONARY
 pub mod xpc_dictionary;
 
+/// xpc_object_t XPC_TYPE_ARRAY
+pub mod xpc_array;
+
+/// xpc_object_t XPC_TYPE_DATA and XPC_TYPE_UUID
+pub mod xpc_data;
+pub mod xpc_uuid;
+
+/// xpc_object_t XPC_TYPE_FD
+pub mod xpc_fd;
+
+/// xpc_connection_t
+pub mod xpc_connection;
+
 /// xpc_object_t -> xpc_type_t
 pub mod xpc_type;
 
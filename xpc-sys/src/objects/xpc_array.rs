@@ -0,0 +1,188 @@
+use std::convert::{TryFrom, TryInto};
+use std::ops::Index;
+use std::slice::Iter;
+use std::sync::Arc;
+
+use crate::objects::xpc_error::XPCError;
+use crate::objects::xpc_error::XPCError::ValueError;
+use crate::objects::xpc_object::XPCObject;
+use crate::traits::xpc_value::TryXPCValue;
+use crate::xpc_object_t;
+
+/// A wrapper around Rust Vec<Arc<XPCObject>> that can
+/// be Into<XPCObject>
+#[derive(Debug, Clone)]
+pub struct XPCArray(pub Vec<Arc<XPCObject>>);
+
+impl XPCArray {
+    pub fn new() -> Self {
+        XPCArray(vec![])
+    }
+
+    pub fn len(&self) -> usize {
+        let XPCArray(vec) = self;
+        vec.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get value at index, erroring if out of bounds
+    pub fn get(&self, index: usize) -> Result<Arc<XPCObject>, XPCError> {
+        let XPCArray(vec) = self;
+
+        vec.get(index)
+            .cloned()
+            .ok_or_else(|| ValueError(format!("Index {} out of bounds ({})", index, vec.len())))
+    }
+
+    pub fn iter(&self) -> Iter<'_, Arc<XPCObject>> {
+        let XPCArray(vec) = self;
+        vec.iter()
+    }
+
+    /// Every element as a Rust value, e.g. Vec<String> for "names"
+    pub fn values<Out>(&self) -> Result<Vec<Out>, XPCError>
+    where
+        XPCObject: TryXPCValue<Out>,
+    {
+        self.iter().map(|o| o.xpc_value()).collect()
+    }
+}
+
+impl Default for XPCArray {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Index<usize> for XPCArray {
+    type Output = Arc<XPCObject>;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        let XPCArray(vec) = self;
+        &vec[index]
+    }
+}
+
+impl IntoIterator for XPCArray {
+    type Item = Arc<XPCObject>;
+    type IntoIter = std::vec::IntoIter<Arc<XPCObject>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let XPCArray(vec) = self;
+        vec.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a XPCArray {
+    type Item = &'a Arc<XPCObject>;
+    type IntoIter = Iter<'a, Arc<XPCObject>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl From<Vec<Arc<XPCObject>>> for XPCArray {
+    fn from(vec: Vec<Arc<XPCObject>>) -> XPCArray {
+        XPCArray(vec)
+    }
+}
+
+impl TryFrom<&XPCObject> for XPCArray {
+    type Error = XPCError;
+
+    /// Copy elements from XPC array into a Rust Vec
+    fn try_from(object: &XPCObject) -> Result<XPCArray, XPCError> {
+        object.xpc_value().map(XPCArray)
+    }
+}
+
+impl TryFrom<Arc<XPCObject>> for XPCArray {
+    type Error = XPCError;
+
+    fn try_from(value: Arc<XPCObject>) -> Result<XPCArray, XPCError> {
+        (&*value).try_into()
+    }
+}
+
+impl TryFrom<XPCObject> for XPCArray {
+    type Error = XPCError;
+
+    fn try_from(value: XPCObject) -> Result<XPCArray, XPCError> {
+        (&value).try_into()
+    }
+}
+
+impl TryFrom<xpc_object_t> for XPCArray {
+    type Error = XPCError;
+
+    /// Errors if the pointer is not XPC_TYPE_ARRAY
+    fn try_from(value: xpc_object_t) -> Result<XPCArray, XPCError> {
+        let obj: XPCObject = value.into();
+        obj.try_into()
+    }
+}
+
+impl TryXPCValue<XPCArray> for XPCObject {
+    fn xpc_value(&self) -> Result<XPCArray, XPCError> {
+        self.try_into()
+    }
+}
+
+impl From<XPCArray> for XPCObject {
+    /// Creates a XPC array
+    fn from(XPCArray(vec): XPCArray) -> Self {
+        vec.into()
+    }
+}
+
+impl From<&XPCArray> for XPCObject {
+    fn from(XPCArray(vec): &XPCArray) -> Self {
+        vec.clone().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::objects::xpc_array::XPCArray;
+    use crate::objects::xpc_dictionary::XPCDictionary;
+    use crate::objects::xpc_object::XPCObject;
+    use crate::traits::xpc_value::TryXPCValue;
+    use std::collections::HashMap;
+    use std::convert::TryInto;
+    use std::sync::Arc;
+
+    #[test]
+    fn array_round_trip() {
+        let xpc_array = XPCObject::from(vec!["eins", "zwei", "polizei"]);
+        let array: XPCArray = xpc_array.try_into().unwrap();
+
+        assert_eq!(array.len(), 3);
+        assert_eq!(
+            TryXPCValue::<String>::xpc_value(&*array[1]).unwrap(),
+            "zwei"
+        );
+        assert!(array.get(3).is_err());
+
+        let back: XPCArray = XPCObject::from(&array).xpc_value().unwrap();
+        assert_eq!(
+            back.values::<String>().unwrap(),
+            vec!["eins", "zwei", "polizei"]
+        );
+    }
+
+    #[test]
+    fn array_in_dictionary() {
+        let mut hm: HashMap<&str, Arc<XPCObject>> = HashMap::new();
+        hm.insert("names", XPCObject::from(vec![1_i64, 2, 3]).into());
+
+        let dict: XPCDictionary = XPCObject::from(hm).try_into().unwrap();
+        let names = dict.get_as_array(["names"]).unwrap();
+
+        assert_eq!(names.values::<i64>().unwrap(), vec![1, 2, 3]);
+        assert!(dict.get_as_dictionary(["names"]).is_err());
+    }
+}
@@ -0,0 +1,196 @@
+use std::ffi::CString;
+use std::ptr::{self, null_mut};
+
+use block::ConcreteBlock;
+
+use crate::objects::xpc_error::XPCError;
+use crate::objects::xpc_error::XPCError::ConnectionError;
+use crate::objects::xpc_object::XPCObject;
+use crate::objects::xpc_type;
+use crate::{
+    _xpc_dictionary_s, _xpc_error_connection_interrupted, _xpc_error_connection_invalid,
+    _xpc_error_termination_imminent, pid_t, uid_t, xpc_connection_cancel,
+    xpc_connection_create_mach_service, xpc_connection_get_euid, xpc_connection_get_pid,
+    xpc_connection_resume, xpc_connection_send_message,
+    xpc_connection_send_message_with_reply_sync, xpc_connection_set_event_handler,
+    xpc_connection_suspend, xpc_connection_t, xpc_object_t, xpc_release,
+    XPC_CONNECTION_MACH_SERVICE_PRIVILEGED,
+};
+
+/// Delivered to a connection's event handler
+#[derive(Debug)]
+pub enum XPCConnectionEvent {
+    /// A message from the peer
+    Message(XPCObject),
+    /// The service went away (e.g. crashed) but the connection may be used
+    /// again, resend any state it needs
+    Interrupted,
+    /// Cancelled, or the service does not exist: nothing more will arrive
+    Invalid,
+    /// This process is about to be terminated
+    TerminationImminent,
+    /// Any other error object
+    Error(XPCObject),
+}
+
+impl XPCConnectionEvent {
+    /// The handler does not own event, so copy anything it keeps
+    fn from_raw(event: xpc_object_t) -> Self {
+        let is = |error: *const _xpc_dictionary_s| ptr::eq(event as *const _, error);
+
+        unsafe {
+            if is(&_xpc_error_connection_interrupted) {
+                XPCConnectionEvent::Interrupted
+            } else if is(&_xpc_error_connection_invalid) {
+                XPCConnectionEvent::Invalid
+            } else if is(&_xpc_error_termination_imminent) {
+                XPCConnectionEvent::TerminationImminent
+            } else if xpc_type::XPCType::from(event) == *xpc_type::Error {
+                XPCConnectionEvent::Error(XPCObject::xpc_copy(event))
+            } else {
+                XPCConnectionEvent::Message(XPCObject::xpc_copy(event))
+            }
+        }
+    }
+}
+
+/// A synchronous reply, or what went wrong instead
+fn reply_result(event: XPCConnectionEvent) -> Result<XPCObject, XPCError> {
+    match event {
+        XPCConnectionEvent::Message(object) => Ok(object),
+        XPCConnectionEvent::Interrupted => {
+            Err(ConnectionError("Connection interrupted".to_string()))
+        }
+        XPCConnectionEvent::Invalid => Err(ConnectionError("Connection invalid".to_string())),
+        XPCConnectionEvent::TerminationImminent => {
+            Err(ConnectionError("Termination imminent".to_string()))
+        }
+        XPCConnectionEvent::Error(e) => Err(ConnectionError(e.to_string())),
+    }
+}
+
+/// xpc_connection_t to a Mach service, for talking to anything other
+/// than launchd's bootstrap pipe. Cancelled and released when dropped.
+#[derive(Debug)]
+pub struct XPCConnection(xpc_connection_t);
+
+unsafe impl Send for XPCConnection {}
+unsafe impl Sync for XPCConnection {}
+
+impl XPCConnection {
+    /// xpc_connection_create_mach_service, privileged for services in
+    /// the system domain. Suspended until resume()
+    pub fn mach_service(name: &str, privileged: bool) -> Result<Self, XPCError> {
+        let cstr = CString::new(name).map_err(|e| ConnectionError(e.to_string()))?;
+        let flags = if privileged {
+            XPC_CONNECTION_MACH_SERVICE_PRIVILEGED as u64
+        } else {
+            0
+        };
+
+        let connection =
+            unsafe { xpc_connection_create_mach_service(cstr.as_ptr(), null_mut(), flags) };
+
+        if connection.is_null() {
+            return Err(ConnectionError(format!("Cannot connect to {}", name)));
+        }
+
+        Ok(XPCConnection(connection))
+    }
+
+    /// Must be set before resume(), handler runs on XPC's queue
+    pub fn set_event_handler<F>(&self, handler: F)
+    where
+        F: Fn(XPCConnectionEvent) + Send + 'static,
+    {
+        let block = ConcreteBlock::new(move |event: xpc_object_t| {
+            handler(XPCConnectionEvent::from_raw(event))
+        });
+        let block = block.copy();
+
+        // XPC keeps its own copy of the block
+        unsafe { xpc_connection_set_event_handler(self.0, &*block as *const _ as *mut _) };
+    }
+
+    pub fn resume(&self) {
+        unsafe { xpc_connection_resume(self.0) }
+    }
+
+    pub fn suspend(&self) {
+        unsafe { xpc_connection_suspend(self.0) }
+    }
+
+    /// Fire and forget
+    pub fn send(&self, message: &XPCObject) {
+        unsafe { xpc_connection_send_message(self.0, message.as_ptr()) }
+    }
+
+    /// Block until the peer replies, error objects (e.g. the service does
+    /// not exist) are returned as Err
+    pub fn send_with_reply_sync(&self, message: &XPCObject) -> Result<XPCObject, XPCError> {
+        let reply =
+            unsafe { xpc_connection_send_message_with_reply_sync(self.0, message.as_ptr()) };
+
+        // Ours whatever it is, from_raw took a copy of anything it keeps
+        let event = XPCConnectionEvent::from_raw(reply);
+        unsafe { xpc_release(reply) };
+
+        reply_result(event)
+    }
+
+    /// No more events after Invalid, safe to call more than once
+    pub fn cancel(&self) {
+        unsafe { xpc_connection_cancel(self.0) }
+    }
+
+    /// Of the peer, 0 until a message has been received
+    pub fn pid(&self) -> pid_t {
+        unsafe { xpc_connection_get_pid(self.0) }
+    }
+
+    pub fn euid(&self) -> uid_t {
+        unsafe { xpc_connection_get_euid(self.0) }
+    }
+}
+
+impl Drop for XPCConnection {
+    /// Cancel before releasing, as required once a handler is set
+    fn drop(&mut self) {
+        unsafe {
+            xpc_connection_cancel(self.0);
+            xpc_release(self.0 as xpc_object_t);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{reply_result, XPCConnectionEvent};
+    use crate::objects::xpc_error::XPCError::ConnectionError;
+    use crate::objects::xpc_object::XPCObject;
+    use crate::{_xpc_error_connection_interrupted, _xpc_error_connection_invalid, xpc_object_t};
+
+    #[test]
+    fn reply_errors() {
+        let from_raw = |error: &_| XPCConnectionEvent::from_raw(error as *const _ as xpc_object_t);
+
+        assert_eq!(
+            reply_result(from_raw(unsafe { &_xpc_error_connection_interrupted })).err(),
+            Some(ConnectionError("Connection interrupted".to_string()))
+        );
+        assert_eq!(
+            reply_result(from_raw(unsafe { &_xpc_error_connection_invalid })).err(),
+            Some(ConnectionError("Connection invalid".to_string()))
+        );
+
+        let error = XPCObject::from("not a reply");
+        let description = error.to_string();
+        assert_eq!(
+            reply_result(XPCConnectionEvent::Error(error)).err(),
+            Some(ConnectionError(description))
+        );
+
+        let message = XPCObject::from("reply");
+        assert!(reply_result(XPCConnectionEvent::Message(message)).is_ok());
+    }
+}
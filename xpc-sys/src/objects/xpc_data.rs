@@ -0,0 +1,111 @@
+use std::convert::{TryFrom, TryInto};
+use std::ffi::c_void;
+use std::slice;
+
+use crate::objects::xpc_error::XPCError;
+use crate::objects::xpc_object::XPCObject;
+use crate::objects::xpc_type;
+use crate::objects::xpc_type::check_xpc_type;
+use crate::traits::xpc_value::TryXPCValue;
+use crate::{size_t, xpc_data_create, xpc_data_get_bytes_ptr, xpc_data_get_length};
+
+/// Bytes of an xpc_data_t, copied out so they outlive the reply
+/// (e.g. an audit token)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct XPCData(pub Vec<u8>);
+
+impl XPCData {
+    pub fn as_bytes(&self) -> &[u8] {
+        let XPCData(bytes) = self;
+        bytes
+    }
+
+    pub fn len(&self) -> usize {
+        self.as_bytes().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.as_bytes().is_empty()
+    }
+}
+
+impl From<&[u8]> for XPCData {
+    fn from(bytes: &[u8]) -> Self {
+        XPCData(bytes.to_vec())
+    }
+}
+
+impl TryFrom<&XPCObject> for XPCData {
+    type Error = XPCError;
+
+    fn try_from(object: &XPCObject) -> Result<XPCData, XPCError> {
+        check_xpc_type(object, &xpc_type::Data)?;
+
+        let (ptr, len) = unsafe {
+            (
+                xpc_data_get_bytes_ptr(object.as_ptr()) as *const u8,
+                xpc_data_get_length(object.as_ptr()) as usize,
+            )
+        };
+
+        // NULL for empty data
+        if ptr.is_null() || len == 0 {
+            return Ok(XPCData::default());
+        }
+
+        Ok(XPCData(unsafe { slice::from_raw_parts(ptr, len) }.to_vec()))
+    }
+}
+
+impl TryFrom<XPCObject> for XPCData {
+    type Error = XPCError;
+
+    fn try_from(value: XPCObject) -> Result<XPCData, XPCError> {
+        (&value).try_into()
+    }
+}
+
+impl TryXPCValue<XPCData> for XPCObject {
+    fn xpc_value(&self) -> Result<XPCData, XPCError> {
+        self.try_into()
+    }
+}
+
+impl From<&XPCData> for XPCObject {
+    /// Create XPCObject via xpc_data_create, which copies the bytes
+    fn from(data: &XPCData) -> Self {
+        let bytes = data.as_bytes();
+        unsafe { xpc_data_create(bytes.as_ptr() as *const c_void, bytes.len() as size_t).into() }
+    }
+}
+
+impl From<XPCData> for XPCObject {
+    fn from(data: XPCData) -> Self {
+        (&data).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::objects::xpc_data::XPCData;
+    use crate::objects::xpc_object::XPCObject;
+    use crate::traits::xpc_value::TryXPCValue;
+
+    #[test]
+    fn data_round_trip() {
+        let data = XPCData::from(&[0xde_u8, 0xad, 0xbe, 0xef][..]);
+        let xpc_data = XPCObject::from(&data);
+        let back: XPCData = xpc_data.xpc_value().unwrap();
+
+        assert_eq!(back.as_bytes(), &[0xde, 0xad, 0xbe, 0xef]);
+
+        let empty: XPCData = XPCObject::from(XPCData::default()).xpc_value().unwrap();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn data_wrong_type() {
+        let not_data: Result<XPCData, _> = XPCObject::from("foo").xpc_value();
+        assert!(not_data.is_err());
+    }
+}
@@ -7,8 +7,10 @@ use std::ptr::{null, null_mut};
 use std::sync::Arc;
 
 use crate::objects;
+use crate::objects::xpc_array::XPCArray;
 use crate::objects::xpc_error::XPCError;
 use crate::objects::xpc_error::XPCError::DictionaryError;
+use crate::objects::xpc_fd::XPCFd;
 use crate::objects::xpc_object::XPCObject;
 use crate::rs_strerror;
 use crate::{
@@ -69,6 +71,24 @@ impl XPCDictionary {
     {
         self.get(items).and_then(|r| XPCDictionary::try_from(r))
     }
+
+    /// Retrieve an array, e.g. names or paths
+    pub fn get_as_array<I, S>(&self, items: I) -> Result<XPCArray, XPCError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.get(items).and_then(XPCArray::try_from)
+    }
+
+    /// Dup a file descriptor, like xpc_dictionary_dup_fd
+    pub fn get_as_fd<I, S>(&self, items: I) -> Result<XPCFd, XPCError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.get(items).and_then(XPCFd::try_from)
+    }
 }
 
 impl From<HashMap<String, Arc<XPCObject>>> for XPCDictionary {
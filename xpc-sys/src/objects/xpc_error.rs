@@ -1,27 +1,96 @@
 use crate::objects::xpc_error::XPCError::{
-    DictionaryError, IOError, PipeError, QueryError, ValueError,
+    ConnectionError, DictionaryError, IOError, PipeErrno, PipeError, QueryError, ValueError,
 };
+use crate::{rs_strerror, rs_xpc_strerror};
 use std::error::Error;
 use std::fmt::{Display, Formatter};
 
+/// First launchd specific error, anything lower is errno
+const LAUNCHD_ERROR_BASE: i64 = 107;
+
+/// launchd's own error codes, as printed by `launchctl error <code>`
+static LAUNCHD_ERRORS: [&str; 52] = [
+    "Malformed bundle",
+    "Invalid path",
+    "Invalid property list",
+    "Invalid or missing service identifier",
+    "Invalid or missing Program/ProgramArguments",
+    "Could not find specified domain",
+    "Could not find specified service",
+    "The specified username does not exist",
+    "The specified group does not exist",
+    "Routine not yet implemented",
+    "(n/a)",
+    "Bad response from server",
+    "Service is disabled",
+    "Bad subsystem destination for request",
+    "Path not searched for services",
+    "Path had bad ownership/permissions",
+    "Path is whitelisted for domain",
+    "Domain is tearing down",
+    "Domain does not support specified action",
+    "Request type is no longer supported",
+    "The specified service did not ship with the operating system",
+    "The specified path is not a bundle",
+    "The service was superseded by a later version",
+    "The system encountered a condition where behavior was undefined",
+    "Out of order requests",
+    "Request for stale data",
+    "Multiple errors were returned; see stderr",
+    "Service cannot load in requested session",
+    "Process is not managed",
+    "Action not allowed on singleton service",
+    "Service does not support the specified action",
+    "Service cannot be loaded on this hardware",
+    "Service cannot presently execute",
+    "Service name is reserved or invalid",
+    "Reentrancy avoided",
+    "Operation only supported on development build",
+    "Requested entry was cached",
+    "Requestor lacks required entitlement",
+    "Endpoint is hidden",
+    "Domain is in on-demand-only mode",
+    "The specified service did not ship in the requestor's bundle",
+    "The specified service path was not in the service cache",
+    "Could not find a bundle of the given identifier through LaunchServices",
+    "Operation not permitted while System Integrity Protection is engaged",
+    "A complete hack",
+    "Service cannot load in current boot environment",
+    "Completely unexpected error",
+    "Requestor is not a platform binary",
+    "Refusing to execute/trust quarantined program/file",
+    "Domain creation with that UID is not allowed anymore",
+    "System service is not in system service whitelist",
+    "Service cannot be loaded on current os variant",
+];
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum XPCError {
     DictionaryError(String),
     PipeError(String),
+    /// xpc_pipe_routine failed with this errno
+    PipeErrno(i32),
     ValueError(String),
     QueryError(String),
     IOError(String),
+    /// From an XPCConnection
+    ConnectionError(String),
     NotFound,
 }
 
 impl Display for XPCError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let PipeErrno(errno) = self {
+            return write!(f, "{}", launchd_strerror(*errno as i64));
+        }
+
         let err = match self {
             DictionaryError(e) => e,
             PipeError(e) => e,
             QueryError(e) => e,
             ValueError(e) => e,
             IOError(e) => e,
+            ConnectionError(e) => e,
             _ => "",
         };
 
@@ -30,3 +99,42 @@ impl Display for XPCError {
 }
 
 impl Error for XPCError {}
+
+/// Message for an "error" code in a launchd reply: errno, or one of
+/// launchd's own codes, falling back on xpc_strerror
+pub fn launchd_strerror(code: i64) -> String {
+    if (0..LAUNCHD_ERROR_BASE).contains(&code) {
+        return rs_strerror(code as i32);
+    }
+
+    code.checked_sub(LAUNCHD_ERROR_BASE)
+        .and_then(|i| LAUNCHD_ERRORS.get(i as usize))
+        .map(|e| e.to_string())
+        .unwrap_or_else(|| rs_xpc_strerror(code as i32))
+}
+
+/// e.g. "113: Could not find specified service"
+pub fn launchd_error_message(code: i64) -> String {
+    format!("{}: {}", code, launchd_strerror(code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::launchd_error_message;
+
+    #[test]
+    fn launchd_error_messages() {
+        assert_eq!(
+            launchd_error_message(113),
+            "113: Could not find specified service"
+        );
+        assert_eq!(
+            launchd_error_message(134),
+            "134: Service cannot load in requested session"
+        );
+        assert_eq!(
+            launchd_error_message(158),
+            "158: Service cannot be loaded on current os variant"
+        );
+    }
+}
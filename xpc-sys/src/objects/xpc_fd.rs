@@ -0,0 +1,138 @@
+use std::convert::{TryFrom, TryInto};
+use std::io;
+use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+use std::sync::Arc;
+
+use crate::objects::xpc_error::XPCError;
+use crate::objects::xpc_error::XPCError::ValueError;
+use crate::objects::xpc_object::XPCObject;
+use crate::objects::xpc_type;
+use crate::objects::xpc_type::check_xpc_type;
+use crate::traits::xpc_value::TryXPCValue;
+use crate::{errno, rs_strerror, xpc_fd_create, xpc_fd_dup};
+
+/// An owned file descriptor to send in a request, or one received in a
+/// reply. Closed when dropped.
+#[derive(Debug, PartialEq, Eq)]
+pub struct XPCFd(RawFd);
+
+impl XPCFd {
+    /// dup(), e.g. to keep a copy after handing one to a File
+    pub fn try_clone(&self) -> io::Result<XPCFd> {
+        match unsafe { libc::dup(self.0) } {
+            -1 => Err(io::Error::last_os_error()),
+            fd => Ok(XPCFd(fd)),
+        }
+    }
+}
+
+impl FromRawFd for XPCFd {
+    /// Takes ownership of fd
+    unsafe fn from_raw_fd(fd: RawFd) -> Self {
+        XPCFd(fd)
+    }
+}
+
+impl AsRawFd for XPCFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0
+    }
+}
+
+impl IntoRawFd for XPCFd {
+    fn into_raw_fd(self) -> RawFd {
+        let fd = self.0;
+        std::mem::forget(self);
+        fd
+    }
+}
+
+impl Drop for XPCFd {
+    fn drop(&mut self) {
+        unsafe { libc::close(self.0) };
+    }
+}
+
+impl TryFrom<&XPCObject> for XPCFd {
+    type Error = XPCError;
+
+    /// xpc_fd_dup, the XPC object keeps its own descriptor
+    fn try_from(object: &XPCObject) -> Result<XPCFd, XPCError> {
+        check_xpc_type(object, &xpc_type::Fd)?;
+
+        match unsafe { xpc_fd_dup(object.as_ptr()) } {
+            -1 => Err(ValueError(format!(
+                "xpc_fd_dup failed: {}",
+                rs_strerror(unsafe { errno })
+            ))),
+            fd => Ok(XPCFd(fd)),
+        }
+    }
+}
+
+impl TryFrom<Arc<XPCObject>> for XPCFd {
+    type Error = XPCError;
+
+    fn try_from(value: Arc<XPCObject>) -> Result<XPCFd, XPCError> {
+        (&*value).try_into()
+    }
+}
+
+impl TryFrom<XPCObject> for XPCFd {
+    type Error = XPCError;
+
+    fn try_from(value: XPCObject) -> Result<XPCFd, XPCError> {
+        (&value).try_into()
+    }
+}
+
+impl TryXPCValue<XPCFd> for XPCObject {
+    fn xpc_value(&self) -> Result<XPCFd, XPCError> {
+        self.try_into()
+    }
+}
+
+impl From<&XPCFd> for XPCObject {
+    /// Create XPCObject via xpc_fd_create, which dups the descriptor so
+    /// the XPCFd can still be closed (or dropped) after sending
+    fn from(fd: &XPCFd) -> Self {
+        unsafe { xpc_fd_create(fd.as_raw_fd()).into() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::{Read, Write};
+    use std::os::unix::io::{FromRawFd, IntoRawFd};
+
+    use crate::objects::xpc_fd::XPCFd;
+    use crate::objects::xpc_object::XPCObject;
+    use crate::traits::xpc_value::TryXPCValue;
+
+    #[test]
+    fn fd_round_trip() {
+        let mut fds = [-1; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+
+        let [read_fd, write_fd] = fds;
+        let write = unsafe { XPCFd::from_raw_fd(write_fd) };
+        let xpc_fd = XPCObject::from(&write);
+        drop(write);
+
+        // Both the XPC object and the received copy stay open
+        let received: XPCFd = xpc_fd.xpc_value().unwrap();
+        drop(xpc_fd);
+
+        let mut file = unsafe { File::from_raw_fd(received.into_raw_fd()) };
+        file.write_all(b"jetsam").unwrap();
+        drop(file);
+
+        let mut out = String::new();
+        unsafe { File::from_raw_fd(read_fd) }
+            .read_to_string(&mut out)
+            .unwrap();
+
+        assert_eq!(out, "jetsam");
+    }
+}
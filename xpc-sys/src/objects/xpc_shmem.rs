@@ -10,8 +10,9 @@ use std::ptr::null_mut;
 use std::sync::Arc;
 
 /// Wrapper around vm_allocate() vm_deallocate() with an XPCObject
-/// member of XPC type _xpc_type_shmem
-#[derive(Debug, Clone)]
+/// member of XPC type _xpc_type_shmem. Not Clone, the region is
+/// deallocated exactly once when this is dropped
+#[derive(Debug)]
 pub struct XPCShmem {
     pub task: mach_port_t,
     pub size: vm_size_t,
@@ -38,8 +39,16 @@ impl XPCShmem {
         if err > 0 {
             Err(XPCError::IOError(rs_strerror(err)))
         } else {
-            let xpc_object: XPCObject =
-                unsafe { xpc_shmem_create(region as *mut c_void, size as u64).into() };
+            let xpc_object = unsafe { xpc_shmem_create(region as *mut c_void, size as u64) };
+
+            if xpc_object.is_null() {
+                unsafe { vm_deallocate(task, region as vm_address_t, size) };
+                return Err(XPCError::IOError(
+                    "xpc_shmem_create failed, region deallocated".to_string(),
+                ));
+            }
+
+            let xpc_object: XPCObject = xpc_object.into();
 
             log::info!(
                 "XPCShmem new (region: {:p}, xpc_object_t {:p})",
@@ -80,8 +89,9 @@ impl Drop for XPCShmem {
 
         let ok = unsafe { vm_deallocate(*task, *region as vm_address_t, *size) };
 
+        // Drop may run while unwinding from an error, so don't panic again
         if ok != 0 {
-            panic!("shmem won't drop (vm_deallocate errno {})", ok);
+            log::error!("shmem won't drop (vm_deallocate errno {})", ok);
         }
     }
 }
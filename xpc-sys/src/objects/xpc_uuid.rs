@@ -0,0 +1,115 @@
+use std::convert::{TryFrom, TryInto};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::objects::xpc_error::XPCError;
+use crate::objects::xpc_error::XPCError::ValueError;
+use crate::objects::xpc_object::XPCObject;
+use crate::objects::xpc_type;
+use crate::objects::xpc_type::check_xpc_type;
+use crate::traits::xpc_value::TryXPCValue;
+use crate::{xpc_uuid_create, xpc_uuid_get_bytes};
+
+/// xpc_uuid_t, e.g. a process' executable UUID
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct XPCUuid(pub [u8; 16]);
+
+impl XPCUuid {
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        let XPCUuid(bytes) = self;
+        bytes
+    }
+}
+
+impl fmt::Display for XPCUuid {
+    /// Like uuidgen, e.g. 68753A44-4D6F-1226-9C60-0050E4C00067
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, byte) in self.as_bytes().iter().enumerate() {
+            if [4, 6, 8, 10].contains(&i) {
+                write!(f, "-")?;
+            }
+
+            write!(f, "{:02X}", byte)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for XPCUuid {
+    type Err = XPCError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex: Vec<char> = s.chars().filter(|c| *c != '-').collect();
+        let invalid = || ValueError(format!("Invalid UUID {}", s));
+
+        if hex.len() != 32 {
+            return Err(invalid());
+        }
+
+        let mut bytes = [0u8; 16];
+        for (byte, pair) in bytes.iter_mut().zip(hex.chunks(2)) {
+            let pair: String = pair.iter().collect();
+            *byte = u8::from_str_radix(&pair, 16).map_err(|_| invalid())?;
+        }
+
+        Ok(XPCUuid(bytes))
+    }
+}
+
+impl TryFrom<&XPCObject> for XPCUuid {
+    type Error = XPCError;
+
+    fn try_from(object: &XPCObject) -> Result<XPCUuid, XPCError> {
+        check_xpc_type(object, &xpc_type::Uuid)?;
+
+        let ptr = unsafe { xpc_uuid_get_bytes(object.as_ptr()) };
+        if ptr.is_null() {
+            return Err(ValueError("xpc_uuid_get_bytes returned NULL".to_string()));
+        }
+
+        // Always 16 bytes
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(unsafe { std::slice::from_raw_parts(ptr, 16) });
+
+        Ok(XPCUuid(bytes))
+    }
+}
+
+impl TryFrom<XPCObject> for XPCUuid {
+    type Error = XPCError;
+
+    fn try_from(value: XPCObject) -> Result<XPCUuid, XPCError> {
+        (&value).try_into()
+    }
+}
+
+impl TryXPCValue<XPCUuid> for XPCObject {
+    fn xpc_value(&self) -> Result<XPCUuid, XPCError> {
+        self.try_into()
+    }
+}
+
+impl From<XPCUuid> for XPCObject {
+    /// Create XPCObject via xpc_uuid_create, which copies the bytes
+    fn from(uuid: XPCUuid) -> Self {
+        unsafe { xpc_uuid_create(uuid.as_bytes().as_ptr()).into() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::objects::xpc_object::XPCObject;
+    use crate::objects::xpc_uuid::XPCUuid;
+    use crate::traits::xpc_value::TryXPCValue;
+
+    #[test]
+    fn uuid_round_trip() {
+        let uuid: XPCUuid = "68753A44-4D6F-1226-9C60-0050E4C00067".parse().unwrap();
+        let back: XPCUuid = XPCObject::from(uuid).xpc_value().unwrap();
+
+        assert_eq!(back, uuid);
+        assert_eq!(back.to_string(), "68753A44-4D6F-1226-9C60-0050E4C00067");
+        assert!("68753A44".parse::<XPCUuid>().is_err());
+    }
+}
@@ -1,3 +1,4 @@
 pub mod query_builder;
+pub mod xpc_deserialize;
 pub mod xpc_pipeable;
 pub mod xpc_value;
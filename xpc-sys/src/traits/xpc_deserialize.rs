@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use crate::objects::xpc_dictionary::XPCDictionary;
+use crate::objects::xpc_error::XPCError;
+use crate::objects::xpc_object::XPCObject;
+use crate::traits::xpc_value::TryXPCValue;
+
+/// Map a reply onto a Rust struct, reading each field by key path with
+/// `XPCDictionary::value` (required) or `optional_value`:
+///
+/// ```ignore
+/// impl FromXPCDictionary for ListedService {
+///     fn from_xpc_dictionary(dict: &XPCDictionary) -> Result<Self, XPCError> {
+///         Ok(ListedService {
+///             pid: dict.value(["pid"])?,
+///             status: dict.optional_value(["status"])?,
+///         })
+///     }
+/// }
+/// ```
+pub trait FromXPCDictionary: Sized {
+    fn from_xpc_dictionary(dict: &XPCDictionary) -> Result<Self, XPCError>;
+}
+
+/// A field read without failing the rest of the reply, for keys a newer
+/// launchd may drop or change the type of
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Field<T> {
+    Value(T),
+    Missing,
+    /// Present, but not convertible to T
+    Unparseable,
+}
+
+impl<T> Field<T> {
+    pub fn value(self) -> Option<T> {
+        match self {
+            Field::Value(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn is_unparseable(&self) -> bool {
+        matches!(self, Field::Unparseable)
+    }
+}
+
+/// Parsed values by key, and keys that failed with why
+pub type LossyMap<T> = (HashMap<String, T>, Vec<(String, XPCError)>);
+
+impl XPCDictionary {
+    /// Value at key path, converted with TryXPCValue
+    pub fn value<T, I, S>(&self, items: I) -> Result<T, XPCError>
+    where
+        XPCObject: TryXPCValue<T>,
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.get(items)?.xpc_value()
+    }
+
+    /// None if the key path is missing, Err if it holds the wrong type
+    pub fn optional_value<T, I, S>(&self, items: I) -> Result<Option<T>, XPCError>
+    where
+        XPCObject: TryXPCValue<T>,
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        match self.get(items) {
+            Ok(object) => object.xpc_value().map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Value at key path as a Field, never an error
+    pub fn field<T, I, S>(&self, items: I) -> Field<T>
+    where
+        XPCObject: TryXPCValue<T>,
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        match self.get(items) {
+            Ok(object) => object
+                .xpc_value()
+                .map(Field::Value)
+                .unwrap_or(Field::Unparseable),
+            Err(_) => Field::Missing,
+        }
+    }
+
+    /// This dictionary as T
+    pub fn parse<T: FromXPCDictionary>(&self) -> Result<T, XPCError> {
+        T::from_xpc_dictionary(self)
+    }
+
+    /// A dictionary of dictionaries at key path (e.g. list's "services")
+    /// with each value as T, keyed as in the reply
+    pub fn parse_map<T, I, S>(&self, items: I) -> Result<HashMap<String, T>, XPCError>
+    where
+        T: FromXPCDictionary,
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let XPCDictionary(map) = self.get_as_dictionary(items)?;
+
+        map.into_iter()
+            .map(|(key, object)| {
+                let value = XPCDictionary::try_from(object)?.parse()?;
+                Ok((key, value))
+            })
+            .collect()
+    }
+
+    /// parse_map, but values that are not dictionaries or do not parse
+    /// are returned with their error instead of failing the others
+    pub fn parse_map_lossy<T, I, S>(&self, items: I) -> Result<LossyMap<T>, XPCError>
+    where
+        T: FromXPCDictionary,
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let XPCDictionary(map) = self.get_as_dictionary(items)?;
+        let mut parsed = HashMap::new();
+        let mut errors = vec![];
+
+        for (key, object) in map {
+            match XPCDictionary::try_from(object).and_then(|d| d.parse()) {
+                Ok(value) => {
+                    parsed.insert(key, value);
+                }
+                Err(e) => errors.push((key, e)),
+            }
+        }
+
+        Ok((parsed, errors))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::objects::xpc_dictionary::XPCDictionary;
+    use crate::objects::xpc_error::XPCError;
+    use crate::traits::query_builder::QueryBuilder;
+    use crate::traits::xpc_deserialize::{Field, FromXPCDictionary};
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct Service {
+        pid: i64,
+        status: Option<i64>,
+    }
+
+    impl FromXPCDictionary for Service {
+        fn from_xpc_dictionary(dict: &XPCDictionary) -> Result<Self, XPCError> {
+            Ok(Service {
+                pid: dict.value(["pid"])?,
+                status: dict.optional_value(["status"])?,
+            })
+        }
+    }
+
+    #[test]
+    fn parse_nested_services() {
+        let running = XPCDictionary::new()
+            .entry("pid", 42_i64)
+            .entry("status", 0_i64);
+        let idle = XPCDictionary::new().entry("pid", 0_i64);
+        let services = XPCDictionary::new()
+            .entry("com.example.running", running)
+            .entry("com.example.idle", idle);
+
+        let reply = XPCDictionary::new().entry("services", services);
+        let parsed = reply.parse_map::<Service, _, _>(["services"]).unwrap();
+
+        assert_eq!(
+            parsed["com.example.running"],
+            Service {
+                pid: 42,
+                status: Some(0)
+            }
+        );
+        assert_eq!(parsed["com.example.idle"].status, None);
+
+        let wrong_type = XPCDictionary::new().entry("pid", "nope");
+        assert!(wrong_type.parse::<Service>().is_err());
+    }
+
+    #[test]
+    fn lossy_fields_and_maps() {
+        let dict = XPCDictionary::new()
+            .entry("pid", "nope")
+            .entry("status", 1_i64);
+
+        assert_eq!(dict.field::<i64, _, _>(["pid"]), Field::Unparseable);
+        assert_eq!(dict.field::<i64, _, _>(["status"]), Field::Value(1));
+        assert_eq!(dict.field::<i64, _, _>(["missing"]), Field::Missing);
+
+        let services = XPCDictionary::new()
+            .entry("com.example.ok", XPCDictionary::new().entry("pid", 1_i64))
+            .entry("com.example.bad", XPCDictionary::new().entry("pid", "nope"))
+            .entry("com.example.flat", 3_i64);
+        let reply = XPCDictionary::new().entry("services", services);
+
+        let (parsed, errors) = reply
+            .parse_map_lossy::<Service, _, _>(["services"])
+            .unwrap();
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed["com.example.ok"].pid, 1);
+        assert_eq!(errors.len(), 2);
+    }
+}
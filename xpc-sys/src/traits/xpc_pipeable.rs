@@ -1,11 +1,8 @@
 use crate::objects::xpc_dictionary::XPCDictionary;
-use crate::objects::xpc_error::XPCError;
-use crate::objects::xpc_error::XPCError::PipeError;
+use crate::objects::xpc_error::XPCError::PipeErrno;
+use crate::objects::xpc_error::{launchd_error_message, XPCError};
 use crate::objects::xpc_object::XPCObject;
-use crate::{
-    get_xpc_bootstrap_pipe, rs_xpc_strerror, xpc_object_t, xpc_pipe_routine,
-    xpc_pipe_routine_with_flags,
-};
+use crate::{get_xpc_bootstrap_pipe, xpc_object_t, xpc_pipe_routine, xpc_pipe_routine_with_flags};
 
 use crate::traits::xpc_value::TryXPCValue;
 use std::convert::TryInto;
@@ -13,52 +10,53 @@ use std::ptr::null_mut;
 
 pub type XPCPipeResult = Result<XPCObject, XPCError>;
 
+/// Reply from a pipe routine, or the launchd error in its "error" or
+/// "errors" keys
+pub fn check_reply(response: XPCDictionary) -> Result<XPCDictionary, XPCError> {
+    let XPCDictionary(hm) = &response;
+
+    if hm.contains_key("error") {
+        let errcode: i64 = response.get(&["error"])?.xpc_value()?;
+        Err(XPCError::QueryError(launchd_error_message(errcode)))
+    } else if hm.contains_key("errors") {
+        let XPCDictionary(errors_hm) = response.get_as_dictionary(&["errors"])?;
+        if errors_hm.is_empty() {
+            return Ok(response);
+        }
+
+        let errors: Vec<String> = errors_hm
+            .iter()
+            .flat_map(|(_, e)| {
+                let e: Result<i64, XPCError> = e.xpc_value();
+                e.map(launchd_error_message)
+            })
+            .collect();
+
+        Err(XPCError::QueryError(errors.join("\n")))
+    } else {
+        Ok(response)
+    }
+}
+
 pub trait XPCPipeable {
     /// Try to safely call xpc_pipe_routine, returning an XPCObject if successful,
-    /// otherwise a string with xpc_strerror
+    /// otherwise the errno
     fn pipe_routine(&self) -> XPCPipeResult;
 
     /// Try to safely call xpc_pipe_routine_with_flags, returning an XPCObject
-    /// if successful, otherwise a string with xpc_strerror
+    /// if successful, otherwise the errno
     fn pipe_routine_with_flags(&self, flags: u64) -> XPCPipeResult;
 
     /// Pipe routine expecting XPC dictionary reply, with checking of "error" and "errors" keys
     fn pipe_routine_with_error_handling(&self) -> Result<XPCDictionary, XPCError> {
-        let response = self.pipe_routine()?.try_into()?;
-        let XPCDictionary(hm) = &response;
-
-        if hm.contains_key("error") {
-            let errcode: i64 = response.get(&["error"])?.xpc_value()?;
-            Err(XPCError::QueryError(format!(
-                "{}: {}",
-                errcode,
-                rs_xpc_strerror(errcode as i32)
-            )))
-        } else if hm.contains_key("errors") {
-            let XPCDictionary(errors_hm) = response.get_as_dictionary(&["errors"])?;
-            if errors_hm.is_empty() {
-                return Ok(response);
-            }
-
-            let errors: Vec<String> = errors_hm
-                .iter()
-                .flat_map(|(_, e)| {
-                    let e: Result<i64, XPCError> = e.xpc_value();
-                    e.map(|e_i64| format!("{}: {}", e_i64, rs_xpc_strerror(e_i64 as i32)))
-                })
-                .collect();
-
-            Err(XPCError::QueryError(errors.join("\n")))
-        } else {
-            Ok(response)
-        }
+        check_reply(self.pipe_routine()?.try_into()?)
     }
 
     fn handle_pipe_routine(ptr: xpc_object_t, errno: i32) -> XPCPipeResult {
         if errno == 0 {
             Ok(ptr.into())
         } else {
-            Err(PipeError(rs_xpc_strerror(errno)))
+            Err(PipeErrno(errno))
         }
     }
 }